@@ -27,10 +27,10 @@ fn subscription(index: usize) -> Subscription {
         },
         vec![MonitoringTarget {
             label: "home".to_string(),
-            point: GeoPoint {
+            point: Some(GeoPoint {
                 latitude: 39.9 + (index % 10) as f64 / 100.0,
                 longitude: 116.4,
-            },
+            }),
             region: AdministrativeRegion {
                 province: "北京市".to_string(),
                 city: "北京市".to_string(),
@@ -62,6 +62,9 @@ fn event() -> DisasterEvent {
         final_report: false,
         cancel: false,
         training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
     }
 }
 
@@ -1,10 +1,13 @@
 use html_minifier::HTMLMinifier;
-use std::{env, fs, io, path::PathBuf};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
 const INSTANCE_NOTICE_MARKER: &[u8] = b"__DISASTER_ALERT_INSTANCE_NOTICE__";
 
 fn main() -> io::Result<()> {
-    println!("cargo:rerun-if-changed=web/index.html");
+    println!("cargo:rerun-if-changed=web");
 
     let source = fs::read("web/index.html")?;
     if source
@@ -21,10 +24,51 @@ fn main() -> io::Result<()> {
     let mut minifier = HTMLMinifier::new();
     minifier.digest(source).map_err(io::Error::other)?;
 
-    let output = PathBuf::from(
+    let out_dir = PathBuf::from(
         env::var_os("OUT_DIR")
             .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cargo did not set OUT_DIR"))?,
+    );
+    fs::write(out_dir.join("index.min.html"), minifier.get_html())?;
+    fs::write(
+        out_dir.join("web_assets.rs"),
+        asset_table(Path::new("web"))?,
     )
-    .join("index.min.html");
-    fs::write(output, minifier.get_html())
+}
+
+/// 把 `web/` 下除首页外的文件按（请求路径，内容）嵌入二进制，按路径排序以便二分查找。
+fn asset_table(root: &Path) -> io::Result<String> {
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+    let mut assets = files
+        .into_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(root).ok()?.to_str()?.replace('\\', "/");
+            (relative != "index.html").then_some((format!("/{relative}"), path))
+        })
+        .collect::<Vec<_>>();
+    assets.sort();
+    let mut table = String::from("&[\n");
+    for (request_path, path) in assets {
+        let absolute = fs::canonicalize(path)?;
+        let absolute = absolute.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "web asset path is not UTF-8")
+        })?;
+        table.push_str(&format!(
+            "    ({request_path:?}, include_bytes!({absolute:?}).as_slice()),\n"
+        ));
+    }
+    table.push(']');
+    Ok(table)
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
 }
@@ -1,59 +1,90 @@
-use crate::config::{Config, load_dotenv};
-use crate::delivery::{BarkNotifier, BarkPushConfig, NotificationLinkService};
+use crate::config::{
+    Config, ConfigReloader, LiveSettings, TunableSettings, config_file_path, load_config_file,
+    load_dotenv,
+};
+use crate::delivery::{BarkNotifier, BarkPushConfig, MessageBudget, NotificationLinkService};
 use crate::lifecycle;
-use crate::providers::{FanStudioSource, HuaniaSource, WolfxSource};
+use crate::providers::{FanStudioSource, HuaniaSource, P2pquakeSource, RawArchive, WolfxSource};
 use crate::routes::{
-    AppState, ReverseGeocoder, bark_urls_handler, health_handler, incident_detail_handler,
-    index_handler, reverse_geocode_handler, status_handler, subscribe_handler,
-    subscription_options_handler, unsubscribe_handler,
+    AdminAuth, AppState, ClientIpResolver, REQUEST_ID_HEADER, ReverseGeocoder, RouteLimits,
+    SubscriptionChallenges, SubscriptionExports, SubscriptionQuota, admin_felt_calibration_handler,
+    admin_import_catalog_handler, admin_parse_diagnostics_handler,
+    admin_purge_subscription_handler, admin_reload_config_handler,
+    admin_restore_subscription_handler, admin_subscription_breakdown_handler,
+    admin_subscription_density_handler, assign_request_id, bark_urls_handler,
+    conditional_history_read, distance_handler, earthquake_search_handler, enforce_route_limits,
+    felt_report_handler, felt_summary_handler, health_handler, incident_detail_handler,
+    index_handler, intensity_grid_handler, liveness_handler, log_http_request, metrics_handler,
+    notification_open_handler, preview_handler, public_config_handler, readiness_handler,
+    require_admin, restore_subscription_handler, reverse_geocode_handler, seismicity_stats_handler,
+    status_handler, subscribe_challenge_handler, subscribe_handler, subscription_export_handler,
+    subscription_export_request_handler, subscription_options_handler, unsubscribe_handler,
+    web_fallback_handler,
+};
+use crate::runtime::{
+    CanaryMonitor, EventRuntime, OperatorAlerts, OperatorThresholds, RuntimeStatus,
 };
-use crate::runtime::{EventRuntime, RuntimeStatus};
-use crate::storage::{RetentionPolicy, Storage};
+use crate::storage::{RetentionPolicy, Storage, StorageProtection};
 use crate::subscriptions::SubscriptionConfirmationService;
+use crate::telemetry::Telemetry;
+use crate::tls::{ServerListener, TlsFiles};
+use crate::upstream_tls::UpstreamTls;
 use anyhow::{Context, Result};
 use axum::{
     Router,
     extract::DefaultBodyLimit,
-    http::{HeaderValue, Method},
+    http::{HeaderValue, Method, request},
+    middleware,
     routing::{delete, get, post},
 };
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tower_http::compression::CompressionLayer;
-use tower_http::cors::CorsLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 const SUBSCRIPTION_BODY_LIMIT_BYTES: usize = 32 * 1024;
+const CATALOG_BODY_LIMIT_BYTES: usize = 16 * 1024 * 1024;
+/// 启动预热日志中列出的监测点最多的 H3 二级格子数量。
+const WARMUP_REPORTED_CELLS: usize = 10;
 
 pub fn run_from_env() -> Result<()> {
     let dotenv_path = load_dotenv().context("failed to load .env configuration")?;
 
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "disaster_alert=info,tower_http=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let telemetry = Telemetry::init().context("failed to initialize telemetry")?;
 
     if let Some(path) = dotenv_path {
         tracing::info!(event = "config.dotenv_loaded", path = %path.display(), "config.dotenv_loaded");
     }
+    let config_file = config_file_path(std::env::args().skip(1))?;
+    if let Some(path) = &config_file {
+        let settings = load_config_file(path)?;
+        tracing::info!(
+            event = "config.file_loaded",
+            path = %path.display(),
+            settings,
+            "config.file_loaded"
+        );
+    }
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .context("failed to create Tokio runtime")?;
-    let result = runtime.block_on(run());
+    let result = runtime.block_on(run(config_file));
     runtime.shutdown_timeout(lifecycle::FORCED_SHUTDOWN_TIMEOUT);
+    telemetry.shutdown();
     result
 }
 
-async fn run() -> Result<()> {
+async fn run(config_file: Option<PathBuf>) -> Result<()> {
     let config = Config::from_env().context("failed to load configuration")?;
+    let live_settings = LiveSettings::new(TunableSettings::from_config(&config));
+    let config_reloader = ConfigReloader::new(config_file, live_settings.clone());
     tracing::info!(
         event = "config.loaded",
         instance_terms_accepted = config.instance_terms_accepted,
+        features = ?config.features,
         server_host = %config.server_host,
         server_port = config.server_port,
         db_path = %config.db_path,
@@ -69,16 +100,31 @@ async fn run() -> Result<()> {
         );
     }
 
+    let storage_protection = match &config.storage_encryption_key {
+        Some(key) => StorageProtection::from_encoded_key(key.expose())?,
+        None => {
+            tracing::warn!(
+                event = "config.storage_encryption_disabled",
+                variable = "STORAGE_ENCRYPTION_KEY",
+                "config.storage_encryption_disabled"
+            );
+            StorageProtection::disabled()
+        }
+    };
     let db_path = config.db_path.clone();
-    let storage = tokio::task::spawn_blocking(move || Storage::open(db_path))
-        .await
-        .context("database open task failed")??;
+    let storage = tokio::task::spawn_blocking(move || {
+        Storage::open_with_protection(db_path, storage_protection)
+    })
+    .await
+    .context("database open task failed")??;
     tracing::info!(event = "database.opened", db_path = %config.db_path, "database.opened");
     let prune_storage = storage.clone();
     let retention_policy = RetentionPolicy {
         incident_days: config.incident_retention_days,
         delivery_ledger_days: config.delivery_ledger_retention_days,
         operation_days: config.operation_retention_days,
+        subscription_restore_days: config.subscription_restore_days,
+        undeliverable_failure_threshold: config.undeliverable_failure_threshold,
     };
     let prune_stats =
         tokio::task::spawn_blocking(move || prune_storage.prune_retained_data(retention_policy))
@@ -91,21 +137,57 @@ async fn run() -> Result<()> {
             incidents = prune_stats.incidents,
             delivery_records = prune_stats.delivery_records,
             events = prune_stats.events,
+            earthquake_history = prune_stats.earthquake_history,
+            felt_reports = prune_stats.felt_reports,
+            notification_opens = prune_stats.notification_opens,
+            subscriptions = prune_stats.subscriptions,
+            undeliverable_subscriptions = prune_stats.undeliverable_subscriptions,
             "database.records_pruned"
         );
     }
 
+    let warmup_storage = storage.clone();
+    let warmup_started = Instant::now();
+    let warmup = tokio::task::spawn_blocking(move || warmup_storage.warm_up_subscriptions())
+        .await
+        .context("subscription warm-up task failed")?
+        .context("failed to warm up subscription indexes")?;
+    tracing::info!(
+        event = "database.subscriptions_warmed",
+        subscriptions = warmup.subscriptions,
+        targets = warmup.targets,
+        posting_keys = warmup.posting_keys,
+        coarse_cells = warmup.coarse_cells.len(),
+        busiest_cells = %warmup
+            .coarse_cells
+            .iter()
+            .take(WARMUP_REPORTED_CELLS)
+            .map(|(cell, targets)| format!("{cell:x}={targets}"))
+            .collect::<Vec<_>>()
+            .join(","),
+        elapsed_ms = u64::try_from(warmup_started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        "database.subscriptions_warmed"
+    );
+
     let push_config = BarkPushConfig::new(
         config.bark_sound.clone(),
         config.bark_volume,
         config.bark_group.clone(),
         config.bark_call,
-    );
-    let bark_notifier = BarkNotifier::new(
+    )
+    .with_budget(MessageBudget {
+        title_chars: config.bark_title_max_chars,
+        subtitle_chars: config.bark_subtitle_max_chars,
+        body_chars: config.bark_body_max_chars,
+    });
+    let upstream_tls = UpstreamTls::load(&config)?;
+    let bark_notifier = BarkNotifier::with_http3_servers(
         config.bark_url_allowlist.clone(),
         config.http_pool_size,
         config.max_concurrent_notifications,
         push_config,
+        &config.bark_http3_urls,
+        upstream_tls.as_ref(),
     )?;
 
     let runtime_status = RuntimeStatus::default();
@@ -129,7 +211,20 @@ async fn run() -> Result<()> {
         subscription_confirmations.clone(),
         config.max_concurrent_notifications,
     )
-    .with_instance_terms_accepted(config.instance_terms_accepted);
+    .with_instance_terms_accepted(config.instance_terms_accepted)
+    .with_subscription_challenges(SubscriptionChallenges::new(&config, live_settings.clone()))
+    .with_subscription_quota(
+        ClientIpResolver::new(&config.trusted_proxies)?,
+        SubscriptionQuota::with_settings(live_settings.clone()),
+    )
+    .with_subscription_exports(if config.features.subscription_export {
+        SubscriptionExports::new(&config)
+    } else {
+        SubscriptionExports::disabled()
+    })
+    .with_subscription_restore_days(config.subscription_restore_days)
+    .with_readiness_grace(Duration::from_secs(config.readiness_grace_seconds))
+    .with_config_reloader(config_reloader.clone());
     if pruned_contexts > 0 {
         tracing::info!(
             event = "database.notification_contexts_pruned",
@@ -138,22 +233,26 @@ async fn run() -> Result<()> {
         );
     }
 
-    let cors = build_cors_layer(&config)?;
+    let cors = build_cors_layer(live_settings.clone());
 
-    let app = Router::new()
+    let request_timeout = Duration::from_secs(config.http_request_timeout_seconds);
+    let heavy_limits = RouteLimits::new("heavy", config.http_heavy_max_in_flight, request_timeout);
+    let mut routes = Router::new()
         .route("/", get(index_handler))
         .route("/index.html", get(index_handler))
-        .route(
-            "/incidents/{incident_id}/notifications/{token}",
-            get(incident_detail_handler),
-        )
-        .route("/health", get(health_handler))
         .route(
             "/api/subscribe",
             post(subscribe_handler).layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
         )
+        .route("/api/subscribe-challenge", get(subscribe_challenge_handler))
+        .route("/api/config", get(public_config_handler))
         .route("/api/bark-urls", get(bark_urls_handler))
         .route("/api/reverse-geocode", get(reverse_geocode_handler))
+        .route("/api/distance", get(distance_handler))
+        .route(
+            "/api/felt",
+            post(felt_report_handler).layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+        )
         .route(
             "/api/subscription-options",
             get(subscription_options_handler),
@@ -162,9 +261,129 @@ async fn run() -> Result<()> {
             "/api/unsubscribe",
             delete(unsubscribe_handler).layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
         )
+        .route(
+            "/api/subscription-restore",
+            post(restore_subscription_handler)
+                .layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+        )
+        .route(
+            "/api/preview",
+            post(preview_handler).layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+        )
         .route("/api/status", get(status_handler))
+        .route_layer(middleware::from_fn_with_state(
+            RouteLimits::new("public", config.http_max_in_flight, request_timeout),
+            enforce_route_limits,
+        ))
+        .merge(
+            Router::new()
+                .route(
+                    "/incidents/{incident_id}/notifications/{token}",
+                    get(incident_detail_handler),
+                )
+                .route(
+                    "/incidents/{incident_id}/notifications/{token}/open/{receipt}",
+                    get(notification_open_handler),
+                )
+                .merge(
+                    Router::new()
+                        .route("/api/earthquakes/search", get(earthquake_search_handler))
+                        .route(
+                            "/api/earthquakes/{event_id}/grid",
+                            get(intensity_grid_handler),
+                        )
+                        .route("/api/stats/seismicity", get(seismicity_stats_handler))
+                        .route_layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            conditional_history_read,
+                        )),
+                )
+                .route(
+                    "/api/earthquakes/{event_id}/felt",
+                    get(felt_summary_handler),
+                )
+                .route(
+                    "/api/subscription-export",
+                    post(subscription_export_request_handler)
+                        .layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+                )
+                .route(
+                    "/api/subscription-export/{token}",
+                    get(subscription_export_handler),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    heavy_limits.clone(),
+                    enforce_route_limits,
+                )),
+        )
+        .route("/health", get(health_handler))
+        .route("/healthz", get(liveness_handler))
+        .route("/readyz", get(readiness_handler));
+    if config.features.metrics {
+        routes = routes.route("/metrics", get(metrics_handler));
+    }
+    if let Some(token) = config
+        .admin_token
+        .as_ref()
+        .filter(|_token| config.features.admin_api)
+    {
+        routes = routes.merge(
+            Router::new()
+                .route(
+                    "/api/admin/subscriptions/purge",
+                    post(admin_purge_subscription_handler)
+                        .layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+                )
+                .route(
+                    "/api/admin/diagnostics/parse-failures",
+                    get(admin_parse_diagnostics_handler),
+                )
+                .route(
+                    "/api/admin/earthquakes/import",
+                    post(admin_import_catalog_handler)
+                        .layer(DefaultBodyLimit::max(CATALOG_BODY_LIMIT_BYTES)),
+                )
+                .route(
+                    "/api/admin/subscriptions/density",
+                    get(admin_subscription_density_handler),
+                )
+                .route(
+                    "/api/admin/subscriptions/breakdown",
+                    get(admin_subscription_breakdown_handler),
+                )
+                .route(
+                    "/api/admin/felt/calibration",
+                    get(admin_felt_calibration_handler),
+                )
+                .route(
+                    "/api/admin/config/reload",
+                    post(admin_reload_config_handler),
+                )
+                .route(
+                    "/api/admin/subscriptions/restore",
+                    post(admin_restore_subscription_handler)
+                        .layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES)),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    heavy_limits,
+                    enforce_route_limits,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    AdminAuth::new(token.expose()),
+                    require_admin,
+                )),
+        );
+    }
+    let app = routes
+        .fallback(web_fallback_handler)
+        .layer(DefaultBodyLimit::max(SUBSCRIPTION_BODY_LIMIT_BYTES))
         .layer(cors)
         .layer(CompressionLayer::new())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            log_http_request,
+        ))
+        .layer(middleware::from_fn(assign_request_id))
         .with_state(state);
 
     let addr: SocketAddr = format!("{}:{}", config.server_host, config.server_port)
@@ -173,23 +392,104 @@ async fn run() -> Result<()> {
 
     let event_runtime = EventRuntime::new(
         storage.clone(),
-        &config,
+        live_settings.clone(),
+        config.features,
         bark_notifier.clone(),
         notification_links,
         runtime_status.clone(),
+        retention_policy,
     )?;
     event_runtime
         .recover()
         .await
         .context("failed to recover durable delivery, matching, and event work")?;
+    runtime_status.mark_recovered();
+    let _reload_signal = lifecycle::spawn_reload_on_hangup(config_reloader)?;
+    let _canary = config
+        .canary_bark_url
+        .clone()
+        .zip(config.canary_device_key.as_ref())
+        .map(|(bark_url, device_key)| {
+            CanaryMonitor::new(
+                storage.clone(),
+                event_runtime.clone(),
+                runtime_status.clone(),
+                bark_url,
+                device_key.expose(),
+                Duration::from_secs(config.canary_interval_seconds),
+                Duration::from_secs(config.canary_timeout_seconds),
+            )
+            .spawn()
+        });
+    let _operator_alerts = config
+        .operator_bark_url
+        .clone()
+        .zip(config.operator_device_key.as_ref())
+        .map(|(bark_url, device_key)| {
+            OperatorAlerts::new(
+                storage.clone(),
+                runtime_status.clone(),
+                bark_notifier.clone(),
+                bark_url,
+                device_key.expose(),
+                OperatorThresholds {
+                    disconnect: Duration::from_secs(config.operator_disconnect_seconds),
+                    failure_ratio: f64::from(config.operator_failure_percent) / 100.0,
+                    retry_backlog: config.operator_retry_backlog,
+                    dead_letter_growth: config.operator_dead_letter_growth,
+                },
+            )
+            .spawn()
+        });
 
-    tracing::info!(event = "server.starting", listen_addr = %addr, "server.starting");
-    let listener = tokio::net::TcpListener::bind(&addr)
-        .await
-        .context("failed to bind HTTP listener")?;
-    let wolfx = WolfxSource::new(&config, event_runtime.clone(), runtime_status.clone());
-    let fanstudio = FanStudioSource::new(&config, event_runtime.clone(), runtime_status.clone());
-    let huania = HuaniaSource::new(&config, event_runtime.clone(), runtime_status.clone())?;
+    let tls_files = config
+        .tls_cert_path
+        .as_ref()
+        .zip(config.tls_key_path.as_ref())
+        .map(|(cert_path, key_path)| TlsFiles {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        });
+    tracing::info!(
+        event = "server.starting",
+        listen_addr = %addr,
+        tls = tls_files.is_some(),
+        "server.starting"
+    );
+    let listener = ServerListener::bind(
+        addr,
+        tls_files,
+        Duration::from_secs(config.tls_reload_seconds),
+    )
+    .await?;
+    let raw_archive = RawArchive::spawn(&config)?;
+    let wolfx = WolfxSource::new(
+        &config,
+        event_runtime.clone(),
+        runtime_status.clone(),
+        upstream_tls.as_ref(),
+        raw_archive.clone(),
+    )?;
+    let fanstudio = FanStudioSource::new(
+        &config,
+        event_runtime.clone(),
+        runtime_status.clone(),
+        upstream_tls.as_ref(),
+        raw_archive.clone(),
+    )?;
+    let huania = HuaniaSource::new(
+        &config,
+        event_runtime.clone(),
+        runtime_status.clone(),
+        upstream_tls.as_ref(),
+    )?;
+    let p2pquake = P2pquakeSource::new(
+        &config,
+        event_runtime.clone(),
+        runtime_status.clone(),
+        upstream_tls.as_ref(),
+        raw_archive,
+    )?;
     lifecycle::run_until_shutdown(
         listener,
         app,
@@ -200,32 +500,31 @@ async fn run() -> Result<()> {
             wolfx,
             fanstudio,
             huania,
+            p2pquake,
         ),
         Duration::from_secs(config.shutdown_timeout_seconds),
     )
     .await
 }
 
-fn build_cors_layer(config: &Config) -> Result<CorsLayer> {
-    let mut origins = Vec::new();
-    for origin in &config.allowed_origins {
-        origins.push(
-            origin
-                .parse::<HeaderValue>()
-                .with_context(|| format!("invalid ALLOWED_ORIGINS entry {origin:?}"))?,
-        );
-    }
-
-    let cors = CorsLayer::new()
+/// 允许的来源在每个请求时从 [`LiveSettings`] 读取，`ALLOWED_ORIGINS` 重新加载后即时生效；
+/// 列表为空时不返回任何 CORS 允许头。
+fn build_cors_layer(settings: LiveSettings) -> CorsLayer {
+    CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::AUTHORIZATION,
-        ]);
-
-    if origins.is_empty() {
-        Ok(cors)
-    } else {
-        Ok(cors.allow_origin(origins))
-    }
+            REQUEST_ID_HEADER.clone(),
+        ])
+        .expose_headers([REQUEST_ID_HEADER.clone()])
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _request: &request::Parts| {
+                settings
+                    .load()
+                    .allowed_origins
+                    .iter()
+                    .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+            },
+        ))
 }
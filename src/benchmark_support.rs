@@ -13,10 +13,11 @@ use anyhow::Result;
 use roaring::RoaringBitmap;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::{
-    Arc,
+    Arc, Mutex,
     atomic::{AtomicU64, Ordering},
 };
 use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 pub use crate::models::{
     AdministrativeRegion, AlertRule, DisasterCategory, DisasterEvent, GeoPoint, MonitoringTarget,
@@ -203,10 +204,10 @@ impl SlowBarkIsolationBenchmark {
                 },
                 vec![MonitoringTarget {
                     label: "benchmark".to_string(),
-                    point: GeoPoint {
+                    point: Some(GeoPoint {
                         latitude: 39.9,
                         longitude: 116.4,
-                    },
+                    }),
                     region: AdministrativeRegion::default(),
                 }],
                 vec![AlertRule::default_for(DisasterCategory::EarthquakeReport)],
@@ -281,6 +282,322 @@ impl SlowBarkIsolationBenchmark {
     }
 }
 
+/// `disaster-alert bench` 的压测参数。
+pub struct LoadBenchmarkOptions {
+    pub subscriptions: usize,
+    pub events: usize,
+    /// 模拟 Bark 服务器每次推送的响应延迟。
+    pub push_delay: Duration,
+    pub max_concurrent: usize,
+    /// 单个事件等待全部推送送达的上限。
+    pub event_timeout: Duration,
+}
+
+impl Default for LoadBenchmarkOptions {
+    fn default() -> Self {
+        Self {
+            subscriptions: 10_000,
+            events: 10,
+            push_delay: Duration::ZERO,
+            max_concurrent: 1_000,
+            event_timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl LoadBenchmarkOptions {
+    const USAGE: &'static str = "usage: disaster-alert bench [--subscriptions N] [--events M] \
+        [--push-delay-ms D] [--max-concurrent C] [--event-timeout-seconds T]";
+
+    pub fn from_args(mut arguments: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(argument) = arguments.next() {
+            let (name, inline) = match argument.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (argument, None),
+            };
+            let value = inline
+                .or_else(|| arguments.next())
+                .ok_or_else(|| anyhow::anyhow!("{name} requires a value\n{}", Self::USAGE))?;
+            let number = value
+                .parse::<u64>()
+                .map_err(|error| anyhow::anyhow!("invalid {name} value {value:?}: {error}"))?;
+            let count = usize::try_from(number).unwrap_or(usize::MAX);
+            match name.as_str() {
+                "--subscriptions" => options.subscriptions = count,
+                "--events" => options.events = count,
+                "--push-delay-ms" => options.push_delay = Duration::from_millis(number),
+                "--max-concurrent" => options.max_concurrent = count,
+                "--event-timeout-seconds" => options.event_timeout = Duration::from_secs(number),
+                _ => anyhow::bail!("unknown argument {name}\n{}", Self::USAGE),
+            }
+        }
+        anyhow::ensure!(
+            options.subscriptions > 0 && options.events > 0 && options.max_concurrent > 0,
+            "--subscriptions, --events and --max-concurrent must be positive"
+        );
+        anyhow::ensure!(
+            !options.event_timeout.is_zero(),
+            "--event-timeout-seconds must be positive"
+        );
+        Ok(options)
+    }
+}
+
+/// 延迟分位数。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencySummary {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let percentile = |value: usize| {
+            let Some(last) = samples.len().checked_sub(1) else {
+                return Duration::ZERO;
+            };
+            samples[(last * value).div_ceil(100)]
+        };
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for LatencySummary {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            formatter,
+            "p50={:.1}ms p90={:.1}ms p99={:.1}ms max={:.1}ms",
+            self.p50.as_secs_f64() * 1_000.0,
+            self.p90.as_secs_f64() * 1_000.0,
+            self.p99.as_secs_f64() * 1_000.0,
+            self.max.as_secs_f64() * 1_000.0,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadBenchmarkReport {
+    pub subscriptions: usize,
+    pub events: usize,
+    pub pushes: usize,
+    pub import: Duration,
+    pub elapsed: Duration,
+    /// 事件提交到单条推送到达模拟服务器的耗时。
+    pub push_latency: LatencySummary,
+    /// 事件提交到该事件全部推送到达的耗时。
+    pub event_latency: LatencySummary,
+}
+
+impl std::fmt::Display for LoadBenchmarkReport {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        let rate = |count: usize| {
+            if seconds > 0.0 {
+                count as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+        writeln!(
+            formatter,
+            "subscriptions: {} (imported in {:.2}s)",
+            self.subscriptions,
+            self.import.as_secs_f64()
+        )?;
+        writeln!(
+            formatter,
+            "events: {} in {seconds:.2}s ({:.2} events/s)",
+            self.events,
+            rate(self.events)
+        )?;
+        writeln!(
+            formatter,
+            "pushes: {} ({:.0} pushes/s)",
+            self.pushes,
+            rate(self.pushes)
+        )?;
+        writeln!(formatter, "push latency: {}", self.push_latency)?;
+        writeln!(formatter, "event latency: {}", self.event_latency)
+    }
+}
+
+struct MockBark {
+    delay: Duration,
+    arrivals: Mutex<Vec<Instant>>,
+    delivered: watch::Sender<usize>,
+}
+
+/// 用 `options.subscriptions` 个位于震中附近的合成订阅填充 `path` 处的新数据库，
+/// 再依次提交 `options.events` 个地震报告，经完整的事件、匹配和推送流水线发往本地
+/// 模拟 Bark 服务器，统计吞吐量和延迟分位数。
+///
+/// 事件逐个提交：上一个事件的全部推送到达后才提交下一个，因此延迟反映单次大规模
+/// 扇出的表现，而不是事件之间的排队。
+pub fn run_load_benchmark(
+    path: impl AsRef<std::path::Path>,
+    options: &LoadBenchmarkOptions,
+) -> Result<LoadBenchmarkReport> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(load_benchmark(path.as_ref(), options))
+}
+
+async fn load_benchmark(
+    path: &std::path::Path,
+    options: &LoadBenchmarkOptions,
+) -> Result<LoadBenchmarkReport> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    let (delivered, mut delivered_receiver) = watch::channel(0_usize);
+    let mock = Arc::new(MockBark {
+        delay: options.push_delay,
+        arrivals: Mutex::new(Vec::with_capacity(options.subscriptions)),
+        delivered,
+    });
+    let app = axum::Router::new()
+        .route(
+            "/push",
+            axum::routing::post(
+                |axum::extract::State(mock): axum::extract::State<Arc<MockBark>>| async move {
+                    if !mock.delay.is_zero() {
+                        tokio::time::sleep(mock.delay).await;
+                    }
+                    mock.arrivals
+                        .lock()
+                        .unwrap_or_else(|error| error.into_inner())
+                        .push(Instant::now());
+                    mock.delivered.send_modify(|count| *count += 1);
+                    axum::Json(serde_json::json!({ "code": 200 }))
+                },
+            ),
+        )
+        .with_state(Arc::clone(&mock));
+    let server = tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let base_url = format!("http://{address}");
+    let notifier = BarkNotifier::new(
+        vec![base_url.clone()],
+        options.max_concurrent,
+        options.max_concurrent,
+        BarkPushConfig::new(None, 10, "benchmark".to_string(), false),
+    )?;
+    let storage = Storage::open(path)?;
+    let import_started = Instant::now();
+    let subscriptions = options.subscriptions;
+    let import_storage = storage.clone();
+    tokio::task::spawn_blocking(move || {
+        import_load_subscriptions(&import_storage, &base_url, subscriptions)
+    })
+    .await??;
+    let import = import_started.elapsed();
+
+    let links = NotificationLinkService::for_test(&storage);
+    let event_runtime = EventRuntime::for_benchmark(storage, notifier, links)?;
+    let pipeline = {
+        let event_runtime = event_runtime.clone();
+        tokio::spawn(async move { event_runtime.run().await })
+    };
+
+    let mut push_latency = Vec::with_capacity(subscriptions.saturating_mul(options.events));
+    let mut event_latency = Vec::with_capacity(options.events);
+    let started = Instant::now();
+    let result = async {
+        for sequence in 0..options.events {
+            let expected = subscriptions.saturating_mul(sequence + 1);
+            let mut event = benchmark_event();
+            event.event_id = format!("load-benchmark-{sequence}");
+            let submitted = Instant::now();
+            anyhow::ensure!(
+                event_runtime.submit_nonblocking(event).await,
+                "pipeline rejected benchmark event {sequence}"
+            );
+            let waited = tokio::time::timeout(
+                options.event_timeout,
+                delivered_receiver.wait_for(|count| *count >= expected),
+            )
+            .await
+            .map(|changed| changed.map(|_count| ()));
+            let arrivals = std::mem::take(
+                &mut *mock
+                    .arrivals
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner()),
+            );
+            match waited {
+                Ok(changed) => changed?,
+                Err(_elapsed) => anyhow::bail!(
+                    "benchmark event {sequence} delivered {} of {subscriptions} pushes within {:?}",
+                    arrivals.len(),
+                    options.event_timeout
+                ),
+            }
+            push_latency.extend(arrivals.iter().map(|arrival| *arrival - submitted));
+            event_latency.push(
+                arrivals
+                    .last()
+                    .map_or(Duration::ZERO, |last| *last - submitted),
+            );
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    let elapsed = started.elapsed();
+    event_runtime.close().await;
+    pipeline.abort();
+    server.abort();
+    result?;
+
+    Ok(LoadBenchmarkReport {
+        subscriptions,
+        events: options.events,
+        pushes: push_latency.len(),
+        import,
+        elapsed,
+        push_latency: LatencySummary::from_samples(push_latency),
+        event_latency: LatencySummary::from_samples(event_latency),
+    })
+}
+
+fn import_load_subscriptions(storage: &Storage, base_url: &str, count: usize) -> Result<()> {
+    let mut batch = Vec::with_capacity(count.min(5_000));
+    for index in 0..count {
+        batch.push(Subscription::new(
+            NotificationDestination::Bark {
+                base_url: base_url.to_string(),
+                device_key: format!("load{index:016}"),
+            },
+            vec![MonitoringTarget {
+                label: "benchmark".to_string(),
+                point: Some(GeoPoint {
+                    latitude: 39.9 + (index % 100) as f64 / 1_000.0,
+                    longitude: 116.4 + (index / 100 % 100) as f64 / 1_000.0,
+                }),
+                region: AdministrativeRegion::default(),
+            }],
+            vec![AlertRule::default_for(DisasterCategory::EarthquakeReport)],
+        ));
+        if batch.len() == 5_000 {
+            storage
+                .inner()
+                .import_subscription_batch(std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        storage.inner().import_subscription_batch(batch)?;
+    }
+    Ok(())
+}
+
 fn benchmark_delivery_batch(
     id: u64,
     job: &crate::events::MatchJob,
@@ -327,6 +644,9 @@ fn benchmark_event() -> DisasterEvent {
         final_report: false,
         cancel: false,
         training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
     }
 }
 
@@ -366,3 +686,72 @@ impl DeliveryEncodingBenchmark {
         crate::storage::encode_record(&self.batch)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(values: &[&str]) -> impl Iterator<Item = String> {
+        values
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn bench_arguments_accept_both_forms_and_reject_bad_values() -> Result<()> {
+        let options = LoadBenchmarkOptions::from_args(arguments(&[
+            "--subscriptions",
+            "20",
+            "--events=3",
+            "--push-delay-ms=5",
+        ]))?;
+        anyhow::ensure!(options.subscriptions == 20 && options.events == 3);
+        anyhow::ensure!(options.push_delay == Duration::from_millis(5));
+        anyhow::ensure!(options.max_concurrent == LoadBenchmarkOptions::default().max_concurrent);
+
+        for invalid in [
+            &["--events"][..],
+            &["--events", "-1"],
+            &["--events", "0"],
+            &["--event-timeout-seconds=0"],
+            &["--threads", "4"],
+        ] {
+            anyhow::ensure!(
+                LoadBenchmarkOptions::from_args(arguments(invalid)).is_err(),
+                "{invalid:?} should be rejected"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn latency_percentiles_round_up_to_a_sample() -> Result<()> {
+        let summary =
+            LatencySummary::from_samples((1..=10).rev().map(Duration::from_millis).collect());
+        anyhow::ensure!(summary.p50 == Duration::from_millis(6));
+        anyhow::ensure!(summary.p90 == Duration::from_millis(10));
+        anyhow::ensure!(summary.max == Duration::from_millis(10));
+        anyhow::ensure!(LatencySummary::from_samples(Vec::new()).p99 == Duration::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn load_benchmark_delivers_every_push_through_the_pipeline() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let report = run_load_benchmark(
+            directory.path().join("bench"),
+            &LoadBenchmarkOptions {
+                subscriptions: 5,
+                events: 2,
+                max_concurrent: 4,
+                event_timeout: Duration::from_secs(30),
+                ..LoadBenchmarkOptions::default()
+            },
+        )?;
+        anyhow::ensure!(report.pushes == 10, "{report}");
+        anyhow::ensure!(report.event_latency.max >= report.event_latency.p50);
+        Ok(())
+    }
+}
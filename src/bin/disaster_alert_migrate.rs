@@ -535,10 +535,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "home".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 35.0,
                     longitude: 105.0,
-                },
+                }),
                 region: Default::default(),
             }],
             vec![AlertRule::default_for(DisasterCategory::EarthquakeReport)],
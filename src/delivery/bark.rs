@@ -1,15 +1,14 @@
-use crate::delivery::message::{AlertTiming, format_disaster_alert};
-use crate::models::{DisasterEvent, MonitoringTarget, Subscription, mask_device_key};
+use crate::delivery::message::{AlertTiming, DisasterAlertContent, MessageBudget, PreparedAlert};
+use crate::models::{MonitoringTarget, Subscription, mask_device_key};
+use crate::upstream_tls::UpstreamTls;
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 const MAX_RESPONSE_BYTES: usize = 16 * 1024;
-const MAX_TITLE_CHARS: usize = 180;
-const MAX_SUBTITLE_CHARS: usize = 180;
-const MAX_BODY_CHARS: usize = 4_000;
 const MAX_BARK_PAYLOAD_BYTES: usize = 3_800;
 
 #[derive(Debug)]
@@ -54,6 +53,7 @@ pub(crate) struct BarkPushConfig {
     volume: u8,
     group: String,
     call: bool,
+    budget: MessageBudget,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -79,7 +79,7 @@ impl<'a> AlertRecipient<'a> {
         }
     }
 
-    pub(crate) fn to_countdown_recipient(&self) -> CountdownRecipient {
+    pub(crate) fn to_countdown_recipient(self) -> CountdownRecipient {
         CountdownRecipient {
             bark_url: self.bark_url.to_string(),
             device_key: self.device_key.to_string(),
@@ -96,6 +96,7 @@ struct BarkMessage<'a> {
     subtitle: &'a str,
     body: &'a str,
     detail_url: Option<&'a str>,
+    icon: Option<&'a str>,
     use_alert_sound: bool,
 }
 
@@ -103,71 +104,142 @@ struct BarkMessage<'a> {
 #[derive(Clone)]
 pub(crate) struct BarkNotifier {
     allowed_urls: Arc<Vec<String>>,
-    client: reqwest::Client,
+    servers: Arc<HashMap<String, BarkServer>>,
     push_config: BarkPushConfig,
     concurrency: Arc<Semaphore>,
 }
 
+/// 每个 Bark 服务器独立的连接池和并发上限，避免响应缓慢的自建服务器占满空闲连接
+/// 和全部推送名额，拖慢发往其他服务器的推送。
+struct BarkServer {
+    client: reqwest::Client,
+    in_flight: Arc<Semaphore>,
+}
+
 pub(crate) struct BarkPermit {
     _permit: OwnedSemaphorePermit,
 }
 
 impl BarkNotifier {
+    #[cfg(any(test, feature = "benchmarks"))]
     pub(crate) fn new(
         allowed_urls: Vec<String>,
         pool_size: usize,
         max_concurrent: usize,
         push_config: BarkPushConfig,
+    ) -> Result<Self> {
+        Self::with_http3_servers(
+            allowed_urls,
+            pool_size,
+            max_concurrent,
+            push_config,
+            &[],
+            None,
+        )
+    }
+
+    /// 与 [`Self::new`] 相同，但 `http3_urls` 中的服务器直接以 HTTP/3（QUIC）连接，
+    /// 省去地震触发大量推送时 TCP 与 TLS 分别握手的往返；`upstream_tls` 为自定义 CA
+    /// 和公钥固定设置，只用于 HTTP/1.1 和 HTTP/2 连接。
+    pub(crate) fn with_http3_servers(
+        allowed_urls: Vec<String>,
+        pool_size: usize,
+        max_concurrent: usize,
+        push_config: BarkPushConfig,
+        http3_urls: &[String],
+        upstream_tls: Option<&UpstreamTls>,
     ) -> Result<Self> {
         push_config.validate()?;
         anyhow::ensure!(
             !allowed_urls.is_empty(),
             "Bark URL allowlist cannot be empty"
         );
-        let client = reqwest::Client::builder()
-            .user_agent("DisasterAlert/1.0")
-            .timeout(Duration::from_secs(3))
-            .connect_timeout(Duration::from_secs(3))
-            .pool_max_idle_per_host(pool_size)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60))
-            .http2_adaptive_window(true)
-            .http2_keep_alive_interval(Duration::from_secs(30))
-            .http2_keep_alive_timeout(Duration::from_secs(10))
-            .redirect(reqwest::redirect::Policy::none())
-            .build()?;
+        let max_concurrent = max_concurrent.max(1);
+        let per_server_concurrency = per_server_concurrency(max_concurrent, allowed_urls.len());
+        let tls = upstream_tls
+            .map(|tls| tls.client_config(&[b"h2", b"http/1.1"]))
+            .transpose()?;
+        let servers = allowed_urls
+            .iter()
+            .map(|url| {
+                let builder = reqwest::Client::builder();
+                let builder = if http3_urls.contains(url) {
+                    prefer_http3(builder)?
+                } else if let Some(tls) = &tls {
+                    builder.use_preconfigured_tls(rustls::ClientConfig::clone(tls))
+                } else {
+                    builder
+                };
+                let client = builder
+                    .user_agent("DisasterAlert/1.0")
+                    .timeout(Duration::from_secs(3))
+                    .connect_timeout(Duration::from_secs(3))
+                    .pool_max_idle_per_host(pool_size)
+                    .pool_idle_timeout(Duration::from_secs(90))
+                    .tcp_keepalive(Duration::from_secs(60))
+                    .http2_adaptive_window(true)
+                    .http2_keep_alive_interval(Duration::from_secs(30))
+                    .http2_keep_alive_timeout(Duration::from_secs(10))
+                    .redirect(reqwest::redirect::Policy::none())
+                    .build()?;
+                let server = BarkServer {
+                    client,
+                    in_flight: Arc::new(Semaphore::new(per_server_concurrency)),
+                };
+                Ok((url.clone(), server))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
 
         tracing::info!(
             event = "bark.initialized",
             allowed_url_count = allowed_urls.len(),
+            http3_url_count = http3_urls.len(),
             pool_size,
+            per_server_concurrency,
             "bark.initialized"
         );
         Ok(Self {
             allowed_urls: Arc::new(allowed_urls),
-            client,
+            servers: Arc::new(servers),
             push_config,
-            concurrency: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            concurrency: Arc::new(Semaphore::new(max_concurrent)),
         })
     }
 
     pub(crate) fn allows_bark_url(&self, bark_url: &str) -> bool {
-        self.allowed_urls.iter().any(|allowed| allowed == bark_url)
+        self.servers.contains_key(bark_url)
     }
 
     pub(crate) fn allowed_bark_urls(&self) -> Vec<String> {
         self.allowed_urls.as_ref().clone()
     }
 
+    pub(crate) fn available_delivery_permits(&self) -> usize {
+        self.concurrency.available_permits()
+    }
+
+    /// 按本实例的长度上限渲染一条灾害通知，与实际推送的内容一致，供预览接口复用。
+    pub(crate) fn render_alert(
+        &self,
+        alert: &PreparedAlert,
+        target: &MonitoringTarget,
+        timing: Option<&AlertTiming>,
+        now_ms: i64,
+    ) -> DisasterAlertContent {
+        alert
+            .render(target, timing, now_ms)
+            .fit(self.push_config.budget)
+    }
+
     pub(crate) async fn send_disaster_alert(
         &self,
         recipient: &AlertRecipient<'_>,
         level: &str,
-        event: &DisasterEvent,
+        alert: &PreparedAlert,
         timing: Option<&AlertTiming>,
         detail_url: &str,
     ) -> std::result::Result<(), BarkDeliveryError> {
-        self.send_disaster_alert_inner(recipient, level, event, timing, detail_url, true)
+        self.send_disaster_alert_inner(recipient, level, alert, timing, detail_url, true)
             .await
     }
 
@@ -175,23 +247,21 @@ impl BarkNotifier {
         &self,
         recipient: &AlertRecipient<'_>,
         level: &str,
-        event: &DisasterEvent,
+        alert: &PreparedAlert,
         timing: Option<&AlertTiming>,
         detail_url: &str,
         use_alert_sound: bool,
     ) -> std::result::Result<(), BarkDeliveryError> {
-        let content = format_disaster_alert(event, recipient.target, timing, current_epoch_ms());
-        let title = truncate_chars(&content.title, MAX_TITLE_CHARS);
-        let subtitle = truncate_chars(&content.subtitle, MAX_SUBTITLE_CHARS);
-        let body = truncate_chars(&content.body, MAX_BODY_CHARS);
+        let content = self.render_alert(alert, recipient.target, timing, current_epoch_ms());
         self.send_notification(BarkMessage {
             bark_url: recipient.bark_url,
             device_key: recipient.device_key,
             level,
-            title: &title,
-            subtitle: &subtitle,
-            body: &body,
+            title: &content.title,
+            subtitle: &content.subtitle,
+            body: &content.body,
             detail_url: Some(detail_url),
+            icon: alert.icon(),
             use_alert_sound,
         })
         .await
@@ -200,23 +270,20 @@ impl BarkNotifier {
     pub(crate) async fn send_disaster_countdown(
         &self,
         recipient: &CountdownRecipient,
-        event: &DisasterEvent,
+        alert: &PreparedAlert,
         timing: &AlertTiming,
         detail_url: &str,
     ) -> std::result::Result<(), BarkDeliveryError> {
-        let content =
-            format_disaster_alert(event, &recipient.target, Some(timing), current_epoch_ms());
-        let title = truncate_chars(&content.title, MAX_TITLE_CHARS);
-        let subtitle = truncate_chars(&content.subtitle, MAX_SUBTITLE_CHARS);
-        let body = truncate_chars(&content.body, MAX_BODY_CHARS);
+        let content = self.render_alert(alert, &recipient.target, Some(timing), current_epoch_ms());
         self.send_notification(BarkMessage {
             bark_url: &recipient.bark_url,
             device_key: &recipient.device_key,
             level: "passive",
-            title: &title,
-            subtitle: &subtitle,
-            body: &body,
+            title: &content.title,
+            subtitle: &content.subtitle,
+            body: &content.body,
             detail_url: Some(detail_url),
+            icon: alert.icon(),
             use_alert_sound: false,
         })
         .await
@@ -263,6 +330,7 @@ impl BarkNotifier {
                 subtitle: &subtitle,
                 body: &body,
                 detail_url: None,
+                icon: None,
                 use_alert_sound: false,
             },
             Some(permit),
@@ -270,6 +338,67 @@ impl BarkNotifier {
         .await
     }
 
+    /// 订阅恢复后告知设备本身，非本人操作时可以及时重新取消。
+    pub(crate) async fn send_subscription_restored(
+        &self,
+        subscription: &Subscription,
+    ) -> std::result::Result<(), BarkDeliveryError> {
+        self.send_notification(BarkMessage {
+            bark_url: subscription.bark_base_url(),
+            device_key: subscription.device_key(),
+            level: "active",
+            title: "订阅已恢复",
+            subtitle: "",
+            body: "此设备的灾害预警订阅已恢复；如非本人操作，请重新取消订阅",
+            detail_url: None,
+            icon: None,
+            use_alert_sound: false,
+        })
+        .await
+    }
+
+    /// 运维告警发往运维人员的设备，使用 timeSensitive 级别以穿透专注模式。
+    pub(crate) async fn send_operator_alert(
+        &self,
+        bark_url: &str,
+        device_key: &str,
+        title: &str,
+        body: &str,
+    ) -> std::result::Result<(), BarkDeliveryError> {
+        self.send_notification(BarkMessage {
+            bark_url,
+            device_key,
+            level: "timeSensitive",
+            title,
+            subtitle: "",
+            body,
+            detail_url: None,
+            icon: None,
+            use_alert_sound: false,
+        })
+        .await
+    }
+
+    /// 数据导出链接只通过该设备自己的 Bark 通道送达，Bark Key 本身不能直接读取订阅内容。
+    pub(crate) async fn send_data_export_link(
+        &self,
+        subscription: &Subscription,
+        export_url: &str,
+    ) -> std::result::Result<(), BarkDeliveryError> {
+        self.send_notification(BarkMessage {
+            bark_url: subscription.bark_base_url(),
+            device_key: subscription.device_key(),
+            level: "active",
+            title: "订阅数据导出",
+            subtitle: "",
+            body: "点击查看本设备保存的订阅和通知记录，链接 15 分钟内有效",
+            detail_url: Some(export_url),
+            icon: None,
+            use_alert_sound: false,
+        })
+        .await
+    }
+
     async fn send_notification(
         &self,
         message: BarkMessage<'_>,
@@ -293,20 +422,35 @@ impl BarkNotifier {
             subtitle: _,
             body: _,
             detail_url: _,
+            icon: _,
             use_alert_sound: _,
         } = message;
-        if !self.allows_bark_url(bark_url) {
+        let Some(server) = self.servers.get(bark_url) else {
             return Err(BarkDeliveryError::permanent(anyhow::anyhow!(
                 "订阅使用的 Bark URL 已被管理员停用，请重新配置"
             )));
-        }
+        };
         let url = format!("{bark_url}/push");
 
+        // 先占用该服务器的名额再占用全局名额，排队等待慢服务器的推送不会占着全局名额。
+        let _server_permit = server
+            .in_flight
+            .acquire()
+            .await
+            .context("Bark server concurrency limiter closed")
+            .map_err(BarkDeliveryError::transient)?;
         let _permit = match permit {
             Some(permit) => permit,
             None => self.acquire_permit().await?,
         };
-        let response = match self.client.post(&url).json(&payload).send().await {
+        let response = match server
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload)
+            .send()
+            .await
+        {
             Ok(response) => response,
             Err(error) => {
                 tracing::error!(
@@ -357,6 +501,25 @@ impl BarkNotifier {
     }
 }
 
+#[cfg(feature = "http3")]
+fn prefer_http3(builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+    Ok(builder.http3_prior_knowledge())
+}
+
+#[cfg(not(feature = "http3"))]
+fn prefer_http3(_builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+    anyhow::bail!("HTTP/3 Bark servers require a build with the `http3` feature")
+}
+
+/// 配置多个 Bark 服务器时，单个服务器最多占用四分之三的推送并发，其余留给其他服务器。
+fn per_server_concurrency(max_concurrent: usize, servers: usize) -> usize {
+    if servers > 1 {
+        (max_concurrent.saturating_mul(3) / 4).max(1)
+    } else {
+        max_concurrent
+    }
+}
+
 fn bark_failure_is_transient(status: reqwest::StatusCode) -> bool {
     status.is_server_error()
         || status == reqwest::StatusCode::REQUEST_TIMEOUT
@@ -383,11 +546,14 @@ fn subscription_confirmation_summary(subscription: &Subscription) -> (String, St
             }
         })
         .collect::<Vec<_>>();
-    let category_names = subscription
-        .alerts
-        .iter()
-        .map(|alert| alert.category().label())
-        .collect::<Vec<_>>();
+    // 同一灾种可能按来源拆成多条规则，摘要中只列一次。
+    let mut category_names = Vec::new();
+    for alert in &subscription.alerts {
+        let label = alert.category().label();
+        if !category_names.contains(&label) {
+            category_names.push(label);
+        }
+    }
     let subtitle = format!(
         "Bark 通知通道正常 · {} 个地点 · {} 类预警",
         target_names.len(),
@@ -427,6 +593,9 @@ fn bark_payload(
     if let Some(detail_url) = message.detail_url {
         payload["url"] = serde_json::json!(detail_url);
     }
+    if let Some(icon) = message.icon {
+        payload["icon"] = serde_json::json!(icon);
+    }
     if level != "passive" && message.use_alert_sound {
         payload["volume"] = serde_json::json!(push_config.volume);
         if push_config.call {
@@ -439,14 +608,16 @@ fn bark_payload(
     payload
 }
 
+/// 返回已序列化的请求体；大小检查用的序列化结果直接作为请求体发送，不再重复序列化。
 fn fitted_bark_payload(
     message: &BarkMessage<'_>,
     push_config: &BarkPushConfig,
     level: &str,
-) -> Result<serde_json::Value> {
+) -> Result<Vec<u8>> {
     let mut payload = bark_payload(message, push_config, level);
-    if serde_json::to_vec(&payload)?.len() <= MAX_BARK_PAYLOAD_BYTES {
-        return Ok(payload);
+    let encoded = serde_json::to_vec(&payload)?;
+    if encoded.len() <= MAX_BARK_PAYLOAD_BYTES {
+        return Ok(encoded);
     }
 
     for (field, value) in [
@@ -455,8 +626,9 @@ fn fitted_bark_payload(
         ("title", message.title),
     ] {
         fit_payload_field(&mut payload, field, value)?;
-        if serde_json::to_vec(&payload)?.len() <= MAX_BARK_PAYLOAD_BYTES {
-            return Ok(payload);
+        let encoded = serde_json::to_vec(&payload)?;
+        if encoded.len() <= MAX_BARK_PAYLOAD_BYTES {
+            return Ok(encoded);
         }
     }
     anyhow::bail!("Bark detail URL leaves no room for a valid push payload")
@@ -509,11 +681,26 @@ impl BarkPushConfig {
             volume,
             group,
             call,
+            budget: MessageBudget::default(),
         }
     }
 
+    /// 替换标题、副标题和正文的长度上限，默认为 [`MessageBudget::BARK_MAX`]。
+    #[must_use]
+    pub(crate) fn with_budget(mut self, budget: MessageBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
     fn validate(&self) -> Result<()> {
         anyhow::ensure!(self.volume <= 10, "BARK_VOLUME must be in 0..=10");
+        let max = MessageBudget::BARK_MAX;
+        anyhow::ensure!(
+            (1..=max.title_chars).contains(&self.budget.title_chars)
+                && (1..=max.subtitle_chars).contains(&self.budget.subtitle_chars)
+                && (1..=max.body_chars).contains(&self.budget.body_chars),
+            "Bark message budget exceeds the supported lengths"
+        );
         Ok(())
     }
 }
@@ -635,17 +822,6 @@ async fn limited_response_text(mut response: reqwest::Response) -> Result<String
     Ok(String::from_utf8_lossy(&body).into_owned())
 }
 
-fn truncate_chars(value: &str, max_chars: usize) -> String {
-    if value.chars().count() <= max_chars {
-        return value.to_string();
-    }
-    value
-        .chars()
-        .take(max_chars.saturating_sub(1))
-        .chain(['…'])
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     use anyhow::Context as _;
@@ -653,8 +829,11 @@ mod tests {
     use super::{
         AlertRecipient, AlertTiming, BarkMessage, BarkNotifier, BarkPushConfig,
         MAX_BARK_PAYLOAD_BYTES, bark_failure_is_transient, bark_payload, classify_bark_response,
-        current_epoch_ms, fitted_bark_payload, normalize_bark_level,
-        subscription_confirmation_summary, truncate_chars, truncate_utf8_bytes_with_ellipsis,
+        current_epoch_ms, fitted_bark_payload, normalize_bark_level, per_server_concurrency,
+        subscription_confirmation_summary, truncate_utf8_bytes_with_ellipsis,
+    };
+    use crate::delivery::message::{
+        MessageBudget, MessageTemplates, PreparedAlert, truncate_chars,
     };
     use crate::models::{
         AlertRule, DisasterCategory, DisasterEvent, GeoPoint, MonitoringTarget,
@@ -673,6 +852,40 @@ mod tests {
         assert_eq!(truncate_utf8_bytes_with_ellipsis("灾害abcdef", 2), "");
     }
 
+    #[test]
+    fn one_server_cannot_take_every_delivery_slot_when_several_are_allowed() {
+        assert_eq!(per_server_concurrency(32, 1), 32);
+        assert_eq!(per_server_concurrency(32, 2), 24);
+        assert_eq!(per_server_concurrency(1, 3), 1);
+    }
+
+    // reqwest 的 HTTP/3 客户端在构建时需要 Tokio 运行时。
+    #[tokio::test]
+    async fn http3_servers_are_built_only_with_the_http3_feature() {
+        let allowed = vec![
+            "https://api.day.app".to_string(),
+            "https://bark.example.com".to_string(),
+        ];
+        let notifier = |http3_urls: &[String]| {
+            BarkNotifier::with_http3_servers(
+                allowed.clone(),
+                2,
+                4,
+                BarkPushConfig::new(None, 10, "灾害预警".to_string(), false),
+                http3_urls,
+                None,
+            )
+        };
+
+        assert!(notifier(&[]).is_ok());
+        assert_eq!(
+            notifier(&allowed[1..]).is_ok(),
+            cfg!(feature = "http3"),
+            "HTTP/3 servers must be rejected without the http3 feature"
+        );
+        assert!(notifier(&["https://other.example.com".to_string()]).is_ok());
+    }
+
     #[test]
     fn only_retryable_http_statuses_are_classified_as_transient() {
         assert!(bark_failure_is_transient(
@@ -718,10 +931,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "东京".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 35.6,
                     longitude: 139.6,
-                },
+                }),
                 region: crate::models::AdministrativeRegion::default(),
             }],
             vec![AlertRule::default_for(DisasterCategory::EarthquakeWarning)],
@@ -746,6 +959,7 @@ mod tests {
             subtitle: "接收测试成功",
             body: "订阅配置正在保存",
             detail_url: None,
+            icon: None,
             use_alert_sound: false,
         };
         let config = BarkPushConfig {
@@ -753,6 +967,7 @@ mod tests {
             volume: 10,
             group: "灾害预警".to_string(),
             call: true,
+            budget: MessageBudget::default(),
         };
         let level = normalize_bark_level(message.level);
 
@@ -774,6 +989,7 @@ mod tests {
             subtitle: "接收测试",
             body: "测试内容",
             detail_url: Some("https://alert.example.com/incidents/test"),
+            icon: Some("https://alert.example.com/jma.png"),
             use_alert_sound: true,
         };
         let config = BarkPushConfig {
@@ -781,6 +997,7 @@ mod tests {
             volume: 10,
             group: "灾害预警".to_string(),
             call: true,
+            budget: MessageBudget::default(),
         };
 
         let payload = bark_payload(&message, &config, normalize_bark_level(message.level));
@@ -790,6 +1007,7 @@ mod tests {
         assert_eq!(payload["volume"], 10);
         assert_eq!(payload["call"], "1");
         assert_eq!(payload["url"], "https://alert.example.com/incidents/test");
+        assert_eq!(payload["icon"], "https://alert.example.com/jma.png");
     }
 
     #[tokio::test]
@@ -819,10 +1037,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "上海家中".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 31.2,
                     longitude: 121.5,
-                },
+                }),
                 region: crate::models::AdministrativeRegion::default(),
             }],
             vec![AlertRule::default_for(DisasterCategory::EarthquakeWarning)],
@@ -847,6 +1065,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         };
         let now_ms = current_epoch_ms();
         let timing = AlertTiming {
@@ -863,11 +1084,12 @@ mod tests {
             BarkPushConfig::new(Some("alarm".to_string()), 10, "灾害预警".to_string(), true),
         )?;
         let recipient = AlertRecipient::new(&subscription, &subscription.targets[0]);
+        let alert = PreparedAlert::new(&event, &MessageTemplates::default());
         notifier
             .send_disaster_alert(
                 &recipient,
                 "critical",
-                &event,
+                &alert,
                 Some(&timing),
                 "https://alerts.example.test/detail",
             )
@@ -875,7 +1097,7 @@ mod tests {
         notifier
             .send_disaster_countdown(
                 &recipient.to_countdown_recipient(),
-                &event,
+                &alert,
                 &timing,
                 "https://alerts.example.test/detail",
             )
@@ -923,6 +1145,7 @@ mod tests {
             subtitle: &subtitle,
             body: &body,
             detail_url: Some(&detail_url),
+            icon: None,
             use_alert_sound: true,
         };
         let config = BarkPushConfig {
@@ -930,12 +1153,14 @@ mod tests {
             volume: 10,
             group: "灾害预警".repeat(20),
             call: true,
+            budget: MessageBudget::default(),
         };
 
-        let payload = fitted_bark_payload(&message, &config, "critical")?;
+        let encoded = fitted_bark_payload(&message, &config, "critical")?;
+        let payload: serde_json::Value = serde_json::from_slice(&encoded)?;
 
         anyhow::ensure!(payload["url"] == detail_url);
-        anyhow::ensure!(serde_json::to_vec(&payload)?.len() <= MAX_BARK_PAYLOAD_BYTES);
+        anyhow::ensure!(encoded.len() <= MAX_BARK_PAYLOAD_BYTES);
         anyhow::ensure!(
             payload["body"]
                 .as_str()
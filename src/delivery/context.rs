@@ -5,6 +5,7 @@ use crate::models::{
     MonitoringTarget, SourceSelection,
 };
 use crate::storage::{FjallStorage, Storage, try_now_millis};
+use crate::utils::intensity::{IntensityModel, IsoseismalRadius, MAX_ISOSEISMAL_RADIUS_KM};
 use anyhow::{Context, Result};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
@@ -13,6 +14,7 @@ use std::sync::Arc;
 use zeroize::Zeroizing;
 
 const SIGNATURE_DOMAIN: &[u8] = b"disaster-alert:notification-context:v1\0";
+const OPEN_SIGNATURE_DOMAIN: &[u8] = b"disaster-alert:notification-open:v1\0";
 const MAX_TOKEN_BYTES: usize = 256;
 const MAX_DETAIL_URL_BYTES: usize = 3_000;
 
@@ -51,6 +53,8 @@ pub(crate) struct NotificationContextInput<'a> {
     pub(crate) interruption_level: &'a str,
     pub(crate) matched_rule: &'a AlertRule,
     pub(crate) issued_at_ms: i64,
+    /// 推送时生效的震度模型，用于随事件保存等震线半径。
+    pub(crate) intensity_model: IntensityModel,
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +69,8 @@ struct NotificationLinkInner {
     signing_key: SigningKey,
     verifying_key: VerifyingKey,
     context_storage: NotificationContextStorage,
+    /// 推送中的链接是否先经过记录打开情况的跳转地址。
+    open_tracking: bool,
 }
 
 #[derive(Clone)]
@@ -214,6 +220,18 @@ pub(crate) struct NotificationEventSnapshot {
     pub(crate) cancel: bool,
     #[serde(rename = "q")]
     pub(crate) training: bool,
+    /// 各震度等级的预计半径，仅含坐标和震级的地震事件有。
+    #[serde(rename = "k", default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) isoseismal_radii: Vec<NotificationIsoseismalSnapshot>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NotificationIsoseismalSnapshot {
+    #[serde(rename = "i")]
+    pub(crate) intensity: u8,
+    #[serde(rename = "r")]
+    pub(crate) radius_km: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -256,12 +274,16 @@ pub(crate) enum NotificationRuleSnapshot {
         sources: NotificationSourcesSnapshot,
         #[serde(rename = "b")]
         intensity_bands: Vec<NotificationIntensityBandSnapshot>,
+        #[serde(rename = "d", default, skip_serializing_if = "Option::is_none")]
+        max_depth_km: Option<f64>,
     },
     EarthquakeReport {
         #[serde(rename = "s")]
         sources: NotificationSourcesSnapshot,
         #[serde(rename = "m")]
         min_magnitude: f64,
+        #[serde(rename = "d", default, skip_serializing_if = "Option::is_none")]
+        max_depth_km: Option<f64>,
     },
     WeatherWarning {
         #[serde(rename = "s")]
@@ -318,6 +340,7 @@ impl NotificationLinkService {
                 signing_key,
                 verifying_key,
                 context_storage: NotificationContextStorage::new(storage.inner()),
+                open_tracking: config.features.open_tracking,
             }),
         })
     }
@@ -332,6 +355,7 @@ impl NotificationLinkService {
                 signing_key,
                 verifying_key,
                 context_storage: NotificationContextStorage::new(storage.inner()),
+                open_tracking: true,
             }),
         }
     }
@@ -362,6 +386,7 @@ impl NotificationLinkService {
             interruption_level,
             matched_rule,
             issued_at_ms: try_now_millis()?,
+            intensity_model: IntensityModel::default(),
         })
     }
 
@@ -384,12 +409,13 @@ impl NotificationLinkService {
             interruption_level,
             matched_rule,
             issued_at_ms,
+            intensity_model,
         } = input;
         let snapshot = NotificationSnapshot {
             schema_version: 1,
             incident_id: incident_id.clone(),
             issued_at_ms,
-            event: NotificationEventSnapshot::from_event(event),
+            event: NotificationEventSnapshot::from_event(event, intensity_model),
             target: NotificationTargetSnapshot::from_target(target),
             timing: timing.map(NotificationTimingSnapshot::from_timing),
             interruption_level: interruption_level.to_string(),
@@ -429,6 +455,62 @@ impl NotificationLinkService {
         self.inner.context_storage.put_prepared(context)
     }
 
+    /// 推送给某个目的地的链接。开启打开统计时在详情链接后附加签名的回执，
+    /// 用户点击后先由服务端记录打开，再跳转到详情页；回执只能由本服务签发，无法伪造他人的打开记录。
+    pub(crate) fn delivery_url(
+        &self,
+        context: &PreparedNotificationContext,
+        destination_id: u64,
+    ) -> Result<String> {
+        if !self.inner.open_tracking {
+            return Ok(context.url.clone());
+        }
+        let signature = self
+            .inner
+            .signing_key
+            .sign(&open_signature_message(&context.context_id, destination_id));
+        let url = format!(
+            "{}/open/{destination_id}.{}",
+            context.url,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        );
+        anyhow::ensure!(
+            url.len() <= MAX_DETAIL_URL_BYTES,
+            "notification detail URL exceeded {MAX_DETAIL_URL_BYTES} bytes"
+        );
+        Ok(url)
+    }
+
+    /// 校验带打开回执的链接，返回通知快照和回执中的目的地编号。
+    pub(crate) fn verify_open(
+        &self,
+        incident_id: &IncidentId,
+        token: &str,
+        receipt: &str,
+    ) -> std::result::Result<(NotificationSnapshot, u64), NotificationVerifyError> {
+        let snapshot = self.verify(incident_id, token)?;
+        let destination_id = (|| -> Result<u64> {
+            anyhow::ensure!(receipt.len() <= MAX_TOKEN_BYTES, "invalid open receipt");
+            let (destination_id, signature) =
+                receipt.split_once('.').context("invalid open receipt")?;
+            let destination_id = destination_id
+                .parse::<u64>()
+                .context("invalid open receipt")?;
+            let context_id = token.split('.').next().context("invalid open receipt")?;
+            let signature = decode_array::<64>(signature, "open receipt signature")?;
+            self.inner
+                .verifying_key
+                .verify_strict(
+                    &open_signature_message(context_id, destination_id),
+                    &Signature::from_bytes(&signature),
+                )
+                .context("invalid open receipt signature")?;
+            Ok(destination_id)
+        })()
+        .map_err(NotificationVerifyError::Invalid)?;
+        Ok((snapshot, destination_id))
+    }
+
     pub(crate) fn verify(
         &self,
         incident_id: &IncidentId,
@@ -479,7 +561,7 @@ fn context_id(snapshot: &NotificationSnapshot) -> Result<String> {
 }
 
 impl NotificationEventSnapshot {
-    fn from_event(event: &DisasterEvent) -> Self {
+    fn from_event(event: &DisasterEvent, intensity_model: IntensityModel) -> Self {
         Self {
             category: event.category,
             source: truncate_bytes(&event.source, 128),
@@ -504,16 +586,43 @@ impl NotificationEventSnapshot {
             final_report: event.final_report,
             cancel: event.cancel,
             training: event.training,
+            isoseismal_radii: isoseismal_radii(event, intensity_model)
+                .into_iter()
+                .map(|radius| NotificationIsoseismalSnapshot {
+                    intensity: radius.intensity,
+                    radius_km: radius.radius_km,
+                })
+                .collect(),
         }
     }
 }
 
+/// 地震事件各震度等级的预计半径；取消报、缺坐标或震级时为空。
+pub(crate) fn isoseismal_radii(
+    event: &DisasterEvent,
+    intensity_model: IntensityModel,
+) -> Vec<IsoseismalRadius> {
+    if event.cancel
+        || !matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        )
+        || event.latitude.zip(event.longitude).is_none()
+    {
+        return Vec::new();
+    }
+    event.magnitude.map_or_else(Vec::new, |magnitude| {
+        intensity_model.isoseismal_radii(magnitude, event.depth_km.unwrap_or_default())
+    })
+}
+
 impl NotificationTargetSnapshot {
     fn from_target(target: &MonitoringTarget) -> Self {
+        let anchor = target.anchor();
         Self {
             label: truncate_bytes(&target.label, 80),
-            latitude: target.point.latitude,
-            longitude: target.point.longitude,
+            latitude: anchor.map_or(0.0, |point| point.latitude),
+            longitude: anchor.map_or(0.0, |point| point.longitude),
             province: truncate_bytes(&target.region.province, 80),
             city: truncate_bytes(&target.region.city, 80),
             district: truncate_bytes(&target.region.district, 80),
@@ -527,19 +636,24 @@ impl NotificationRuleSnapshot {
             AlertRule::EarthquakeWarning {
                 sources,
                 estimated_intensity_bands,
+                max_depth_km,
+                ..
             } => Self::EarthquakeWarning {
                 sources: NotificationSourcesSnapshot::from_sources(sources),
                 intensity_bands: estimated_intensity_bands
                     .iter()
                     .map(NotificationIntensityBandSnapshot::from_band)
                     .collect(),
+                max_depth_km: *max_depth_km,
             },
             AlertRule::EarthquakeReport {
                 sources,
                 min_magnitude,
+                max_depth_km,
             } => Self::EarthquakeReport {
                 sources: NotificationSourcesSnapshot::from_sources(sources),
                 min_magnitude: *min_magnitude,
+                max_depth_km: *max_depth_km,
             },
             AlertRule::WeatherWarning {
                 sources,
@@ -637,6 +751,11 @@ fn validate_snapshot(snapshot: &NotificationSnapshot) -> Result<()> {
                 .radius_km
                 .is_none_or(|radius| radius.is_finite() && radius >= 0.0)
             && snapshot.event.latitude.is_some() == snapshot.event.longitude.is_some()
+            && snapshot.event.isoseismal_radii.len() <= 7
+            && snapshot.event.isoseismal_radii.iter().all(|radius| {
+                (1..=7).contains(&radius.intensity)
+                    && (0.0..=MAX_ISOSEISMAL_RADIUS_KM).contains(&radius.radius_km)
+            })
             && snapshot
                 .event
                 .latitude
@@ -690,6 +809,7 @@ fn validate_rule(category: DisasterCategory, rule: &NotificationRuleSnapshot) ->
         NotificationRuleSnapshot::EarthquakeWarning {
             sources,
             intensity_bands,
+            max_depth_km,
         } => {
             anyhow::ensure!(
                 !intensity_bands.is_empty()
@@ -699,16 +819,19 @@ fn validate_rule(category: DisasterCategory, rule: &NotificationRuleSnapshot) ->
                         .all(|band| band.min <= band.max && band.max <= 7),
                 "invalid intensity rule"
             );
+            validate_max_depth(*max_depth_km)?;
             (DisasterCategory::EarthquakeWarning, sources)
         }
         NotificationRuleSnapshot::EarthquakeReport {
             sources,
             min_magnitude,
+            max_depth_km,
         } => {
             anyhow::ensure!(
                 min_magnitude.is_finite() && (0.0..=10.0).contains(min_magnitude),
                 "invalid magnitude rule"
             );
+            validate_max_depth(*max_depth_km)?;
             (DisasterCategory::EarthquakeReport, sources)
         }
         NotificationRuleSnapshot::WeatherWarning {
@@ -758,6 +881,14 @@ fn validate_rule(category: DisasterCategory, rule: &NotificationRuleSnapshot) ->
     Ok(())
 }
 
+fn validate_max_depth(max_depth_km: Option<f64>) -> Result<()> {
+    anyhow::ensure!(
+        max_depth_km.is_none_or(|depth| depth.is_finite() && (0.0..=700.0).contains(&depth)),
+        "invalid depth rule"
+    );
+    Ok(())
+}
+
 fn truncate_bytes(value: &str, max_bytes: usize) -> String {
     if value.len() <= max_bytes {
         return value.to_string();
@@ -776,6 +907,14 @@ fn signature_message(context_id: &str) -> Vec<u8> {
     message
 }
 
+fn open_signature_message(context_id: &str, destination_id: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(OPEN_SIGNATURE_DOMAIN.len() + context_id.len() + 8);
+    message.extend_from_slice(OPEN_SIGNATURE_DOMAIN);
+    message.extend_from_slice(context_id.as_bytes());
+    message.extend_from_slice(&destination_id.to_be_bytes());
+    message
+}
+
 fn decode_array<const N: usize>(value: &str, name: &str) -> Result<[u8; N]> {
     let decoded = URL_SAFE_NO_PAD
         .decode(value.trim())
@@ -820,6 +959,7 @@ mod tests {
                     signing_key,
                     verifying_key,
                     context_storage: NotificationContextStorage::new(storage.inner()),
+                    open_tracking: true,
                 }),
             },
             _directory: directory,
@@ -847,16 +987,19 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
     fn target() -> MonitoringTarget {
         MonitoringTarget {
             label: "住所".to_string(),
-            point: GeoPoint {
+            point: Some(GeoPoint {
                 latitude: 35.6,
                 longitude: 139.6,
-            },
+            }),
             region: AdministrativeRegion {
                 province: "东京都".to_string(),
                 city: "东京".to_string(),
@@ -889,6 +1032,9 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
         )
     }
@@ -933,11 +1079,14 @@ mod tests {
         let incident = IncidentId::derive("legacy:event");
         let mut event = event();
         event.radius_km = None;
+        let mut event_snapshot =
+            NotificationEventSnapshot::from_event(&event, IntensityModel::default());
+        event_snapshot.isoseismal_radii.clear();
         let snapshot = NotificationSnapshot {
             schema_version: 1,
             incident_id: incident,
             issued_at_ms: 123,
-            event: NotificationEventSnapshot::from_event(&event),
+            event: event_snapshot,
             target: NotificationTargetSnapshot::from_target(&target()),
             timing: Some(NotificationTimingSnapshot::from_timing(&timing())),
             interruption_level: "critical".to_string(),
@@ -948,10 +1097,14 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             }),
         };
         let legacy_json = serde_json::to_vec(&snapshot)?;
         anyhow::ensure!(!String::from_utf8_lossy(&legacy_json).contains("\"o\""));
+        anyhow::ensure!(serde_json::to_value(&snapshot.event)?.get("k").is_none());
 
         let decoded: NotificationSnapshot = serde_json::from_slice(&legacy_json)?;
         anyhow::ensure!(decoded.event.radius_km.is_none());
@@ -960,6 +1113,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn earthquake_snapshots_carry_isoseismal_radii() -> Result<()> {
+        let mut event = event();
+        let snapshot = NotificationEventSnapshot::from_event(&event, IntensityModel::default());
+        let expected = IntensityModel::default()
+            .isoseismal_radii(event.magnitude.context("missing magnitude")?, 12.0);
+        anyhow::ensure!(!snapshot.isoseismal_radii.is_empty());
+        anyhow::ensure!(snapshot.isoseismal_radii.len() == expected.len());
+
+        event.cancel = true;
+        let cancelled = NotificationEventSnapshot::from_event(&event, IntensityModel::default());
+        anyhow::ensure!(cancelled.isoseismal_radii.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn retries_reuse_the_same_context_url() -> Result<()> {
         let service = service([14; 32])?.service;
@@ -978,8 +1146,12 @@ mod tests {
                         max: 7,
                         interruption_level: InterruptionLevel::Critical,
                     }],
+                    max_depth_km: None,
+                    final_followup: false,
+                    official_warnings_only: false,
                 },
                 issued_at_ms: 123,
+                intensity_model: IntensityModel::default(),
             })
         };
         anyhow::ensure!(create()? == create()?);
@@ -1015,8 +1187,12 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
             issued_at_ms: 123,
+            intensity_model: IntensityModel::default(),
         })?;
         let wrong_context_id = "AAAAAAAAAAAAAAAAAAAAAA";
         anyhow::ensure!(wrong_context_id != prepared.context_id);
@@ -1052,6 +1228,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn open_receipts_are_bound_to_context_and_destination() -> Result<()> {
+        let service = service([12; 32])?.service;
+        let incident = IncidentId::derive("event-a");
+        let prepared = service.prepare_url_for(NotificationContextInput {
+            incident_id: &incident,
+            event: &event(),
+            target: &target(),
+            timing: Some(&timing()),
+            interruption_level: "critical",
+            matched_rule: &AlertRule::EarthquakeWarning {
+                sources: SourceSelection::All,
+                estimated_intensity_bands: vec![IntensityBand {
+                    min: 3,
+                    max: 7,
+                    interruption_level: InterruptionLevel::Critical,
+                }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
+            },
+            issued_at_ms: 123,
+            intensity_model: IntensityModel::default(),
+        })?;
+        service.persist_prepared(&prepared)?;
+        let url = service.delivery_url(&prepared, 42)?;
+        let (detail, receipt) = url.split_once("/open/").context("missing open receipt")?;
+        anyhow::ensure!(detail == prepared.url);
+        let token = token(detail);
+        let (snapshot, destination_id) = service.verify_open(&incident, token, receipt)?;
+        anyhow::ensure!(snapshot.incident_id == incident && destination_id == 42);
+
+        let forged = receipt.replacen("42.", "43.", 1);
+        anyhow::ensure!(service.verify_open(&incident, token, &forged).is_err());
+        anyhow::ensure!(
+            service
+                .verify_open(&incident, token, &mutate_part(receipt, 1))
+                .is_err()
+        );
+
+        let untracked = NotificationLinkService {
+            inner: Arc::new(NotificationLinkInner {
+                base_url: service.inner.base_url.clone(),
+                signing_key: service.inner.signing_key.clone(),
+                verifying_key: service.inner.verifying_key,
+                context_storage: service.inner.context_storage.clone(),
+                open_tracking: false,
+            }),
+        };
+        anyhow::ensure!(untracked.delivery_url(&prepared, 42)? == prepared.url);
+        Ok(())
+    }
+
     #[test]
     fn link_signed_by_a_different_key_is_rejected() -> Result<()> {
         let old = service([10; 32])?.service;
@@ -1063,6 +1292,7 @@ mod tests {
                 signing_key: current_signing_key,
                 verifying_key: current_verifying_key,
                 context_storage: old.inner.context_storage.clone(),
+                open_tracking: true,
             }),
         };
         let incident = IncidentId::derive("event-a");
@@ -1099,6 +1329,9 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
         )?;
 
@@ -1143,6 +1376,9 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
         )?;
         let snapshot = service.verify(&incident, token(&url))?;
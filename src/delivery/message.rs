@@ -1,7 +1,12 @@
-use crate::models::{DisasterCategory, DisasterEvent, MonitoringTarget};
+use crate::models::{
+    DisasterCategory, DisasterEvent, MonitoringTarget, display_event_time, parse_event_epoch_ms,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 const MAX_INLINE_REGIONS: usize = 20;
+/// S 波到达监测点超过该时长后才送达的预警，改用速报措辞，避免震后收到“预警”。
+const LATE_WARNING_GRACE_MS: i64 = 10_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -20,22 +25,532 @@ pub(crate) struct DisasterAlertContent {
     pub(crate) body: String,
 }
 
+/// 推送渠道对标题、副标题和正文的长度上限，按字符计。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MessageBudget {
+    pub(crate) title_chars: usize,
+    pub(crate) subtitle_chars: usize,
+    pub(crate) body_chars: usize,
+}
+
+impl MessageBudget {
+    /// Bark 允许配置的最大长度，同时也是默认值；整个请求仍受 Bark 负载字节上限约束。
+    pub(crate) const BARK_MAX: Self = Self {
+        title_chars: 180,
+        subtitle_chars: 180,
+        body_chars: 4_000,
+    };
+}
+
+impl Default for MessageBudget {
+    fn default() -> Self {
+        Self::BARK_MAX
+    }
+}
+
+impl DisasterAlertContent {
+    /// 按长度上限裁剪消息：标题和副标题从末尾整段丢弃 ` · ` 片段，正文先缩短地区列表、
+    /// 再从末尾整行丢弃，都放不下时才按字符截断，尽量不把地名或数值截成半截。
+    #[must_use]
+    pub(crate) fn fit(self, budget: MessageBudget) -> Self {
+        Self {
+            title: fit_segments(self.title, " · ", budget.title_chars),
+            subtitle: fit_segments(self.subtitle, " · ", budget.subtitle_chars),
+            body: fit_body(self.body, budget.body_chars),
+        }
+    }
+}
+
+/// 可在配置文件 `[template]` 表中替换的通知文案，修改后随配置热更新生效。
+///
+/// 标题模板中 `{title}` 为灾种标题，`{seconds}` 为 S 波剩余秒数；其余文案不含占位符。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct MessageTemplates {
+    pub(crate) earthquake_warning_title: String,
+    pub(crate) earthquake_report_title: String,
+    pub(crate) weather_warning_title: String,
+    pub(crate) tsunami_title: String,
+    pub(crate) typhoon_title: String,
+    pub(crate) countdown_title: String,
+    pub(crate) arrived_title: String,
+    pub(crate) earthquake_tip: String,
+    pub(crate) weather_warning_tip: String,
+    pub(crate) tsunami_tip: String,
+    pub(crate) typhoon_tip: String,
+    /// 按数据源 ID 配置的标题前缀和推送图标，让收件人一眼看出是哪家机构发布的信息。
+    pub(crate) source_branding: BTreeMap<String, SourceBranding>,
+}
+
+/// 单个数据源的品牌标识；未配置的部分沿用默认外观。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct SourceBranding {
+    /// 加在标题最前面的文字或 emoji，如 `🇯🇵`。
+    pub(crate) title_prefix: Option<String>,
+    /// 推送通知使用的图标 URL。
+    pub(crate) icon: Option<String>,
+}
+
+/// 单条模板的长度上限，按字符计。
+const MAX_TEMPLATE_CHARS: usize = 200;
+
+/// 模板对应的配置项名、允许的占位符和必须出现的占位符。
+pub(crate) struct TemplateSpec {
+    pub(crate) name: &'static str,
+    allowed: &'static [&'static str],
+    required: &'static [&'static str],
+}
+
+impl TemplateSpec {
+    const fn plain(name: &'static str) -> Self {
+        Self {
+            name,
+            allowed: &[],
+            required: &[],
+        }
+    }
+}
+
+/// 与 [`MessageTemplates::values`] 顺序一致；配置项名即配置文件 `[template]` 表中的键加上
+/// `TEMPLATE_` 前缀。
+pub(crate) const TEMPLATE_SPECS: [TemplateSpec; 11] = [
+    TemplateSpec::plain("TEMPLATE_EARTHQUAKE_WARNING_TITLE"),
+    TemplateSpec::plain("TEMPLATE_EARTHQUAKE_REPORT_TITLE"),
+    TemplateSpec::plain("TEMPLATE_WEATHER_WARNING_TITLE"),
+    TemplateSpec::plain("TEMPLATE_TSUNAMI_TITLE"),
+    TemplateSpec::plain("TEMPLATE_TYPHOON_TITLE"),
+    TemplateSpec {
+        name: "TEMPLATE_COUNTDOWN_TITLE",
+        allowed: &["title", "seconds"],
+        required: &["seconds"],
+    },
+    TemplateSpec {
+        name: "TEMPLATE_ARRIVED_TITLE",
+        allowed: &["title"],
+        required: &[],
+    },
+    TemplateSpec::plain("TEMPLATE_EARTHQUAKE_TIP"),
+    TemplateSpec::plain("TEMPLATE_WEATHER_WARNING_TIP"),
+    TemplateSpec::plain("TEMPLATE_TSUNAMI_TIP"),
+    TemplateSpec::plain("TEMPLATE_TYPHOON_TIP"),
+];
+
+impl Default for MessageTemplates {
+    fn default() -> Self {
+        Self {
+            earthquake_warning_title: "地震播报".to_string(),
+            earthquake_report_title: "地震速报".to_string(),
+            weather_warning_title: "气象预警".to_string(),
+            tsunami_title: "海啸预警".to_string(),
+            typhoon_title: "台风动态".to_string(),
+            countdown_title: "{title} {seconds}秒后到达".to_string(),
+            arrived_title: "{title} 震波已到达".to_string(),
+            earthquake_tip: "安全提示：请保持冷静，远离玻璃、悬挂物和不稳固家具。".to_string(),
+            weather_warning_tip: "防范提示：请关注临近预报，合理调整出行和户外活动。".to_string(),
+            tsunami_tip: "避险提示：沿海及河口区域人员请远离岸线，按官方指引向高处转移。"
+                .to_string(),
+            typhoon_tip: "防范提示：请加固门窗和室外物品，避免前往沿海、山区及低洼地带。"
+                .to_string(),
+            source_branding: BTreeMap::new(),
+        }
+    }
+}
+
+impl MessageTemplates {
+    pub(crate) fn values_mut(&mut self) -> [&mut String; 11] {
+        [
+            &mut self.earthquake_warning_title,
+            &mut self.earthquake_report_title,
+            &mut self.weather_warning_title,
+            &mut self.tsunami_title,
+            &mut self.typhoon_title,
+            &mut self.countdown_title,
+            &mut self.arrived_title,
+            &mut self.earthquake_tip,
+            &mut self.weather_warning_tip,
+            &mut self.tsunami_tip,
+            &mut self.typhoon_tip,
+        ]
+    }
+
+    fn values(&self) -> [&str; 11] {
+        [
+            &self.earthquake_warning_title,
+            &self.earthquake_report_title,
+            &self.weather_warning_title,
+            &self.tsunami_title,
+            &self.typhoon_title,
+            &self.countdown_title,
+            &self.arrived_title,
+            &self.earthquake_tip,
+            &self.weather_warning_tip,
+            &self.tsunami_tip,
+            &self.typhoon_tip,
+        ]
+    }
+
+    /// 检查全部模板，返回每个问题的说明；为空表示全部有效。
+    pub(crate) fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        for (spec, template) in TEMPLATE_SPECS.iter().zip(self.values()) {
+            let name = spec.name;
+            if template.trim().is_empty() {
+                issues.push(format!("{name} must not be empty"));
+                continue;
+            }
+            if template.chars().count() > MAX_TEMPLATE_CHARS {
+                issues.push(format!(
+                    "{name} must be at most {MAX_TEMPLATE_CHARS} characters"
+                ));
+            }
+            let placeholders = match placeholders(template) {
+                Ok(placeholders) => placeholders,
+                Err(error) => {
+                    issues.push(format!("{name} {error}"));
+                    continue;
+                }
+            };
+            for placeholder in &placeholders {
+                if !spec.allowed.contains(placeholder) {
+                    issues.push(if spec.allowed.is_empty() {
+                        format!("{name} must not contain placeholders, found {{{placeholder}}}")
+                    } else {
+                        format!(
+                            "{name} uses unknown placeholder {{{placeholder}}}; allowed: {}",
+                            spec.allowed
+                                .iter()
+                                .map(|allowed| format!("{{{allowed}}}"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    });
+                }
+            }
+            for required in spec.required {
+                if !placeholders.contains(required) {
+                    issues.push(format!("{name} must contain {{{required}}}"));
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// 列出模板中的占位符；花括号不成对或占位符名为空时报错。
+fn placeholders(template: &str) -> Result<Vec<&str>, &'static str> {
+    let mut found = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("has an unmatched '}'");
+        }
+        let after = &rest[start + 1..];
+        let end = after.find('}').ok_or("has an unmatched '{'")?;
+        let name = &after[..end];
+        if name.is_empty() || name.contains('{') {
+            return Err("has an empty or nested placeholder");
+        }
+        found.push(name);
+        rest = &after[end + 1..];
+    }
+    Ok(found)
+}
+
+fn fill_title(template: &str, title: &str, seconds: i64) -> String {
+    template
+        .replace("{title}", title)
+        .replace("{seconds}", &seconds.to_string())
+}
+
+/// 同一事件发给所有订阅者时共用的消息片段，每个投递批次只渲染一次；逐条推送时只需
+/// 拼接监测点名称和到达时间等与订阅相关的部分。
+#[derive(Debug, Clone)]
+pub(crate) struct PreparedAlert {
+    base_title: String,
+    /// 倒计时和震波已到达的标题模板，以及迟到预警改用的速报标题。
+    countdown_title: String,
+    arrived_title: String,
+    report_title: String,
+    earthquake: bool,
+    warning: bool,
+    cancel: bool,
+    final_report: bool,
+    training: bool,
+    /// 副标题中监测点之前的部分，已用 ` · ` 连接。
+    subtitle_head: String,
+    report_state: Option<&'static str>,
+    /// 正文中监测点之前的行，已用换行连接。
+    body_head: String,
+    /// 正文中监测点和到达时间之后的行，已用换行连接。
+    body_tail: String,
+    /// 数据源配置的推送图标 URL。
+    icon: Option<String>,
+    /// 不随到达时间变化的标题（非地震类消息或已解除），只渲染一次。
+    fixed_title: Option<String>,
+    /// 地震发震时刻（Unix 毫秒），用于在正文中显示距今时长；解除消息和无法解析时为空。
+    origin_ms: Option<i64>,
+}
+
+impl PreparedAlert {
+    pub(crate) fn new(event: &DisasterEvent, templates: &MessageTemplates) -> Self {
+        let mut subtitle_head = Vec::new();
+        let mut body_head = Vec::new();
+        let mut body_tail = Vec::new();
+        let base_title = match event.category {
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport => {
+                let place = earthquake_place(event);
+                if !place.is_empty() {
+                    subtitle_head.push(place.clone());
+                }
+                if let Some(magnitude) = event.magnitude {
+                    subtitle_head.push(format!("M{magnitude:.1}"));
+                }
+                if event.cancel && event.category == DisasterCategory::EarthquakeWarning {
+                    body_head.push(
+                        "预警取消：发布机构已取消此前的预警，可能为误报，无需继续避险。"
+                            .to_string(),
+                    );
+                }
+                if event.training {
+                    body_head.push("演练信息：这是一条模拟预警，请勿恐慌。".to_string());
+                }
+                if !place.is_empty() {
+                    body_head.push(format!("震中位置：{place}"));
+                }
+                if event.assumed_hypocenter {
+                    body_head.push("震源位置暂为推定，实际位置和震级可能有较大偏差。".to_string());
+                }
+                let mut earthquake = Vec::new();
+                if let Some(magnitude) = event.magnitude {
+                    earthquake.push(format!("震级 M{magnitude:.1}"));
+                }
+                if let Some(depth_km) = event.depth_km {
+                    earthquake.push(format!("深度 {depth_km:.0} km"));
+                }
+                if !earthquake.is_empty() {
+                    body_tail.push(format!("地震参数：{}", earthquake.join(" · ")));
+                }
+                if let Some(max_intensity) = &event.max_intensity {
+                    body_tail.push(format!("最大烈度：{max_intensity}"));
+                }
+                if let Some(agency) = agency_label(event) {
+                    body_tail.push(format!("发布机构：{agency} · 第 {} 报", event.report_num));
+                }
+                append_regions(event, "可能影响", &mut body_tail);
+                append_time(event, "发生时间", &mut body_tail);
+                body_tail.push(templates.earthquake_tip.clone());
+                if event.category == DisasterCategory::EarthquakeWarning {
+                    &templates.earthquake_warning_title
+                } else {
+                    &templates.earthquake_report_title
+                }
+            }
+            DisasterCategory::WeatherWarning => {
+                subtitle_head.push(headline(event, "气象部门发布预警"));
+                append_regions(event, "预警区域", &mut body_tail);
+                append_description(event, "预警内容", &mut body_tail);
+                append_time(event, "发布时间", &mut body_tail);
+                body_tail.push(templates.weather_warning_tip.clone());
+                &templates.weather_warning_title
+            }
+            DisasterCategory::Tsunami => {
+                subtitle_head.push(headline(event, "海啸风险信息"));
+                append_regions(event, "影响区域", &mut body_tail);
+                append_description(event, "预警说明", &mut body_tail);
+                let mut earthquake = Vec::new();
+                if let Some(magnitude) = event.magnitude {
+                    earthquake.push(format!("震级 M{magnitude:.1}"));
+                }
+                if let Some(depth_km) = event.depth_km {
+                    earthquake.push(format!("深度 {depth_km:.0} km"));
+                }
+                if !earthquake.is_empty() {
+                    body_tail.push(format!("相关地震：{}", earthquake.join(" · ")));
+                }
+                append_time(event, "更新时间", &mut body_tail);
+                body_tail.push(templates.tsunami_tip.clone());
+                &templates.tsunami_title
+            }
+            DisasterCategory::Typhoon => {
+                subtitle_head.push(headline(event, "台风最新动态"));
+                if let Some((latitude, longitude)) = event.latitude.zip(event.longitude) {
+                    body_tail.push(format!("台风中心：{latitude:.2}°, {longitude:.2}°"));
+                }
+                if let Some(radius_km) = event.radius_km {
+                    body_tail.push(format!("七级风圈：约 {radius_km:.0} km"));
+                }
+                append_regions(event, "可能影响", &mut body_tail);
+                append_description(event, "强度信息", &mut body_tail);
+                append_time(event, "更新时间", &mut body_tail);
+                body_tail.push(templates.typhoon_tip.clone());
+                &templates.typhoon_title
+            }
+        };
+        // 日本气象厅区分紧急地震速报的警报与预报，标题中注明以免把预报当作警报。
+        let base_title = match event.official_warning {
+            Some(true) => format!("{base_title}（警报）"),
+            Some(false) => format!("{base_title}（预报）"),
+            None => base_title.clone(),
+        };
+        let branding = templates.source_branding.get(&event.source);
+        let branded =
+            |title: String| match branding.and_then(|branding| branding.title_prefix.as_deref()) {
+                Some(prefix) => format!("{prefix} {title}"),
+                None => title,
+            };
+        let earthquake = matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        );
+        let mut prepared = Self {
+            base_title: branded(base_title),
+            countdown_title: templates.countdown_title.clone(),
+            arrived_title: templates.arrived_title.clone(),
+            report_title: branded(templates.earthquake_report_title.clone()),
+            earthquake,
+            warning: event.category == DisasterCategory::EarthquakeWarning,
+            cancel: event.cancel,
+            final_report: event.final_report,
+            training: event.training,
+            subtitle_head: subtitle_head.join(" · "),
+            report_state: if event.cancel {
+                Some("解除/取消")
+            } else if event.final_report {
+                Some("最终报告")
+            } else {
+                None
+            },
+            body_head: body_head.join("\n"),
+            body_tail: body_tail.join("\n"),
+            icon: branding.and_then(|branding| branding.icon.clone()),
+            fixed_title: None,
+            origin_ms: if earthquake && !event.cancel {
+                parse_event_epoch_ms(event)
+            } else {
+                None
+            },
+        };
+        if !earthquake || event.cancel {
+            prepared.fixed_title = Some(prepared.title(None, 0));
+        }
+        prepared
+    }
+
+    pub(crate) fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    /// 为一个监测点渲染完整消息；到达时间只用于地震类消息。
+    pub(crate) fn render(
+        &self,
+        target: &MonitoringTarget,
+        timing: Option<&AlertTiming>,
+        now_ms: i64,
+    ) -> DisasterAlertContent {
+        let timing = timing.filter(|_timing| self.earthquake);
+        let target_name = target_name(target);
+        let mut subtitle = Joined::new(" · ", &self.subtitle_head);
+        if let Some(timing) = timing {
+            subtitle.push(&format!("预计烈度 {:.1}", timing.estimated_intensity));
+        }
+        subtitle.push(&format!("监测点 {target_name}"));
+        if let Some(state) = self.report_state {
+            subtitle.push(state);
+        }
+
+        let mut body = Joined::new("\n", &self.body_head);
+        body.push(&format!("监测地点：{target_name}"));
+        if let Some(elapsed) = self
+            .origin_ms
+            .and_then(|origin_ms| elapsed_since_origin(origin_ms, now_ms))
+        {
+            body.push(&format!("发震距今：{elapsed}"));
+        }
+        if let Some(timing) = timing {
+            body.push(&format!(
+                "震波到达：{} · {}",
+                wave_status("P波", timing.p_arrival_at_ms, now_ms),
+                wave_status("S波", timing.s_arrival_at_ms, now_ms)
+            ));
+            body.push(&format!(
+                "距离估算：震中距 {:.0} km · 震源距 {:.0} km",
+                timing.distance_km, timing.hypocentral_km
+            ));
+        }
+        body.push(&self.body_tail);
+
+        DisasterAlertContent {
+            title: self.title(timing, now_ms),
+            subtitle: subtitle.text,
+            body: body.text,
+        }
+    }
+
+    fn title(&self, timing: Option<&AlertTiming>, now_ms: i64) -> String {
+        if let Some(title) = &self.fixed_title {
+            return title.clone();
+        }
+        let base = &self.base_title;
+        let title = if self.cancel {
+            format!("{base}已解除")
+        } else if let Some(timing) = timing {
+            let seconds = remaining_seconds(timing.s_arrival_at_ms, now_ms);
+            if seconds > 0 {
+                fill_title(&self.countdown_title, base, seconds)
+            } else if self.final_report {
+                // 震波过后的终报是确认信息，不再提示到达状态。
+                format!("{base}终报")
+            } else if self.warning
+                && now_ms.saturating_sub(timing.s_arrival_at_ms) > LATE_WARNING_GRACE_MS
+            {
+                format!("{} 震波已过", self.report_title)
+            } else {
+                fill_title(&self.arrived_title, base, 0)
+            }
+        } else if self.final_report {
+            format!("{base}终报")
+        } else {
+            base.to_string()
+        };
+        if self.training {
+            format!("演练 · {title}")
+        } else {
+            title
+        }
+    }
+}
+
+#[cfg(test)]
 pub(crate) fn format_disaster_alert(
     event: &DisasterEvent,
     target: &MonitoringTarget,
     timing: Option<&AlertTiming>,
     now_ms: i64,
 ) -> DisasterAlertContent {
-    match event.category {
-        DisasterCategory::EarthquakeWarning => {
-            format_earthquake(event, target, timing, now_ms, true)
+    PreparedAlert::new(event, &MessageTemplates::default()).render(target, timing, now_ms)
+}
+
+/// 以分隔符拼接非空片段，相当于收集后 `join`，但不为每个片段单独分配。
+struct Joined {
+    text: String,
+    separator: &'static str,
+}
+
+impl Joined {
+    fn new(separator: &'static str, head: &str) -> Self {
+        Self {
+            text: head.to_string(),
+            separator,
+        }
+    }
+
+    fn push(&mut self, part: &str) {
+        if part.is_empty() {
+            return;
         }
-        DisasterCategory::EarthquakeReport => {
-            format_earthquake(event, target, timing, now_ms, false)
+        if !self.text.is_empty() {
+            self.text.push_str(self.separator);
         }
-        DisasterCategory::WeatherWarning => format_weather(event, target),
-        DisasterCategory::Tsunami => format_tsunami(event, target),
-        DisasterCategory::Typhoon => format_typhoon(event, target),
+        self.text.push_str(part);
     }
 }
 
@@ -48,195 +563,6 @@ pub(crate) fn remaining_seconds(arrival_at_ms: i64, now_ms: i64) -> i64 {
     }
 }
 
-fn format_earthquake(
-    event: &DisasterEvent,
-    target: &MonitoringTarget,
-    timing: Option<&AlertTiming>,
-    now_ms: i64,
-    warning: bool,
-) -> DisasterAlertContent {
-    let base_title = if warning {
-        "地震播报"
-    } else {
-        "地震速报"
-    };
-    let title = stateful_title(base_title, event, timing, now_ms);
-    let target_name = target_name(target);
-    let place = earthquake_place(event);
-    let mut subtitle = Vec::new();
-    if !place.is_empty() {
-        subtitle.push(place.clone());
-    }
-    if let Some(magnitude) = event.magnitude {
-        subtitle.push(format!("M{magnitude:.1}"));
-    }
-    if let Some(timing) = timing {
-        subtitle.push(format!("预计烈度 {:.1}", timing.estimated_intensity));
-    }
-    subtitle.push(format!("监测点 {target_name}"));
-    append_report_state(event, &mut subtitle);
-
-    let mut lines = Vec::new();
-    if event.training {
-        lines.push("演练信息：这是一条模拟预警，请勿恐慌。".to_string());
-    }
-    if !place.is_empty() {
-        lines.push(format!("震中位置：{place}"));
-    }
-    lines.push(format!("监测地点：{target_name}"));
-    if let Some(timing) = timing {
-        lines.push(format!(
-            "震波到达：{} · {}",
-            wave_status("P波", timing.p_arrival_at_ms, now_ms),
-            wave_status("S波", timing.s_arrival_at_ms, now_ms)
-        ));
-        lines.push(format!(
-            "距离估算：震中距 {:.0} km · 震源距 {:.0} km",
-            timing.distance_km, timing.hypocentral_km
-        ));
-    }
-    let mut earthquake = Vec::new();
-    if let Some(magnitude) = event.magnitude {
-        earthquake.push(format!("震级 M{magnitude:.1}"));
-    }
-    if let Some(depth_km) = event.depth_km {
-        earthquake.push(format!("深度 {depth_km:.0} km"));
-    }
-    if !earthquake.is_empty() {
-        lines.push(format!("地震参数：{}", earthquake.join(" · ")));
-    }
-    append_regions(event, "可能影响", &mut lines);
-    append_time(event, "发生时间", &mut lines);
-    lines.push("安全提示：请保持冷静，远离玻璃、悬挂物和不稳固家具。".to_string());
-
-    DisasterAlertContent {
-        title,
-        subtitle: subtitle.join(" · "),
-        body: lines.join("\n"),
-    }
-}
-
-fn format_weather(event: &DisasterEvent, target: &MonitoringTarget) -> DisasterAlertContent {
-    let title = stateful_title("气象预警", event, None, 0);
-    let target_name = target_name(target);
-    let event_title = clean_inline(&event.title);
-    let mut subtitle = vec![
-        event_title
-            .is_empty()
-            .then_some("气象部门发布预警".to_string())
-            .unwrap_or(event_title),
-    ];
-    subtitle.push(format!("监测点 {target_name}"));
-    append_report_state(event, &mut subtitle);
-
-    let mut lines = vec![format!("监测地点：{target_name}")];
-    append_regions(event, "预警区域", &mut lines);
-    append_description(event, "预警内容", &mut lines);
-    append_time(event, "发布时间", &mut lines);
-    lines.push("防范提示：请关注临近预报，合理调整出行和户外活动。".to_string());
-
-    DisasterAlertContent {
-        title,
-        subtitle: subtitle.join(" · "),
-        body: lines.join("\n"),
-    }
-}
-
-fn format_tsunami(event: &DisasterEvent, target: &MonitoringTarget) -> DisasterAlertContent {
-    let title = stateful_title("海啸预警", event, None, 0);
-    let target_name = target_name(target);
-    let event_title = clean_inline(&event.title);
-    let mut subtitle = vec![
-        event_title
-            .is_empty()
-            .then_some("海啸风险信息".to_string())
-            .unwrap_or(event_title),
-    ];
-    subtitle.push(format!("监测点 {target_name}"));
-    append_report_state(event, &mut subtitle);
-
-    let mut lines = vec![format!("监测地点：{target_name}")];
-    append_regions(event, "影响区域", &mut lines);
-    append_description(event, "预警说明", &mut lines);
-    let mut earthquake = Vec::new();
-    if let Some(magnitude) = event.magnitude {
-        earthquake.push(format!("震级 M{magnitude:.1}"));
-    }
-    if let Some(depth_km) = event.depth_km {
-        earthquake.push(format!("深度 {depth_km:.0} km"));
-    }
-    if !earthquake.is_empty() {
-        lines.push(format!("相关地震：{}", earthquake.join(" · ")));
-    }
-    append_time(event, "更新时间", &mut lines);
-    lines.push("避险提示：沿海及河口区域人员请远离岸线，按官方指引向高处转移。".to_string());
-
-    DisasterAlertContent {
-        title,
-        subtitle: subtitle.join(" · "),
-        body: lines.join("\n"),
-    }
-}
-
-fn format_typhoon(event: &DisasterEvent, target: &MonitoringTarget) -> DisasterAlertContent {
-    let title = stateful_title("台风动态", event, None, 0);
-    let target_name = target_name(target);
-    let event_title = clean_inline(&event.title);
-    let mut subtitle = vec![
-        event_title
-            .is_empty()
-            .then_some("台风最新动态".to_string())
-            .unwrap_or(event_title),
-    ];
-    subtitle.push(format!("监测点 {target_name}"));
-    append_report_state(event, &mut subtitle);
-
-    let mut lines = vec![format!("监测地点：{target_name}")];
-    if let Some((latitude, longitude)) = event.latitude.zip(event.longitude) {
-        lines.push(format!("台风中心：{latitude:.2}°, {longitude:.2}°"));
-    }
-    if let Some(radius_km) = event.radius_km {
-        lines.push(format!("七级风圈：约 {radius_km:.0} km"));
-    }
-    append_regions(event, "可能影响", &mut lines);
-    append_description(event, "强度信息", &mut lines);
-    append_time(event, "更新时间", &mut lines);
-    lines.push("防范提示：请加固门窗和室外物品，避免前往沿海、山区及低洼地带。".to_string());
-
-    DisasterAlertContent {
-        title,
-        subtitle: subtitle.join(" · "),
-        body: lines.join("\n"),
-    }
-}
-
-fn stateful_title(
-    base: &str,
-    event: &DisasterEvent,
-    timing: Option<&AlertTiming>,
-    now_ms: i64,
-) -> String {
-    let title = if event.cancel {
-        format!("{base}已解除")
-    } else if let Some(timing) = timing {
-        let seconds = remaining_seconds(timing.s_arrival_at_ms, now_ms);
-        if seconds > 0 {
-            format!("{base} {seconds}秒后到达")
-        } else {
-            format!("{base} 震波已到达")
-        }
-    } else if event.final_report {
-        format!("{base}终报")
-    } else {
-        base.to_string()
-    };
-    if event.training {
-        format!("演练 · {title}")
-    } else {
-        title
-    }
-}
-
 fn wave_status(name: &str, arrival_at_ms: i64, now_ms: i64) -> String {
     let seconds = remaining_seconds(arrival_at_ms, now_ms);
     if seconds > 0 {
@@ -246,6 +572,31 @@ fn wave_status(name: &str, arrival_at_ms: i64, now_ms: i64) -> String {
     }
 }
 
+/// 发震至今的时长，便于判断震动是即将到来、正在发生还是已经过去；发震时刻晚于当前
+/// 时间（时钟偏差）时不显示。
+fn elapsed_since_origin(origin_ms: i64, now_ms: i64) -> Option<String> {
+    let elapsed_ms = now_ms.checked_sub(origin_ms)?;
+    if elapsed_ms < 0 {
+        return None;
+    }
+    let seconds = elapsed_ms / 1_000;
+    Some(if seconds < 60 {
+        format!("{seconds}秒前发生")
+    } else if seconds < 3_600 {
+        format!("{}分钟前发生", seconds / 60)
+    } else if seconds < 86_400 {
+        format!("{}小时前发生", seconds / 3_600)
+    } else {
+        format!("{}天前发生", seconds / 86_400)
+    })
+}
+
+/// 发布机构名称；经 Wolfx 转发的数据源去掉转发方前缀，只保留原始机构。
+fn agency_label(event: &DisasterEvent) -> Option<&'static str> {
+    let label = crate::source_registry::find(&event.source)?.label;
+    Some(label.strip_prefix("Wolfx ").unwrap_or(label))
+}
+
 fn earthquake_place(event: &DisasterEvent) -> String {
     let title = clean_inline(&event.title);
     for prefix in ["地震预警", "地震信息", "地震速报", "地震播报"] {
@@ -256,6 +607,15 @@ fn earthquake_place(event: &DisasterEvent) -> String {
     title
 }
 
+fn headline(event: &DisasterEvent, fallback: &str) -> String {
+    let title = clean_inline(&event.title);
+    if title.is_empty() {
+        fallback.to_string()
+    } else {
+        title
+    }
+}
+
 fn target_name(target: &MonitoringTarget) -> String {
     let label = clean_inline(&target.label);
     if !label.is_empty() {
@@ -271,15 +631,7 @@ fn target_name(target: &MonitoringTarget) -> String {
             return value;
         }
     }
-    "所选地点".to_string()
-}
-
-fn append_report_state(event: &DisasterEvent, parts: &mut Vec<String>) {
-    if event.cancel {
-        parts.push("解除/取消".to_string());
-    } else if event.final_report {
-        parts.push("最终报告".to_string());
-    }
+    crate::utils::places::describe_target(target)
 }
 
 fn append_regions(event: &DisasterEvent, label: &str, lines: &mut Vec<String>) {
@@ -303,12 +655,93 @@ fn append_description(event: &DisasterEvent, label: &str, lines: &mut Vec<String
 }
 
 fn append_time(event: &DisasterEvent, label: &str, lines: &mut Vec<String>) {
-    let occurred_at = clean_inline(&event.occurred_at);
+    let occurred_at = clean_inline(&display_event_time(event));
     if !occurred_at.is_empty() {
         lines.push(format!("{label}：{occurred_at}"));
     }
 }
 
+/// 以“、”分隔、可以按项缩短的地区列表所用的标签。
+const REGION_LIST_LABELS: [&str; 3] = ["可能影响", "预警区域", "影响区域"];
+
+fn fit_segments(value: String, separator: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        return value;
+    }
+    let mut kept = value.as_str();
+    while let Some((head, _tail)) = kept.rsplit_once(separator) {
+        kept = head;
+        if kept.chars().count() <= max_chars {
+            return kept.to_string();
+        }
+    }
+    truncate_chars(kept, max_chars)
+}
+
+fn fit_body(value: String, max_chars: usize) -> String {
+    let mut total = value.chars().count();
+    if total <= max_chars {
+        return value;
+    }
+    let mut lines = value.lines().map(str::to_string).collect::<Vec<_>>();
+    for line in &mut lines {
+        if total <= max_chars {
+            break;
+        }
+        let original = line.chars().count();
+        let Some(shortened) = shorten_region_list(line, original.saturating_sub(total - max_chars))
+        else {
+            continue;
+        };
+        total = total - original + shortened.chars().count();
+        *line = shortened;
+    }
+    while total > max_chars && lines.len() > 1 {
+        if let Some(removed) = lines.pop() {
+            total = total.saturating_sub(removed.chars().count() + 1);
+        }
+    }
+    truncate_chars(&lines.join("\n"), max_chars)
+}
+
+/// 保留地区列表前面尽量多的项并注明总数，使该行不超过 `target_chars`；至少保留一项。
+/// 不是地区列表或无法缩短时返回 `None`。
+fn shorten_region_list(line: &str, target_chars: usize) -> Option<String> {
+    let (label, list) = line.split_once('：')?;
+    if !REGION_LIST_LABELS.contains(&label) {
+        return None;
+    }
+    let items = list.split('、').collect::<Vec<_>>();
+    let original = line.chars().count();
+    let mut shortened = None;
+    for count in (1..items.len()).rev() {
+        let candidate = format!(
+            "{label}：{}等 {} 地",
+            items[..count].join("、"),
+            items.len()
+        );
+        let length = candidate.chars().count();
+        if length < original {
+            shortened = Some(candidate);
+        }
+        if length <= target_chars {
+            break;
+        }
+    }
+    shortened
+}
+
+pub(crate) fn truncate_chars(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        return value.to_string();
+    }
+    value
+        .chars()
+        .take(max_chars.saturating_sub(1))
+        .chain(['…'])
+        .collect()
+}
+
 fn clean_inline(value: &str) -> String {
     value.split_whitespace().collect::<Vec<_>>().join(" ")
 }
@@ -321,10 +754,10 @@ mod tests {
     fn target() -> MonitoringTarget {
         MonitoringTarget {
             label: "上海家中".to_string(),
-            point: GeoPoint {
+            point: Some(GeoPoint {
                 latitude: 31.2,
                 longitude: 121.5,
-            },
+            }),
             region: AdministrativeRegion {
                 province: "上海市".to_string(),
                 city: "上海市".to_string(),
@@ -360,6 +793,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -403,6 +839,58 @@ mod tests {
         assert_no_internal_fields(&content);
     }
 
+    #[test]
+    fn late_warning_switches_to_report_wording_after_grace_period() {
+        let warning = event(DisasterCategory::EarthquakeWarning);
+        let within_grace = format_disaster_alert(&warning, &target(), Some(&timing()), 122_000);
+        let late = format_disaster_alert(&warning, &target(), Some(&timing()), 122_001);
+
+        assert_eq!(within_grace.title, "地震播报 震波已到达");
+        assert_eq!(late.title, "地震速报 震波已过");
+        assert!(late.body.contains("P波已到达 · S波已到达"));
+    }
+
+    #[test]
+    fn final_report_after_arrival_shows_confirmed_values() {
+        let mut warning = event(DisasterCategory::EarthquakeWarning);
+        warning.final_report = true;
+        warning.magnitude = Some(5.8);
+        warning.max_intensity = Some("7.1".to_string());
+        let content = format_disaster_alert(&warning, &target(), Some(&timing()), 130_000);
+
+        assert_eq!(content.title, "地震播报终报");
+        assert!(content.subtitle.contains("M5.8"));
+        assert!(content.body.contains("地震参数：震级 M5.8 · 深度 10 km"));
+        assert!(content.body.contains("最大烈度：7.1"));
+    }
+
+    #[test]
+    fn jma_warning_classification_is_named_in_the_title() {
+        let mut warning = event(DisasterCategory::EarthquakeWarning);
+        warning.official_warning = Some(false);
+        let forecast = format_disaster_alert(&warning, &target(), Some(&timing()), 101_000);
+        assert_eq!(forecast.title, "地震播报（预报） 11秒后到达");
+        warning.official_warning = Some(true);
+        let official = format_disaster_alert(&warning, &target(), None, 101_000);
+        assert_eq!(official.title, "地震播报（警报）");
+    }
+
+    #[test]
+    fn assumed_hypocenter_is_flagged_in_the_body() {
+        let mut warning = event(DisasterCategory::EarthquakeWarning);
+        assert!(
+            !format_disaster_alert(&warning, &target(), None, 101_000)
+                .body
+                .contains("震源位置暂为推定")
+        );
+        warning.assumed_hypocenter = true;
+        assert!(
+            format_disaster_alert(&warning, &target(), None, 101_000)
+                .body
+                .contains("震源位置暂为推定")
+        );
+    }
+
     #[test]
     fn non_earthquake_categories_have_distinct_user_facing_layouts() {
         let cases = [
@@ -426,6 +914,185 @@ mod tests {
 
         assert_eq!(content.title, "地震播报已解除");
         assert!(content.subtitle.contains("解除/取消"));
+        assert!(content.body.starts_with("预警取消："));
+
+        let mut withdrawn_report = event(DisasterCategory::EarthquakeReport);
+        withdrawn_report.cancel = true;
+        let content = format_disaster_alert(&withdrawn_report, &target(), None, 0);
+        assert!(!content.body.contains("预警取消"));
+    }
+
+    #[test]
+    fn prepared_alert_is_reused_across_monitoring_targets() {
+        let alert = PreparedAlert::new(
+            &event(DisasterCategory::EarthquakeWarning),
+            &MessageTemplates::default(),
+        );
+        let mut office = target();
+        office.label = "公司".to_string();
+        let home = alert.render(&target(), Some(&timing()), 101_000);
+        let work = alert.render(&office, Some(&timing()), 101_000);
+
+        assert_eq!(
+            home.subtitle,
+            "四川泸定 · M6.2 · 预计烈度 3.2 · 监测点 上海家中"
+        );
+        assert_eq!(
+            work.subtitle,
+            "四川泸定 · M6.2 · 预计烈度 3.2 · 监测点 公司"
+        );
+        assert_eq!(
+            home.body.replace("监测地点：上海家中", "监测地点：公司"),
+            work.body
+        );
+        assert!(
+            home.body
+                .starts_with("震中位置：四川泸定\n监测地点：上海家中\n")
+        );
+    }
+
+    #[test]
+    fn fixed_titles_are_rendered_once_and_ignore_timing() {
+        let mut cancelled = event(DisasterCategory::EarthquakeWarning);
+        cancelled.cancel = true;
+        let mut drill = event(DisasterCategory::WeatherWarning);
+        drill.training = true;
+        for (event, title) in [(cancelled, "地震播报已解除"), (drill, "演练 · 气象预警")]
+        {
+            let alert = PreparedAlert::new(&event, &MessageTemplates::default());
+            assert_eq!(alert.fixed_title.as_deref(), Some(title));
+            assert_eq!(alert.render(&target(), Some(&timing()), 0).title, title);
+            assert_eq!(
+                alert.render(&target(), Some(&timing()), 10_000_000).title,
+                title
+            );
+        }
+
+        let warning = PreparedAlert::new(
+            &event(DisasterCategory::EarthquakeWarning),
+            &MessageTemplates::default(),
+        );
+        assert!(warning.fixed_title.is_none());
+        assert_ne!(
+            warning.render(&target(), Some(&timing()), 101_000).title,
+            warning.render(&target(), Some(&timing()), 10_000_000).title
+        );
+    }
+
+    #[test]
+    fn earthquake_body_shows_elapsed_time_agency_and_report_number() {
+        let mut quake = event(DisasterCategory::EarthquakeReport);
+        quake.source = "wolfx.cenc_eew".to_string();
+        quake.occurred_at = "2026-07-14T02:20:30.250Z".to_string();
+        let origin_ms = 1_783_995_630_250;
+        let alert = PreparedAlert::new(&quake, &MessageTemplates::default());
+
+        let fresh = alert.render(&target(), None, origin_ms + 12_900);
+        assert!(fresh.body.contains("发震距今：12秒前发生"));
+        assert!(fresh.body.contains("发布机构：中国地震台网 · 第 3 报"));
+        let later = alert.render(&target(), None, origin_ms + 5 * 60_000);
+        assert!(later.body.contains("发震距今：5分钟前发生"));
+        let skewed = alert.render(&target(), None, origin_ms - 1_000);
+        assert!(!skewed.body.contains("发震距今"));
+    }
+
+    #[test]
+    fn fitting_shortens_region_lists_and_drops_whole_segments() {
+        let mut quake = event(DisasterCategory::EarthquakeReport);
+        quake.affected_regions = (1..=12).map(|index| format!("测试地区{index}")).collect();
+        let content = format_disaster_alert(&quake, &target(), Some(&timing()), 101_000);
+        let budget = MessageBudget {
+            title_chars: 10,
+            subtitle_chars: 20,
+            body_chars: content.body.chars().count() - 40,
+        };
+
+        let fitted = content.clone().fit(budget);
+
+        assert_eq!(fitted.title, "地震速报 11秒后…");
+        assert!(fitted.title.chars().count() <= budget.title_chars);
+        assert_eq!(fitted.subtitle, "四川泸定 · M6.2");
+        assert!(fitted.body.chars().count() <= budget.body_chars);
+        assert!(fitted.body.contains("、测试地区"));
+        assert!(fitted.body.contains("等 12 地"));
+        assert!(
+            fitted
+                .body
+                .ends_with("安全提示：请保持冷静，远离玻璃、悬挂物和不稳固家具。")
+        );
+        assert_eq!(content.clone().fit(MessageBudget::default()), content);
+
+        let tight = content.fit(MessageBudget {
+            body_chars: 30,
+            ..budget
+        });
+        assert!(
+            tight
+                .body
+                .starts_with("震中位置：四川泸定\n监测地点：上海家中")
+        );
+        assert!(tight.body.chars().count() <= 30);
+    }
+
+    #[test]
+    fn custom_templates_replace_titles_and_tips() {
+        let templates = MessageTemplates {
+            earthquake_warning_title: "地震预警".to_string(),
+            countdown_title: "{seconds}秒后{title}到达".to_string(),
+            earthquake_tip: "安全提示：就近避险。".to_string(),
+            ..MessageTemplates::default()
+        };
+        let alert = PreparedAlert::new(&event(DisasterCategory::EarthquakeWarning), &templates);
+        let content = alert.render(&target(), Some(&timing()), 101_000);
+
+        assert_eq!(content.title, "11秒后地震预警到达");
+        assert!(content.body.ends_with("安全提示：就近避险。"));
+        assert!(MessageTemplates::default().validate().is_empty());
+    }
+
+    #[test]
+    fn source_branding_prefixes_the_title_and_carries_the_icon() {
+        let mut templates = MessageTemplates::default();
+        templates.source_branding.insert(
+            "internal.provider.channel".to_string(),
+            SourceBranding {
+                title_prefix: Some("🇯🇵".to_string()),
+                icon: Some("https://alert.example.com/jma.png".to_string()),
+            },
+        );
+        let alert = PreparedAlert::new(&event(DisasterCategory::EarthquakeWarning), &templates);
+        assert_eq!(alert.icon(), Some("https://alert.example.com/jma.png"));
+        let content = alert.render(&target(), Some(&timing()), 101_000);
+        assert_eq!(content.title, "🇯🇵 地震播报 11秒后到达");
+
+        let mut other = event(DisasterCategory::EarthquakeWarning);
+        other.source = "wolfx.cenc_eew".to_string();
+        let alert = PreparedAlert::new(&other, &templates);
+        assert_eq!(alert.icon(), None);
+        assert_eq!(alert.render(&target(), None, 101_000).title, "地震播报");
+    }
+
+    #[test]
+    fn template_validation_reports_each_problem() {
+        let templates = MessageTemplates {
+            countdown_title: "{title} 即将到达".to_string(),
+            arrived_title: "{title} {distance}".to_string(),
+            tsunami_tip: "{".to_string(),
+            typhoon_title: " ".to_string(),
+            earthquake_tip: "提示 {seconds}".to_string(),
+            ..MessageTemplates::default()
+        };
+
+        assert_eq!(
+            templates.validate(),
+            [
+                "TEMPLATE_TYPHOON_TITLE must not be empty",
+                "TEMPLATE_COUNTDOWN_TITLE must contain {seconds}",
+                "TEMPLATE_ARRIVED_TITLE uses unknown placeholder {distance}; allowed: {title}",
+                "TEMPLATE_EARTHQUAKE_TIP must not contain placeholders, found {seconds}",
+                "TEMPLATE_TSUNAMI_TIP has an unmatched '{'",
+            ]
+        );
     }
 
     fn assert_no_internal_fields(content: &DisasterAlertContent) {
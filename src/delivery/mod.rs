@@ -5,16 +5,19 @@ mod message;
 pub(crate) use bark::{AlertRecipient, BarkDeliveryError, BarkPermit, CountdownRecipient};
 pub(crate) use bark::{BarkNotifier, BarkPushConfig};
 pub(crate) use context::NotificationLinkService;
-pub(crate) use context::{NotificationContextInput, NotificationVerifyError};
+pub(crate) use context::{NotificationContextInput, NotificationVerifyError, isoseismal_radii};
 #[cfg(test)]
 pub(crate) use context::{
-    NotificationEventSnapshot, NotificationIntensityBandSnapshot, NotificationTargetSnapshot,
-    NotificationTimingSnapshot,
+    NotificationEventSnapshot, NotificationIntensityBandSnapshot, NotificationIsoseismalSnapshot,
+    NotificationTargetSnapshot, NotificationTimingSnapshot,
 };
 pub(crate) use context::{
     NotificationRuleSnapshot, NotificationSnapshot, NotificationSourcesSnapshot,
 };
-pub(crate) use message::{AlertTiming, remaining_seconds};
+pub(crate) use message::{
+    AlertTiming, MessageBudget, MessageTemplates, PreparedAlert, SourceBranding, TEMPLATE_SPECS,
+    remaining_seconds,
+};
 
 use crate::models::{DisasterCategory, IncidentId, InterruptionLevel};
 use crate::subscriptions::{DestinationNumericId, SubscriptionId};
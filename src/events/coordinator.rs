@@ -1,21 +1,41 @@
-use crate::events::MatchJob;
-use crate::models::{DisasterCategory, IncidentRecord, parse_event_epoch};
+use crate::config::LiveSettings;
+use crate::events::{EventStage, InterimPolicy, MatchJob};
+use crate::models::{DisasterCategory, IncidentApplyOutcome, IncidentRecord, parse_event_epoch};
 use crate::storage::{FjallStorage, InboxItem, IncidentResolutionCapacity, try_now_millis};
+use crate::utils::intensity::ShindoClass;
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Clone)]
 pub(crate) struct EventCoordinator {
     storage: FjallStorage,
-    policy: EventPolicy,
+    settings: LiveSettings,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Serialize)]
 pub(crate) struct EventPolicy {
     pub(crate) push_updates: bool,
     pub(crate) update_min_report_gap: u32,
     pub(crate) ignore_training: bool,
     pub(crate) ignore_cancel: bool,
     pub(crate) stale_origin_seconds: i64,
+    /// 震源深于该值（千米）的地震不匹配订阅；0 表示不限制。
+    pub(crate) max_event_depth_km: f64,
+    /// 续报只推送给尚未收到该事件或级别升高的订阅。
+    pub(crate) update_escalation_only: bool,
+    /// 同一地震已由其他数据源推送过时，后到数据源的报告只推送给尚未收到或级别升高的订阅。
+    pub(crate) cross_source_dedup: bool,
+    /// 震级低于该值的地震在查找候选订阅前直接丢弃；0 表示不限制。
+    pub(crate) min_event_magnitude: f64,
+    /// 只匹配这些地震数据源的事件；为空时全部匹配。其他类别的数据源不受影响。
+    pub(crate) enabled_sources: BTreeSet<String>,
+    /// 按数据源覆盖 `min_event_magnitude`。
+    pub(crate) source_min_magnitude: BTreeMap<String, f64>,
+    /// 按数据源决定中间报是否推送，未列出的数据源全部推送。
+    pub(crate) source_interim_policy: BTreeMap<String, InterimPolicy>,
+    /// 数据源上报的最大烈度低于该震度的地震不匹配订阅；`None` 表示不限制。
+    pub(crate) min_event_max_intensity: Option<ShindoClass>,
 }
 
 impl Default for EventPolicy {
@@ -26,18 +46,48 @@ impl Default for EventPolicy {
             ignore_training: false,
             ignore_cancel: false,
             stale_origin_seconds: 0,
+            max_event_depth_km: 0.0,
+            update_escalation_only: false,
+            cross_source_dedup: true,
+            min_event_magnitude: 0.0,
+            enabled_sources: BTreeSet::new(),
+            source_min_magnitude: BTreeMap::new(),
+            source_interim_policy: BTreeMap::new(),
+            min_event_max_intensity: None,
         }
     }
 }
 
+impl EventPolicy {
+    pub(crate) fn interim_policy(&self, source: &str) -> InterimPolicy {
+        self.source_interim_policy
+            .get(source)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
 impl EventCoordinator {
     #[cfg(test)]
     pub(crate) fn new(storage: FjallStorage) -> Self {
         Self::with_policy(storage, EventPolicy::default())
     }
 
+    #[cfg(any(test, feature = "benchmarks"))]
     pub(crate) fn with_policy(storage: FjallStorage, policy: EventPolicy) -> Self {
-        Self { storage, policy }
+        use crate::config::TunableSettings;
+        Self::with_settings(
+            storage,
+            LiveSettings::new(TunableSettings {
+                event_policy: policy,
+                ..TunableSettings::default()
+            }),
+        )
+    }
+
+    /// 每处理一条事件时读取当前策略，配置重新加载后立即生效。
+    pub(crate) fn with_settings(storage: FjallStorage, settings: LiveSettings) -> Self {
+        Self { storage, settings }
     }
 
     pub(crate) fn process_next(&self) -> Result<Option<MatchJob>> {
@@ -59,6 +109,15 @@ impl EventCoordinator {
             }
         };
         let current = self.storage.incident(&incident_id)?;
+        let previous = current
+            .as_ref()
+            .and_then(|incident| incident.stream_watermark(&item.event));
+        let interim = self
+            .settings
+            .load()
+            .event_policy
+            .interim_policy(&item.event.source);
+        let stage = EventStage::advance(previous, &item.event, interim);
         let now_ms = try_now_millis()?;
         let transition =
             match super::reducer::reduce_incident_at(current.as_ref(), &item.event, now_ms) {
@@ -68,13 +127,34 @@ impl EventCoordinator {
                 }
             };
         if !transition.outcome.applied() {
+            if transition.outcome == IncidentApplyOutcome::Rejected {
+                // 晚到的旧报不能覆盖较新的一报，也不能借此重新触发推送。
+                tracing::info!(
+                    event = "event.out_of_order_dropped",
+                    inbox_id = item.id,
+                    source = %item.event.source,
+                    event_id = %item.event.event_id,
+                    report_num = item.event.report_num,
+                    "event.out_of_order_dropped"
+                );
+            }
             self.storage.complete_inbox(item.id)?;
             return Ok(None);
         }
-        if !self.should_match(current.as_ref(), &item.event, now_ms) {
+        let event = super::merge::best_estimate(&transition.incident, &item.event);
+        if !self.should_match(current.as_ref(), &event, stage, now_ms) {
             self.storage
-                .commit_incident_without_match(&transition.incident, &item.event, item.id)
+                .commit_incident_without_match(&transition.incident, &event, item.id)
                 .context("failed to atomically advance Incident without matching")?;
+            tracing::debug!(
+                event = "event.advanced_without_match",
+                incident_id = incident_id.as_str(),
+                source = %event.source,
+                event_id = %event.event_id,
+                report_num = event.report_num,
+                stage = ?stage,
+                "event.advanced_without_match"
+            );
             return Ok(None);
         }
         let job = MatchJob {
@@ -82,10 +162,25 @@ impl EventCoordinator {
             incident_id,
             event_revision: self.storage.next_id("event_revision")?,
             created_at_ms: now_ms,
+            stage,
+            later_stream: current
+                .as_ref()
+                .is_some_and(|incident| incident.is_later_stream(&item.event)),
         };
         self.storage
-            .commit_incident_match_job(&transition.incident, &item.event, &job, item.id)
+            .commit_incident_match_job(&transition.incident, &event, &job, item.id)
             .context("failed to atomically advance EventCoordinator")?;
+        // 同一事件编号的后续报文按续报、终报处理，日志中的报数与推送正文中的「第 N 报」一致。
+        tracing::info!(
+            event = "event.match_job_created",
+            incident_id = job.incident_id.as_str(),
+            source = %event.source,
+            event_id = %event.event_id,
+            report_num = event.report_num,
+            stage = ?job.stage,
+            later_stream = job.later_stream,
+            "event.match_job_created"
+        );
         Ok(Some(job))
     }
 
@@ -110,30 +205,40 @@ impl EventCoordinator {
         &self,
         current: Option<&IncidentRecord>,
         event: &crate::models::DisasterEvent,
+        stage: EventStage,
         now_ms: i64,
     ) -> bool {
-        if event.training && self.policy.ignore_training
-            || event.cancel && self.policy.ignore_cancel
-            || stale_origin(event, self.policy.stale_origin_seconds, now_ms)
+        let settings = self.settings.load();
+        let policy = &settings.event_policy;
+        if event.training && policy.ignore_training
+            || event.cancel && policy.ignore_cancel
+            || source_disabled(event, &policy.enabled_sources)
+            || stale_origin(event, policy.stale_origin_seconds, now_ms)
+            || too_deep(event, policy.max_event_depth_km)
+            || below_magnitude_floor(event, policy)
+            || below_intensity_floor(event, policy.min_event_max_intensity)
         {
             return false;
         }
-        if event.cancel || current.is_none() {
+        let interim = policy.interim_policy(&event.source);
+        if interim.holds(event) {
+            return false;
+        }
+        // 此前各报都被压下时，放行的这一报是用户第一次收到该事件流。
+        let released = current
+            .and_then(|incident| incident.stream_watermark(event))
+            .is_some_and(|watermark| interim.held(watermark));
+        if event.cancel || current.is_none() || released {
             return true;
         }
-        if !self.policy.push_updates {
-            return false;
+        if !policy.push_updates {
+            // 终报仍生成匹配任务，由投递阶段只发给选择接收终报回顾的订阅。
+            return stage == EventStage::Final;
         }
         let previous_report = current
-            .and_then(|incident| {
-                incident.stream_watermarks.iter().find(|watermark| {
-                    watermark.category == event.category
-                        && watermark.source == event.source
-                        && watermark.event_id == event.event_id
-                })
-            })
+            .and_then(|incident| incident.stream_watermark(event))
             .map_or(0, |watermark| watermark.report_num);
-        event.report_num.saturating_sub(previous_report) >= self.policy.update_min_report_gap.max(1)
+        event.report_num.saturating_sub(previous_report) >= policy.update_min_report_gap.max(1)
             || event.final_report
     }
 }
@@ -158,6 +263,63 @@ fn stale_origin(
     })
 }
 
+/// 只关心部分地区的部署可以关掉其他地区的地震数据源；这些事件仍记入历史，但不推送，
+/// 取消信息也一并丢弃。
+fn source_disabled(event: &crate::models::DisasterEvent, enabled: &BTreeSet<String>) -> bool {
+    !enabled.is_empty()
+        && matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        )
+        && !enabled.contains(&event.source)
+}
+
+/// 深源地震很少造成破坏性的地面震动，但按震级和距离估算的烈度仍可能超过推送门槛。
+/// 深度未知的地震和取消信息照常处理。
+fn too_deep(event: &crate::models::DisasterEvent, max_depth_km: f64) -> bool {
+    max_depth_km > 0.0
+        && !event.cancel
+        && matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        )
+        && event.depth_km.is_some_and(|depth| depth > max_depth_km)
+}
+
+/// 部分数据源持续推送大量微小地震，在入口处丢弃可省去候选订阅查找。
+/// 震级未知的地震和取消信息照常处理。
+fn below_magnitude_floor(event: &crate::models::DisasterEvent, policy: &EventPolicy) -> bool {
+    let floor = policy
+        .source_min_magnitude
+        .get(&event.source)
+        .copied()
+        .unwrap_or(policy.min_event_magnitude);
+    floor > 0.0
+        && !event.cancel
+        && matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        )
+        && event.magnitude.is_some_and(|magnitude| magnitude < floor)
+}
+
+/// 逐个订阅估算的烈度可能偏乐观；数据源自己给出的最大烈度都达不到门槛时，没有人会有明显震感。
+/// 最大烈度未知或无法识别的地震和取消信息照常处理。
+fn below_intensity_floor(event: &crate::models::DisasterEvent, floor: Option<ShindoClass>) -> bool {
+    floor.is_some_and(|floor| {
+        !event.cancel
+            && matches!(
+                event.category,
+                DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+            )
+            && event
+                .max_intensity
+                .as_deref()
+                .and_then(|value| ShindoClass::from_reported(&event.source, value))
+                .is_some_and(|reported| reported < floor)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +351,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         };
         storage.ingest_with_cursor(ProviderChannel::Wolfx, vec![event], None)?;
         let coordinator = EventCoordinator::new(storage.clone());
@@ -209,6 +374,7 @@ mod tests {
             let job = EventCoordinator::new(storage.clone())
                 .process_next()?
                 .context("missing first job")?;
+            anyhow::ensure!(!job.later_stream);
             storage.persist()?;
             job.incident_id
         };
@@ -224,6 +390,7 @@ mod tests {
             .process_next()?
             .context("missing second job")?;
         anyhow::ensure!(second_job.incident_id == first_id);
+        anyhow::ensure!(second_job.later_stream);
         let incident = storage.incident(&first_id)?.context("missing incident")?;
         anyhow::ensure!(incident.source_event_keys.len() == 2);
         Ok(())
@@ -245,6 +412,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn later_report_of_the_same_event_id_becomes_an_update_job() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let coordinator = EventCoordinator::new(storage.clone());
+        storage.ingest_with_cursor(
+            ProviderChannel::FanStudio,
+            vec![test_event("fanstudio.cenc", "same")],
+            None,
+        )?;
+        let first = coordinator.process_next()?.context("missing first job")?;
+        anyhow::ensure!(first.stage == EventStage::First);
+        commit_matched_job(&storage, &first)?;
+
+        let mut update = test_event("fanstudio.cenc", "same");
+        update.report_num = 2;
+        update.revision = "2".to_string();
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![update], None)?;
+        let job = coordinator.process_next()?.context("missing update job")?;
+        anyhow::ensure!(job.stage == EventStage::Update, "{:?}", job.stage);
+        anyhow::ensure!(job.incident_id == first.incident_id);
+        anyhow::ensure!(!job.later_stream);
+        anyhow::ensure!(storage.pending_match_jobs(1)? == vec![job.clone()]);
+        let event = storage
+            .event(job.event_revision)?
+            .context("missing update event")?;
+        anyhow::ensure!(event.report_num == 2);
+        let incident = storage
+            .incident(&first.incident_id)?
+            .context("missing incident")?;
+        anyhow::ensure!(incident.stream_watermarks.len() == 1);
+        anyhow::ensure!(incident.stream_watermarks[0].report_num == 2);
+        Ok(())
+    }
+
     #[test]
     fn suppressed_update_still_advances_incident_without_a_match_job() -> Result<()> {
         let directory = tempfile::tempdir()?;
@@ -276,9 +478,69 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn final_report_still_matches_for_followups_when_updates_are_off() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let coordinator = EventCoordinator::with_policy(
+            storage.clone(),
+            EventPolicy {
+                push_updates: false,
+                ..EventPolicy::default()
+            },
+        );
+        storage.ingest_with_cursor(
+            ProviderChannel::FanStudio,
+            vec![test_event("fanstudio.cenc", "same")],
+            None,
+        )?;
+        let first = coordinator.process_next()?.context("missing first job")?;
+        anyhow::ensure!(first.stage == EventStage::First);
+        commit_matched_job(&storage, &first)?;
+        let mut last = test_event("fanstudio.cenc", "same");
+        last.report_num = 2;
+        last.revision = "2".to_string();
+        last.final_report = true;
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![last], None)?;
+        let followup = coordinator.process_next()?.context("missing final job")?;
+        anyhow::ensure!(followup.stage == EventStage::Final);
+        Ok(())
+    }
+
+    #[test]
+    fn delayed_earlier_report_neither_overwrites_nor_retriggers() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let coordinator = EventCoordinator::new(storage.clone());
+        let mut newer = test_event("fanstudio.cenc", "same");
+        newer.report_num = 3;
+        newer.revision = "3".to_string();
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![newer], None)?;
+        let first = coordinator.process_next()?.context("missing first job")?;
+        commit_matched_job(&storage, &first)?;
+
+        let mut delayed = test_event("fanstudio.cenc", "same");
+        delayed.report_num = 2;
+        delayed.revision = "2".to_string();
+        delayed.magnitude = Some(6.5);
+        delayed.level = 4;
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![delayed], None)?;
+
+        anyhow::ensure!(coordinator.process_next()?.is_none());
+        anyhow::ensure!(storage.pending_inbox(1)?.is_empty());
+        anyhow::ensure!(storage.pending_match_jobs(1)?.is_empty());
+        let incident = storage
+            .incident(&first.incident_id)?
+            .context("missing incident")?;
+        anyhow::ensure!(incident.stream_watermarks[0].report_num == 3);
+        anyhow::ensure!(incident.stream_watermarks[0].level == 2);
+        Ok(())
+    }
+
     #[test]
     fn first_policy_skipped_event_does_not_create_an_incident() -> Result<()> {
-        let cases: [(EventPolicy, fn(&mut DisasterEvent)); 3] = [
+        type Mutation = fn(&mut DisasterEvent);
+        let cases: [(EventPolicy, Mutation); _] = [
             (
                 EventPolicy {
                     ignore_training: true,
@@ -300,6 +562,41 @@ mod tests {
                 },
                 |_: &mut DisasterEvent| {},
             ),
+            (
+                EventPolicy {
+                    max_event_depth_km: 300.0,
+                    ..EventPolicy::default()
+                },
+                |event: &mut DisasterEvent| event.depth_km = Some(450.0),
+            ),
+            (
+                EventPolicy {
+                    min_event_magnitude: 3.0,
+                    ..EventPolicy::default()
+                },
+                |event: &mut DisasterEvent| event.magnitude = Some(2.5),
+            ),
+            (
+                EventPolicy {
+                    enabled_sources: BTreeSet::from(["wolfx.cenc_eew".to_string()]),
+                    ..EventPolicy::default()
+                },
+                |_: &mut DisasterEvent| {},
+            ),
+            (
+                EventPolicy {
+                    source_min_magnitude: BTreeMap::from([("fanstudio.cenc".to_string(), 5.5)]),
+                    ..EventPolicy::default()
+                },
+                |_: &mut DisasterEvent| {},
+            ),
+            (
+                EventPolicy {
+                    min_event_max_intensity: "3".parse().ok(),
+                    ..EventPolicy::default()
+                },
+                |event: &mut DisasterEvent| event.max_intensity = Some("3".to_string()),
+            ),
         ];
         for (policy, mutate) in cases {
             let directory = tempfile::tempdir()?;
@@ -319,6 +616,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn source_magnitude_floor_overrides_the_global_floor() -> Result<()> {
+        let policy = EventPolicy {
+            min_event_magnitude: 5.0,
+            source_min_magnitude: BTreeMap::from([("fanstudio.cenc".to_string(), 3.0)]),
+            ..EventPolicy::default()
+        };
+        let cases = [
+            ("fanstudio.cenc", Some(4.0), true),
+            ("fanstudio.cenc", Some(2.5), false),
+            ("fanstudio.usgs", Some(4.0), false),
+            ("fanstudio.usgs", Some(5.0), true),
+            ("fanstudio.usgs", None, true),
+        ];
+        for (index, (source, magnitude, matched)) in cases.into_iter().enumerate() {
+            let directory = tempfile::tempdir()?;
+            let storage = FjallStorage::open(directory.path())?;
+            let mut event = test_event(source, &format!("floor-{index}"));
+            event.magnitude = magnitude;
+            storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![event], None)?;
+
+            let job =
+                EventCoordinator::with_policy(storage.clone(), policy.clone()).process_next()?;
+
+            anyhow::ensure!(
+                job.is_some() == matched,
+                "unexpected match for {source} at {magnitude:?}"
+            );
+            anyhow::ensure!(storage.pending_match_jobs(1)?.len() == usize::from(matched));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn enabled_sources_restrict_only_earthquake_matching() -> Result<()> {
+        let policy = EventPolicy {
+            enabled_sources: BTreeSet::from(["wolfx.cenc_eew".to_string()]),
+            ..EventPolicy::default()
+        };
+        let mut enabled = test_event("wolfx.cenc_eew", "enabled");
+        enabled.category = DisasterCategory::EarthquakeWarning;
+        enabled.channel = ProviderChannel::Wolfx;
+        let mut weather = test_event("fanstudio.weatheralarm", "weather");
+        weather.category = DisasterCategory::WeatherWarning;
+        weather.magnitude = None;
+        weather.depth_km = None;
+        let disabled = test_event("fanstudio.cenc", "disabled");
+        for (event, matched) in [(enabled, true), (weather, true), (disabled, false)] {
+            let directory = tempfile::tempdir()?;
+            let storage = FjallStorage::open(directory.path())?;
+            let source = event.source.clone();
+            storage.ingest_with_cursor(event.channel, vec![event], None)?;
+
+            let job =
+                EventCoordinator::with_policy(storage.clone(), policy.clone()).process_next()?;
+
+            anyhow::ensure!(job.is_some() == matched, "unexpected match for {source}");
+            anyhow::ensure!(storage.pending_inbox(1)?.is_empty());
+            anyhow::ensure!(storage.pending_match_jobs(1)?.len() == usize::from(matched));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn suppressed_interim_reports_release_the_final_as_a_first_report() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let coordinator = EventCoordinator::with_policy(
+            storage.clone(),
+            EventPolicy {
+                push_updates: false,
+                source_interim_policy: BTreeMap::from([(
+                    "fanstudio.cenc".to_string(),
+                    InterimPolicy::Suppress,
+                )]),
+                ..EventPolicy::default()
+            },
+        );
+        let mut report = test_event("fanstudio.cenc", "interim");
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![report.clone()], None)?;
+        anyhow::ensure!(coordinator.process_next()?.is_none());
+        anyhow::ensure!(storage.pending_inbox(1)?.is_empty());
+
+        report.report_num = 2;
+        report.revision = "2".to_string();
+        report.final_report = true;
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![report], None)?;
+        let job = coordinator.process_next()?.context("missing final job")?;
+        anyhow::ensure!(job.stage == EventStage::First);
+        Ok(())
+    }
+
     fn commit_matched_job(storage: &FjallStorage, job: &MatchJob) -> Result<()> {
         let event = storage
             .event(job.event_revision)?
@@ -389,6 +778,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 }
@@ -0,0 +1,131 @@
+use crate::models::{DisasterCategory, DisasterEvent, IncidentRecord};
+use crate::utils::boundaries::{self, Territory};
+
+/// 同一地震被多家机构测定时，推送和历史记录都以同一组参数为准：日本附近采用日本气象厅的
+/// 测定，中国大陆采用中国地震台网的测定，其余地区沿用最新一报。只替换震中、震级和深度，
+/// 事件编号、报数、终报和取消标记等仍属于触发本次处理的数据源。
+pub(crate) fn best_estimate(incident: &IncidentRecord, event: &DisasterEvent) -> DisasterEvent {
+    let mut merged = event.clone();
+    if event.cancel
+        || event.training
+        || !matches!(
+            event.category,
+            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+        )
+    {
+        return merged;
+    }
+    let Some((latitude, longitude)) = event.latitude.zip(event.longitude) else {
+        return merged;
+    };
+    let authority = match boundaries::territory(latitude, longitude) {
+        Territory::Japan => "jma",
+        Territory::MainlandChina => "cenc",
+        Territory::Other => return merged,
+    };
+    if event.source.contains(authority) {
+        return merged;
+    }
+    // 正式测定比预警更准确，同类报告取报数最大的一报。
+    let preferred = incident
+        .latest_by_source
+        .iter()
+        .filter(|candidate| {
+            candidate.source.contains(authority)
+                && !candidate.cancel
+                && !candidate.training
+                && candidate.latitude.is_some()
+                && candidate.longitude.is_some()
+                && matches!(
+                    candidate.category,
+                    DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+                )
+        })
+        .max_by_key(|candidate| {
+            (
+                candidate.category == DisasterCategory::EarthquakeReport,
+                candidate.report_num,
+            )
+        });
+    if let Some(preferred) = preferred {
+        merged.latitude = preferred.latitude;
+        merged.longitude = preferred.longitude;
+        merged.magnitude = preferred.magnitude.or(event.magnitude);
+        merged.depth_km = preferred.depth_km.or(event.depth_km);
+        tracing::debug!(
+            event = "event.best_estimate_merged",
+            source = %event.source,
+            preferred_source = %preferred.source,
+            "event.best_estimate_merged"
+        );
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{IncidentId, ProviderChannel};
+
+    fn report(source: &str, latitude: f64, longitude: f64, magnitude: f64) -> DisasterEvent {
+        DisasterEvent {
+            category: DisasterCategory::EarthquakeWarning,
+            channel: ProviderChannel::Wolfx,
+            source: source.to_string(),
+            event_id: format!("{source}-event"),
+            revision: "1".to_string(),
+            report_num: 1,
+            title: String::new(),
+            description: String::new(),
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            magnitude: Some(magnitude),
+            depth_km: Some(10.0),
+            affected_regions: Vec::new(),
+            radius_km: None,
+            level: 2,
+            occurred_at: "2026-07-13T00:00:00Z".to_string(),
+            final_report: false,
+            cancel: false,
+            training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
+        }
+    }
+
+    fn incident(first: &DisasterEvent, second: &DisasterEvent) -> IncidentRecord {
+        let mut incident = IncidentRecord::new(IncidentId::derive("incident"), first, 1);
+        assert!(incident.apply(second, 2));
+        incident
+    }
+
+    #[test]
+    fn regional_authority_overrides_other_agencies() {
+        let jma = report("wolfx.jma_eew", 35.6, 139.7, 6.1);
+        let cenc = report("wolfx.cenc_eew", 35.7, 139.9, 5.8);
+        let merged = best_estimate(&incident(&jma, &cenc), &cenc);
+        assert_eq!(merged.source, "wolfx.cenc_eew");
+        assert_eq!(merged.magnitude, Some(6.1));
+        assert_eq!(merged.latitude, Some(35.6));
+
+        let cenc = report("wolfx.cenc_eew", 30.6, 104.0, 5.2);
+        let sichuan = report("wolfx.sc_eew", 30.7, 104.1, 5.6);
+        let merged = best_estimate(&incident(&cenc, &sichuan), &sichuan);
+        assert_eq!(merged.magnitude, Some(5.2));
+    }
+
+    #[test]
+    fn latest_report_stands_without_a_regional_authority() {
+        let cwa = report("fanstudio.cwa-eew", 24.0, 121.6, 6.0);
+        let cenc = report("wolfx.cenc_eew", 24.1, 121.7, 5.7);
+        let merged = best_estimate(&incident(&cenc, &cwa), &cwa);
+        assert_eq!(merged.magnitude, Some(6.0));
+
+        let mut cancelled = report("wolfx.jma_eew", 35.6, 139.7, 6.1);
+        cancelled.cancel = true;
+        let cenc = report("wolfx.cenc_eew", 35.7, 139.9, 5.8);
+        let merged = best_estimate(&incident(&cancelled, &cenc), &cenc);
+        assert_eq!(merged.magnitude, Some(5.8));
+    }
+}
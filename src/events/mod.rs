@@ -1,7 +1,10 @@
 mod coordinator;
+mod merge;
 mod reducer;
+mod tracker;
 
 pub(crate) use coordinator::{EventCoordinator, EventPolicy};
+pub(crate) use tracker::{DispatchMode, EventStage, InterimPolicy, dispatch_mode, escalated_rows};
 
 use crate::models::IncidentId;
 use serde::{Deserialize, Serialize};
@@ -13,4 +16,9 @@ pub(crate) struct MatchJob {
     pub(crate) incident_id: IncidentId,
     pub(crate) event_revision: u64,
     pub(crate) created_at_ms: i64,
+    #[serde(default)]
+    pub(crate) stage: EventStage,
+    /// 同一地震已有其他数据源先行报告；按 `cross_source_dedup` 只推送给尚未收到或明显升级的订阅。
+    #[serde(default)]
+    pub(crate) later_stream: bool,
 }
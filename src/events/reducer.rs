@@ -77,6 +77,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
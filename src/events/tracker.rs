@@ -0,0 +1,341 @@
+use super::EventPolicy;
+use crate::delivery::DeliveryRow;
+use crate::models::{DisasterEvent, IncidentStreamWatermark};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 同一事件流（类别、数据源、事件编号）所处的阶段：首报 → 续报 → 终报，任意阶段都可能被取消。
+/// 阶段由事件记录中持久化的流水位推出，随 [`super::MatchJob`] 一起保存，投递时据此决定推送方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EventStage {
+    #[default]
+    First,
+    Update,
+    Final,
+    Cancelled,
+}
+
+impl EventStage {
+    /// `previous` 是应用本报之前该事件流的水位；没有水位说明这是该事件流的第一报，
+    /// 即使它同时是终报也按首报推送。此前各报都被 `interim` 压下时，本报同样按首报推送。
+    pub(crate) fn advance(
+        previous: Option<&IncidentStreamWatermark>,
+        event: &DisasterEvent,
+        interim: InterimPolicy,
+    ) -> Self {
+        if event.cancel {
+            Self::Cancelled
+        } else if previous.is_none_or(|watermark| interim.held(watermark)) {
+            Self::First
+        } else if event.final_report {
+            Self::Final
+        } else {
+            Self::Update
+        }
+    }
+}
+
+/// 按数据源决定中间报（尚未标为终报的预警）是否推送。部分数据源的中间报变化频繁，
+/// 可以只推送终报，或等到第 N 报参数稳定后再推送。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum InterimPolicy {
+    #[default]
+    Notify,
+    Suppress,
+    /// 报数小于该值的中间报不推送。
+    Delay(u32),
+}
+
+impl InterimPolicy {
+    /// 解析 `notify`、`suppress` 或 `delay:N`。
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "notify" => Some(Self::Notify),
+            "suppress" => Some(Self::Suppress),
+            value => value
+                .strip_prefix("delay:")?
+                .trim()
+                .parse()
+                .ok()
+                .filter(|report_num| *report_num > 1)
+                .map(Self::Delay),
+        }
+    }
+
+    /// 终报和取消信息从不压下。
+    pub(crate) fn holds(self, event: &DisasterEvent) -> bool {
+        self.holds_report(event.report_num, event.final_report, event.cancel)
+    }
+
+    /// 该事件流上一报是否被压下。
+    pub(crate) fn held(self, watermark: &IncidentStreamWatermark) -> bool {
+        self.holds_report(
+            watermark.report_num,
+            watermark.final_report,
+            watermark.cancel,
+        )
+    }
+
+    fn holds_report(self, report_num: u32, final_report: bool, cancel: bool) -> bool {
+        !final_report
+            && !cancel
+            && match self {
+                Self::Notify => false,
+                Self::Suppress => true,
+                Self::Delay(first_pushed) => report_num < first_pushed,
+            }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DispatchMode {
+    /// 推送给本报匹配到的全部订阅。
+    All,
+    /// 只推送给尚未收到该事件，或本报中断级别、预估烈度明显升高的订阅。
+    EscalationOnly,
+    /// 向已收到该事件的订阅发出取消通知。
+    Cancellation,
+    /// 不推送续报时，终报只作为回顾发给已收到该事件且选择接收终报的订阅。
+    FinalFollowUp,
+}
+
+/// 旧版本写入的任务没有阶段字段，按首报处理；取消标记以事件本身为准。
+/// `later_stream` 表示同一地震已有其他数据源先行报告，开启跨数据源去重时按升级规则推送，
+/// 已收到该地震的订阅不再因另一家机构的测定重复收到推送。
+pub(crate) fn dispatch_mode(
+    stage: EventStage,
+    later_stream: bool,
+    event: &DisasterEvent,
+    policy: &EventPolicy,
+) -> DispatchMode {
+    if event.cancel {
+        return DispatchMode::Cancellation;
+    }
+    match stage {
+        EventStage::Cancelled => DispatchMode::Cancellation,
+        EventStage::Final if !policy.push_updates => DispatchMode::FinalFollowUp,
+        EventStage::Update if policy.update_escalation_only => DispatchMode::EscalationOnly,
+        EventStage::First | EventStage::Update | EventStage::Final
+            if later_stream && policy.cross_source_dedup =>
+        {
+            DispatchMode::EscalationOnly
+        }
+        EventStage::First | EventStage::Update | EventStage::Final => DispatchMode::All,
+    }
+}
+
+/// 预估烈度至少升高一度才算升级，避免续报中的细微修正反复打扰。
+const ESCALATION_INTENSITY_CENT: u16 = 100;
+
+/// 按目的地比较本报与账本中最近一次送达的记录，只保留升级的行。
+pub(crate) fn escalated_rows(
+    rows: Vec<DeliveryRow>,
+    delivered: &[DeliveryRow],
+) -> Vec<DeliveryRow> {
+    let delivered: HashMap<_, _> = delivered
+        .iter()
+        .map(|row| (row.destination_id, row))
+        .collect();
+    rows.into_iter()
+        .filter(|row| {
+            delivered.get(&row.destination_id).is_none_or(|previous| {
+                row.interruption_level > previous.interruption_level
+                    || row.intensity_cent
+                        >= previous
+                            .intensity_cent
+                            .saturating_add(ESCALATION_INTENSITY_CENT)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DisasterCategory, InterruptionLevel, ProviderChannel};
+    use crate::subscriptions::{DestinationNumericId, SubscriptionId};
+
+    fn row(
+        destination_id: u64,
+        interruption_level: InterruptionLevel,
+        intensity_cent: u16,
+    ) -> DeliveryRow {
+        DeliveryRow {
+            destination_id: DestinationNumericId(destination_id),
+            subscription_id: SubscriptionId(destination_id),
+            generation: 1,
+            target_ordinal: 0,
+            match_kind: 1,
+            interruption_level,
+            distance_m: 1_000,
+            intensity_cent,
+        }
+    }
+
+    fn event() -> DisasterEvent {
+        DisasterEvent {
+            category: DisasterCategory::EarthquakeWarning,
+            channel: ProviderChannel::Wolfx,
+            source: "wolfx.cenc_eew".to_string(),
+            event_id: "event".to_string(),
+            revision: "1".to_string(),
+            report_num: 1,
+            title: String::new(),
+            description: String::new(),
+            latitude: Some(35.0),
+            longitude: Some(105.0),
+            magnitude: Some(5.0),
+            depth_km: Some(10.0),
+            affected_regions: Vec::new(),
+            radius_km: None,
+            level: 2,
+            occurred_at: "2026-07-13T00:00:00Z".to_string(),
+            final_report: false,
+            cancel: false,
+            training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
+        }
+    }
+
+    #[test]
+    fn cancellation_and_final_reports_override_update_policy() {
+        let mut cancelled = event();
+        cancelled.cancel = true;
+        let mut first_and_final = event();
+        first_and_final.final_report = true;
+        assert_eq!(
+            EventStage::advance(None, &cancelled, InterimPolicy::Notify),
+            EventStage::Cancelled
+        );
+        assert_eq!(
+            EventStage::advance(None, &first_and_final, InterimPolicy::Notify),
+            EventStage::First
+        );
+        let escalation = EventPolicy {
+            update_escalation_only: true,
+            ..EventPolicy::default()
+        };
+        assert_eq!(
+            dispatch_mode(EventStage::First, false, &cancelled, &escalation),
+            DispatchMode::Cancellation
+        );
+        assert_eq!(
+            dispatch_mode(EventStage::Final, false, &event(), &escalation),
+            DispatchMode::All
+        );
+        assert_eq!(
+            dispatch_mode(EventStage::Update, false, &event(), &escalation),
+            DispatchMode::EscalationOnly
+        );
+        assert_eq!(
+            dispatch_mode(EventStage::Update, false, &event(), &EventPolicy::default()),
+            DispatchMode::All
+        );
+        let quiet = EventPolicy {
+            push_updates: false,
+            ..EventPolicy::default()
+        };
+        assert_eq!(
+            dispatch_mode(EventStage::Final, false, &event(), &quiet),
+            DispatchMode::FinalFollowUp
+        );
+    }
+
+    #[test]
+    fn later_streams_only_reach_unnotified_or_escalated_destinations() {
+        let policy = EventPolicy::default();
+        assert_eq!(
+            dispatch_mode(EventStage::First, true, &event(), &policy),
+            DispatchMode::EscalationOnly
+        );
+        assert_eq!(
+            dispatch_mode(EventStage::First, false, &event(), &policy),
+            DispatchMode::All
+        );
+        let mut cancelled = event();
+        cancelled.cancel = true;
+        assert_eq!(
+            dispatch_mode(EventStage::Cancelled, true, &cancelled, &policy),
+            DispatchMode::Cancellation
+        );
+        let every_source = EventPolicy {
+            cross_source_dedup: false,
+            ..EventPolicy::default()
+        };
+        assert_eq!(
+            dispatch_mode(EventStage::First, true, &event(), &every_source),
+            DispatchMode::All
+        );
+    }
+
+    #[test]
+    fn interim_policy_holds_reports_until_released() {
+        assert_eq!(
+            InterimPolicy::parse("suppress"),
+            Some(InterimPolicy::Suppress)
+        );
+        assert_eq!(
+            InterimPolicy::parse("delay:3"),
+            Some(InterimPolicy::Delay(3))
+        );
+        assert_eq!(InterimPolicy::parse("delay:1"), None);
+        assert_eq!(InterimPolicy::parse("later"), None);
+
+        let delay = InterimPolicy::Delay(3);
+        let mut report = event();
+        report.report_num = 2;
+        assert!(delay.holds(&report));
+        let watermark = IncidentStreamWatermark {
+            category: report.category,
+            source: report.source.clone(),
+            event_id: report.event_id.clone(),
+            report_num: 2,
+            level: report.level,
+            final_report: false,
+            cancel: false,
+            current_report_updates: Vec::new(),
+        };
+        report.report_num = 3;
+        assert!(!delay.holds(&report));
+        assert_eq!(
+            EventStage::advance(Some(&watermark), &report, delay),
+            EventStage::First
+        );
+        assert_eq!(
+            EventStage::advance(Some(&watermark), &report, InterimPolicy::Notify),
+            EventStage::Update
+        );
+
+        report.final_report = true;
+        assert!(InterimPolicy::Suppress.holds(&event()));
+        assert!(!InterimPolicy::Suppress.holds(&report));
+        assert_eq!(
+            EventStage::advance(Some(&watermark), &report, InterimPolicy::Suppress),
+            EventStage::First
+        );
+    }
+
+    #[test]
+    fn escalation_keeps_new_destinations_and_raised_levels() {
+        let delivered = [
+            row(1, InterruptionLevel::Active, 300),
+            row(2, InterruptionLevel::Active, 300),
+            row(3, InterruptionLevel::Active, 300),
+        ];
+        let rows = vec![
+            row(1, InterruptionLevel::Active, 350),
+            row(2, InterruptionLevel::Critical, 350),
+            row(3, InterruptionLevel::Active, 400),
+            row(4, InterruptionLevel::Passive, 100),
+        ];
+        let kept: Vec<_> = escalated_rows(rows, &delivered)
+            .into_iter()
+            .map(|row| row.destination_id.0)
+            .collect();
+        assert_eq!(kept, vec![2, 3, 4]);
+    }
+}
@@ -5,15 +5,20 @@ mod events;
 mod lifecycle;
 mod matching;
 mod models;
+mod monitor;
 mod providers;
 mod routes;
 mod runtime;
 mod source_registry;
 mod storage;
 mod subscriptions;
+mod telemetry;
+mod tls;
+mod upstream_tls;
 mod utils;
 
 pub use application::run_from_env;
+pub use monitor::{MonitorOptions, run_monitor};
 
 #[cfg(feature = "benchmarks")]
 pub mod benchmark_support;
@@ -1,3 +1,38 @@
 fn main() -> anyhow::Result<()> {
-    disaster_alert::run_from_env()
+    let mut arguments = std::env::args().skip(1);
+    match arguments.next().as_deref() {
+        Some("bench") => bench(arguments),
+        Some("monitor") => {
+            let options = disaster_alert::MonitorOptions::from_args(arguments)?;
+            disaster_alert::run_monitor(&options)
+        }
+        _ => disaster_alert::run_from_env(),
+    }
+}
+
+#[cfg(feature = "benchmarks")]
+fn bench(arguments: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use disaster_alert::benchmark_support::{LoadBenchmarkOptions, run_load_benchmark};
+    use std::io::Write;
+
+    let options = LoadBenchmarkOptions::from_args(arguments)?;
+    let path = std::env::temp_dir().join(format!("disaster-alert-bench-{}", std::process::id()));
+    anyhow::ensure!(
+        !path.exists(),
+        "benchmark database already exists: {}",
+        path.display()
+    );
+    let report = run_load_benchmark(&path, &options);
+    let cleanup = std::fs::remove_dir_all(&path)
+        .with_context(|| format!("failed to remove benchmark database {}", path.display()));
+    let report = report?;
+    cleanup?;
+    write!(std::io::stdout().lock(), "{report}")?;
+    Ok(())
+}
+
+#[cfg(not(feature = "benchmarks"))]
+fn bench(_arguments: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    anyhow::bail!("the bench subcommand requires building with --features benchmarks")
 }
@@ -4,11 +4,14 @@ use crate::subscriptions::{
     CompiledRule, CompiledSubscription, CompiledTarget, RegionId, SourceId, SubscriptionId,
     region_id, source_id,
 };
+use crate::utils::boundaries;
+use crate::utils::distance::{CoordinateBatch, haversine_distance_batch};
+use crate::utils::intensity::IntensityModel;
 use crate::utils::region;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use roaring::RoaringBitmap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -19,6 +22,7 @@ pub(crate) struct PostingBlock {
 
 struct EventMatchContext<'a> {
     event: &'a DisasterEvent,
+    model: IntensityModel,
     source_id: SourceId,
     region_ids: Vec<RegionId>,
     coordinate: Option<EventCoordinate>,
@@ -27,7 +31,7 @@ struct EventMatchContext<'a> {
 #[derive(Clone, Copy)]
 struct EventCoordinate {
     latitude_radians: f64,
-    longitude: f64,
+    longitude_radians: f64,
     cos_latitude: f64,
 }
 
@@ -35,6 +39,42 @@ pub(crate) struct MatchEngine {
     pool: rayon::ThreadPool,
 }
 
+/// 影子震度模型与生效模型逐个订阅对比推送决定的结果，只用于记录，不影响推送。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ShadowComparison {
+    /// 两个模型都评估过的候选订阅数。
+    pub(crate) evaluated: u64,
+    /// 影子模型会推送、生效模型不推送。
+    pub(crate) extra: u64,
+    /// 生效模型推送、影子模型不推送。
+    pub(crate) missed: u64,
+    /// 两者都推送，但提醒级别不同。
+    pub(crate) level_changed: u64,
+}
+
+impl ShadowComparison {
+    pub(crate) fn diverged(&self) -> u64 {
+        self.extra + self.missed + self.level_changed
+    }
+
+    fn record(&mut self, active: Option<&DeliveryRow>, shadow: Option<&DeliveryRow>) {
+        self.evaluated += 1;
+        match (active, shadow) {
+            (None, Some(_)) => self.extra += 1,
+            (Some(_), None) => self.missed += 1,
+            (Some(active), Some(shadow))
+                if active.interruption_level != shadow.interruption_level =>
+            {
+                self.level_changed += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 单个候选订阅在生效模型下的匹配结果，以及影子模型给出的决定。
+type CandidateOutcome = (Option<DeliveryRow>, Option<Option<DeliveryRow>>);
+
 impl MatchEngine {
     pub(crate) fn new(threads: usize) -> Result<Self> {
         let pool = rayon::ThreadPoolBuilder::new()
@@ -45,37 +85,100 @@ impl MatchEngine {
         Ok(Self { pool })
     }
 
+    #[cfg(any(test, feature = "benchmarks", feature = "migration"))]
     pub(crate) fn match_blocks(
         &self,
         event: Arc<DisasterEvent>,
         blocks: Vec<PostingBlock>,
         subscriptions: &HashMap<SubscriptionId, CompiledSubscription>,
     ) -> Vec<DeliveryRow> {
-        let context = EventMatchContext::new(&event);
+        self.match_blocks_with_models(
+            event,
+            blocks,
+            subscriptions,
+            IntensityModel::default(),
+            None,
+        )
+        .0
+    }
+
+    /// 用生效模型匹配候选订阅；配置了影子模型且事件为地震预警时，同时用影子模型评估
+    /// 每个候选订阅并统计决定分歧。影子模型只能看到生效模型的候选范围。
+    pub(crate) fn match_blocks_with_models(
+        &self,
+        event: Arc<DisasterEvent>,
+        blocks: Vec<PostingBlock>,
+        subscriptions: &HashMap<SubscriptionId, CompiledSubscription>,
+        model: IntensityModel,
+        shadow_model: Option<IntensityModel>,
+    ) -> (Vec<DeliveryRow>, Option<ShadowComparison>) {
+        let context = EventMatchContext::new(&event, model);
+        let shadow_context = shadow_model
+            .filter(|_model| event.category == DisasterCategory::EarthquakeWarning)
+            .map(|shadow_model| EventMatchContext::new(&event, shadow_model));
         self.pool.install(|| {
-            let rows = blocks
+            let outcomes = blocks
                 .into_par_iter()
                 .flat_map_iter(|block| {
-                    let mut best = HashMap::new();
-                    for raw_id in block.ids {
-                        let Some(id) = SubscriptionId::from_posting(block.id_block, raw_id) else {
-                            continue;
-                        };
-                        let Some(subscription) = subscriptions.get(&id) else {
-                            continue;
-                        };
-                        if let Some(row) = match_compiled_with_context(subscription, &context) {
-                            best.entry(row.subscription_id.0).or_insert(row);
-                        }
+                    let mut seen = HashSet::with_capacity(block.ids.len() as usize);
+                    let candidates = block
+                        .ids
+                        .iter()
+                        .filter_map(|raw_id| SubscriptionId::from_posting(block.id_block, raw_id))
+                        .filter(|id| seen.insert(id.0))
+                        .filter_map(|id| Some((id.0, subscriptions.get(&id)?)))
+                        .collect::<Vec<_>>();
+                    let mut outcomes = Vec::with_capacity(candidates.len());
+                    // 规则不接受该事件的订阅两个模型都不会匹配，不必计算距离。
+                    let (candidates, rejected): (Vec<_>, Vec<_>) = candidates
+                        .into_iter()
+                        .partition(|(_id, subscription)| accepts_event(subscription, &context));
+                    outcomes.extend(rejected.into_iter().map(|(id, _subscription)| {
+                        (id, (None, shadow_context.as_ref().map(|_context| None)))
+                    }));
+                    let distances = target_distances(
+                        context.coordinate,
+                        candidates.iter().map(|(_id, subscription)| *subscription),
+                    );
+                    let mut offset = 0;
+                    for (id, subscription) in candidates {
+                        let end = offset + subscription.targets.len();
+                        let distances = distances
+                            .as_deref()
+                            .and_then(|distances| distances.get(offset..end));
+                        offset = end;
+                        outcomes.push((
+                            id,
+                            (
+                                match_compiled_with_context(subscription, &context, distances),
+                                shadow_context.as_ref().map(|shadow_context| {
+                                    match_compiled_with_context(
+                                        subscription,
+                                        shadow_context,
+                                        distances,
+                                    )
+                                }),
+                            ),
+                        ));
                     }
-                    best.into_values()
+                    outcomes
                 })
-                .collect::<Vec<_>>();
-            let mut best = HashMap::with_capacity(rows.len());
-            for row in rows {
-                best.entry(row.subscription_id.0).or_insert(row);
+                .collect::<Vec<(u64, CandidateOutcome)>>();
+            let mut unique = HashMap::with_capacity(outcomes.len());
+            for (id, outcome) in outcomes {
+                unique.entry(id).or_insert(outcome);
+            }
+            let mut comparison = shadow_context
+                .as_ref()
+                .map(|_context| ShadowComparison::default());
+            let mut rows = Vec::with_capacity(unique.len());
+            for (active, shadow) in unique.into_values() {
+                if let (Some(comparison), Some(shadow)) = (comparison.as_mut(), shadow) {
+                    comparison.record(active.as_ref(), shadow.as_ref());
+                }
+                rows.extend(active);
             }
-            best.into_values().collect()
+            (rows, comparison)
         })
     }
 }
@@ -85,50 +188,106 @@ fn match_compiled(
     subscription: &CompiledSubscription,
     event: &DisasterEvent,
 ) -> Option<DeliveryRow> {
-    match_compiled_with_context(subscription, &EventMatchContext::new(event))
+    let context = EventMatchContext::new(event, IntensityModel::default());
+    let distances = target_distances(context.coordinate, std::iter::once(subscription));
+    match_compiled_with_context(subscription, &context, distances.as_deref())
 }
 
+/// 一次性计算事件到一批订阅全部监测点的距离，按订阅和监测点的顺序排列；
+/// 事件没有坐标时返回 `None`。
+fn target_distances<'a>(
+    coordinate: Option<EventCoordinate>,
+    subscriptions: impl Iterator<Item = &'a CompiledSubscription> + Clone,
+) -> Option<Vec<f64>> {
+    let coordinate = coordinate?;
+    let count = subscriptions
+        .clone()
+        .map(|subscription| subscription.targets.len())
+        .sum();
+    let mut batch = CoordinateBatch::with_capacity(count);
+    for target in subscriptions.flat_map(|subscription| &subscription.targets) {
+        batch.push(
+            target.latitude_radians,
+            (f64::from(target.longitude_e7) / 10_000_000.0).to_radians(),
+            target.cos_latitude,
+        );
+    }
+    let mut distances = Vec::with_capacity(count);
+    haversine_distance_batch(
+        coordinate.latitude_radians,
+        coordinate.longitude_radians,
+        coordinate.cos_latitude,
+        &batch,
+        &mut distances,
+    );
+    Some(distances)
+}
+
+/// 订阅是否有接受该事件类别、来源、震级和等级的规则。
+fn accepts_event(subscription: &CompiledSubscription, context: &EventMatchContext<'_>) -> bool {
+    subscription
+        .rule_for(context.event.category, context.source_id)
+        .is_some_and(|rule| rule_matches(rule, context.event, context.source_id))
+}
+
+/// `distances` 与 `subscription.targets` 一一对应，由 [`target_distances`] 批量算出。
 fn match_compiled_with_context(
     subscription: &CompiledSubscription,
     context: &EventMatchContext<'_>,
+    distances: Option<&[f64]>,
 ) -> Option<DeliveryRow> {
     let event = context.event;
-    let rule = subscription
-        .rules
-        .iter()
-        .find(|rule| rule.category == event.category)?;
+    let rule = subscription.rule_for(event.category, context.source_id)?;
     if !rule_matches(rule, event, context.source_id) {
         return None;
     }
     let mut best: Option<(&CompiledTarget, f64, u8, f64, InterruptionLevel)> = None;
-    for target in &subscription.targets {
+    for (index, target) in subscription.targets.iter().enumerate() {
         let administrative = regions_intersect(&target.region_ids, &context.region_ids);
-        let distance = context
-            .coordinate
-            .map(|coordinate| haversine_precomputed(coordinate, target));
-        let (distance, match_kind) = match event.category {
-            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport => {
+        let distance = distances.and_then(|distances| distances.get(index).copied());
+        let regional = target.boundary.map(|boundary| {
+            administrative || boundaries::get(boundary).is_some_and(|value| value.covers(event))
+        });
+        let (distance, match_kind) = match (regional, event.category) {
+            (Some(false), _) => continue,
+            // 按地区订阅没有具体位置，视为位于事件所在地，烈度按震中估算。
+            (Some(true), _) => (0.0, 2),
+            (None, DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport) => {
                 (distance?, 1)
             }
-            DisasterCategory::WeatherWarning if administrative => (distance.unwrap_or(0.0), 2),
-            DisasterCategory::WeatherWarning => {
+            (None, DisasterCategory::WeatherWarning) if administrative => {
+                (distance.unwrap_or(0.0), 2)
+            }
+            (None, DisasterCategory::WeatherWarning) => {
+                (distance.filter(|value| *value <= rule.distance_km)?, 1)
+            }
+            (None, DisasterCategory::Tsunami) if administrative => (distance.unwrap_or(0.0), 2),
+            (None, DisasterCategory::Tsunami) => continue,
+            (None, DisasterCategory::Typhoon) => {
                 (distance.filter(|value| *value <= rule.distance_km)?, 1)
             }
-            DisasterCategory::Tsunami if administrative => (distance.unwrap_or(0.0), 2),
-            DisasterCategory::Tsunami => continue,
-            DisasterCategory::Typhoon => (distance.filter(|value| *value <= rule.distance_km)?, 1),
         };
-        if matches!(
-            event.category,
-            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
-        ) && distance > rule.distance_km
+        if regional.is_none()
+            && matches!(
+                event.category,
+                DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+            )
+            && distance > rule.distance_km
         {
             continue;
         }
+        // 有行政区划的坐标目标以警报区域为准；没有区划信息的目标仍按距离估算。
+        let warned =
+            event.has_warned_areas() && regional.is_none() && !target.region_ids.is_empty();
+        if warned && !administrative {
+            continue;
+        }
+        let match_kind = if warned { 2 } else { match_kind };
         let estimated = if event.category == DisasterCategory::EarthquakeWarning {
             let depth = event.depth_km.unwrap_or_default().max(0.0);
-            let hypocentral = (distance.mul_add(distance, depth * depth)).sqrt();
-            crate::utils::intensity::estimate_intensity(event.magnitude?, hypocentral)
+            let epicentral = event.estimation_distance_km(distance);
+            let hypocentral = (epicentral.mul_add(epicentral, depth * depth)).sqrt();
+            context.model.estimate(event.magnitude?, hypocentral)
         } else {
             0.0
         };
@@ -136,14 +295,23 @@ fn match_compiled_with_context(
             bark_level(event.level)
         } else {
             let value = estimated.round() as u8;
-            let Some(band) = rule
+            // 警报区域内预计有强烈震动，估算烈度偏低时按最高一档提醒而不是漏报。
+            let Some(level) = rule
                 .intensity_bands
                 .iter()
                 .find(|band| value >= band.min && value <= band.max)
+                .map(|band| band.interruption_level)
+                .or_else(|| {
+                    rule.intensity_bands
+                        .iter()
+                        .map(|band| band.interruption_level)
+                        .max()
+                        .filter(|_level| warned)
+                })
             else {
                 continue;
             };
-            band.interruption_level
+            level
         };
         if best.is_none_or(|(_, current, _, _, _)| distance < current) {
             best = Some((target, distance, match_kind, estimated, interruption_level));
@@ -168,20 +336,16 @@ fn rule_matches(rule: &CompiledRule, event: &DisasterEvent, event_source: Source
     rule.accepts_source(event_source)
         && event.magnitude.unwrap_or_default() >= rule.min_magnitude
         && event.level >= rule.min_severity
-}
-
-fn haversine_precomputed(event: EventCoordinate, target: &CompiledTarget) -> f64 {
-    let delta_latitude = target.latitude_radians - event.latitude_radians;
-    let target_longitude = f64::from(target.longitude_e7) / 10_000_000.0;
-    let delta_longitude = (target_longitude - event.longitude).to_radians();
-    let a = ((delta_latitude / 2.0).sin().powi(2)
-        + event.cos_latitude * target.cos_latitude * (delta_longitude / 2.0).sin().powi(2))
-    .clamp(0.0, 1.0);
-    6_371.008_8 * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+        && rule.max_depth_km.is_none_or(|max_depth_km| {
+            event
+                .depth_km
+                .is_none_or(|depth_km| depth_km <= max_depth_km)
+        })
+        && !(rule.official_warnings_only && event.official_warning == Some(false))
 }
 
 impl<'a> EventMatchContext<'a> {
-    fn new(event: &'a DisasterEvent) -> Self {
+    fn new(event: &'a DisasterEvent, model: IntensityModel) -> Self {
         let mut region_ids = event
             .affected_regions
             .iter()
@@ -198,12 +362,13 @@ impl<'a> EventMatchContext<'a> {
                 let latitude_radians = latitude.to_radians();
                 EventCoordinate {
                     latitude_radians,
-                    longitude,
+                    longitude_radians: longitude.to_radians(),
                     cos_latitude: latitude_radians.cos(),
                 }
             });
         Self {
             event,
+            model,
             source_id: source_id(&event.source),
             region_ids,
             coordinate,
@@ -268,6 +433,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -288,6 +456,7 @@ mod tests {
                     .into_iter()
                     .map(|value| region_id(&region::normalize(value)))
                     .collect(),
+                boundary: None,
                 h3_cells: [0; 3],
             }],
             rules: vec![CompiledRule {
@@ -306,6 +475,8 @@ mod tests {
                 } else {
                     Vec::new()
                 },
+                max_depth_km: None,
+                official_warnings_only: false,
             }],
         }
     }
@@ -331,6 +502,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn region_only_targets_match_by_boundary_instead_of_distance() {
+        let mut sichuan = subscription(DisasterCategory::EarthquakeWarning, None);
+        sichuan.targets[0].boundary = boundaries::position("四川省");
+        let mut distant = event(DisasterCategory::EarthquakeWarning);
+        distant.latitude = Some(29.6);
+        distant.longitude = Some(102.1);
+        distant.affected_regions.clear();
+        let row = match_compiled(&sichuan, &distant);
+        assert_eq!(
+            row.map(|row| (row.match_kind, row.distance_m)),
+            Some((2, 0))
+        );
+        // 监测点坐标就在上海，但按地区订阅只认边界。
+        assert!(match_compiled(&sichuan, &event(DisasterCategory::EarthquakeWarning)).is_none());
+
+        let mut ishikawa = subscription(DisasterCategory::EarthquakeWarning, None);
+        ishikawa.targets[0].boundary = boundaries::position("石川県");
+        let mut offshore = event(DisasterCategory::EarthquakeWarning);
+        offshore.latitude = Some(37.5);
+        offshore.longitude = Some(137.5);
+        offshore.affected_regions = vec!["石川県能登".to_string()];
+        assert!(match_compiled(&ishikawa, &offshore).is_some());
+    }
+
     #[test]
     fn earthquakes_and_typhoons_require_coordinates() {
         for category in [
@@ -344,6 +540,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn explicit_source_rule_overrides_the_wildcard_rule() {
+        let report = event(DisasterCategory::EarthquakeReport);
+        let mut value = subscription(DisasterCategory::EarthquakeReport, None);
+        value.rules[0].min_magnitude = 4.0;
+        let mut cenc = value.rules[0].clone();
+        cenc.wildcard_source = false;
+        cenc.source_mask = 1_u64 << (source_id(&report.source).0 - 1);
+        cenc.min_magnitude = 6.0;
+        value.rules.push(cenc);
+        assert!(match_compiled(&value, &report).is_none());
+
+        value.rules[1].min_magnitude = 5.0;
+        assert!(match_compiled(&value, &report).is_some());
+    }
+
+    #[test]
+    fn events_deeper_than_the_rule_limit_are_skipped() {
+        let mut deep = event(DisasterCategory::EarthquakeWarning);
+        deep.depth_km = Some(450.0);
+        let mut value = subscription(DisasterCategory::EarthquakeWarning, None);
+        assert!(match_compiled(&value, &deep).is_some());
+        value.rules[0].max_depth_km = Some(300.0);
+        assert!(match_compiled(&value, &deep).is_none());
+        deep.depth_km = None;
+        assert!(match_compiled(&value, &deep).is_some());
+    }
+
+    #[test]
+    fn official_warning_rules_skip_forecasts_only() {
+        let mut forecast = event(DisasterCategory::EarthquakeWarning);
+        forecast.official_warning = Some(false);
+        let mut value = subscription(DisasterCategory::EarthquakeWarning, None);
+        assert!(match_compiled(&value, &forecast).is_some());
+        value.rules[0].official_warnings_only = true;
+        assert!(match_compiled(&value, &forecast).is_none());
+        forecast.official_warning = Some(true);
+        assert!(match_compiled(&value, &forecast).is_some());
+        forecast.official_warning = None;
+        assert!(match_compiled(&value, &forecast).is_some());
+    }
+
+    #[test]
+    fn assumed_hypocenter_estimates_intensity_as_if_closer() {
+        let mut precise = event(DisasterCategory::EarthquakeWarning);
+        precise.latitude = Some(31.8);
+        let mut assumed = precise.clone();
+        assumed.assumed_hypocenter = true;
+        let value = subscription(DisasterCategory::EarthquakeWarning, None);
+        let precise = match_compiled(&value, &precise);
+        let assumed = match_compiled(&value, &assumed);
+        assert!(
+            precise
+                .zip(assumed)
+                .is_some_and(
+                    |(precise, assumed)| assumed.intensity_cent > precise.intensity_cent
+                        && assumed.distance_m == precise.distance_m
+                )
+        );
+    }
+
+    #[test]
+    fn official_warnings_with_areas_prefer_the_warned_regions() {
+        let mut warning = event(DisasterCategory::EarthquakeWarning);
+        warning.official_warning = Some(true);
+        warning.magnitude = Some(3.0);
+        let mut inside = subscription(DisasterCategory::EarthquakeWarning, Some("上海市"));
+        inside.rules[0].intensity_bands = vec![CompiledIntensityBand {
+            min: 6,
+            max: 7,
+            interruption_level: InterruptionLevel::Critical,
+        }];
+        let row = match_compiled(&inside, &warning);
+        assert_eq!(
+            row.map(|row| (row.match_kind, row.interruption_level)),
+            Some((2, InterruptionLevel::Critical))
+        );
+        let outside = subscription(DisasterCategory::EarthquakeWarning, Some("北京"));
+        assert!(match_compiled(&outside, &warning).is_none());
+        let unknown = subscription(DisasterCategory::EarthquakeWarning, None);
+        assert_eq!(
+            match_compiled(&unknown, &warning).map(|row| row.match_kind),
+            Some(1)
+        );
+        warning.official_warning = Some(false);
+        assert!(match_compiled(&outside, &warning).is_some());
+        assert!(match_compiled(&inside, &warning).is_none());
+    }
+
     #[test]
     fn posting_block_reconstructs_the_full_subscription_id() -> Result<()> {
         let expected = SubscriptionId((5_u64 << 16) | 17);
@@ -364,6 +649,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn rejected_candidates_do_not_shift_the_distances_of_later_ones() -> Result<()> {
+        let rejected_id = SubscriptionId((1_u64 << 16) | 1);
+        let accepted_id = SubscriptionId((1_u64 << 16) | 2);
+        let mut rejected = subscription(DisasterCategory::EarthquakeReport, None);
+        rejected.subscription_id = rejected_id;
+        rejected.rules[0].min_magnitude = 9.0;
+        let far = 0.0_f64.to_radians();
+        rejected.targets.insert(
+            0,
+            CompiledTarget {
+                latitude_e7: 0,
+                longitude_e7: 0,
+                latitude_radians: far,
+                sin_latitude: far.sin(),
+                cos_latitude: far.cos(),
+                ..rejected.targets[0].clone()
+            },
+        );
+        let mut accepted = subscription(DisasterCategory::EarthquakeReport, None);
+        accepted.subscription_id = accepted_id;
+        let mut ids = RoaringBitmap::new();
+        ids.insert(1);
+        ids.insert(2);
+
+        let rows = MatchEngine::new(1)?.match_blocks(
+            Arc::new(event(DisasterCategory::EarthquakeReport)),
+            vec![PostingBlock { id_block: 1, ids }],
+            &HashMap::from([(rejected_id, rejected), (accepted_id, accepted)]),
+        );
+        anyhow::ensure!(rows.len() == 1, "{} rows", rows.len());
+        anyhow::ensure!(rows[0].subscription_id == accepted_id);
+        anyhow::ensure!(rows[0].distance_m < 1_000, "{} m", rows[0].distance_m);
+        Ok(())
+    }
+
     #[test]
     fn earthquake_warning_selects_from_targets_that_match_an_intensity_band() -> Result<()> {
         let warning = event(DisasterCategory::EarthquakeWarning);
@@ -383,6 +704,7 @@ mod tests {
             sin_latitude: latitude.sin(),
             cos_latitude: latitude.cos(),
             region_ids: Vec::new(),
+            boundary: None,
             h3_cells: [0; 3],
         });
 
@@ -3,7 +3,7 @@ mod plan;
 #[cfg(any(test, feature = "migration"))]
 mod reference;
 
-pub(crate) use engine::{MatchEngine, PostingBlock};
+pub(crate) use engine::{MatchEngine, PostingBlock, ShadowComparison};
 pub(crate) use plan::{MatchPlan, MatchScope};
 #[cfg(any(test, feature = "migration"))]
 pub(crate) use reference::match_subscription;
@@ -2,13 +2,22 @@ use crate::models::{DisasterCategory, DisasterEvent};
 use crate::subscriptions::{H3_RESOLUTIONS, RegionId, SourceId, region_id, source_id};
 use crate::utils::region;
 use anyhow::{Context, Result};
-use h3o::LatLng;
+use h3o::{CellIndex, LatLng};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// 缓存的 H3 邻域数量。台风和天气预警的后续报告通常落在同一格子，粗分辨率的大半径
+/// 邻域有上万个格子，每次重新展开都要分配并遍历整圈。
+const CELL_DISK_CACHE_CAPACITY: usize = 64;
+
+static CELL_DISKS: LazyLock<Mutex<CellDiskCache>> =
+    LazyLock::new(|| Mutex::new(CellDiskCache::new(CELL_DISK_CACHE_CAPACITY)));
 
 #[derive(Debug, Clone)]
 pub(crate) enum MatchScope {
     Cells {
         resolution_index: u8,
-        cells: Vec<u64>,
+        cells: Arc<[u64]>,
     },
     Regions(Vec<RegionId>),
     Broad,
@@ -19,11 +28,14 @@ pub(crate) struct MatchPlan {
     pub(crate) category: DisasterCategory,
     pub(crate) source_id: SourceId,
     pub(crate) scopes: Vec<MatchScope>,
+    /// 按坐标展开候选格子时的检索半径。
+    candidate_radius_km: Option<f64>,
 }
 
 impl MatchPlan {
     pub(crate) fn for_event(event: &DisasterEvent) -> Result<Self> {
         let mut scopes = Vec::with_capacity(2);
+        let mut candidate_radius_km = None;
         if matches!(
             event.category,
             DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
@@ -57,12 +69,9 @@ impl MatchPlan {
             let ring = (radius / edge_km).ceil() as u32 + 2;
             scopes.push(MatchScope::Cells {
                 resolution_index,
-                cells: cell
-                    .grid_disk::<Vec<_>>(ring)
-                    .into_iter()
-                    .map(u64::from)
-                    .collect(),
+                cells: cell_disk(cell, ring),
             });
+            candidate_radius_km = Some(radius);
         }
         if scopes.is_empty() {
             scopes.push(MatchScope::Broad);
@@ -71,8 +80,85 @@ impl MatchPlan {
             category: event.category,
             source_id: source_id(&event.source),
             scopes,
+            candidate_radius_km,
         })
     }
+
+    /// 候选集合包含距事件 `radius_km` 以内的全部坐标订阅时返回 `true`；含全量扫描的计划
+    /// 总能覆盖，只按地区匹配的计划不覆盖任何坐标。
+    pub(crate) fn covers_radius(&self, radius_km: f64) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope| matches!(scope, MatchScope::Broad))
+            || self
+                .candidate_radius_km
+                .is_some_and(|candidate| radius_km <= candidate)
+    }
+}
+
+/// 返回 `cell` 周围 `ring` 圈内的全部格子，命中缓存时不再重新展开。
+fn cell_disk(cell: CellIndex, ring: u32) -> Arc<[u64]> {
+    let key = (u64::from(cell), ring);
+    if let Some(cells) = CELL_DISKS
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .get(key)
+    {
+        return cells;
+    }
+    let cells = cell
+        .grid_disk::<Vec<_>>(ring)
+        .into_iter()
+        .map(u64::from)
+        .collect::<Arc<[u64]>>();
+    CELL_DISKS
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .insert(key, Arc::clone(&cells));
+    cells
+}
+
+/// 邻域缓存的键（中心格子，圈数）和值（最近使用时刻，邻域格子）。
+type CellDiskKey = (u64, u32);
+type CellDiskEntry = (u64, Arc<[u64]>);
+
+/// 按（中心格子，圈数）保存邻域的最近最少使用缓存。
+struct CellDiskCache {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<CellDiskKey, CellDiskEntry>,
+}
+
+impl CellDiskCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, key: (u64, u32)) -> Option<Arc<[u64]>> {
+        self.clock += 1;
+        let (last_used, cells) = self.entries.get_mut(&key)?;
+        *last_used = self.clock;
+        Some(Arc::clone(cells))
+    }
+
+    fn insert(&mut self, key: (u64, u32), cells: Arc<[u64]>) {
+        self.clock += 1;
+        if self.entries.len() >= self.capacity
+            && !self.entries.contains_key(&key)
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_key, (last_used, _cells))| *last_used)
+                .map(|(key, _entry)| *key)
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key, (self.clock, cells));
+    }
 }
 
 fn resolution_for(radius_km: f64) -> u8 {
@@ -130,6 +216,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -149,6 +238,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn repeated_plans_share_the_cached_cell_disk() -> Result<()> {
+        let cells = |plan: &MatchPlan| {
+            plan.scopes.iter().find_map(|scope| match scope {
+                MatchScope::Cells { cells, .. } => Some(Arc::clone(cells)),
+                MatchScope::Regions(_) | MatchScope::Broad => None,
+            })
+        };
+        let first = MatchPlan::for_event(&event(DisasterCategory::WeatherWarning))?;
+        let second = MatchPlan::for_event(&event(DisasterCategory::WeatherWarning))?;
+        let (first, second) = cells(&first)
+            .zip(cells(&second))
+            .context("missing cell scope")?;
+        anyhow::ensure!(Arc::ptr_eq(&first, &second));
+        Ok(())
+    }
+
+    #[test]
+    fn cell_disk_cache_evicts_the_least_recently_used_entry() {
+        let mut cache = CellDiskCache::new(2);
+        cache.insert((1, 1), Arc::from([1_u64]));
+        cache.insert((2, 1), Arc::from([2_u64]));
+        assert!(cache.get((1, 1)).is_some());
+        cache.insert((3, 1), Arc::from([3_u64]));
+        assert!(cache.get((2, 1)).is_none());
+        assert!(cache.get((1, 1)).is_some());
+        assert!(cache.get((3, 1)).is_some());
+    }
+
+    #[test]
+    fn coverage_follows_the_candidate_radius() -> Result<()> {
+        let earthquake = MatchPlan::for_event(&event(DisasterCategory::EarthquakeWarning))?;
+        anyhow::ensure!(earthquake.covers_radius(3_000.0));
+        let typhoon = MatchPlan::for_event(&event(DisasterCategory::Typhoon))?;
+        anyhow::ensure!(typhoon.covers_radius(3_000.0) && !typhoon.covers_radius(3_500.0));
+        Ok(())
+    }
+
     #[test]
     fn tsunami_without_regions_does_not_use_coordinate_candidates() -> Result<()> {
         let plan = MatchPlan::for_event(&event(DisasterCategory::Tsunami))?;
@@ -1,7 +1,7 @@
 use crate::models::{
     AlertRule, DisasterCategory, DisasterEvent, InterruptionLevel, SourceSelection, Subscription,
 };
-use crate::utils::region;
+use crate::utils::{boundaries, region};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct ReferenceMatch {
@@ -14,10 +14,7 @@ pub(crate) fn match_subscription(
     subscription: &Subscription,
     event: &DisasterEvent,
 ) -> Option<ReferenceMatch> {
-    let rule = subscription
-        .alerts
-        .iter()
-        .find(|rule| rule.category() == event.category)?;
+    let rule = subscription.alert_for_source(event.category, &event.source)?;
     if !source_matches(rule.sources(), &event.source) || !threshold_matches(rule, event) {
         return None;
     }
@@ -30,7 +27,7 @@ pub(crate) fn match_subscription(
         .collect::<Vec<_>>();
     let mut best = None;
     for (ordinal, target) in subscription.targets.iter().enumerate() {
-        let administrative = [
+        let target_regions = [
             target.region.province.as_str(),
             target.region.city.as_str(),
             target.region.district.as_str(),
@@ -38,55 +35,79 @@ pub(crate) fn match_subscription(
         .into_iter()
         .map(region::normalize)
         .filter(|value| !value.is_empty())
-        .any(|target_region| event_regions.contains(&target_region));
+        .collect::<Vec<_>>();
+        let administrative = target_regions
+            .iter()
+            .any(|target_region| event_regions.contains(target_region));
+        let anchor = target.anchor()?;
         let distance = event
             .latitude
             .zip(event.longitude)
             .map(|(latitude, longitude)| {
-                spherical_distance_km(
-                    latitude,
-                    longitude,
-                    target.point.latitude,
-                    target.point.longitude,
-                )
+                spherical_distance_km(latitude, longitude, anchor.latitude, anchor.longitude)
             });
+        let regional = target.boundary().map(|boundary| {
+            administrative || boundaries::get(boundary).is_some_and(|value| value.covers(event))
+        });
         let distance_limit = rule_distance_km(rule);
-        let (distance_km, match_kind) = match event.category {
-            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport => {
+        let (distance_km, match_kind) = match (regional, event.category) {
+            (Some(false), _) => continue,
+            (Some(true), _) => (0.0, 2),
+            (None, DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport) => {
                 (distance?, 1)
             }
-            DisasterCategory::WeatherWarning if administrative => (distance.unwrap_or(0.0), 2),
-            DisasterCategory::WeatherWarning => {
+            (None, DisasterCategory::WeatherWarning) if administrative => {
+                (distance.unwrap_or(0.0), 2)
+            }
+            (None, DisasterCategory::WeatherWarning) => {
+                (distance.filter(|value| *value <= distance_limit)?, 1)
+            }
+            (None, DisasterCategory::Tsunami) if administrative => (distance.unwrap_or(0.0), 2),
+            (None, DisasterCategory::Tsunami) => continue,
+            (None, DisasterCategory::Typhoon) => {
                 (distance.filter(|value| *value <= distance_limit)?, 1)
             }
-            DisasterCategory::Tsunami if administrative => (distance.unwrap_or(0.0), 2),
-            DisasterCategory::Tsunami => continue,
-            DisasterCategory::Typhoon => (distance.filter(|value| *value <= distance_limit)?, 1),
         };
-        if matches!(
-            event.category,
-            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
-        ) && distance_km > distance_limit
+        if regional.is_none()
+            && matches!(
+                event.category,
+                DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+            )
+            && distance_km > distance_limit
         {
             continue;
         }
+        let warned = event.has_warned_areas() && regional.is_none() && !target_regions.is_empty();
+        if warned && !administrative {
+            continue;
+        }
+        let match_kind = if warned { 2 } else { match_kind };
         let interruption_level = match rule {
             AlertRule::EarthquakeWarning {
                 estimated_intensity_bands,
                 ..
             } => {
                 let depth = event.depth_km.unwrap_or_default().max(0.0);
-                let hypocentral = (distance_km.mul_add(distance_km, depth * depth)).sqrt();
+                let epicentral = event.estimation_distance_km(distance_km);
+                let hypocentral = (epicentral.mul_add(epicentral, depth * depth)).sqrt();
                 let estimated =
                     crate::utils::intensity::estimate_intensity(event.magnitude?, hypocentral);
                 let rounded = estimated.round() as u8;
-                let Some(band) = estimated_intensity_bands
+                let Some(level) = estimated_intensity_bands
                     .iter()
                     .find(|band| rounded >= band.min && rounded <= band.max)
+                    .map(|band| band.interruption_level)
+                    .or_else(|| {
+                        estimated_intensity_bands
+                            .iter()
+                            .map(|band| band.interruption_level)
+                            .max()
+                            .filter(|_level| warned)
+                    })
                 else {
                     continue;
                 };
-                band.interruption_level
+                level
             }
             AlertRule::EarthquakeReport { .. }
             | AlertRule::WeatherWarning { .. }
@@ -133,8 +154,8 @@ pub(crate) fn sample_events(subscriptions: &[Subscription], limit: usize) -> Vec
                     report_num: 1,
                     title: String::new(),
                     description: String::new(),
-                    latitude: Some(target.point.latitude),
-                    longitude: Some(target.point.longitude),
+                    latitude: target.anchor().map(|point| point.latitude),
+                    longitude: target.anchor().map(|point| point.longitude),
                     magnitude: Some(8.0),
                     depth_km: Some(0.0),
                     affected_regions,
@@ -144,6 +165,9 @@ pub(crate) fn sample_events(subscriptions: &[Subscription], limit: usize) -> Vec
                     final_report: false,
                     cancel: false,
                     training: false,
+                    max_intensity: None,
+                    official_warning: None,
+                    assumed_hypocenter: false,
                 });
                 if events.len() >= limit {
                     return events;
@@ -173,6 +197,16 @@ fn source_matches(selection: &SourceSelection, source: &str) -> bool {
 }
 
 fn threshold_matches(rule: &AlertRule, event: &DisasterEvent) -> bool {
+    if let Some(max_depth_km) = rule.max_depth_km()
+        && event
+            .depth_km
+            .is_some_and(|depth_km| depth_km > max_depth_km)
+    {
+        return false;
+    }
+    if rule.official_warnings_only() && event.official_warning == Some(false) {
+        return false;
+    }
     match rule {
         AlertRule::EarthquakeWarning { .. } | AlertRule::Typhoon { .. } => true,
         AlertRule::EarthquakeReport { min_magnitude, .. } => {
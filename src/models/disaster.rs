@@ -2,6 +2,12 @@ use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+/// 展示时间统一使用的北京时间偏移。
+const BEIJING_UTC_OFFSET_SECONDS: i64 = 8 * 3_600;
+
+/// 推定震源的位置误差余量；估算烈度时按目标离震中再近这么多计算。
+pub const ASSUMED_HYPOCENTER_MARGIN_KM: f64 = 50.0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DisasterCategory {
@@ -64,21 +70,51 @@ pub struct DisasterEvent {
     pub final_report: bool,
     pub cancel: bool,
     pub training: bool,
+    /// 数据源给出的最大烈度（如日本震度“5-”、中国烈度“7.1”），原样保存。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_intensity: Option<String>,
+    /// 区分正式警报与预报的数据源（日本气象厅 `isWarn`）给出的分类；其他数据源为空。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub official_warning: Option<bool>,
+    /// 数据源标注震源为推定值（日本气象厅的 PLUM 法、阈值法等），位置可能偏差数十公里。
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub assumed_hypocenter: bool,
 }
 
 impl DisasterEvent {
     pub fn event_key(&self) -> String {
-        let category = self.category.as_str();
-        format!(
-            "{}:{}{}:{}{}:{}",
-            category.len(),
-            category,
-            self.source.len(),
-            self.source,
-            self.event_id.len(),
-            self.event_id
-        )
+        event_key_for(self.category, &self.source, &self.event_id)
     }
+
+    /// 正式警报附带了警报区域时，按区域而不是距离和估算烈度决定推送对象。
+    pub fn has_warned_areas(&self) -> bool {
+        self.category == DisasterCategory::EarthquakeWarning
+            && self.official_warning == Some(true)
+            && !self.affected_regions.is_empty()
+    }
+
+    /// 估算烈度时使用的震中距。推定震源可能偏离实际位置，按偏近估算以免低估附近的烈度。
+    pub fn estimation_distance_km(&self, distance_km: f64) -> f64 {
+        if self.assumed_hypocenter {
+            (distance_km - ASSUMED_HYPOCENTER_MARGIN_KM).max(0.0)
+        } else {
+            distance_km
+        }
+    }
+}
+
+/// 按类别、数据源和事件编号拼出与 [`DisasterEvent::event_key`] 相同的键，用于不持有事件时查找。
+pub(crate) fn event_key_for(category: DisasterCategory, source: &str, event_id: &str) -> String {
+    let category = category.as_str();
+    format!(
+        "{}:{}{}:{}{}:{}",
+        category.len(),
+        category,
+        source.len(),
+        source,
+        event_id.len(),
+        event_id
+    )
 }
 
 pub(crate) fn event_update_digest(event: &DisasterEvent) -> [u8; 16] {
@@ -154,13 +190,92 @@ fn hash_optional_f64(hash: &mut Sha256, value: Option<f64>) {
 }
 
 pub(crate) fn parse_event_epoch(event: &DisasterEvent) -> Option<i64> {
-    parse_datetime_epoch_seconds(
+    parse_event_epoch_ms(event).map(|value| value.div_euclid(1_000))
+}
+
+/// 按来源的默认时区解析发生时间，保留毫秒；时间自带偏移或 `Z` 时以自带的为准。
+pub(crate) fn parse_event_epoch_ms(event: &DisasterEvent) -> Option<i64> {
+    parse_datetime_epoch_ms(
         &event.occurred_at,
         crate::source_registry::default_utc_offset_seconds(&event.source),
     )
 }
 
+/// 用于展示的发生时间。各来源分别使用 UTC、UTC+8 和 UTC+9，统一换算为北京时间；
+/// 无法解析时原样返回，交由调用方清理。
+pub(crate) fn display_event_time(event: &DisasterEvent) -> String {
+    display_source_time(&event.occurred_at, &event.source)
+}
+
+/// 与 [`display_event_time`] 相同，用于只保存了来源和原始时间的通知快照。
+pub(crate) fn display_source_time(occurred_at: &str, source: &str) -> String {
+    let Some(value) = parse_datetime_epoch_ms(
+        occurred_at,
+        crate::source_registry::default_utc_offset_seconds(source),
+    ) else {
+        return occurred_at.to_string();
+    };
+    let local = value.div_euclid(1_000) + BEIJING_UTC_OFFSET_SECONDS;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let day_seconds = local.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}（北京时间）",
+        day_seconds / 3_600,
+        day_seconds % 3_600 / 60,
+        day_seconds % 60
+    )
+}
+
+/// 将 Unix 毫秒时间戳格式化为 UTC 的 RFC 3339 时间。
+pub(crate) fn epoch_millis_to_rfc3339(value: i64) -> anyhow::Result<String> {
+    let seconds = value.div_euclid(1_000);
+    let millis = value.rem_euclid(1_000);
+    let days = seconds.div_euclid(86_400);
+    let day_seconds = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    if !(1970..=9999).contains(&year) {
+        anyhow::bail!("timestamp is outside the supported date range");
+    }
+    let hour = day_seconds / 3_600;
+    let minute = day_seconds.rem_euclid(3_600) / 60;
+    let second = day_seconds.rem_euclid(60);
+    Ok(format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{millis:03}Z"
+    ))
+}
+
+/// 解析带时区偏移或 `Z` 的 RFC 3339 时间为 Unix 毫秒；没有偏移时无法确定时区，返回 `None`。
+pub(crate) fn rfc3339_to_epoch_millis(value: &str) -> Option<i64> {
+    parse_datetime_epoch_ms(value, None)
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let shifted = days + 719_468;
+    let era = (if shifted >= 0 {
+        shifted
+    } else {
+        shifted - 146_096
+    })
+    .div_euclid(146_097);
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        .div_euclid(365);
+    let year = year_of_era + era * 400;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_part = (5 * day_of_year + 2).div_euclid(153);
+    let day = day_of_year - (153 * month_part + 2).div_euclid(5) + 1;
+    let month = month_part + if month_part < 10 { 3 } else { -9 };
+    let year = year + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
 fn parse_datetime_epoch_seconds(value: &str, default_offset_seconds: Option<i64>) -> Option<i64> {
+    parse_datetime_epoch_ms(value, default_offset_seconds).map(|value| value.div_euclid(1_000))
+}
+
+fn parse_datetime_epoch_ms(value: &str, default_offset_seconds: Option<i64>) -> Option<i64> {
     let (date, raw_time) = value.trim().split_once([' ', 'T'])?;
     let mut date_parts = date.split(['-', '/']);
     let year = date_parts.next()?.parse::<i64>().ok()?;
@@ -173,7 +288,7 @@ fn parse_datetime_epoch_seconds(value: &str, default_offset_seconds: Option<i64>
     let mut time_parts = time.split(':');
     let hour = time_parts.next()?.parse::<i64>().ok()?;
     let minute = time_parts.next()?.parse::<i64>().ok()?;
-    let second = time_parts.next().map_or(Some(0), |item| {
+    let (second, millis) = time_parts.next().map_or(Some((0, 0)), |item| {
         let (seconds, fraction) = item
             .split_once('.')
             .map_or((item, None), |(seconds, fraction)| {
@@ -184,7 +299,15 @@ fn parse_datetime_epoch_seconds(value: &str, default_offset_seconds: Option<i64>
         }) {
             return None;
         }
-        seconds.parse().ok()
+        // 只保留到毫秒，更细的部分直接截断。
+        let millis = fraction.map_or(0, |fraction| {
+            fraction
+                .bytes()
+                .chain(std::iter::repeat(b'0'))
+                .take(3)
+                .fold(0, |value, digit| value * 10 + i64::from(digit - b'0'))
+        });
+        Some((seconds.parse::<i64>().ok()?, millis))
     })?;
     if time_parts.next().is_some()
         || !(1970..=9999).contains(&year)
@@ -196,10 +319,9 @@ fn parse_datetime_epoch_seconds(value: &str, default_offset_seconds: Option<i64>
     {
         return None;
     }
-    Some(
-        days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
-            - explicit_offset.or(default_offset_seconds)?,
-    )
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - explicit_offset.or(default_offset_seconds)?;
+    Some(seconds * 1_000 + millis)
 }
 
 fn parse_time_offset(value: &str) -> Option<(&str, Option<i64>)> {
@@ -255,12 +377,13 @@ fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
 }
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ProviderChannel {
     Wolfx,
     FanStudio,
     Huania,
+    P2pquake,
 }
 
 impl ProviderChannel {
@@ -269,6 +392,7 @@ impl ProviderChannel {
             Self::Wolfx => "wolfx",
             Self::FanStudio => "fanstudio",
             Self::Huania => "huania",
+            Self::P2pquake => "p2pquake",
         }
     }
 }
@@ -304,9 +428,41 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
+    #[test]
+    fn keeps_milliseconds_and_displays_beijing_time() {
+        let mut jma = event(DisasterCategory::EarthquakeWarning, "wolfx.jma_eew", "jma");
+        jma.occurred_at = "2026-07-10 13:34:56.789".to_string();
+        assert_eq!(
+            parse_event_epoch_ms(&jma).map(|value| value.rem_euclid(1_000)),
+            Some(789)
+        );
+        assert_eq!(display_event_time(&jma), "2026-07-10 12:34:56（北京时间）");
+
+        let mut usgs = event(DisasterCategory::EarthquakeReport, "fanstudio.usgs", "usgs");
+        usgs.occurred_at = "2026-07-10T20:00:00.5Z".to_string();
+        assert_eq!(
+            parse_event_epoch_ms(&usgs).map(|value| value.rem_euclid(1_000)),
+            Some(500)
+        );
+        assert_eq!(display_event_time(&usgs), "2026-07-11 04:00:00（北京时间）");
+
+        usgs.occurred_at = "2026-07-10 20:00:00".to_string();
+        assert_eq!(display_event_time(&usgs), "2026-07-10 20:00:00");
+    }
+
+    #[test]
+    fn converts_epoch_millis_to_utc() -> anyhow::Result<()> {
+        anyhow::ensure!(epoch_millis_to_rfc3339(0)? == "1970-01-01T00:00:00.000Z");
+        anyhow::ensure!(epoch_millis_to_rfc3339(1_735_689_600_123)? == "2025-01-01T00:00:00.123Z");
+        Ok(())
+    }
+
     #[test]
     fn event_key_includes_category_and_is_unambiguous() {
         let warning = event(DisasterCategory::EarthquakeWarning, "ab", "c");
@@ -384,6 +540,7 @@ mod tests {
             "wolfx.sc_eew",
             "wolfx.fj_eew",
             "wolfx.cq_eew",
+            "wolfx.cwa_eew",
         ] {
             let value = event(DisasterCategory::EarthquakeWarning, source, "event-1");
             assert_eq!(parse_event_epoch(&value), expected, "source {source}");
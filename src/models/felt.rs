@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// 震感报告的最高震度，与 JMA 震度的上限一致。
+pub const MAX_FELT_INTENSITY: u8 = 7;
+
+/// 用户提交的震感报告：对哪次地震、实际感受到的震度和大致位置。
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeltReportRequest {
+    /// 数据源的事件编号，与历史查询返回的 `event_id` 相同。
+    pub event_id: String,
+    /// 事件所属的地震数据源；不指定时在全部地震数据源和导入目录中查找。
+    #[serde(default)]
+    pub source: Option<String>,
+    /// 实际感受到的震度，0 表示没有感觉。
+    pub intensity: u8,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+/// 存储的震感报告。坐标只保留两位小数（约 1 千米），不记录来源 IP。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeltReport {
+    pub intensity: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    pub reported_at_ms: i64,
+}
@@ -142,6 +142,20 @@ impl IncidentRecord {
         self.apply_outcome(event, now_ms).applied()
     }
 
+    /// 事件是否属于后加入的事件流，即同一事件已有其他数据源先行报告。
+    pub fn is_later_stream(&self, event: &DisasterEvent) -> bool {
+        self.stream_watermarks
+            .first()
+            .is_some_and(|first| !first.matches(event))
+    }
+
+    /// 事件所属数据源事件流的当前水位。
+    pub fn stream_watermark(&self, event: &DisasterEvent) -> Option<&IncidentStreamWatermark> {
+        self.stream_watermarks
+            .iter()
+            .find(|watermark| watermark.matches(event))
+    }
+
     pub fn apply_outcome(&mut self, event: &DisasterEvent, now_ms: i64) -> IncidentApplyOutcome {
         let event = bounded_event(event);
         if self.state_version == u64::MAX {
@@ -366,6 +380,10 @@ fn bounded_event(event: &DisasterEvent) -> Cow<'_, DisasterEvent> {
     let exceeds_bounds = exceeds_char_limit(&event.title, 180)
         || exceeds_char_limit(&event.description, 4_000)
         || exceeds_char_limit(&event.occurred_at, 80)
+        || event
+            .max_intensity
+            .as_deref()
+            .is_some_and(|value| exceeds_char_limit(value, 16))
         || event.affected_regions.len() > 20
         || event
             .affected_regions
@@ -379,6 +397,9 @@ fn bounded_event(event: &DisasterEvent) -> Cow<'_, DisasterEvent> {
     truncate_in_place(&mut bounded.title, 180);
     truncate_in_place(&mut bounded.description, 4_000);
     truncate_in_place(&mut bounded.occurred_at, 80);
+    if let Some(max_intensity) = &mut bounded.max_intensity {
+        truncate_in_place(max_intensity, 16);
+    }
     bounded.affected_regions.truncate(20);
     for region in &mut bounded.affected_regions {
         truncate_in_place(region, 80);
@@ -422,6 +443,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -1,7 +1,9 @@
 mod disaster;
+mod felt;
 mod incident;
 mod subscription;
 
 pub use disaster::*;
+pub use felt::*;
 pub use incident::*;
 pub use subscription::*;
@@ -3,12 +3,82 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+const MAX_TARGETS: usize = 3;
 const MAX_TARGET_FIELD_CHARS: usize = 80;
+pub const MAX_DEVICE_LABEL_CHARS: usize = 40;
+const MAX_ALERT_RULES: usize = 10;
+pub(crate) const UNKNOWN_REGION_MESSAGE: &str =
+    "省略坐标按地区订阅时，省级行政区须为中国省级行政区或日本都道府县";
+const MAX_INTENSITY: u8 = 7;
+const MAGNITUDE_RANGE: LimitRange<f64> = LimitRange {
+    min: 0.0,
+    max: 10.0,
+};
+const SEVERITY_RANGE: LimitRange<u8> = LimitRange { min: 1, max: 4 };
+/// 已知最深的地震约 700 千米。
+const DEPTH_KM_RANGE: LimitRange<f64> = LimitRange {
+    min: 0.0,
+    max: 700.0,
+};
+const FALLBACK_RADIUS_KM_RANGE: LimitRange<f64> = LimitRange {
+    min: 1.0,
+    max: 2_000.0,
+};
+const TYPHOON_DISTANCE_KM_RANGE: LimitRange<f64> = LimitRange {
+    min: 1.0,
+    max: 3_000.0,
+};
+
+/// 订阅校验使用的取值范围，通过 `GET /api/config` 提供给前端做同样的表单校验。
+pub const SUBSCRIPTION_LIMITS: SubscriptionLimits = SubscriptionLimits {
+    max_targets: MAX_TARGETS,
+    max_target_field_chars: MAX_TARGET_FIELD_CHARS,
+    max_device_label_chars: MAX_DEVICE_LABEL_CHARS,
+    max_alert_rules: MAX_ALERT_RULES,
+    intensity: LimitRange {
+        min: 0,
+        max: MAX_INTENSITY,
+    },
+    magnitude: MAGNITUDE_RANGE,
+    max_depth_km: DEPTH_KM_RANGE,
+    severity: SEVERITY_RANGE,
+    weather_fallback_radius_km: FALLBACK_RADIUS_KM_RANGE,
+    typhoon_max_center_distance_km: TYPHOON_DISTANCE_KM_RANGE,
+};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SubscriptionLimits {
+    pub max_targets: usize,
+    pub max_target_field_chars: usize,
+    pub max_device_label_chars: usize,
+    pub max_alert_rules: usize,
+    pub intensity: LimitRange<u8>,
+    pub magnitude: LimitRange<f64>,
+    pub max_depth_km: LimitRange<f64>,
+    pub severity: LimitRange<u8>,
+    pub weather_fallback_radius_km: LimitRange<f64>,
+    pub typhoon_max_center_distance_km: LimitRange<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LimitRange<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T: PartialOrd> LimitRange<T> {
+    fn contains(&self, value: &T) -> bool {
+        *value >= self.min && *value <= self.max
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Subscription {
     pub destination: NotificationDestination,
+    /// 用户为设备填写的备注，例如“妈妈的手机”，只用于区分同一用户的多台设备。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
     pub targets: Vec<MonitoringTarget>,
     pub alerts: Vec<AlertRule>,
     pub created_at: i64,
@@ -37,6 +107,13 @@ impl NotificationDestination {
         }
     }
 
+    /// 推送渠道类型，与序列化时的 `type` 字段一致。
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Bark { .. } => "bark",
+        }
+    }
+
     pub fn id(&self) -> DestinationId {
         DestinationId {
             base_url: self.bark_base_url().to_string(),
@@ -57,11 +134,35 @@ pub struct DestinationId {
 pub struct MonitoringTarget {
     #[serde(default)]
     pub label: String,
-    pub point: GeoPoint,
+    /// 省略坐标时按地区订阅：省级行政区必须是内置边界之一，按震中和影响地区列表匹配。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point: Option<GeoPoint>,
     #[serde(default)]
     pub region: AdministrativeRegion,
 }
 
+impl MonitoringTarget {
+    /// 按地区订阅时对应的内置边界下标；按坐标订阅时为 `None`。
+    pub(crate) fn boundary(&self) -> Option<u16> {
+        if self.point.is_some() {
+            return None;
+        }
+        crate::utils::boundaries::position(&self.region.province)
+    }
+
+    /// 用于测距的坐标：按坐标订阅时是该坐标，按地区订阅时是内置边界的代表点。
+    pub(crate) fn anchor(&self) -> Option<GeoPoint> {
+        self.point.or_else(|| {
+            let (latitude, longitude) =
+                crate::utils::boundaries::find(&self.region.province)?.anchor;
+            Some(GeoPoint {
+                latitude,
+                longitude,
+            })
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct GeoPoint {
@@ -86,10 +187,21 @@ pub enum AlertRule {
     EarthquakeWarning {
         sources: SourceSelection,
         estimated_intensity_bands: Vec<IntensityBand>,
+        /// 震源深于该值（千米）的地震不推送；深度未知时照常推送。
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_depth_km: Option<f64>,
+        /// 收到过预警的地震发布终报后，以较温和的级别再推送一次确认后的震级、深度和最大烈度。
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        final_followup: bool,
+        /// 只接收正式警报；区分警报与预报的数据源发布的预报不推送，其他数据源不受影响。
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        official_warnings_only: bool,
     },
     EarthquakeReport {
         sources: SourceSelection,
         min_magnitude: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_depth_km: Option<f64>,
     },
     WeatherWarning {
         sources: SourceSelection,
@@ -127,6 +239,36 @@ impl AlertRule {
         }
     }
 
+    /// 地震规则的最大震源深度；其他灾种没有该限制。
+    pub fn max_depth_km(&self) -> Option<f64> {
+        match self {
+            Self::EarthquakeWarning { max_depth_km, .. }
+            | Self::EarthquakeReport { max_depth_km, .. } => *max_depth_km,
+            Self::WeatherWarning { .. } | Self::Tsunami { .. } | Self::Typhoon { .. } => None,
+        }
+    }
+
+    /// 是否接收地震预警终报回顾；只有地震预警规则可以开启。
+    pub fn final_followup(&self) -> bool {
+        matches!(
+            self,
+            Self::EarthquakeWarning {
+                final_followup: true,
+                ..
+            }
+        )
+    }
+
+    pub fn official_warnings_only(&self) -> bool {
+        matches!(
+            self,
+            Self::EarthquakeWarning {
+                official_warnings_only: true,
+                ..
+            }
+        )
+    }
+
     pub fn default_for(category: DisasterCategory) -> Self {
         let sources = SourceSelection::All;
         match category {
@@ -149,10 +291,14 @@ impl AlertRule {
                         interruption_level: InterruptionLevel::Critical,
                     },
                 ],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
             DisasterCategory::EarthquakeReport => Self::EarthquakeReport {
                 sources,
                 min_magnitude: 4.5,
+                max_depth_km: None,
             },
             DisasterCategory::WeatherWarning => Self::WeatherWarning {
                 sources,
@@ -186,7 +332,7 @@ pub struct IntensityBand {
     pub interruption_level: InterruptionLevel,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum InterruptionLevel {
     Passive,
@@ -213,6 +359,7 @@ impl Subscription {
         let now = current_timestamp_millis();
         Self {
             destination,
+            label: None,
             targets,
             alerts,
             created_at: now,
@@ -220,6 +367,11 @@ impl Subscription {
         }
     }
 
+    pub fn with_label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
     pub fn device_key(&self) -> &str {
         self.destination.bark_device_key()
     }
@@ -238,6 +390,25 @@ impl Subscription {
             .find(|alert| alert.category() == category)
     }
 
+    /// 同一灾种可以按来源配置多条规则：明确列出该来源的规则优先，其次是适用全部来源的规则。
+    pub fn alert_for_source(&self, category: DisasterCategory, source: &str) -> Option<&AlertRule> {
+        let mut fallback = None;
+        for alert in self
+            .alerts
+            .iter()
+            .filter(|alert| alert.category() == category)
+        {
+            match alert.sources() {
+                SourceSelection::Include { ids } if ids.iter().any(|id| id == source) => {
+                    return Some(alert);
+                }
+                SourceSelection::Include { .. } => {}
+                SourceSelection::All => fallback = Some(alert),
+            }
+        }
+        fallback
+    }
+
     #[cfg(test)]
     pub fn interruption_level_for_intensity(
         &self,
@@ -283,31 +454,69 @@ impl Subscription {
                 }
             }
         }
-        if self.targets.is_empty() || self.targets.len() > 3 {
+        if let Some(label) = &self.label {
+            if label.trim().is_empty() || label.chars().any(char::is_control) {
+                return Err("设备备注不能为空或包含控制字符".to_string());
+            }
+            if label.chars().count() > MAX_DEVICE_LABEL_CHARS {
+                return Err(format!("设备备注最多 {MAX_DEVICE_LABEL_CHARS} 个字符"));
+            }
+        }
+        if self.targets.is_empty() || self.targets.len() > MAX_TARGETS {
             return Err("监测目标数量必须在 1 到 3 个之间".to_string());
         }
-        if self.alerts.is_empty() || self.alerts.len() > DisasterCategory::ALL.len() {
+        if self.alerts.is_empty() {
             return Err("请至少启用一种灾害类别".to_string());
         }
+        if self.alerts.len() > MAX_ALERT_RULES {
+            return Err(format!("预警规则最多 {MAX_ALERT_RULES} 条"));
+        }
 
-        let mut categories = HashSet::new();
         for target in &self.targets {
             validate_target(target)?;
         }
+        // 每个来源最多命中一条明确规则，每个灾种最多一条适用全部来源的规则。
+        let mut wildcard_categories = HashSet::new();
+        let mut selected_sources = HashSet::new();
         for alert in &self.alerts {
-            if !categories.insert(alert.category()) {
-                return Err(format!("灾害类别 {} 不能重复", alert.category().as_str()));
-            }
             validate_alert(alert)?;
+            match alert.sources() {
+                SourceSelection::All => {
+                    if !wildcard_categories.insert(alert.category()) {
+                        return Err(format!(
+                            "灾害类别 {} 只能有一条适用全部来源的规则",
+                            alert.category().as_str()
+                        ));
+                    }
+                }
+                SourceSelection::Include { ids } => {
+                    for id in ids {
+                        if !selected_sources.insert(id.as_str()) {
+                            return Err(format!("灾害来源 {id} 只能出现在一条规则中"));
+                        }
+                    }
+                }
+            }
         }
         Ok(())
     }
 }
 
 fn validate_target(target: &MonitoringTarget) -> Result<(), String> {
-    if !crate::utils::distance::validate_coordinates(target.point.latitude, target.point.longitude)
-    {
-        return Err("监测地点坐标无效".to_string());
+    match target.point {
+        Some(point)
+            if !crate::utils::distance::validate_coordinates(point.latitude, point.longitude) =>
+        {
+            return Err(crate::utils::distance::normalize_coordinates(
+                point.latitude,
+                point.longitude,
+            )
+            .err()
+            .map_or("监测地点坐标无效", |error| error.message())
+            .to_string());
+        }
+        None if target.boundary().is_none() => return Err(UNKNOWN_REGION_MESSAGE.to_string()),
+        Some(_) | None => {}
     }
     for (label, value) in [
         ("名称", &target.label),
@@ -329,13 +538,18 @@ fn validate_target(target: &MonitoringTarget) -> Result<(), String> {
 
 fn validate_alert(alert: &AlertRule) -> Result<(), String> {
     validate_sources(alert.category(), alert.sources())?;
+    if let Some(max_depth_km) = alert.max_depth_km()
+        && !(max_depth_km.is_finite() && DEPTH_KM_RANGE.contains(&max_depth_km))
+    {
+        return Err("地震最大震源深度必须在 0 到 700 千米之间".to_string());
+    }
     match alert {
         AlertRule::EarthquakeWarning {
             estimated_intensity_bands,
             ..
         } => validate_intensity_bands(estimated_intensity_bands),
         AlertRule::EarthquakeReport { min_magnitude, .. } => {
-            if min_magnitude.is_finite() && (0.0..=10.0).contains(min_magnitude) {
+            if min_magnitude.is_finite() && MAGNITUDE_RANGE.contains(min_magnitude) {
                 Ok(())
             } else {
                 Err("地震信息最低震级必须在 0 到 10 之间".to_string())
@@ -347,7 +561,9 @@ fn validate_alert(alert: &AlertRule) -> Result<(), String> {
             ..
         } => {
             validate_severity(*min_severity)?;
-            if fallback_radius_km.is_finite() && (1.0..=2_000.0).contains(fallback_radius_km) {
+            if fallback_radius_km.is_finite()
+                && FALLBACK_RADIUS_KM_RANGE.contains(fallback_radius_km)
+            {
                 Ok(())
             } else {
                 Err("气象预警回退半径必须在 1 到 2000 公里之间".to_string())
@@ -359,7 +575,7 @@ fn validate_alert(alert: &AlertRule) -> Result<(), String> {
             ..
         } => {
             if max_center_distance_km.is_finite()
-                && (1.0..=3_000.0).contains(max_center_distance_km)
+                && TYPHOON_DISTANCE_KM_RANGE.contains(max_center_distance_km)
             {
                 Ok(())
             } else {
@@ -398,13 +614,13 @@ fn validate_intensity_bands(bands: &[IntensityBand]) -> Result<(), String> {
     let mut levels = HashSet::new();
     let mut covered = HashSet::new();
     for band in bands {
-        if band.min > band.max || band.max > 7 {
+        if band.min > band.max || band.max > MAX_INTENSITY {
             return Err("地震预警烈度范围必须在 0 到 7 之间".to_string());
         }
         if !levels.insert(band.interruption_level) {
             return Err("每个 Bark 中断级别只能配置一条烈度规则".to_string());
         }
-        if band.interruption_level == InterruptionLevel::Critical && band.max != 7 {
+        if band.interruption_level == InterruptionLevel::Critical && band.max != MAX_INTENSITY {
             return Err("critical 烈度规则必须覆盖到烈度 7".to_string());
         }
         for intensity in band.min..=band.max {
@@ -417,7 +633,7 @@ fn validate_intensity_bands(bands: &[IntensityBand]) -> Result<(), String> {
 }
 
 fn validate_severity(severity: u8) -> Result<(), String> {
-    if (1..=4).contains(&severity) {
+    if SEVERITY_RANGE.contains(&severity) {
         Ok(())
     } else {
         Err("灾害最低严重度必须在 1 到 4 之间".to_string())
@@ -435,14 +651,79 @@ fn current_timestamp_millis() -> i64 {
 #[serde(deny_unknown_fields)]
 pub struct SubscribeRequest {
     pub destination: NotificationDestination,
+    /// 可选的设备备注，空白备注视为未填写。
+    #[serde(default)]
+    pub label: Option<String>,
     pub targets: Vec<MonitoringTarget>,
     pub alerts: Vec<AlertRule>,
+    /// 实例开启订阅验证时必填，见 `GET /api/subscribe-challenge`。
+    #[serde(default)]
+    pub challenge: Option<ChallengeSolution>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChallengeSolution {
+    pub token: String,
+    pub nonce: String,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct UnsubscribeRequest {
     pub destination: NotificationDestination,
+    /// 同时删除送达、重试和死信等历史记录。
+    #[serde(default)]
+    pub purge: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreSubscriptionRequest {
+    pub destination: NotificationDestination,
+}
+
+/// 预览请求：假设的事件和一组监测地点，只渲染通知内容，不保存也不推送。
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationPreviewRequest {
+    pub event: PreviewEvent,
+    pub targets: Vec<MonitoringTarget>,
+}
+
+/// 预览用的事件，只包含影响通知内容的字段；未填写的发生时间视为当前时间。
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreviewEvent {
+    pub category: DisasterCategory,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default = "default_preview_report_num")]
+    pub report_num: u32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub magnitude: Option<f64>,
+    pub depth_km: Option<f64>,
+    #[serde(default)]
+    pub affected_regions: Vec<String>,
+    pub radius_km: Option<f64>,
+    #[serde(default)]
+    pub level: u8,
+    pub occurred_at: Option<String>,
+    #[serde(default)]
+    pub final_report: bool,
+    #[serde(default)]
+    pub cancel: bool,
+    #[serde(default)]
+    pub training: bool,
+}
+
+const fn default_preview_report_num() -> u32 {
+    1
 }
 
 pub fn mask_device_key(value: &str) -> String {
@@ -498,10 +779,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "home".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 35.0,
                     longitude: 105.0,
-                },
+                }),
                 region: AdministrativeRegion::default(),
             }],
             alerts,
@@ -517,6 +798,9 @@ mod tests {
                 max: 7,
                 interruption_level: InterruptionLevel::Critical,
             }],
+            max_depth_km: None,
+            final_followup: false,
+            official_warnings_only: false,
         }]);
 
         assert_eq!(subscription.interruption_level_for_intensity(2), None);
@@ -543,7 +827,10 @@ mod tests {
         let mut subscription = subscription(vec![AlertRule::default_for(
             DisasterCategory::WeatherWarning,
         )]);
-        subscription.targets[0].point.latitude = 91.0;
+        subscription.targets[0].point = Some(GeoPoint {
+            latitude: 91.0,
+            longitude: 0.0,
+        });
 
         assert!(subscription.validate().is_err());
     }
@@ -569,7 +856,9 @@ mod tests {
                         "min": 3,
                         "max": 7,
                         "interruption_level": "critical"
-                    }]
+                    }],
+                    "final_followup": true,
+                    "official_warnings_only": true
                 },
                 {
                     "category": "earthquake_report",
@@ -597,6 +886,9 @@ mod tests {
         let subscription = Subscription::new(request.destination, request.targets, request.alerts);
 
         anyhow::ensure!(subscription.validate().is_ok());
+        anyhow::ensure!(subscription.alerts[0].final_followup());
+        anyhow::ensure!(!subscription.alerts[1].final_followup());
+        anyhow::ensure!(subscription.alerts[0].official_warnings_only());
         Ok(())
     }
 
@@ -645,6 +937,92 @@ mod tests {
         assert!(empty_sources.validate().is_err());
     }
 
+    #[test]
+    fn published_limits_match_validation() {
+        let report = |min_magnitude| {
+            subscription(vec![AlertRule::EarthquakeReport {
+                sources: SourceSelection::All,
+                min_magnitude,
+                max_depth_km: None,
+            }])
+        };
+        let typhoon = |max_center_distance_km| {
+            subscription(vec![AlertRule::Typhoon {
+                sources: SourceSelection::All,
+                max_center_distance_km,
+            }])
+        };
+        let limits = SUBSCRIPTION_LIMITS;
+
+        assert!(report(limits.magnitude.max).validate().is_ok());
+        assert!(report(limits.magnitude.max + 0.1).validate().is_err());
+        let deep = |max_depth_km| {
+            subscription(vec![AlertRule::EarthquakeReport {
+                sources: SourceSelection::All,
+                min_magnitude: 4.0,
+                max_depth_km: Some(max_depth_km),
+            }])
+        };
+        assert!(deep(limits.max_depth_km.max).validate().is_ok());
+        assert!(deep(limits.max_depth_km.max + 1.0).validate().is_err());
+        assert!(deep(f64::NAN).validate().is_err());
+        assert!(
+            typhoon(limits.typhoon_max_center_distance_km.min)
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            typhoon(limits.typhoon_max_center_distance_km.min - 0.1)
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn per_source_rules_prefer_the_explicit_source() -> anyhow::Result<()> {
+        let source_ids = |category| {
+            crate::source_registry::SOURCES
+                .iter()
+                .filter(move |source| source.category == category)
+                .map(|source| source.id.to_string())
+        };
+        let mut report_sources = source_ids(DisasterCategory::EarthquakeReport);
+        let (Some(first), Some(second)) = (report_sources.next(), report_sources.next()) else {
+            anyhow::bail!("at least two earthquake report sources are required");
+        };
+        let rule = |ids: Vec<String>, min_magnitude| AlertRule::EarthquakeReport {
+            sources: SourceSelection::Include { ids },
+            min_magnitude,
+            max_depth_km: None,
+        };
+        let profiles = subscription(vec![
+            rule(vec![first.clone()], 3.0),
+            rule(vec![second.clone()], 5.0),
+            AlertRule::EarthquakeReport {
+                sources: SourceSelection::All,
+                min_magnitude: 4.0,
+                max_depth_km: None,
+            },
+        ]);
+        anyhow::ensure!(profiles.validate().is_ok());
+        let threshold = |source: &str| match profiles
+            .alert_for_source(DisasterCategory::EarthquakeReport, source)
+        {
+            Some(AlertRule::EarthquakeReport { min_magnitude, .. }) => Some(*min_magnitude),
+            _ => None,
+        };
+        anyhow::ensure!(threshold(&first) == Some(3.0));
+        anyhow::ensure!(threshold(&second) == Some(5.0));
+        anyhow::ensure!(threshold("unlisted") == Some(4.0));
+
+        let overlapping = subscription(vec![
+            rule(vec![first.clone()], 3.0),
+            rule(vec![first, second], 5.0),
+        ]);
+        anyhow::ensure!(overlapping.validate().is_err());
+        Ok(())
+    }
+
     #[test]
     fn rejects_noncanonical_destination_identity() {
         let mut trailing_slash = subscription(vec![AlertRule::default_for(
@@ -682,5 +1060,16 @@ mod tests {
             }))
             .is_err()
         );
+        assert!(
+            serde_json::from_value::<UnsubscribeRequest>(serde_json::json!({
+                "destination": {
+                    "type": "bark",
+                    "base_url": "https://api.day.app",
+                    "device_key": "abc123"
+                },
+                "purge": true
+            }))
+            .is_ok_and(|request| request.purge)
+        );
     }
 }
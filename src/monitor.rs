@@ -0,0 +1,453 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::Duration;
+
+/// 仪表盘保留的最近事件条数。
+const RECENT_EVENTS: usize = 8;
+/// 进度条宽度（字符）。
+const BAR_WIDTH: usize = 24;
+const MONITOR_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `monitor` 子命令的参数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorOptions {
+    pub url: String,
+    pub interval: Duration,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:30010".to_string(),
+            interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl MonitorOptions {
+    const USAGE: &'static str = "usage: disaster-alert monitor [--url URL] [--interval-seconds N]";
+
+    pub fn from_args(mut arguments: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(argument) = arguments.next() {
+            let (name, inline) = match argument.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (argument, None),
+            };
+            let value = inline
+                .or_else(|| arguments.next())
+                .ok_or_else(|| anyhow::anyhow!("{name} requires a value\n{}", Self::USAGE))?;
+            match name.as_str() {
+                "--url" => options.url = value.trim_end_matches('/').to_string(),
+                "--interval-seconds" => {
+                    let seconds = value.parse::<u64>().map_err(|error| {
+                        anyhow::anyhow!("invalid {name} value {value:?}: {error}")
+                    })?;
+                    anyhow::ensure!(
+                        (1..=3_600).contains(&seconds),
+                        "--interval-seconds must be in 1..=3600"
+                    );
+                    options.interval = Duration::from_secs(seconds);
+                }
+                _ => anyhow::bail!("unknown argument {name}\n{}", Self::USAGE),
+            }
+        }
+        anyhow::ensure!(
+            options.url.starts_with("http://") || options.url.starts_with("https://"),
+            "--url must start with http:// or https://"
+        );
+        Ok(options)
+    }
+}
+
+/// 终端仪表盘：定期读取运行中实例的 `/api/status`，在终端重绘上游连接、最近事件、
+/// 投递队列和错误计数，按 Ctrl-C 退出。只读取公开的状态接口，不需要管理令牌。
+pub fn run_monitor(options: &MonitorOptions) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("failed to create Tokio runtime")?;
+    runtime.block_on(monitor(options))
+}
+
+async fn monitor(options: &MonitorOptions) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(MONITOR_REQUEST_TIMEOUT)
+        .build()
+        .context("failed to build monitor HTTP client")?;
+    let status_url = format!("{}/api/status", options.url);
+    let mut dashboard = Dashboard::default();
+    let mut ticker = tokio::time::interval(options.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    loop {
+        tokio::select! {
+            result = &mut interrupted => {
+                result.context("failed to listen for Ctrl-C")?;
+                writeln!(std::io::stdout().lock(), "\x1b[0m")?;
+                return Ok(());
+            }
+            _tick = ticker.tick() => {}
+        }
+        match fetch_status(&client, &status_url).await {
+            Ok(status) => dashboard.update(status),
+            Err(error) => dashboard.error = Some(format!("{error:#}")),
+        }
+        let mut screen = String::from("\x1b[H\x1b[2J");
+        dashboard.render(&mut screen, &options.url)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(screen.as_bytes())?;
+        stdout.flush()?;
+    }
+}
+
+async fn fetch_status(client: &reqwest::Client, url: &str) -> Result<StatusView> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("failed to reach {url}"))?;
+    let http_status = response.status();
+    let body: StatusEnvelope = response
+        .json()
+        .await
+        .with_context(|| format!("invalid status response from {url}"))?;
+    anyhow::ensure!(
+        http_status.is_success() && body.success,
+        "{url} returned {http_status}: {}",
+        body.message
+    );
+    body.data
+        .with_context(|| format!("status response from {url} has no data"))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusEnvelope {
+    success: bool,
+    message: String,
+    data: Option<StatusView>,
+}
+
+/// `/api/status` 中仪表盘用到的部分；未知字段忽略，便于新旧版本互相监控。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct StatusView {
+    total_subscriptions: usize,
+    wolfx: ChannelView,
+    fanstudio: ChannelView,
+    huania: ChannelView,
+    p2pquake: ChannelView,
+    durable: DurableView,
+    ready_queues: ReadyQueuesView,
+    dispatch: DispatchView,
+    process: ProcessView,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ChannelView {
+    connected: bool,
+    reconnects: u64,
+    messages: u64,
+    parse_errors: u64,
+    notifications_succeeded: u64,
+    notifications_failed: u64,
+    seconds_since_last_message: Option<u64>,
+    last_event: Option<EventView>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+struct EventView {
+    source: String,
+    event_id: String,
+    title: String,
+    received_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct DurableView {
+    inbox_pending: usize,
+    match_jobs_pending: usize,
+    delivery_batches_pending: usize,
+    retries_pending: usize,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ReadyQueuesView {
+    inbox: QueueView,
+    matching: QueueView,
+    delivery: QueueView,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct QueueView {
+    depth: usize,
+    backpressure: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct DispatchView {
+    active_delivery_batches: usize,
+    active_retries: usize,
+    notifications_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct ProcessView {
+    uptime_seconds: u64,
+}
+
+/// 两次刷新之间保留的状态：最近一次快照、跨快照累积的最近事件和各队列的峰值深度。
+#[derive(Debug, Default)]
+struct Dashboard {
+    status: Option<StatusView>,
+    recent: VecDeque<EventView>,
+    peak_depth: usize,
+    error: Option<String>,
+}
+
+impl Dashboard {
+    fn update(&mut self, status: StatusView) {
+        for event in [
+            &status.wolfx,
+            &status.fanstudio,
+            &status.huania,
+            &status.p2pquake,
+        ]
+        .into_iter()
+        .filter_map(|channel| channel.last_event.as_ref())
+        {
+            if !self
+                .recent
+                .iter()
+                .any(|seen| seen.source == event.source && seen.event_id == event.event_id)
+            {
+                self.recent.push_front(event.clone());
+            }
+        }
+        self.recent
+            .make_contiguous()
+            .sort_by_key(|event| std::cmp::Reverse(event.received_at_ms));
+        self.recent.truncate(RECENT_EVENTS);
+        let queues = &status.ready_queues;
+        self.peak_depth = self
+            .peak_depth
+            .max(queues.inbox.depth)
+            .max(queues.matching.depth)
+            .max(queues.delivery.depth);
+        self.status = Some(status);
+        self.error = None;
+    }
+
+    fn render(&self, output: &mut String, url: &str) -> std::fmt::Result {
+        writeln!(output, "灾害预警监控 {url}")?;
+        if let Some(error) = &self.error {
+            writeln!(output, "\x1b[31m刷新失败：{error}\x1b[0m")?;
+        }
+        let Some(status) = &self.status else {
+            output.push_str("等待首次状态数据…\n");
+            return Ok(());
+        };
+        writeln!(
+            output,
+            "运行 {} 秒 · 订阅 {} 个\n\n上游连接",
+            status.process.uptime_seconds, status.total_subscriptions
+        )?;
+        for (name, channel) in [
+            ("wolfx", &status.wolfx),
+            ("fanstudio", &status.fanstudio),
+            ("huania", &status.huania),
+            ("p2pquake", &status.p2pquake),
+        ] {
+            let state = if channel.connected {
+                "\x1b[32m● 已连接\x1b[0m"
+            } else {
+                "\x1b[31m○ 未连接\x1b[0m"
+            };
+            let last_message = channel
+                .seconds_since_last_message
+                .map_or_else(|| "-".to_string(), |seconds| format!("{seconds} 秒前"));
+            writeln!(
+                output,
+                "  {name:<10} {state}  消息 {}  最近消息 {last_message}  重连 {}",
+                channel.messages, channel.reconnects
+            )?;
+        }
+
+        output.push_str("\n最近事件\n");
+        if self.recent.is_empty() {
+            output.push_str("  （暂无）\n");
+        }
+        for event in &self.recent {
+            writeln!(output, "  [{}] {}", event.source, event.title)?;
+        }
+
+        let queues = &status.ready_queues;
+        let peak = self.peak_depth.max(1);
+        writeln!(
+            output,
+            "\n投递进度\n  入库队列 {}\n  匹配队列 {}\n  投递队列 {}",
+            bar(queues.inbox.depth, peak),
+            bar(queues.matching.depth, peak),
+            bar(queues.delivery.depth, peak),
+        )?;
+        let (succeeded, failed) = [
+            &status.wolfx,
+            &status.fanstudio,
+            &status.huania,
+            &status.p2pquake,
+        ]
+        .into_iter()
+        .fold((0_u64, 0_u64), |(succeeded, failed), channel| {
+            (
+                succeeded.saturating_add(channel.notifications_succeeded),
+                failed.saturating_add(channel.notifications_failed),
+            )
+        });
+        let attempted = usize::try_from(succeeded.saturating_add(failed)).unwrap_or(usize::MAX);
+        writeln!(
+            output,
+            "  推送成功 {}\n  进行中：批次 {} · 重试 {} · 推送 {}",
+            bar(
+                usize::try_from(succeeded).unwrap_or(usize::MAX),
+                attempted.max(1)
+            ),
+            status.dispatch.active_delivery_batches,
+            status.dispatch.active_retries,
+            status.dispatch.notifications_in_flight,
+        )?;
+
+        let durable = &status.durable;
+        let parse_errors = [
+            &status.wolfx,
+            &status.fanstudio,
+            &status.huania,
+            &status.p2pquake,
+        ]
+        .into_iter()
+        .fold(0_u64, |total, channel| {
+            total.saturating_add(channel.parse_errors)
+        });
+        let backpressure = [queues.inbox, queues.matching, queues.delivery]
+            .into_iter()
+            .fold(0_u64, |total, queue| {
+                total.saturating_add(queue.backpressure)
+            });
+        writeln!(
+            output,
+            "\n错误计数\n  推送失败 {failed} · 解析失败 {parse_errors} · 队列背压 {backpressure}\n  \
+             持久化积压：入库 {} · 匹配 {} · 投递 {} · 待重试 {}",
+            durable.inbox_pending,
+            durable.match_jobs_pending,
+            durable.delivery_batches_pending,
+            durable.retries_pending,
+        )
+    }
+}
+
+/// 按 `value / total` 绘制定宽进度条，并附上数值。
+fn bar(value: usize, total: usize) -> String {
+    let filled = value
+        .min(total)
+        .saturating_mul(BAR_WIDTH)
+        .checked_div(total)
+        .unwrap_or(0);
+    format!(
+        "[{}{}] {value}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH.saturating_sub(filled))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_monitor_arguments() -> Result<()> {
+        let options = MonitorOptions::from_args(
+            [
+                "--url=https://alert.example.com/",
+                "--interval-seconds",
+                "5",
+            ]
+            .into_iter()
+            .map(str::to_string),
+        )?;
+        anyhow::ensure!(options.url == "https://alert.example.com");
+        anyhow::ensure!(options.interval == Duration::from_secs(5));
+        anyhow::ensure!(
+            MonitorOptions::from_args(
+                ["--url", "alert.example.com"]
+                    .into_iter()
+                    .map(str::to_string)
+            )
+            .is_err()
+        );
+        anyhow::ensure!(
+            MonitorOptions::from_args(["--interval-seconds=0"].into_iter().map(str::to_string))
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dashboard_keeps_recent_events_across_refreshes() -> Result<()> {
+        let status: StatusView = serde_json::from_str(
+            r#"{
+                "total_subscriptions": 3,
+                "wolfx": {"connected": true, "notifications_succeeded": 3, "notifications_failed": 1,
+                    "last_event": {"source": "wolfx.jma_eew", "event_id": "a", "title": "能登半岛地震", "received_at_ms": 1}},
+                "ready_queues": {"delivery": {"depth": 4}},
+                "unknown_field": true
+            }"#,
+        )?;
+        let mut dashboard = Dashboard::default();
+        dashboard.update(status.clone());
+        let mut next = status;
+        next.wolfx.last_event = Some(EventView {
+            source: "wolfx.jma_eew".to_string(),
+            event_id: "b".to_string(),
+            title: "千叶县东方近海地震".to_string(),
+            received_at_ms: 2,
+        });
+        dashboard.update(next);
+
+        let titles: Vec<_> = dashboard
+            .recent
+            .iter()
+            .map(|event| event.title.as_str())
+            .collect();
+        anyhow::ensure!(titles == ["千叶县东方近海地震", "能登半岛地震"]);
+        let mut rendered = String::new();
+        dashboard.render(&mut rendered, "http://127.0.0.1:30010")?;
+        anyhow::ensure!(rendered.contains("已连接"));
+        anyhow::ensure!(rendered.contains(&format!("投递队列 [{}] 4", "#".repeat(BAR_WIDTH))));
+        anyhow::ensure!(rendered.contains("推送失败 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn bar_is_clamped_to_width() {
+        assert_eq!(bar(0, 0), format!("[{}] 0", "-".repeat(BAR_WIDTH)));
+        assert_eq!(bar(9, 3), format!("[{}] 9", "#".repeat(BAR_WIDTH)));
+        assert_eq!(
+            bar(1, 2),
+            format!(
+                "[{}{}] 1",
+                "#".repeat(BAR_WIDTH / 2),
+                "-".repeat(BAR_WIDTH / 2)
+            )
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::config::Config;
+use crate::models::ProviderChannel;
+use crate::storage::try_now_millis;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+/// 写入线程落后时最多缓存的报文数；超出后丢弃新报文，不阻塞数据源接收。
+const ARCHIVE_QUEUE_CAPACITY: usize = 4_096;
+const ARCHIVE_FILE_PREFIX: &str = "raw-";
+const ARCHIVE_FILE_SUFFIX: &str = ".jsonl";
+
+#[derive(Debug, Serialize)]
+struct ArchivedFrame {
+    received_at_ms: i64,
+    channel: &'static str,
+    payload: String,
+}
+
+/// 上游原始报文归档：每条 WebSocket 报文按行追加为 JSON（接收时间、数据源通道和原文），
+/// 单个文件超过上限后轮换到新文件，只保留最近的若干个文件，便于排查解析失败和回放事件。
+/// 未配置 `RAW_ARCHIVE_DIR` 时不做任何事。
+#[derive(Clone, Default)]
+pub(crate) struct RawArchive {
+    sender: Option<SyncSender<ArchivedFrame>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl RawArchive {
+    pub(crate) fn spawn(config: &Config) -> Result<Self> {
+        let Some(dir) = config.raw_archive_dir.as_deref() else {
+            return Ok(Self::default());
+        };
+        let writer = ArchiveWriter::open(
+            PathBuf::from(dir),
+            config.raw_archive_max_file_mb.saturating_mul(1024 * 1024),
+            config.raw_archive_max_files,
+        )?;
+        let (sender, receiver) = mpsc::sync_channel(ARCHIVE_QUEUE_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer_dropped = Arc::clone(&dropped);
+        std::thread::Builder::new()
+            .name("raw-archive".to_string())
+            .spawn(move || write_frames(writer, &receiver, &writer_dropped))
+            .context("failed to start raw archive writer")?;
+        tracing::info!(event = "raw_archive.enabled", dir, "raw_archive.enabled");
+        Ok(Self {
+            sender: Some(sender),
+            dropped,
+        })
+    }
+
+    pub(crate) fn record(&self, channel: ProviderChannel, payload: &str) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let frame = ArchivedFrame {
+            received_at_ms: try_now_millis().unwrap_or_default(),
+            channel: channel.as_str(),
+            payload: payload.to_string(),
+        };
+        if let Err(TrySendError::Full(_frame)) = sender.try_send(frame) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 每次取空队列后刷新一次缓冲，进程退出时最多丢失正在写入的一批报文。
+fn write_frames(
+    mut writer: ArchiveWriter,
+    receiver: &Receiver<ArchivedFrame>,
+    dropped: &AtomicU64,
+) {
+    while let Ok(frame) = receiver.recv() {
+        let mut result = writer.append(&frame);
+        while let Ok(frame) = receiver.try_recv() {
+            result = result.and_then(|()| writer.append(&frame));
+        }
+        if let Err(error) = result.and_then(|()| writer.flush()) {
+            tracing::error!(
+                event = "raw_archive.write_failed",
+                error = ?error,
+                "raw_archive.write_failed"
+            );
+        }
+        let dropped = dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::warn!(
+                event = "raw_archive.dropped",
+                dropped,
+                "raw_archive.dropped"
+            );
+        }
+    }
+}
+
+struct ArchiveWriter {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    sequence: u64,
+    file: BufWriter<File>,
+    written: u64,
+}
+
+impl ArchiveWriter {
+    /// 每次启动都从新文件开始写，编号接在目录中已有文件之后。
+    fn open(dir: PathBuf, max_file_bytes: u64, max_files: usize) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create raw archive directory {}", dir.display()))?;
+        let sequence = archive_files(&dir)?
+            .last()
+            .map_or(1, |(sequence, _path)| sequence.saturating_add(1));
+        let file = create_archive_file(&dir, sequence)?;
+        let writer = Self {
+            dir,
+            max_file_bytes,
+            max_files,
+            sequence,
+            file,
+            written: 0,
+        };
+        writer.prune()?;
+        Ok(writer)
+    }
+
+    fn append(&mut self, frame: &ArchivedFrame) -> Result<()> {
+        if self.written >= self.max_file_bytes {
+            self.rotate()?;
+        }
+        let mut line = serde_json::to_vec(frame).context("failed to encode raw archive frame")?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .context("failed to write raw archive frame")?;
+        self.written = self.written.saturating_add(line.len() as u64);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush().context("failed to flush raw archive")
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+        self.sequence = self.sequence.saturating_add(1);
+        self.file = create_archive_file(&self.dir, self.sequence)?;
+        self.written = 0;
+        self.prune()
+    }
+
+    fn prune(&self) -> Result<()> {
+        let files = archive_files(&self.dir)?;
+        let expired = files.len().saturating_sub(self.max_files);
+        for (_sequence, path) in files.into_iter().take(expired) {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove raw archive {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn create_archive_file(dir: &Path, sequence: u64) -> Result<BufWriter<File>> {
+    let path = dir.join(format!(
+        "{ARCHIVE_FILE_PREFIX}{sequence:08}{ARCHIVE_FILE_SUFFIX}"
+    ));
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open raw archive {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+/// 目录中的归档文件，按编号从旧到新排列；忽略其他文件。
+fn archive_files(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to list raw archive directory {}", dir.display()))?
+    {
+        let path = entry
+            .context("failed to read raw archive directory entry")?
+            .path();
+        let sequence = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(ARCHIVE_FILE_PREFIX))
+            .and_then(|name| name.strip_suffix(ARCHIVE_FILE_SUFFIX))
+            .and_then(|sequence| sequence.parse::<u64>().ok());
+        if let Some(sequence) = sequence {
+            files.push((sequence, path));
+        }
+    }
+    files.sort_unstable();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload: &str) -> ArchivedFrame {
+        ArchivedFrame {
+            received_at_ms: 1,
+            channel: ProviderChannel::Wolfx.as_str(),
+            payload: payload.to_string(),
+        }
+    }
+
+    #[test]
+    fn spawned_archive_rotates_after_the_configured_megabytes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let archive_dir = dir.path().join("raw");
+        let config = Config::from_file_values(&Arc::new(
+            [
+                ("ALERT_DETAIL_BASE_URL", "http://127.0.0.1:30010"),
+                ("ALERT_SIGNING_KEY", "test-signing-key-test-signing-key"),
+                ("RAW_ARCHIVE_DIR", archive_dir.to_str().unwrap_or_default()),
+                ("RAW_ARCHIVE_MAX_FILE_MB", "1"),
+                ("RAW_ARCHIVE_MAX_FILES", "8"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        ))?;
+        let archive = RawArchive::spawn(&config)?;
+        let payload = "x".repeat(300 * 1024);
+        for _frame in 0..5 {
+            archive.record(ProviderChannel::Wolfx, &payload);
+        }
+
+        // 每个文件写满 1 MiB 后轮换：前四条约 1.2 MiB 在第一个文件，第五条在第二个文件。
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let sizes = loop {
+            let sizes = archive_files(&archive_dir)?
+                .iter()
+                .map(|(_sequence, path)| Ok(fs::metadata(path)?.len()))
+                .collect::<Result<Vec<_>>>()?;
+            if sizes.iter().sum::<u64>() >= 5 * 300 * 1024 {
+                break sizes;
+            }
+            anyhow::ensure!(
+                std::time::Instant::now() < deadline,
+                "archive writer did not catch up: {sizes:?}"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        anyhow::ensure!(sizes.len() == 2, "{sizes:?}");
+        anyhow::ensure!(sizes[0] >= 1024 * 1024, "{sizes:?}");
+        anyhow::ensure!(archive.dropped.load(Ordering::Relaxed) == 0);
+        Ok(())
+    }
+
+    #[test]
+    fn full_queue_counts_dropped_frames() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let archive = RawArchive {
+            sender: Some(sender),
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        for payload in ["a", "b", "c"] {
+            archive.record(ProviderChannel::Wolfx, payload);
+        }
+        assert_eq!(archive.dropped.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            receiver
+                .try_iter()
+                .map(|frame| frame.payload)
+                .collect::<Vec<_>>(),
+            ["a"]
+        );
+    }
+
+    #[test]
+    fn rotates_full_files_and_keeps_only_the_newest() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut writer = ArchiveWriter::open(dir.path().to_path_buf(), 1, 2)?;
+        for payload in ["a", "b", "c"] {
+            writer.append(&frame(payload))?;
+        }
+        writer.flush()?;
+        let files = archive_files(dir.path())?;
+        anyhow::ensure!(
+            files.iter().map(|(sequence, _path)| *sequence).eq([2, 3]),
+            "{files:?}"
+        );
+        let line: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(&files[1].1)?.trim_end())?;
+        anyhow::ensure!(line["payload"] == "c" && line["channel"] == "wolfx");
+
+        drop(writer);
+        ArchiveWriter::open(dir.path().to_path_buf(), 1, 2)?;
+        anyhow::ensure!(
+            archive_files(dir.path())?
+                .iter()
+                .map(|(sequence, _path)| *sequence)
+                .eq([3, 4])
+        );
+        Ok(())
+    }
+}
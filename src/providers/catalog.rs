@@ -0,0 +1,514 @@
+use super::fanstudio_protocol::severity_from_magnitude;
+use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel, parse_event_epoch_ms};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// 单次导入的事件数上限，超出时应拆分文件。
+pub(crate) const MAX_CATALOG_EVENTS: usize = 50_000;
+
+/// 外部历史地震目录的格式。导入的事件以 `catalog.*` 为来源，与实时数据源区分，只进入历史记录，
+/// 不触发匹配和推送。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CatalogFormat {
+    /// USGS FDSN 事件查询的 CSV 输出（`format=csv`）。
+    UsgsCsv,
+    /// 日本气象厅地震情报列表（`bosai/quake/data/list.json`）。
+    JmaJson,
+    /// Wolfx 推送的日本气象厅最近地震列表（`jma_eqlist`）。
+    WolfxJmaEqlist,
+    /// Wolfx 推送的中国地震台网最近地震列表（`cenc_eqlist`），只取正式测定。
+    WolfxCencEqlist,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ParsedCatalog {
+    pub(crate) events: Vec<DisasterEvent>,
+    /// 缺少时间、坐标或编号而无法导入的记录数。
+    pub(crate) rejected: usize,
+}
+
+impl CatalogFormat {
+    const ALL: [Self; 4] = [
+        Self::UsgsCsv,
+        Self::JmaJson,
+        Self::WolfxJmaEqlist,
+        Self::WolfxCencEqlist,
+    ];
+
+    pub(crate) const fn source(self) -> &'static str {
+        match self {
+            Self::UsgsCsv => "catalog.usgs",
+            Self::JmaJson => "catalog.jma",
+            Self::WolfxJmaEqlist => "catalog.wolfx_jma",
+            Self::WolfxCencEqlist => "catalog.wolfx_cenc",
+        }
+    }
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::UsgsCsv => "USGS 历史目录",
+            Self::JmaJson => "日本气象厅历史目录",
+            Self::WolfxJmaEqlist => "日本气象厅地震列表",
+            Self::WolfxCencEqlist => "中国地震台网地震列表",
+        }
+    }
+
+    const fn channel(self) -> ProviderChannel {
+        match self {
+            Self::UsgsCsv => ProviderChannel::FanStudio,
+            Self::JmaJson | Self::WolfxJmaEqlist | Self::WolfxCencEqlist => ProviderChannel::Wolfx,
+        }
+    }
+
+    /// Wolfx 列表报文的 `type` 对应的目录格式；其他报文返回 `None`。
+    pub(crate) fn from_wolfx_type(message_type: &str) -> Option<Self> {
+        match message_type {
+            "jma_eqlist" => Some(Self::WolfxJmaEqlist),
+            "cenc_eqlist" => Some(Self::WolfxCencEqlist),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse(self, body: &str) -> Result<ParsedCatalog> {
+        let parsed = match self {
+            Self::UsgsCsv => parse_usgs_csv(body)?,
+            Self::JmaJson => parse_jma_json(body)?,
+            Self::WolfxJmaEqlist => parse_wolfx_eqlist::<WolfxJmaEntry>(self, body)?,
+            Self::WolfxCencEqlist => parse_wolfx_eqlist::<WolfxCencEntry>(self, body)?,
+        };
+        anyhow::ensure!(
+            parsed.events.len() <= MAX_CATALOG_EVENTS,
+            "catalog contains more than {MAX_CATALOG_EVENTS} events"
+        );
+        Ok(parsed)
+    }
+}
+
+/// 全部导入来源。
+pub(crate) const CATALOG_SOURCES: [&str; 4] = [
+    CatalogFormat::UsgsCsv.source(),
+    CatalogFormat::JmaJson.source(),
+    CatalogFormat::WolfxJmaEqlist.source(),
+    CatalogFormat::WolfxCencEqlist.source(),
+];
+
+/// 导入来源的显示名称；不是导入来源时返回 `None`。
+pub(crate) fn catalog_label(source: &str) -> Option<&'static str> {
+    CatalogFormat::ALL
+        .into_iter()
+        .find(|format| format.source() == source)
+        .map(CatalogFormat::label)
+}
+
+fn parse_usgs_csv(body: &str) -> Result<ParsedCatalog> {
+    let mut lines = body.lines().filter(|line| !line.trim().is_empty());
+    let header = csv_fields(lines.next().context("USGS catalog is empty")?);
+    let column = |name: &str| header.iter().position(|field| field == name);
+    let time = column("time").context("USGS catalog has no time column")?;
+    let latitude = column("latitude").context("USGS catalog has no latitude column")?;
+    let longitude = column("longitude").context("USGS catalog has no longitude column")?;
+    let id = column("id").context("USGS catalog has no id column")?;
+    let (depth, magnitude, place, kind) = (
+        column("depth"),
+        column("mag"),
+        column("place"),
+        column("type"),
+    );
+
+    let mut parsed = ParsedCatalog::default();
+    for line in lines {
+        let fields = csv_fields(line);
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| fields.get(index))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        // 目录里还有爆破、冰震等非地震事件。
+        if field(kind).is_some_and(|kind| kind != "earthquake") {
+            continue;
+        }
+        let number =
+            |index: Option<usize>| field(index).and_then(|value| value.parse::<f64>().ok());
+        let (Some(occurred_at), Some(latitude), Some(longitude), Some(event_id)) = (
+            field(Some(time)),
+            number(Some(latitude)),
+            number(Some(longitude)),
+            field(Some(id)),
+        ) else {
+            parsed.rejected += 1;
+            continue;
+        };
+        let place = field(place).unwrap_or_default();
+        push_event(
+            &mut parsed,
+            catalog_event(
+                CatalogFormat::UsgsCsv,
+                event_id,
+                occurred_at,
+                place,
+                (latitude, longitude),
+                number(magnitude),
+                number(depth),
+            ),
+        );
+    }
+    Ok(parsed)
+}
+
+/// 按 RFC 4180 拆分一行 CSV；USGS 的地名字段带逗号时会加引号。
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = line.trim_end_matches('\r').chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if quoted && characters.peek() == Some(&'"') => {
+                field.push('"');
+                characters.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(character),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[derive(Deserialize)]
+struct JmaEntry {
+    eid: String,
+    #[serde(default)]
+    at: String,
+    #[serde(default)]
+    anm: String,
+    #[serde(default)]
+    cod: String,
+    #[serde(default)]
+    mag: String,
+    #[serde(default)]
+    ser: String,
+}
+
+/// 气象厅列表中一次地震当前选用的报告：续报号、报告、震源坐标和深度。
+type JmaLatestReport<'a> = (u32, &'a JmaEntry, (f64, f64), Option<f64>);
+
+fn parse_jma_json(body: &str) -> Result<ParsedCatalog> {
+    let entries: Vec<JmaEntry> =
+        serde_json::from_str(body).context("JMA catalog is not a JSON array of reports")?;
+    // 同一地震有震度速报、震源情报等多条，取带震源且续报号最大的一条。
+    let mut latest: HashMap<&str, JmaLatestReport<'_>> = HashMap::new();
+    let mut rejected = HashMap::new();
+    for entry in &entries {
+        let Some((point, depth_km)) = parse_iso6709(&entry.cod) else {
+            rejected.entry(entry.eid.as_str()).or_insert(true);
+            continue;
+        };
+        rejected.insert(entry.eid.as_str(), false);
+        let serial = entry.ser.parse::<u32>().unwrap_or(0);
+        if latest
+            .get(entry.eid.as_str())
+            .is_none_or(|(current, ..)| serial >= *current)
+        {
+            latest.insert(entry.eid.as_str(), (serial, entry, point, depth_km));
+        }
+    }
+    let mut parsed = ParsedCatalog {
+        rejected: rejected.values().filter(|rejected| **rejected).count(),
+        ..ParsedCatalog::default()
+    };
+    let mut latest = latest.into_values().collect::<Vec<_>>();
+    latest.sort_by(|left, right| left.1.eid.cmp(&right.1.eid));
+    for (_serial, entry, point, depth_km) in latest {
+        push_event(
+            &mut parsed,
+            catalog_event(
+                CatalogFormat::JmaJson,
+                &entry.eid,
+                &entry.at,
+                &entry.anm,
+                point,
+                entry.mag.trim().parse::<f64>().ok(),
+                depth_km,
+            ),
+        );
+    }
+    Ok(parsed)
+}
+
+/// Wolfx 地震列表中的一条记录；数值均以字符串给出。
+trait WolfxEqlistEntry: serde::de::DeserializeOwned {
+    /// 转换为历史地震；尚未正式测定的记录返回 `None`。
+    fn into_event(self, format: CatalogFormat) -> Option<DisasterEvent>;
+}
+
+#[derive(Deserialize)]
+struct WolfxJmaEntry {
+    #[serde(default)]
+    time_full: String,
+    #[serde(default)]
+    location: String,
+    #[serde(default)]
+    magnitude: String,
+    #[serde(default)]
+    depth: String,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
+}
+
+impl WolfxEqlistEntry for WolfxJmaEntry {
+    fn into_event(self, format: CatalogFormat) -> Option<DisasterEvent> {
+        Some(wolfx_eqlist_event(
+            format,
+            &self.time_full,
+            "+09:00",
+            &self.location,
+            (&self.latitude, &self.longitude),
+            &self.magnitude,
+            &self.depth,
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+struct WolfxCencEntry {
+    #[serde(rename = "type", default)]
+    measurement: String,
+    #[serde(default)]
+    time: String,
+    #[serde(default)]
+    location: String,
+    #[serde(default)]
+    magnitude: String,
+    #[serde(default)]
+    depth: String,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
+}
+
+impl WolfxEqlistEntry for WolfxCencEntry {
+    fn into_event(self, format: CatalogFormat) -> Option<DisasterEvent> {
+        // 自动测定的记录稍后会被正式测定取代，只保留正式测定。
+        (self.measurement == "reviewed").then(|| {
+            wolfx_eqlist_event(
+                format,
+                &self.time,
+                "+08:00",
+                &self.location,
+                (&self.latitude, &self.longitude),
+                &self.magnitude,
+                &self.depth,
+            )
+        })
+    }
+}
+
+/// 解析 Wolfx 地震列表：`No1`、`No2`…… 为按时间倒序的最近地震，另有 `type` 和 `md5` 字段。
+fn parse_wolfx_eqlist<T: WolfxEqlistEntry>(
+    format: CatalogFormat,
+    body: &str,
+) -> Result<ParsedCatalog> {
+    let message: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(body).context("Wolfx earthquake list is not a JSON object")?;
+    let mut entries = message
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let number = key.strip_prefix("No")?.parse::<u32>().ok()?;
+            Some((number, value))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|(number, _value)| *number);
+    let mut parsed = ParsedCatalog::default();
+    for (_number, value) in entries {
+        let Ok(entry) = serde_json::from_value::<T>(value) else {
+            parsed.rejected += 1;
+            continue;
+        };
+        if let Some(event) = entry.into_event(format) {
+            push_event(&mut parsed, event);
+        }
+    }
+    Ok(parsed)
+}
+
+/// 列表不带事件编号，以发生时刻（精确到秒）作编号，与气象厅 `eid` 的写法一致。
+fn wolfx_eqlist_event(
+    format: CatalogFormat,
+    time: &str,
+    utc_offset: &str,
+    place: &str,
+    (latitude, longitude): (&str, &str),
+    magnitude: &str,
+    depth: &str,
+) -> DisasterEvent {
+    let time = time.trim();
+    let event_id = time
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    let occurred_at = format!(
+        "{}{utc_offset}",
+        time.replace('/', "-").replacen(' ', "T", 1)
+    );
+    let number = |value: &str| value.trim().trim_end_matches("km").parse::<f64>().ok();
+    let (latitude, longitude) = (number(latitude), number(longitude));
+    let mut event = catalog_event(
+        format,
+        &event_id,
+        &occurred_at,
+        place.trim(),
+        (latitude.unwrap_or_default(), longitude.unwrap_or_default()),
+        number(magnitude),
+        number(depth),
+    );
+    // 缺少坐标的记录（如震度速报）在 `push_event` 中按无效记录剔除。
+    event.latitude = latitude;
+    event.longitude = longitude;
+    event
+}
+
+/// 解析气象厅的 ISO 6709 坐标，如 `+37.5+137.2-10000/`；深度以米为单位、向下为负。
+fn parse_iso6709(value: &str) -> Option<((f64, f64), Option<f64>)> {
+    let value = value.trim().trim_end_matches('/');
+    let starts = value
+        .char_indices()
+        .filter(|(_index, character)| matches!(character, '+' | '-'))
+        .map(|(index, _character)| index)
+        .chain(std::iter::once(value.len()))
+        .collect::<Vec<_>>();
+    let parts = starts
+        .windows(2)
+        .map(|window| value.get(window[0]..window[1])?.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts.as_slice() {
+        [latitude, longitude] => Some(((*latitude, *longitude), None)),
+        [latitude, longitude, depth_m] => {
+            Some(((*latitude, *longitude), Some(depth_m.abs() / 1_000.0)))
+        }
+        _ => None,
+    }
+}
+
+fn catalog_event(
+    format: CatalogFormat,
+    event_id: &str,
+    occurred_at: &str,
+    place: &str,
+    (latitude, longitude): (f64, f64),
+    magnitude: Option<f64>,
+    depth_km: Option<f64>,
+) -> DisasterEvent {
+    DisasterEvent {
+        category: DisasterCategory::EarthquakeReport,
+        channel: format.channel(),
+        source: format.source().to_string(),
+        event_id: event_id.to_string(),
+        revision: "1".to_string(),
+        report_num: 1,
+        title: format!("地震信息 {place}"),
+        description: magnitude.map_or_else(
+            || place.to_string(),
+            |magnitude| format!("M{magnitude:.1} {place}"),
+        ),
+        latitude: Some(latitude),
+        longitude: Some(longitude),
+        magnitude,
+        depth_km,
+        affected_regions: if place.is_empty() {
+            Vec::new()
+        } else {
+            vec![place.to_string()]
+        },
+        radius_km: None,
+        level: magnitude.map_or(1, severity_from_magnitude),
+        occurred_at: occurred_at.to_string(),
+        final_report: true,
+        cancel: false,
+        training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
+    }
+}
+
+fn push_event(parsed: &mut ParsedCatalog, event: DisasterEvent) {
+    let valid = parse_event_epoch_ms(&event).is_some()
+        && event
+            .latitude
+            .is_some_and(|latitude| (-90.0..=90.0).contains(&latitude))
+        && event
+            .longitude
+            .is_some_and(|longitude| (-180.0..=180.0).contains(&longitude));
+    if valid {
+        parsed.events.push(event);
+    } else {
+        parsed.rejected += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reviewed_wolfx_earthquake_lists() -> Result<()> {
+        let jma = r#"{"type":"jma_eqlist","No2":{"Title":"震度速報","time_full":"2024/01/01 16:06:00","location":"","magnitude":"","depth":"","latitude":"","longitude":""},"No1":{"Title":"震源・震度情報","time_full":"2024/01/01 16:10:09","location":"石川県能登地方","magnitude":"7.6","shindo":"7","depth":"16km","latitude":"37.5","longitude":"137.3"},"md5":"x"}"#;
+        let parsed = CatalogFormat::from_wolfx_type("jma_eqlist")
+            .context("jma_eqlist is not a catalog")?
+            .parse(jma)?;
+        anyhow::ensure!(parsed.events.len() == 1 && parsed.rejected == 1);
+        let event = parsed.events.first().context("missing event")?;
+        anyhow::ensure!(event.source == "catalog.wolfx_jma" && event.event_id == "20240101161009");
+        anyhow::ensure!(event.occurred_at == "2024-01-01T16:10:09+09:00");
+        anyhow::ensure!(event.magnitude == Some(7.6) && event.depth_km == Some(16.0));
+
+        let cenc = r#"{"type":"cenc_eqlist","No1":{"type":"automatic","time":"2024-01-02 08:00:05","location":"四川宜宾市","magnitude":"3.3","depth":"10","latitude":"28.2","longitude":"104.9"},"No2":{"type":"reviewed","time":"2024-01-02 07:58:01","location":"四川宜宾市珙县","magnitude":"3.1","depth":"8","latitude":"28.3","longitude":"104.8"},"md5":"y"}"#;
+        let parsed = CatalogFormat::WolfxCencEqlist.parse(cenc)?;
+        anyhow::ensure!(parsed.events.len() == 1 && parsed.rejected == 0);
+        let event = parsed.events.first().context("missing event")?;
+        anyhow::ensure!(event.source == "catalog.wolfx_cenc" && event.event_id == "20240102075801");
+        anyhow::ensure!(parse_event_epoch_ms(event).is_some());
+        anyhow::ensure!(CatalogFormat::from_wolfx_type("cenc_eew").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn parses_usgs_csv_with_quoted_places() -> Result<()> {
+        let body = "time,latitude,longitude,depth,mag,magType,id,place,type\r\n\
+            2024-01-01T07:10:09.476Z,37.4874,136.9437,10,7.5,mww,us6000m0xl,\"42 km NE of Anamizu, Japan\",earthquake\r\n\
+            2024-01-02T00:00:00.000Z,40.0,-120.0,0,1.2,ml,nc1,\"quarry, CA\",quarry blast\r\n\
+            not-a-time,1,2,3,4,ml,bad1,,earthquake\r\n";
+        let parsed = CatalogFormat::UsgsCsv.parse(body)?;
+        anyhow::ensure!(parsed.events.len() == 1 && parsed.rejected == 1);
+        let event = parsed.events.first().context("missing event")?;
+        anyhow::ensure!(event.source == "catalog.usgs" && event.event_id == "us6000m0xl");
+        anyhow::ensure!(event.affected_regions == ["42 km NE of Anamizu, Japan"]);
+        anyhow::ensure!(event.magnitude == Some(7.5) && event.depth_km == Some(10.0));
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_the_latest_jma_report_with_a_hypocenter() -> Result<()> {
+        let body = r#"[
+            {"eid":"20240101161010","at":"2024-01-01T16:10:00+09:00","anm":"","cod":"","mag":"","ser":"1"},
+            {"eid":"20240101161010","at":"2024-01-01T16:10:00+09:00","anm":"石川県能登地方","cod":"+37.5+137.2-10000/","mag":"7.4","ser":"2"},
+            {"eid":"20240101161010","at":"2024-01-01T16:10:00+09:00","anm":"石川県能登地方","cod":"+37.5+137.3-16000/","mag":"7.6","ser":"3"},
+            {"eid":"20240102000000","at":"2024-01-02T00:00:00+09:00","anm":"","cod":"","mag":"","ser":"1"}
+        ]"#;
+        let parsed = CatalogFormat::JmaJson.parse(body)?;
+        anyhow::ensure!(parsed.events.len() == 1 && parsed.rejected == 1);
+        let event = parsed.events.first().context("missing event")?;
+        anyhow::ensure!(event.source == "catalog.jma" && event.magnitude == Some(7.6));
+        anyhow::ensure!(event.longitude == Some(137.3) && event.depth_km == Some(16.0));
+        anyhow::ensure!(catalog_label(&event.source).is_some());
+        Ok(())
+    }
+}
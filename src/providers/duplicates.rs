@@ -0,0 +1,73 @@
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// 上游中转在重连时会把最近的帧原样重发一遍，窗口只需覆盖一次重连的时间。
+pub(super) const DUPLICATE_FRAME_WINDOW: Duration = Duration::from_secs(60);
+/// 窗口内最多记住的帧数，防止上游刷屏时无限增长。
+const MAX_RECENT_FRAMES: usize = 4_096;
+
+/// 按原始文本摘要丢弃短时间内逐字节相同的重复帧；跨重连保留，在解析之前判断。
+#[derive(Debug)]
+pub(super) struct RecentFrames {
+    window: Duration,
+    order: VecDeque<(Instant, [u8; 16])>,
+    seen: HashSet<[u8; 16]>,
+}
+
+impl RecentFrames {
+    pub(super) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// 窗口内已见过同样的帧时返回 `true`；窗口从首次收到时起算，重复帧不会延长窗口。
+    pub(super) fn is_repeat(&mut self, frame: &str, now: Instant) -> bool {
+        while let Some(&(seen_at, digest)) = self.order.front() {
+            if now.saturating_duration_since(seen_at) < self.window
+                && self.order.len() < MAX_RECENT_FRAMES
+            {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&digest);
+        }
+        let digest = frame_digest(frame);
+        if !self.seen.insert(digest) {
+            return true;
+        }
+        self.order.push_back((now, digest));
+        false
+    }
+}
+
+fn frame_digest(frame: &str) -> [u8; 16] {
+    let hash = Sha256::digest(frame.as_bytes());
+    let mut digest = [0_u8; 16];
+    digest.copy_from_slice(&hash[..16]);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_identical_frames_only_within_the_window() {
+        let start = Instant::now();
+        let mut frames = RecentFrames::new(Duration::from_secs(60));
+        assert!(!frames.is_repeat(r#"{"type":"jma_eew","Serial":1}"#, start));
+        assert!(!frames.is_repeat(r#"{"type":"jma_eew","Serial":2}"#, start));
+        assert!(frames.is_repeat(
+            r#"{"type":"jma_eew","Serial":1}"#,
+            start + Duration::from_secs(30)
+        ));
+        assert!(!frames.is_repeat(
+            r#"{"type":"jma_eew","Serial":1}"#,
+            start + Duration::from_secs(60)
+        ));
+    }
+}
@@ -1,3 +1,5 @@
+use super::RawArchive;
+use super::duplicates::{DUPLICATE_FRAME_WINDOW, RecentFrames};
 use super::fanstudio_protocol::{parse_fanstudio_snapshot, parse_fanstudio_update_value};
 use super::reconnect;
 use crate::config::Config;
@@ -6,25 +8,31 @@ use crate::providers::ProviderCursor;
 use crate::runtime::EventRuntime;
 use crate::runtime::RuntimeStatus;
 use crate::source_registry::SOURCES;
+use crate::upstream_tls::UpstreamTls;
 use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use tokio_tungstenite::{
-    connect_async_with_config,
+    Connector, connect_async_tls_with_config,
     tungstenite::{Message, protocol::WebSocketConfig},
 };
 
 const MAX_WEBSOCKET_MESSAGE_BYTES: usize = 8 * 1024 * 1024;
-const FANSTUDIO_WEBSOCKET_URL: &str = "wss://ws.fanstudio.tech/all";
+pub(super) const FANSTUDIO_WEBSOCKET_URL: &str = "wss://ws.fanstudio.tech/all";
 
 #[derive(Clone)]
 pub(crate) struct FanStudioSource {
     event_runtime: EventRuntime,
     reconnect_min: Duration,
     reconnect_max: Duration,
+    stale_window: Duration,
     runtime_status: RuntimeStatus,
+    raw_archive: RawArchive,
+    /// 配置了自定义 CA 或公钥固定时使用的 TLS 设置，否则为默认校验。
+    tls: Option<Arc<rustls::ClientConfig>>,
 }
 
 impl FanStudioSource {
@@ -32,22 +40,33 @@ impl FanStudioSource {
         config: &Config,
         event_runtime: EventRuntime,
         runtime_status: RuntimeStatus,
-    ) -> Self {
-        Self {
+        upstream_tls: Option<&UpstreamTls>,
+        raw_archive: RawArchive,
+    ) -> Result<Self> {
+        Ok(Self {
             event_runtime,
             reconnect_min: Duration::from_secs(config.reconnect_min_seconds),
             reconnect_max: Duration::from_secs(config.reconnect_max_seconds),
+            stale_window: Duration::from_secs(config.stale_connection_seconds),
             runtime_status,
-        }
+            raw_archive,
+            tls: upstream_tls
+                .map(|tls| tls.client_config(&[b"http/1.1"]))
+                .transpose()?,
+        })
     }
 
     pub(crate) async fn run(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
         let mut delay = self.reconnect_min;
+        let mut frames = RecentFrames::new(DUPLICATE_FRAME_WINDOW);
         loop {
             if *shutdown.borrow() {
                 break;
             }
-            match self.connect_once(&mut delay, &mut shutdown).await {
+            match self
+                .connect_once(&mut delay, &mut frames, &mut shutdown)
+                .await
+            {
                 Ok(true) => break,
                 Ok(false) => {}
                 Err(error) => tracing::error!(
@@ -76,11 +95,12 @@ impl FanStudioSource {
     async fn connect_once(
         &self,
         delay: &mut Duration,
+        frames: &mut RecentFrames,
         shutdown: &mut watch::Receiver<bool>,
     ) -> Result<bool> {
         let connect = tokio::time::timeout(
             Duration::from_secs(10),
-            connect_async_with_config(
+            connect_async_tls_with_config(
                 FANSTUDIO_WEBSOCKET_URL,
                 Some(
                     WebSocketConfig::default()
@@ -88,6 +108,7 @@ impl FanStudioSource {
                         .max_frame_size(Some(MAX_WEBSOCKET_MESSAGE_BYTES)),
                 ),
                 false,
+                self.tls.clone().map(Connector::Rustls),
             ),
         );
         let (socket, _) = tokio::select! {
@@ -107,6 +128,7 @@ impl FanStudioSource {
         );
         let (mut write, mut read) = socket.split();
         let outcome: Result<bool> = async {
+            let mut watchdog = reconnect::StaleWatchdog::new(self.stale_window);
             let mut streams = SOURCES
             .iter()
             .filter(|source| source.channel == ProviderChannel::FanStudio)
@@ -127,8 +149,19 @@ impl FanStudioSource {
             }
             let message = tokio::select! {
                 biased;
-                result = tokio::time::timeout(Duration::from_secs(90), read.next()) => result
-                    .map_err(|error| anyhow::anyhow!("Fan Studio heartbeat timed out: {error}"))?,
+                message = read.next() => message,
+                action = watchdog.due() => match action {
+                    reconnect::WatchdogAction::Stale => anyhow::bail!(
+                        "Fan Studio connection stale: no frame within {:?}",
+                        watchdog.window()
+                    ),
+                    reconnect::WatchdogAction::Ping => {
+                        if reconnect::send_ping(&mut write, shutdown).await? {
+                            return Ok(true);
+                        }
+                        continue;
+                    }
+                },
                 result = shutdown.changed() => {
                     if result.is_err() || *shutdown.borrow() {
                         return Ok(true);
@@ -137,13 +170,27 @@ impl FanStudioSource {
                 }
             };
             let Some(message) = message else { break };
+            watchdog.record_frame();
             match message? {
                 Message::Text(text) => {
                     self.runtime_status.fanstudio().record_message();
+                    self.raw_archive.record(ProviderChannel::FanStudio, &text);
+                    if frames.is_repeat(&text, Instant::now()) {
+                        tracing::debug!(
+                            event = "provider.duplicate_frame",
+                            provider = "fanstudio",
+                            "provider.duplicate_frame"
+                        );
+                        continue;
+                    }
                     let envelope: serde_json::Value = match serde_json::from_str(&text) {
                         Ok(value) => value,
                         Err(error) => {
-                            self.runtime_status.fanstudio().record_parse_error();
+                            self.runtime_status.fanstudio().record_parse_error(
+                                "invalid_json",
+                                &error,
+                                Some(text.as_str()),
+                            );
                             tracing::warn!(
                                 event = "fanstudio.invalid_json",
                                 error = ?error,
@@ -184,7 +231,11 @@ impl FanStudioSource {
                             )
                             .is_none()
                             {
-                                self.runtime_status.fanstudio().record_parse_error();
+                                self.runtime_status.fanstudio().record_parse_error(
+                                    "unsupported_source",
+                                    &format_args!("unsupported source {source:?}"),
+                                    Some(text.as_str()),
+                                );
                                 tracing::warn!(
                                     event = "fanstudio.unsupported_source",
                                     source,
@@ -197,7 +248,15 @@ impl FanStudioSource {
                                     let cursor = match update_cursor(&envelope) {
                                         Ok(cursor) => cursor,
                                         Err(error) => {
-                                            self.runtime_status.fanstudio().record_parse_error();
+                                            self.runtime_status.fanstudio().record_parse_error(
+                                                "invalid_cursor",
+                                                &error,
+                                                Some(text.as_str()),
+                                            );
+                                            self.runtime_status.record_source_parse_error(
+                                                ProviderChannel::FanStudio,
+                                                source,
+                                            );
                                             tracing::warn!(
                                                 event = "fanstudio.invalid_cursor",
                                                 error = ?error,
@@ -222,7 +281,15 @@ impl FanStudioSource {
                                     commit_update_revision(&envelope, &mut source_md5);
                                 }
                                 Err(error) => {
-                                    self.runtime_status.fanstudio().record_parse_error();
+                                    self.runtime_status.fanstudio().record_parse_error(
+                                        "update_parse_failed",
+                                        &error,
+                                        Some(text.as_str()),
+                                    );
+                                    self.runtime_status.record_source_parse_error(
+                                        ProviderChannel::FanStudio,
+                                        source,
+                                    );
                                     tracing::warn!(
                                         event = "fanstudio.update_parse_failed",
                                         error = ?error,
@@ -247,6 +314,9 @@ impl FanStudioSource {
         }
         .await;
         reconnect::reset_after_healthy_uptime(delay, self.reconnect_min, connected_at.elapsed());
+        if matches!(outcome, Ok(true)) {
+            reconnect::close_websocket(&mut write, "fanstudio").await;
+        }
         outcome
     }
 
@@ -265,7 +335,13 @@ impl FanStudioSource {
                         Some(md5) => match ProviderCursor::new(&batch.source, md5) {
                             Ok(cursor) => Some(cursor),
                             Err(error) => {
-                                self.runtime_status.fanstudio().record_parse_error();
+                                self.runtime_status.fanstudio().record_parse_error(
+                                    "invalid_snapshot_cursor",
+                                    &error,
+                                    None,
+                                );
+                                self.runtime_status
+                                    .record_source_parse_error(ProviderChannel::FanStudio, &source);
                                 tracing::warn!(event = "fanstudio.invalid_cursor", source, error = ?error, "fanstudio.invalid_cursor");
                                 continue;
                             }
@@ -273,7 +349,11 @@ impl FanStudioSource {
                         None => {
                             // Preserve the disaster data even when the provider cannot supply a
                             // replay cursor. The durable cursor remains unchanged.
-                            self.runtime_status.fanstudio().record_parse_error();
+                            self.runtime_status.fanstudio().record_parse_error(
+                                "missing_snapshot_cursor",
+                                &format_args!("snapshot for {source} has no md5"),
+                                None,
+                            );
                             tracing::warn!(
                                 event = "fanstudio.missing_snapshot_cursor",
                                 source,
@@ -298,7 +378,13 @@ impl FanStudioSource {
                     }
                 }
                 Err(error) => {
-                    self.runtime_status.fanstudio().record_parse_error();
+                    self.runtime_status.fanstudio().record_parse_error(
+                        "snapshot_parse_failed",
+                        &format_args!("{source}: {error}"),
+                        None,
+                    );
+                    self.runtime_status
+                        .record_source_parse_error(ProviderChannel::FanStudio, &source);
                     tracing::warn!(event = "fanstudio.snapshot_parse_failed", source, error = ?error, "fanstudio.snapshot_parse_failed");
                 }
             }
@@ -154,6 +154,12 @@ fn parse_fanstudio_earthquake(
         final_report: value::bool(data, &["final", "Final", "isFinal"]),
         cancel: value::bool(data, &["cancel", "Cancel", "isCancel"]),
         training: false,
+        max_intensity: json_string(data, &["maxIntensity", "epiIntensity"]).or_else(|| {
+            value::f64(data, &["maxIntensity", "epiIntensity"])
+                .map(|intensity| format!("{intensity:.1}"))
+        }),
+        official_warning: None,
+        assumed_hypocenter: false,
     })
 }
 
@@ -182,6 +188,9 @@ fn parse_weather_alarm(data: &serde_json::Value) -> Option<DisasterEvent> {
         final_report: false,
         cancel,
         training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
     })
 }
 
@@ -219,6 +228,9 @@ fn parse_tsunami(data: &serde_json::Value) -> Option<DisasterEvent> {
         final_report: false,
         cancel: level_name == "解除",
         training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
     })
 }
 
@@ -263,6 +275,9 @@ fn parse_typhoons(data: &serde_json::Value) -> anyhow::Result<Vec<DisasterEvent>
                 final_report: false,
                 cancel: false,
                 training: false,
+                max_intensity: None,
+                official_warning: None,
+                assumed_hypocenter: false,
             })
         })
         .collect()
@@ -397,7 +412,7 @@ fn severity_from_cn_level(value: &str) -> u8 {
     }
 }
 
-fn severity_from_magnitude(magnitude: f64) -> u8 {
+pub(super) fn severity_from_magnitude(magnitude: f64) -> u8 {
     if magnitude >= 7.0 {
         4
     } else if magnitude >= 6.0 {
@@ -1,6 +1,7 @@
 use super::ProviderCursor;
-use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel};
+use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel, epoch_millis_to_rfc3339};
 use crate::runtime::{EventRuntime, RuntimeStatus};
+use crate::upstream_tls::UpstreamTls;
 use anyhow::{Context, Result, bail};
 use base64::{
     Engine as _,
@@ -18,6 +19,8 @@ const HUANIA_API_URL_BASE64: &str = concat!(
     "cmx5d2FybmluZ3M=",
 );
 const HUANIA_CURSOR_STREAM: &str = "earlywarning";
+/// 数据源登记表中 `huania.earlywarning` 的上游标识。
+const HUANIA_PROVIDER_KEY: &str = "earlywarning";
 const HUANIA_POLL_INTERVAL: Duration = Duration::from_secs(1);
 const MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
 const MAX_SNAPSHOT_EVENTS: usize = 128;
@@ -40,8 +43,16 @@ impl HuaniaSource {
         config: &crate::config::Config,
         event_runtime: EventRuntime,
         runtime_status: RuntimeStatus,
+        upstream_tls: Option<&UpstreamTls>,
     ) -> Result<Self> {
-        let client = reqwest::Client::builder()
+        let builder = match upstream_tls {
+            Some(tls) => {
+                let tls = tls.client_config(&[b"h2", b"http/1.1"])?;
+                reqwest::Client::builder().use_preconfigured_tls(rustls::ClientConfig::clone(&tls))
+            }
+            None => reqwest::Client::builder(),
+        };
+        let client = builder
             .user_agent("disaster-alert/1.0 (https://github.com/noctiro/disaster-alert)")
             .connect_timeout(Duration::from_secs(3))
             .timeout(Duration::from_secs(5))
@@ -139,7 +150,13 @@ impl HuaniaSource {
         let snapshot = self.fetch_warnings(request).await?;
         let Some(known) = known else {
             let cursor = HuaniaCursor::from_initial_snapshot(&snapshot).inspect_err(|error| {
-                self.runtime_status.huania().record_parse_error();
+                self.runtime_status.huania().record_parse_error(
+                    "initial_snapshot_invalid",
+                    error,
+                    None,
+                );
+                self.runtime_status
+                    .record_source_parse_error(ProviderChannel::Huania, HUANIA_PROVIDER_KEY);
                 tracing::warn!(
                     event = "huania.initial_snapshot_invalid",
                     error = ?error,
@@ -193,7 +210,13 @@ impl HuaniaSource {
     }
 
     fn record_parse_error(&self, event_id: i64, updates: u32, error: &anyhow::Error) {
-        self.runtime_status.huania().record_parse_error();
+        self.runtime_status.huania().record_parse_error(
+            "event_parse_failed",
+            &format_args!("event {event_id} (updates {updates}): {error}"),
+            None,
+        );
+        self.runtime_status
+            .record_source_parse_error(ProviderChannel::Huania, HUANIA_PROVIDER_KEY);
         tracing::warn!(
             event = "huania.event_parse_failed",
             event_id,
@@ -246,7 +269,7 @@ impl HuaniaSource {
     }
 }
 
-fn huania_api_url() -> Result<Url> {
+pub(super) fn huania_api_url() -> Result<Url> {
     let decoded = STANDARD
         .decode(HUANIA_API_URL_BASE64)
         .context("invalid Huania API URL encoding")?;
@@ -667,18 +690,16 @@ fn normalize(earthquake: HuaniaEarthquakeDto) -> Result<DisasterEvent> {
     if place.is_empty() {
         bail!("Huania epicenter is empty");
     }
-    let description = earthquake
-        .epi_intensity
-        .filter(|value| value.is_finite())
-        .map_or_else(
-            || format!("M{:.1} {place}", earthquake.magnitude),
-            |intensity| {
-                format!(
-                    "M{:.1} 最大烈度{intensity:.1} {place}",
-                    earthquake.magnitude
-                )
-            },
-        );
+    let epi_intensity = earthquake.epi_intensity.filter(|value| value.is_finite());
+    let description = epi_intensity.map_or_else(
+        || format!("M{:.1} {place}", earthquake.magnitude),
+        |intensity| {
+            format!(
+                "M{:.1} 最大烈度{intensity:.1} {place}",
+                earthquake.magnitude
+            )
+        },
+    );
     Ok(DisasterEvent {
         category: DisasterCategory::EarthquakeWarning,
         channel: ProviderChannel::Huania,
@@ -699,6 +720,9 @@ fn normalize(earthquake: HuaniaEarthquakeDto) -> Result<DisasterEvent> {
         final_report: false,
         cancel: false,
         training: false,
+        max_intensity: epi_intensity.map(|intensity| format!("{intensity:.1}")),
+        official_warning: None,
+        assumed_hypocenter: false,
     })
 }
 
@@ -714,44 +738,6 @@ fn severity_from_magnitude(magnitude: f64) -> u8 {
     }
 }
 
-fn epoch_millis_to_rfc3339(value: i64) -> Result<String> {
-    let seconds = value.div_euclid(1_000);
-    let millis = value.rem_euclid(1_000);
-    let days = seconds.div_euclid(86_400);
-    let day_seconds = seconds.rem_euclid(86_400);
-    let (year, month, day) = civil_from_days(days);
-    if !(1970..=9999).contains(&year) {
-        bail!("Huania timestamp is outside the supported date range");
-    }
-    let hour = day_seconds / 3_600;
-    let minute = day_seconds.rem_euclid(3_600) / 60;
-    let second = day_seconds.rem_euclid(60);
-    Ok(format!(
-        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{millis:03}Z"
-    ))
-}
-
-fn civil_from_days(days: i64) -> (i64, i64, i64) {
-    let shifted = days + 719_468;
-    let era = (if shifted >= 0 {
-        shifted
-    } else {
-        shifted - 146_096
-    })
-    .div_euclid(146_097);
-    let day_of_era = shifted - era * 146_097;
-    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
-        - day_of_era / 146_096)
-        .div_euclid(365);
-    let year = year_of_era + era * 400;
-    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
-    let month_part = (5 * day_of_year + 2).div_euclid(153);
-    let day = day_of_year - (153 * month_part + 2).div_euclid(5) + 1;
-    let month = month_part + if month_part < 10 { 3 } else { -9 };
-    let year = year + i64::from(month <= 2);
-    (year, month, day)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -802,6 +788,7 @@ mod tests {
         anyhow::ensure!(event.report_num == 2);
         anyhow::ensure!(event.revision == "1784353653622");
         anyhow::ensure!(event.occurred_at == "2026-07-18T05:47:19.800Z");
+        anyhow::ensure!(event.max_intensity.as_deref() == Some("5.6"));
         Ok(())
     }
 
@@ -922,13 +909,6 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn converts_epoch_millis_to_utc() -> Result<()> {
-        anyhow::ensure!(epoch_millis_to_rfc3339(0)? == "1970-01-01T00:00:00.000Z");
-        anyhow::ensure!(epoch_millis_to_rfc3339(1_735_689_600_123)? == "2025-01-01T00:00:00.123Z");
-        Ok(())
-    }
-
     #[test]
     fn rejects_invalid_huania_values() {
         let mut event = sample(1);
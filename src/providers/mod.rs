@@ -1,20 +1,39 @@
+mod archive;
+mod catalog;
+mod duplicates;
 mod fanstudio;
 mod fanstudio_protocol;
 mod huania;
+mod p2pquake;
+mod p2pquake_protocol;
 mod reconnect;
 mod value;
 mod wolfx;
+mod wolfx_http;
 mod wolfx_protocol;
 
 use anyhow::Result;
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
 const MAX_PROVIDER_CURSOR_VALUE_BYTES: usize = 8 * 1024;
 
+pub(crate) use archive::RawArchive;
+pub(crate) use catalog::{CATALOG_SOURCES, CatalogFormat, catalog_label};
 pub(crate) use fanstudio::FanStudioSource;
 pub(crate) use huania::HuaniaSource;
+pub(crate) use p2pquake::P2pquakeSource;
 pub(crate) use wolfx::WolfxSource;
 
+/// 地址固定、不随配置变化的上游数据源，校验 `UPSTREAM_TLS_PINS` 时与可配置的地址合并。
+pub(crate) fn fixed_upstream_urls() -> Result<Vec<Url>> {
+    Ok(vec![
+        Url::parse(fanstudio::FANSTUDIO_WEBSOCKET_URL)?,
+        Url::parse(p2pquake::P2PQUAKE_WEBSOCKET_URL)?,
+        huania::huania_api_url()?,
+    ])
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct ProviderCursor {
@@ -0,0 +1,213 @@
+use super::RawArchive;
+use super::duplicates::{DUPLICATE_FRAME_WINDOW, RecentFrames};
+use super::p2pquake_protocol::{self, JMA_QUAKE_PROVIDER_KEY};
+use super::reconnect;
+use crate::config::Config;
+use crate::models::ProviderChannel;
+use crate::runtime::EventRuntime;
+use crate::runtime::RuntimeStatus;
+use crate::upstream_tls::UpstreamTls;
+use anyhow::Result;
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_tungstenite::{
+    Connector, connect_async_tls_with_config,
+    tungstenite::{Message, protocol::WebSocketConfig},
+};
+
+const MAX_WEBSOCKET_MESSAGE_BYTES: usize = 1024 * 1024;
+pub(super) const P2PQUAKE_WEBSOCKET_URL: &str = "wss://api.p2pquake.net/v2/ws";
+
+/// P2P地震情報 WebSocket：补充 Wolfx 不转发的气象厅震度速報和地震情報，
+/// 并与 Wolfx 的紧急地震速報相互印证。
+#[derive(Clone)]
+pub(crate) struct P2pquakeSource {
+    event_runtime: EventRuntime,
+    reconnect_min: Duration,
+    reconnect_max: Duration,
+    stale_window: Duration,
+    runtime_status: RuntimeStatus,
+    raw_archive: RawArchive,
+    /// 配置了自定义 CA 或公钥固定时使用的 TLS 设置，否则为默认校验。
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl P2pquakeSource {
+    pub(crate) fn new(
+        config: &Config,
+        event_runtime: EventRuntime,
+        runtime_status: RuntimeStatus,
+        upstream_tls: Option<&UpstreamTls>,
+        raw_archive: RawArchive,
+    ) -> Result<Self> {
+        Ok(Self {
+            event_runtime,
+            reconnect_min: Duration::from_secs(config.reconnect_min_seconds),
+            reconnect_max: Duration::from_secs(config.reconnect_max_seconds),
+            stale_window: Duration::from_secs(config.stale_connection_seconds),
+            runtime_status,
+            raw_archive,
+            tls: upstream_tls
+                .map(|tls| tls.client_config(&[b"http/1.1"]))
+                .transpose()?,
+        })
+    }
+
+    pub(crate) async fn run(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
+        let mut delay = self.reconnect_min;
+        let mut frames = RecentFrames::new(DUPLICATE_FRAME_WINDOW);
+        loop {
+            if *shutdown.borrow() {
+                break;
+            }
+            match self
+                .connect_once(&mut delay, &mut frames, &mut shutdown)
+                .await
+            {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(error) => tracing::error!(
+                    event = "p2pquake.websocket_error",
+                    error = ?error,
+                    "p2pquake.websocket_error"
+                ),
+            }
+            self.runtime_status.p2pquake().set_connected(false);
+            self.runtime_status.p2pquake().record_reconnect();
+            tokio::select! {
+                biased;
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+                () = tokio::time::sleep(delay) => {}
+            }
+            delay = delay.saturating_mul(2).min(self.reconnect_max);
+        }
+        self.runtime_status.p2pquake().set_connected(false);
+        Ok(())
+    }
+
+    async fn connect_once(
+        &self,
+        delay: &mut Duration,
+        frames: &mut RecentFrames,
+        shutdown: &mut watch::Receiver<bool>,
+    ) -> Result<bool> {
+        let connect = tokio::time::timeout(
+            Duration::from_secs(10),
+            connect_async_tls_with_config(
+                P2PQUAKE_WEBSOCKET_URL,
+                Some(
+                    WebSocketConfig::default()
+                        .max_message_size(Some(MAX_WEBSOCKET_MESSAGE_BYTES))
+                        .max_frame_size(Some(MAX_WEBSOCKET_MESSAGE_BYTES)),
+                ),
+                false,
+                self.tls.clone().map(Connector::Rustls),
+            ),
+        );
+        let (socket, _) = tokio::select! {
+            biased;
+            result = shutdown.changed() => {
+                return Ok(result.is_err() || *shutdown.borrow());
+            }
+            result = connect => result
+                .map_err(|error| anyhow::anyhow!("P2PQuake connection timed out: {error}"))??,
+        };
+        let connected_at = Instant::now();
+        self.runtime_status.p2pquake().set_connected(true);
+        tracing::info!(
+            event = "p2pquake.connected",
+            websocket_url = P2PQUAKE_WEBSOCKET_URL,
+            "p2pquake.connected"
+        );
+        let (mut write, mut read) = socket.split();
+        let outcome: Result<bool> = async {
+            // 上游没有应用层心跳，依靠主动 ping 的 pong 判断连接是否存活。
+            let mut watchdog = reconnect::StaleWatchdog::new(self.stale_window);
+            loop {
+                let message = tokio::select! {
+                    biased;
+                    result = shutdown.changed() => {
+                        if result.is_err() || *shutdown.borrow() {
+                            return Ok(true);
+                        }
+                        continue;
+                    }
+                    message = read.next() => message,
+                    action = watchdog.due() => match action {
+                        reconnect::WatchdogAction::Stale => anyhow::bail!(
+                            "P2PQuake connection stale: no frame within {:?}",
+                            watchdog.window()
+                        ),
+                        reconnect::WatchdogAction::Ping => {
+                            if reconnect::send_ping(&mut write, shutdown).await? {
+                                return Ok(true);
+                            }
+                            continue;
+                        }
+                    },
+                };
+                let Some(message) = message else { break };
+                watchdog.record_frame();
+                match message? {
+                    Message::Text(text) => {
+                        self.runtime_status.p2pquake().record_message();
+                        self.raw_archive.record(ProviderChannel::P2pquake, &text);
+                        if frames.is_repeat(&text, Instant::now()) {
+                            tracing::debug!(
+                                event = "provider.duplicate_frame",
+                                provider = "p2pquake",
+                                "provider.duplicate_frame"
+                            );
+                            continue;
+                        }
+                        match p2pquake_protocol::parse(&text) {
+                            Ok(Some(event)) => {
+                                let accepted = self.event_runtime.submit_nonblocking(event).await;
+                                if !accepted {
+                                    anyhow::bail!("P2PQuake event was not durably committed");
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(error) => {
+                                self.runtime_status.p2pquake().record_parse_error(
+                                    "parse_failed",
+                                    &error,
+                                    Some(text.as_str()),
+                                );
+                                self.runtime_status.record_source_parse_error(
+                                    ProviderChannel::P2pquake,
+                                    JMA_QUAKE_PROVIDER_KEY,
+                                );
+                                tracing::warn!(
+                                    event = "p2pquake.parse_failed",
+                                    error = ?error,
+                                    "p2pquake.parse_failed"
+                                );
+                            }
+                        }
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+                reconnect::reset_after_healthy_uptime(
+                    delay,
+                    self.reconnect_min,
+                    connected_at.elapsed(),
+                );
+            }
+            Ok(false)
+        }
+        .await;
+        reconnect::reset_after_healthy_uptime(delay, self.reconnect_min, connected_at.elapsed());
+        if matches!(outcome, Ok(true)) {
+            reconnect::close_websocket(&mut write, "p2pquake").await;
+        }
+        outcome
+    }
+}
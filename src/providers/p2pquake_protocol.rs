@@ -0,0 +1,248 @@
+use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel};
+use serde::Deserialize;
+
+/// P2P地震情報 API 的地震情報（気象庁発表）报文代码。
+const JMA_QUAKE_CODE: u32 = 551;
+/// 数据源登记表中 `p2pquake.jma_quake` 的上游标识。
+pub(super) const JMA_QUAKE_PROVIDER_KEY: &str = "jma_quake";
+/// 计入受影响地区的最小震度（震度 3）。
+const MIN_AFFECTED_SCALE: i32 = 30;
+
+#[derive(Debug, Deserialize)]
+struct P2pquakeMessage {
+    code: u32,
+}
+
+/// 地震情報（code 551）。震度速報只有震度观测点，震源坐标、深度和震级均为 `-1` 或 `-200`。
+#[derive(Debug, Deserialize)]
+struct JmaQuake {
+    issue: JmaQuakeIssue,
+    earthquake: JmaQuakeEarthquake,
+    #[serde(default)]
+    points: Vec<JmaQuakePoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JmaQuakeIssue {
+    time: String,
+    #[serde(rename = "type")]
+    issue_type: String,
+    #[serde(default)]
+    correct: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JmaQuakeEarthquake {
+    time: String,
+    #[serde(default)]
+    hypocenter: Option<JmaQuakeHypocenter>,
+    #[serde(default = "unknown_scale")]
+    max_scale: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct JmaQuakeHypocenter {
+    #[serde(default)]
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    depth: f64,
+    magnitude: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JmaQuakePoint {
+    pref: String,
+    addr: String,
+    #[serde(default)]
+    is_area: bool,
+    scale: i32,
+}
+
+fn unknown_scale() -> i32 {
+    -1
+}
+
+/// 解析一条 P2P地震情報报文；只处理地震情報，其余代码（津波予報、緊急地震速報、
+/// 地震感知情報等）返回 `Ok(None)`。
+pub(super) fn parse(json: &str) -> Result<Option<DisasterEvent>, serde_json::Error> {
+    let message: P2pquakeMessage = serde_json::from_str(json)?;
+    if message.code != JMA_QUAKE_CODE {
+        return Ok(None);
+    }
+    let quake: JmaQuake = serde_json::from_str(json)?;
+    Ok(Some(normalize(quake)))
+}
+
+fn normalize(quake: JmaQuake) -> DisasterEvent {
+    let hypocenter = quake.earthquake.hypocenter.as_ref();
+    let place = hypocenter
+        .map(|hypocenter| hypocenter.name.trim())
+        .filter(|name| !name.is_empty());
+    let position = hypocenter.filter(|hypocenter| {
+        (-90.0..=90.0).contains(&hypocenter.latitude)
+            && (-180.0..=180.0).contains(&hypocenter.longitude)
+    });
+    let magnitude = hypocenter
+        .map(|hypocenter| hypocenter.magnitude)
+        .filter(|magnitude| *magnitude >= 0.0);
+    let max_intensity = scale_label(quake.earthquake.max_scale);
+    let affected_regions = affected_regions(&quake.points);
+    let title = match place {
+        Some(place) => format!("地震信息 {place}"),
+        None if affected_regions.is_empty() => "震度速报".to_string(),
+        None => format!(
+            "震度速报 {}",
+            affected_regions
+                .iter()
+                .filter(|region| region.ends_with(['都', '道', '府', '县']))
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("、")
+        ),
+    };
+    let intensity_text = max_intensity.map_or_else(String::new, |label| format!("最大震度{label}"));
+    let (report_num, final_report) = match quake.issue.issue_type.as_str() {
+        "ScalePrompt" => (1, false),
+        "Destination" => (2, false),
+        "ScaleAndDestination" => (3, false),
+        "DetailScale" => (4, true),
+        _ => (1, true),
+    };
+    let correct = quake
+        .issue
+        .correct
+        .as_deref()
+        .filter(|correct| *correct != "None")
+        .unwrap_or_default();
+    DisasterEvent {
+        category: DisasterCategory::EarthquakeReport,
+        channel: ProviderChannel::P2pquake,
+        source: format!("p2pquake.{JMA_QUAKE_PROVIDER_KEY}"),
+        // 同一地震的震度速報、震源情報和各地震度報文只共用发生时刻。
+        event_id: format!(
+            "jma-{}",
+            quake
+                .earthquake
+                .time
+                .chars()
+                .filter(char::is_ascii_digit)
+                .collect::<String>()
+        ),
+        revision: format!("{}:{}:{correct}", quake.issue.issue_type, quake.issue.time),
+        report_num,
+        title: title.trim_end().to_string(),
+        description: match magnitude {
+            Some(magnitude) => format!("M{magnitude:.1} {intensity_text}"),
+            None => intensity_text,
+        }
+        .trim()
+        .to_string(),
+        latitude: position.map(|hypocenter| hypocenter.latitude),
+        longitude: position.map(|hypocenter| hypocenter.longitude),
+        magnitude,
+        depth_km: hypocenter
+            .map(|hypocenter| hypocenter.depth)
+            .filter(|depth| *depth >= 0.0),
+        affected_regions,
+        radius_km: None,
+        level: level_from_scale(quake.earthquake.max_scale),
+        occurred_at: quake.earthquake.time,
+        final_report,
+        cancel: false,
+        training: false,
+        max_intensity: max_intensity.map(str::to_string),
+        official_warning: None,
+        assumed_hypocenter: false,
+    }
+}
+
+/// 震度 3 以上的都道府县（县名按简体写法，与订阅的省级地区求交集）和震度速報的细分区域。
+fn affected_regions(points: &[JmaQuakePoint]) -> Vec<String> {
+    let mut regions = Vec::new();
+    for point in points
+        .iter()
+        .filter(|point| point.scale >= MIN_AFFECTED_SCALE)
+    {
+        let prefecture = point.pref.trim();
+        if !prefecture.is_empty() {
+            regions.push(prefecture.replace('県', "县"));
+        }
+        let area = point.addr.trim();
+        if point.is_area && !area.is_empty() {
+            regions.push(area.to_string());
+        }
+    }
+    regions.sort_unstable();
+    regions.dedup();
+    regions
+}
+
+/// P2P地震情報以 10 倍整数表示震度，46 为「5弱以上と推定」。
+fn scale_label(scale: i32) -> Option<&'static str> {
+    Some(match scale {
+        10 => "1",
+        20 => "2",
+        30 => "3",
+        40 => "4",
+        45 | 46 => "5弱",
+        50 => "5強",
+        55 => "6弱",
+        60 => "6強",
+        70 => "7",
+        _ => return None,
+    })
+}
+
+fn level_from_scale(scale: i32) -> u8 {
+    match scale {
+        55.. => 4,
+        45.. => 3,
+        40.. => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scale_prompt_and_detail_reports_into_one_event() -> anyhow::Result<()> {
+        let prompt = parse(
+            r#"{"code":551,"id":"a","time":"2024/01/01 16:11:30.123","issue":{"source":"気象庁","time":"2024/01/01 16:12:00","type":"ScalePrompt","correct":"None"},"earthquake":{"time":"2024/01/01 16:10:00","hypocenter":{"name":"","latitude":-200,"longitude":-200,"depth":-1,"magnitude":-1},"maxScale":70,"domesticTsunami":"Checking"},"points":[{"pref":"石川県","addr":"石川県能登","isArea":true,"scale":70},{"pref":"新潟県","addr":"新潟県上越","isArea":true,"scale":20}]}"#,
+        )?
+        .ok_or_else(|| anyhow::anyhow!("scale prompt was skipped"))?;
+        anyhow::ensure!(prompt.source == "p2pquake.jma_quake");
+        anyhow::ensure!(prompt.event_id == "jma-20240101161000");
+        anyhow::ensure!(prompt.title == "震度速报 石川县", "{}", prompt.title);
+        anyhow::ensure!(prompt.latitude.is_none() && prompt.magnitude.is_none());
+        anyhow::ensure!(prompt.affected_regions == ["石川县", "石川県能登"]);
+        anyhow::ensure!(prompt.max_intensity.as_deref() == Some("7") && prompt.level == 4);
+        anyhow::ensure!(!prompt.final_report);
+
+        let detail = parse(
+            r#"{"code":551,"issue":{"time":"2024/01/01 16:20:00","type":"DetailScale","correct":"None"},"earthquake":{"time":"2024/01/01 16:10:00","hypocenter":{"name":"石川県能登地方","latitude":37.5,"longitude":137.2,"depth":10,"magnitude":7.6},"maxScale":70},"points":[]}"#,
+        )?
+        .ok_or_else(|| anyhow::anyhow!("detail report was skipped"))?;
+        anyhow::ensure!(detail.event_id == prompt.event_id);
+        anyhow::ensure!(
+            detail.revision != prompt.revision && detail.report_num > prompt.report_num
+        );
+        anyhow::ensure!(detail.title == "地震信息 石川県能登地方");
+        anyhow::ensure!(detail.description == "M7.6 最大震度7");
+        anyhow::ensure!(detail.latitude == Some(37.5) && detail.depth_km == Some(10.0));
+        anyhow::ensure!(detail.final_report);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_other_message_codes() -> anyhow::Result<()> {
+        anyhow::ensure!(parse(r#"{"code":555,"areas":[]}"#)?.is_none());
+        anyhow::ensure!(parse(r#"{"code":551}"#).is_err());
+        Ok(())
+    }
+}
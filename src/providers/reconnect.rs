@@ -1,6 +1,118 @@
+use anyhow::Result;
+use futures_util::{Sink, SinkExt};
 use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 
 pub(super) const HEALTHY_CONNECTION_UPTIME: Duration = Duration::from_secs(30);
+const CLOSE_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+const PING_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 进程关闭时向上游发送 Going Away 关闭帧，避免对端把这次断开当作异常；
+/// 发送失败或超时只记录日志，不影响关闭流程。
+pub(super) async fn close_websocket<S>(write: &mut S, provider: &'static str)
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Debug,
+{
+    let frame = CloseFrame {
+        code: CloseCode::Away,
+        reason: "shutdown".into(),
+    };
+    match tokio::time::timeout(CLOSE_FRAME_TIMEOUT, write.send(Message::Close(Some(frame)))).await {
+        Ok(Ok(())) => {
+            tracing::info!(
+                event = "provider.websocket_closed",
+                provider,
+                "provider.websocket_closed"
+            );
+        }
+        Ok(Err(error)) => {
+            tracing::debug!(event = "provider.websocket_close_failed", provider, error = ?error, "provider.websocket_close_failed");
+        }
+        Err(_elapsed) => {
+            tracing::debug!(
+                event = "provider.websocket_close_timed_out",
+                provider,
+                "provider.websocket_close_timed_out"
+            );
+        }
+    }
+}
+
+/// 连接假死检测：每隔静默窗口的三分之一主动发送一次 WebSocket ping，超过窗口仍未收到
+/// 任何帧（包括 pong）时判定连接已半断开，由调用方断开重连。
+pub(super) struct StaleWatchdog {
+    window: Duration,
+    last_frame: Instant,
+    ping: Interval,
+}
+
+pub(super) enum WatchdogAction {
+    Ping,
+    Stale,
+}
+
+impl StaleWatchdog {
+    pub(super) fn new(window: Duration) -> Self {
+        let period = ping_interval(window);
+        let now = Instant::now();
+        let mut ping = tokio::time::interval_at(now + period, period);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            window,
+            last_frame: now,
+            ping,
+        }
+    }
+
+    pub(super) fn record_frame(&mut self) {
+        self.last_frame = Instant::now();
+    }
+
+    pub(super) fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// 等到该发送 ping 或连接已超过静默窗口，二者同时到期时优先判定假死。
+    pub(super) async fn due(&mut self) -> WatchdogAction {
+        tokio::select! {
+            biased;
+            () = tokio::time::sleep_until(self.last_frame + self.window) => WatchdogAction::Stale,
+            _ = self.ping.tick() => WatchdogAction::Ping,
+        }
+    }
+}
+
+fn ping_interval(window: Duration) -> Duration {
+    (window / 3).max(Duration::from_secs(1))
+}
+
+/// 发送一次 ping；进程关闭时放弃发送并返回 `true`。
+pub(super) async fn send_ping<S>(
+    write: &mut S,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<bool>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let send = tokio::time::timeout(
+        PING_SEND_TIMEOUT,
+        write.send(Message::Ping(Default::default())),
+    );
+    tokio::select! {
+        biased;
+        result = shutdown.changed() => Ok(result.is_err() || *shutdown.borrow()),
+        result = send => {
+            result.map_err(|error| anyhow::anyhow!("WebSocket ping timed out: {error}"))??;
+            Ok(false)
+        }
+    }
+}
 
 pub(super) fn reset_after_healthy_uptime(
     delay: &mut Duration,
@@ -30,4 +142,27 @@ mod tests {
         reset_after_healthy_uptime(&mut delay, minimum, HEALTHY_CONNECTION_UPTIME);
         assert_eq!(delay, minimum);
     }
+
+    #[test]
+    fn pings_three_times_per_stale_window() {
+        assert_eq!(
+            ping_interval(Duration::from_secs(90)),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            ping_interval(Duration::from_secs(2)),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_sends_a_going_away_close_frame() -> anyhow::Result<()> {
+        let mut sent = Vec::<Message>::new();
+        close_websocket(&mut sent, "test").await;
+        let [Message::Close(Some(frame))] = sent.as_slice() else {
+            anyhow::bail!("expected one close frame, got {sent:?}");
+        };
+        anyhow::ensure!(frame.code == CloseCode::Away && frame.reason == "shutdown");
+        Ok(())
+    }
 }
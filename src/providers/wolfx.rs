@@ -1,28 +1,41 @@
+use super::RawArchive;
+use super::catalog::CatalogFormat;
+use super::duplicates::{DUPLICATE_FRAME_WINDOW, RecentFrames};
 use super::reconnect;
+use super::wolfx_http::WolfxHttpFallback;
 use super::wolfx_protocol::{self, CommonEarthquakeInfo};
 use crate::config::Config;
 use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel};
 use crate::runtime::EventRuntime;
 use crate::runtime::RuntimeStatus;
 use crate::source_registry;
+use crate::upstream_tls::UpstreamTls;
 use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use tokio_tungstenite::{
-    connect_async_with_config,
+    Connector, connect_async_tls_with_config,
     tungstenite::{Message, protocol::WebSocketConfig},
 };
 
 const MAX_WEBSOCKET_MESSAGE_BYTES: usize = 1024 * 1024;
-const WOLFX_WEBSOCKET_URL: &str = "wss://ws-api.wolfx.jp/all_eew";
 
 #[derive(Clone)]
 pub(crate) struct WolfxSource {
     event_runtime: EventRuntime,
+    /// `EEW_WS_URL` 列出的地址，至少一个；断线后轮换到下一个。
+    websocket_urls: Arc<[String]>,
     reconnect_min: Duration,
     reconnect_max: Duration,
+    stale_window: Duration,
     runtime_status: RuntimeStatus,
+    raw_archive: RawArchive,
+    /// 配置了自定义 CA 或公钥固定时使用的 TLS 设置，否则为默认校验。
+    tls: Option<Arc<rustls::ClientConfig>>,
+    /// `EEW_HTTP_FALLBACK` 关闭时为 `None`。
+    http_fallback: Option<WolfxHttpFallback>,
 }
 
 impl WolfxSource {
@@ -30,30 +43,71 @@ impl WolfxSource {
         config: &Config,
         event_runtime: EventRuntime,
         runtime_status: RuntimeStatus,
-    ) -> Self {
-        Self {
+        upstream_tls: Option<&UpstreamTls>,
+        raw_archive: RawArchive,
+    ) -> Result<Self> {
+        let http_fallback = config
+            .eew_http_fallback
+            .then(|| {
+                WolfxHttpFallback::new(
+                    config.eew_http_url.clone(),
+                    event_runtime.clone(),
+                    runtime_status.clone(),
+                    upstream_tls,
+                )
+            })
+            .transpose()?;
+        Ok(Self {
             event_runtime,
+            websocket_urls: config.eew_ws_urls.clone().into(),
             reconnect_min: Duration::from_secs(config.reconnect_min_seconds),
             reconnect_max: Duration::from_secs(config.reconnect_max_seconds),
+            stale_window: Duration::from_secs(config.stale_connection_seconds),
             runtime_status,
-        }
+            raw_archive,
+            tls: upstream_tls
+                .map(|tls| tls.client_config(&[b"http/1.1"]))
+                .transpose()?,
+            http_fallback,
+        })
     }
 
-    pub(crate) async fn run(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
+    pub(crate) async fn run(&self, shutdown: watch::Receiver<bool>) -> Result<()> {
+        let Some(http_fallback) = &self.http_fallback else {
+            return self.run_websocket(shutdown).await;
+        };
+        tokio::try_join!(
+            self.run_websocket(shutdown.clone()),
+            http_fallback.run(shutdown)
+        )?;
+        Ok(())
+    }
+
+    async fn run_websocket(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
         let mut delay = self.reconnect_min;
+        let mut frames = RecentFrames::new(DUPLICATE_FRAME_WINDOW);
+        let mut url_index = 0_usize;
         loop {
             if *shutdown.borrow() {
                 break;
             }
-            match self.connect_once(&mut delay, &mut shutdown).await {
+            let Some(websocket_url) = self.websocket_urls.get(url_index) else {
+                anyhow::bail!("no Wolfx WebSocket URL is configured");
+            };
+            match self
+                .connect_once(websocket_url, &mut delay, &mut frames, &mut shutdown)
+                .await
+            {
                 Ok(true) => break,
                 Ok(false) => {}
                 Err(error) => tracing::error!(
                     event = "wolfx.websocket_error",
+                    websocket_url = %websocket_url,
                     error = ?error,
                     "wolfx.websocket_error"
                 ),
             }
+            url_index = url_index.saturating_add(1) % self.websocket_urls.len();
             self.runtime_status.wolfx().set_connected(false);
             self.runtime_status.wolfx().record_reconnect();
             tokio::select! {
@@ -73,19 +127,22 @@ impl WolfxSource {
 
     async fn connect_once(
         &self,
+        websocket_url: &str,
         delay: &mut Duration,
+        frames: &mut RecentFrames,
         shutdown: &mut watch::Receiver<bool>,
     ) -> Result<bool> {
         let connect = tokio::time::timeout(
             Duration::from_secs(10),
-            connect_async_with_config(
-                WOLFX_WEBSOCKET_URL,
+            connect_async_tls_with_config(
+                websocket_url,
                 Some(
                     WebSocketConfig::default()
                         .max_message_size(Some(MAX_WEBSOCKET_MESSAGE_BYTES))
                         .max_frame_size(Some(MAX_WEBSOCKET_MESSAGE_BYTES)),
                 ),
                 false,
+                self.tls.clone().map(Connector::Rustls),
             ),
         );
         let (socket, _) = tokio::select! {
@@ -100,19 +157,31 @@ impl WolfxSource {
         self.runtime_status.wolfx().set_connected(true);
         tracing::info!(
             event = "wolfx.connected",
-            websocket_url = WOLFX_WEBSOCKET_URL,
+            websocket_url = %websocket_url,
             "wolfx.connected"
         );
         let (mut write, mut read) = socket.split();
         let outcome: Result<bool> = async {
+            let mut watchdog = reconnect::StaleWatchdog::new(self.stale_window);
             loop {
             if *shutdown.borrow() {
                 return Ok(true);
             }
             let message = tokio::select! {
                 biased;
-                result = tokio::time::timeout(Duration::from_secs(90), read.next()) => result
-                    .map_err(|error| anyhow::anyhow!("Wolfx heartbeat timed out: {error}"))?,
+                message = read.next() => message,
+                action = watchdog.due() => match action {
+                    reconnect::WatchdogAction::Stale => anyhow::bail!(
+                        "Wolfx connection stale: no frame within {:?}",
+                        watchdog.window()
+                    ),
+                    reconnect::WatchdogAction::Ping => {
+                        if reconnect::send_ping(&mut write, shutdown).await? {
+                            return Ok(true);
+                        }
+                        continue;
+                    }
+                },
                 result = shutdown.changed() => {
                     if result.is_err() || *shutdown.borrow() {
                         return Ok(true);
@@ -121,9 +190,19 @@ impl WolfxSource {
                 }
             };
             let Some(message) = message else { break };
+            watchdog.record_frame();
             match message? {
                 Message::Text(text) => {
                     self.runtime_status.wolfx().record_message();
+                    self.raw_archive.record(ProviderChannel::Wolfx, &text);
+                    if frames.is_repeat(&text, Instant::now()) {
+                        tracing::debug!(
+                            event = "provider.duplicate_frame",
+                            provider = "wolfx",
+                            "provider.duplicate_frame"
+                        );
+                        continue;
+                    }
                     let message_type = message_type(&text);
                     if message_type.as_deref() == Some("heartbeat") {
                         let send = tokio::time::timeout(
@@ -143,14 +222,22 @@ impl WolfxSource {
                         }
                         continue;
                     }
-                    if matches!(
-                        message_type.as_deref(),
-                        Some("pong" | "jma_eqlist" | "cenc_eqlist")
-                    ) {
+                    if message_type.as_deref() == Some("pong") {
+                        continue;
+                    }
+                    if let Some(format) = message_type
+                        .as_deref()
+                        .and_then(CatalogFormat::from_wolfx_type)
+                    {
+                        self.record_eqlist(format, &text).await;
                         continue;
                     }
                     let Some(provider_key) = message_type.as_deref() else {
-                        self.runtime_status.wolfx().record_parse_error();
+                        self.runtime_status.wolfx().record_parse_error(
+                            "missing_type",
+                            &"message has no type field",
+                            Some(text.as_str()),
+                        );
                         continue;
                     };
                     if source_registry::find_provider(ProviderChannel::Wolfx, provider_key)
@@ -174,7 +261,13 @@ impl WolfxSource {
                             }
                         }
                         Err(error) => {
-                            self.runtime_status.wolfx().record_parse_error();
+                            self.runtime_status.wolfx().record_parse_error(
+                                "parse_failed",
+                                &error,
+                                Some(text.as_str()),
+                            );
+                            self.runtime_status
+                                .record_source_parse_error(ProviderChannel::Wolfx, provider_key);
                             tracing::warn!(
                                 event = "wolfx.parse_failed",
                                 error = ?error,
@@ -196,8 +289,48 @@ impl WolfxSource {
         }
         .await;
         reconnect::reset_after_healthy_uptime(delay, self.reconnect_min, connected_at.elapsed());
+        if matches!(outcome, Ok(true)) {
+            reconnect::close_websocket(&mut write, "wolfx").await;
+        }
         outcome
     }
+
+    /// 最近地震列表只补充历史记录：列表中的地震已经通过预警或后续报文推送过。
+    async fn record_eqlist(&self, format: CatalogFormat, text: &str) {
+        let parsed = match format.parse(text) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                self.runtime_status.wolfx().record_parse_error(
+                    "eqlist_invalid",
+                    &error,
+                    Some(text),
+                );
+                tracing::warn!(
+                    event = "wolfx.eqlist_invalid",
+                    source = format.source(),
+                    error = ?error,
+                    "wolfx.eqlist_invalid"
+                );
+                return;
+            }
+        };
+        match self.event_runtime.record_history(parsed.events).await {
+            Ok(imported) if imported > 0 => tracing::info!(
+                event = "wolfx.eqlist_recorded",
+                source = format.source(),
+                imported,
+                rejected = parsed.rejected,
+                "wolfx.eqlist_recorded"
+            ),
+            Ok(_) => {}
+            Err(error) => tracing::error!(
+                event = "wolfx.eqlist_record_failed",
+                source = format.source(),
+                error = ?error,
+                "wolfx.eqlist_record_failed"
+            ),
+        }
+    }
 }
 
 fn message_type(message: &str) -> Option<String> {
@@ -234,13 +367,17 @@ pub(super) fn normalize(earthquake: CommonEarthquakeInfo) -> DisasterEvent {
         longitude: Some(earthquake.longitude),
         magnitude: Some(earthquake.magnitude),
         depth_km: earthquake.depth,
-        affected_regions: Vec::new(),
+        affected_regions: earthquake.warned_regions,
         radius_km: None,
         level,
         occurred_at: earthquake.origin_time,
         final_report: earthquake.final_report,
         cancel: earthquake.cancel,
         training: earthquake.training,
+        max_intensity: Some(earthquake.max_intensity)
+            .filter(|value| !value.trim().is_empty() && value != "未知"),
+        official_warning: earthquake.official_warning,
+        assumed_hypocenter: earthquake.assumed_hypocenter,
     }
 }
 
@@ -264,10 +401,14 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            official_warning: None,
+            assumed_hypocenter: false,
+            warned_regions: Vec::new(),
         });
         assert_eq!(event.channel, ProviderChannel::Wolfx);
         assert_eq!(event.source, "wolfx.cenc_eew");
         assert_eq!(event.category, DisasterCategory::EarthquakeWarning);
         assert_eq!(event.report_num, 2);
+        assert_eq!(event.max_intensity.as_deref(), Some("4"));
     }
 }
@@ -0,0 +1,240 @@
+use super::wolfx::normalize;
+use super::wolfx_protocol;
+use crate::models::{DisasterCategory, ProviderChannel};
+use crate::runtime::{EventRuntime, RuntimeStatus};
+use crate::source_registry;
+use crate::upstream_tls::UpstreamTls;
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// WebSocket 断开后通常很快就能重连，断开超过该时长才开始轮询，避免短暂抖动时重复请求。
+const FALLBACK_START_AFTER: Duration = Duration::from_secs(5);
+const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+/// Wolfx WebSocket 断开期间轮询各预警数据源的 HTTP 接口（如 `jma_eew.json`），
+/// 只取每个接口的最新一报；重连成功后停止轮询。
+///
+/// 每次断线后的第一轮轮询也会提交各接口当前的报文，以免断线期间发布的一报被当作基线吞掉；
+/// 其中 WebSocket 已送达的旧报由事件协调器的报数水位和过期发震时间过滤。
+#[derive(Clone)]
+pub(super) struct WolfxHttpFallback {
+    client: reqwest::Client,
+    /// `EEW_HTTP_URL`，不含末尾的 `/`。
+    base_url: String,
+    event_runtime: EventRuntime,
+    runtime_status: RuntimeStatus,
+}
+
+impl WolfxHttpFallback {
+    pub(super) fn new(
+        base_url: String,
+        event_runtime: EventRuntime,
+        runtime_status: RuntimeStatus,
+        upstream_tls: Option<&UpstreamTls>,
+    ) -> Result<Self> {
+        let builder = match upstream_tls {
+            Some(tls) => {
+                let tls = tls.client_config(&[b"h2", b"http/1.1"])?;
+                reqwest::Client::builder().use_preconfigured_tls(rustls::ClientConfig::clone(&tls))
+            }
+            None => reqwest::Client::builder(),
+        };
+        let client = builder
+            .user_agent("disaster-alert/1.0 (https://github.com/noctiro/disaster-alert)")
+            .connect_timeout(Duration::from_secs(3))
+            .timeout(Duration::from_secs(5))
+            .redirect(reqwest::redirect::Policy::none())
+            .pool_max_idle_per_host(2)
+            .build()
+            .context("failed to build Wolfx HTTP client")?;
+        Ok(Self {
+            client,
+            base_url,
+            event_runtime,
+            runtime_status,
+        })
+    }
+
+    pub(super) async fn run(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
+        let mut latest = HashMap::new();
+        let mut down_since: Option<Instant> = None;
+        loop {
+            tokio::select! {
+                biased;
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                    continue;
+                }
+                () = tokio::time::sleep(FALLBACK_POLL_INTERVAL) => {}
+            }
+            if self.runtime_status.wolfx().is_connected() {
+                latest.clear();
+                if down_since.take().is_some() {
+                    tracing::info!(
+                        event = "wolfx.http_fallback_stopped",
+                        "wolfx.http_fallback_stopped"
+                    );
+                }
+                continue;
+            }
+            let since = *down_since.get_or_insert_with(Instant::now);
+            if since.elapsed() < FALLBACK_START_AFTER {
+                continue;
+            }
+            for provider_key in warning_provider_keys() {
+                if let Err(error) = self.poll(provider_key, &mut latest).await {
+                    tracing::warn!(
+                        event = "wolfx.http_fallback_failed",
+                        provider_key,
+                        error = ?error,
+                        "wolfx.http_fallback_failed"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 接口返回的内容与上次相同时跳过，否则按 WebSocket 报文同样解析并提交。
+    async fn poll(
+        &self,
+        provider_key: &'static str,
+        latest: &mut HashMap<&'static str, String>,
+    ) -> Result<()> {
+        let response = self
+            .client
+            .get(format!("{}/{provider_key}.json", self.base_url))
+            .send()
+            .await
+            .context("Wolfx HTTP request failed")?
+            .error_for_status()
+            .context("Wolfx HTTP request was rejected")?;
+        let body = limited_response_text(response).await?;
+        if latest.insert(provider_key, body.clone()).as_ref() == Some(&body) {
+            return Ok(());
+        }
+        let mut message: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&body).context("Wolfx HTTP response is not a JSON object")?;
+        message.entry("type").or_insert_with(|| provider_key.into());
+        let text = serde_json::Value::Object(message).to_string();
+        let earthquake = match wolfx_protocol::parse(&text) {
+            Ok(earthquake) => earthquake,
+            Err(error) => {
+                self.runtime_status.wolfx().record_parse_error(
+                    "http_parse_failed",
+                    &error,
+                    Some(text.as_str()),
+                );
+                self.runtime_status
+                    .record_source_parse_error(ProviderChannel::Wolfx, provider_key);
+                return Err(error).context("failed to parse Wolfx HTTP response");
+            }
+        };
+        if !self
+            .event_runtime
+            .submit_nonblocking(normalize(earthquake))
+            .await
+        {
+            bail!("Wolfx HTTP event was not durably committed");
+        }
+        Ok(())
+    }
+}
+
+/// 数据源登记表中经 Wolfx 接收的地震预警数据源，如 `jma_eew`、`cenc_eew`。
+fn warning_provider_keys() -> impl Iterator<Item = &'static str> {
+    source_registry::SOURCES
+        .iter()
+        .filter(|definition| {
+            definition.channel == ProviderChannel::Wolfx
+                && definition.category == DisasterCategory::EarthquakeWarning
+        })
+        .map(|definition| definition.provider_key)
+}
+
+async fn limited_response_text(mut response: reqwest::Response) -> Result<String> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_RESPONSE_BYTES as u64)
+    {
+        bail!("Wolfx HTTP response exceeds {MAX_RESPONSE_BYTES} bytes");
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .context("failed to read Wolfx HTTP response")?
+    {
+        if body.len().saturating_add(chunk.len()) > MAX_RESPONSE_BYTES {
+            bail!("Wolfx HTTP response exceeds {MAX_RESPONSE_BYTES} bytes");
+        }
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8(body).context("Wolfx HTTP response is not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::{BarkNotifier, BarkPushConfig, NotificationLinkService};
+    use crate::storage::Storage;
+
+    const JMA_EEW: &str = r#"{"EventID":"jma-http-1","Serial":2,"AnnouncedTime":"2026/07/10 01:22:52","OriginTime":"2026/07/10 01:21:43","Hypocenter":"宮古島北西沖","Latitude":25.5,"Longitude":125.0,"Magunitude":4.4,"Depth":100,"MaxIntensity":"2","isTraining":false,"isFinal":false,"isCancel":false}"#;
+
+    #[tokio::test]
+    async fn first_poll_after_a_disconnect_submits_the_latest_report() -> Result<()> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let app =
+            axum::Router::new().route("/jma_eew.json", axum::routing::get(|| async { JMA_EEW }));
+        let server = tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let directory = tempfile::tempdir()?;
+        let storage = Storage::open(directory.path())?;
+        let notifier = BarkNotifier::new(
+            vec!["https://api.day.app".to_string()],
+            1,
+            1,
+            BarkPushConfig::new(None, 10, "test".to_string(), false),
+        )?;
+        let links = NotificationLinkService::for_test(&storage);
+        let fallback = WolfxHttpFallback::new(
+            format!("http://{address}"),
+            EventRuntime::for_test(storage.clone(), notifier, links)?,
+            RuntimeStatus::default(),
+            None,
+        )?;
+
+        // `run` 在重连后清空 `latest`，断线后的第一次轮询即从空表开始。
+        let mut latest = HashMap::new();
+        fallback.poll("jma_eew", &mut latest).await?;
+        let inbox = storage.inner().pending_inbox(10)?;
+        anyhow::ensure!(
+            inbox.len() == 1,
+            "expected one submitted event, got {}",
+            inbox.len()
+        );
+
+        fallback.poll("jma_eew", &mut latest).await?;
+        anyhow::ensure!(storage.inner().pending_inbox(10)?.len() == 1);
+        server.abort();
+        Ok(())
+    }
+
+    #[test]
+    fn polls_only_wolfx_warning_sources() {
+        let keys = warning_provider_keys().collect::<Vec<_>>();
+        assert!(keys.contains(&"jma_eew") && keys.contains(&"cenc_eew"));
+        assert!(!keys.contains(&"jma_eqlist"));
+        assert!(
+            keys.iter().all(|key| {
+                source_registry::find_provider(ProviderChannel::Wolfx, key).is_some()
+            })
+        );
+    }
+}
@@ -28,6 +28,16 @@ struct JmaEew {
     max_intensity: String,
     #[serde(rename = "isFinal", default)]
     is_final: bool,
+    /// `true` 为紧急地震速报（警报），`false` 为预报。
+    #[serde(rename = "isWarn", default)]
+    is_warn: Option<bool>,
+    /// 震源为仮定震源要素（PLUM 法或阈值法）时为 `true`。
+    #[serde(rename = "isAssumption", default)]
+    is_assumption: bool,
+    #[serde(rename = "Accuracy", default)]
+    accuracy: Option<JmaAccuracy>,
+    #[serde(rename = "WarnArea", default)]
+    warn_area: Vec<JmaWarnArea>,
     #[serde(rename = "Cancel", alias = "isCancel", default)]
     cancel: bool,
     #[serde(
@@ -39,6 +49,68 @@ struct JmaEew {
     training: bool,
 }
 
+/// JMA 对震源、深度和震级测定方法的说明。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct JmaAccuracy {
+    #[serde(rename = "Epicenter", default)]
+    epicenter: String,
+}
+
+/// 警报发布的地区（気象庁の細分区域）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JmaWarnArea {
+    #[serde(rename = "Chiiki")]
+    chiiki: String,
+    #[serde(rename = "Type", default)]
+    area_type: String,
+}
+
+impl JmaEew {
+    /// 只依据单个测站或震度阈值（PLUM 法、P 波/S 波阈值法、仮定震源）测定的震源不可靠。
+    fn assumed_hypocenter(&self) -> bool {
+        self.is_assumption
+            || self.accuracy.as_ref().is_some_and(|accuracy| {
+                ["仮定", "レベル", "PLUM"]
+                    .iter()
+                    .any(|marker| accuracy.epicenter.contains(marker))
+            })
+    }
+
+    /// 警报区域名称及其所属都道府县。订阅地区来自中文地理编码，县名按简体写法一并加入，
+    /// 以便与订阅的省级地区求交集。
+    fn warned_regions(&self) -> Vec<String> {
+        let mut regions = Vec::new();
+        for area in &self.warn_area {
+            let name = area.chiiki.trim();
+            if name.is_empty() || !matches!(area.area_type.as_str(), "" | "警報") {
+                continue;
+            }
+            regions.push(name.to_string());
+            if let Some(prefecture) = prefecture_of(name) {
+                regions.push(prefecture);
+            }
+        }
+        regions.sort_unstable();
+        regions.dedup();
+        regions
+    }
+}
+
+fn prefecture_of(area: &str) -> Option<String> {
+    if let Some(prefecture) = ["北海道", "東京都"]
+        .into_iter()
+        .find(|prefecture| area.starts_with(prefecture))
+    {
+        return Some(prefecture.to_string());
+    }
+    let end = area
+        .char_indices()
+        .find(|(_, value)| matches!(value, '府' | '県'))
+        .map(|(index, value)| index + value.len_utf8())?;
+    area.get(..end)
+        .map(|prefecture| prefecture.replace('県', "县"))
+}
+
 /// 四川地震局预警数据，时间字段为 UTC+8
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SichuanEew {
@@ -183,6 +255,53 @@ struct ChongqingEew {
     training: bool,
 }
 
+/// 台湾气象署（CWA）预警数据，时间字段为 UTC+8。事件编号 `ID` 为数字，最大震度为
+/// 「5弱」这类字符串，上游不提供最终报和取消标记。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TaiwanEew {
+    #[serde(rename = "type")]
+    alert_type: String,
+    #[serde(rename = "ID", alias = "EventID")]
+    event_id: serde_json::Value,
+    #[serde(rename = "ReportNum", alias = "Serial", default)]
+    report_num: u32,
+    #[serde(rename = "OriginTime")]
+    origin_time: String,
+    #[serde(rename = "HypoCenter")]
+    hypocenter: String,
+    #[serde(rename = "Latitude")]
+    latitude: f64,
+    #[serde(rename = "Longitude")]
+    longitude: f64,
+    // 上游字段拼写为 Magunitude，同时兼容正确拼写
+    #[serde(rename = "Magunitude", alias = "Magnitude")]
+    magnitude: f64,
+    #[serde(rename = "Depth", default)]
+    depth: Option<f64>,
+    #[serde(rename = "MaxIntensity", default)]
+    max_intensity: Option<String>,
+    #[serde(rename = "isFinal", default)]
+    is_final: bool,
+    #[serde(rename = "Cancel", alias = "isCancel", default)]
+    cancel: bool,
+    #[serde(
+        rename = "isTraining",
+        alias = "is_training",
+        alias = "Training",
+        default
+    )]
+    training: bool,
+}
+
+impl TaiwanEew {
+    fn event_id(&self) -> String {
+        match &self.event_id {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum EarthquakeData {
     Jma(JmaEew),
@@ -190,6 +309,7 @@ enum EarthquakeData {
     Cenc(CencEew),
     Fujian(FujianEew),
     Chongqing(ChongqingEew),
+    Taiwan(TaiwanEew),
 }
 
 impl EarthquakeData {
@@ -217,6 +337,10 @@ impl EarthquakeData {
                 let data: ChongqingEew = serde_json::from_str(json)?;
                 Ok(EarthquakeData::Chongqing(data))
             }
+            "cwa_eew" => {
+                let data: TaiwanEew = serde_json::from_str(json)?;
+                Ok(EarthquakeData::Taiwan(data))
+            }
             _ => Err(serde_json::Error::io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("unsupported Wolfx source: {}", msg.message_type),
@@ -240,6 +364,9 @@ impl EarthquakeData {
                 region: data.hypocenter.clone(),
                 origin_time: data.origin_time.clone(),
                 source_type: "jma_eew".to_string(),
+                official_warning: data.is_warn,
+                assumed_hypocenter: data.assumed_hypocenter(),
+                warned_regions: data.warned_regions(),
             },
             EarthquakeData::Sichuan(data) => CommonEarthquakeInfo {
                 event_id: data.event_id.clone(),
@@ -255,6 +382,9 @@ impl EarthquakeData {
                 region: data.hypocenter.clone(),
                 origin_time: data.origin_time.clone(),
                 source_type: "sc_eew".to_string(),
+                official_warning: None,
+                assumed_hypocenter: false,
+                warned_regions: Vec::new(),
             },
             EarthquakeData::Cenc(data) => CommonEarthquakeInfo {
                 event_id: data.event_id.clone(),
@@ -270,6 +400,9 @@ impl EarthquakeData {
                 region: data.hypocenter.clone(),
                 origin_time: data.origin_time.clone(),
                 source_type: "cenc_eew".to_string(),
+                official_warning: None,
+                assumed_hypocenter: false,
+                warned_regions: Vec::new(),
             },
             EarthquakeData::Fujian(data) => CommonEarthquakeInfo {
                 event_id: data.event_id.clone(),
@@ -285,6 +418,9 @@ impl EarthquakeData {
                 region: data.hypocenter.clone(),
                 origin_time: data.origin_time.clone(),
                 source_type: "fj_eew".to_string(),
+                official_warning: None,
+                assumed_hypocenter: false,
+                warned_regions: Vec::new(),
             },
             EarthquakeData::Chongqing(data) => CommonEarthquakeInfo {
                 event_id: data.event_id.clone(),
@@ -303,6 +439,30 @@ impl EarthquakeData {
                 region: data.hypocenter.clone(),
                 origin_time: data.origin_time.clone(),
                 source_type: "cq_eew".to_string(),
+                official_warning: None,
+                assumed_hypocenter: false,
+                warned_regions: Vec::new(),
+            },
+            EarthquakeData::Taiwan(data) => CommonEarthquakeInfo {
+                event_id: data.event_id(),
+                report_num: data.report_num,
+                final_report: data.is_final,
+                cancel: data.cancel,
+                training: data.training,
+                latitude: data.latitude,
+                longitude: data.longitude,
+                magnitude: data.magnitude,
+                depth: data.depth,
+                max_intensity: data
+                    .max_intensity
+                    .clone()
+                    .unwrap_or_else(|| "未知".to_string()),
+                region: data.hypocenter.clone(),
+                origin_time: data.origin_time.clone(),
+                source_type: "cwa_eew".to_string(),
+                official_warning: None,
+                assumed_hypocenter: false,
+                warned_regions: Vec::new(),
             },
         }
     }
@@ -327,6 +487,9 @@ pub(super) struct CommonEarthquakeInfo {
     pub(super) final_report: bool,
     pub(super) cancel: bool,
     pub(super) training: bool,
+    pub(super) official_warning: Option<bool>,
+    pub(super) assumed_hypocenter: bool,
+    pub(super) warned_regions: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -342,6 +505,37 @@ pub(super) fn parse(json: &str) -> Result<CommonEarthquakeInfo, serde_json::Erro
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{DisasterCategory, parse_event_epoch_ms};
+    use crate::providers::wolfx::normalize;
+    use crate::utils::intensity::ShindoClass;
+
+    #[test]
+    fn parses_a_cwa_frame_into_a_taiwan_earthquake_warning() -> anyhow::Result<()> {
+        let frame = r#"{"type":"cwa_eew","ID":1140514,"ReportTime":"2025-01-21 00:17:37","ReportNum":2,"OriginTime":"2025-01-21 00:17:26","HypoCenter":"臺南市楠西區","Latitude":23.22,"Longitude":120.53,"Magunitude":6.4,"Depth":10,"MaxIntensity":"6強"}"#;
+        let event = normalize(parse(frame)?);
+        anyhow::ensure!(event.source == "wolfx.cwa_eew", "{}", event.source);
+        anyhow::ensure!(event.category == DisasterCategory::EarthquakeWarning);
+        anyhow::ensure!(event.event_id == "1140514");
+        anyhow::ensure!(event.latitude == Some(23.22) && event.longitude == Some(120.53));
+        anyhow::ensure!(event.magnitude == Some(6.4) && event.level == 3);
+        anyhow::ensure!(event.depth_km == Some(10.0));
+        anyhow::ensure!(event.report_num == 2 && event.revision == "2");
+        anyhow::ensure!(!event.final_report && !event.cancel && !event.training);
+        // 气象署的时间为台湾时间（UTC+8），即 2025-01-20T16:17:26Z。
+        anyhow::ensure!(parse_event_epoch_ms(&event) == Some(1_737_389_846_000));
+
+        let intensity = event
+            .max_intensity
+            .as_deref()
+            .and_then(|value| ShindoClass::from_reported(&event.source, value));
+        anyhow::ensure!(intensity.map(ShindoClass::label) == Some("6強"));
+        anyhow::ensure!(intensity > "6弱".parse().ok() && intensity < "7".parse().ok());
+
+        let closing = frame.replace("\"Depth\"", "\"isFinal\":true,\"Cancel\":true,\"Depth\"");
+        let event = normalize(parse(&closing)?);
+        anyhow::ensure!(event.final_report && event.cancel);
+        Ok(())
+    }
     #[test]
     fn parses_all_documented_wolfx_eew_sources() {
         let cases = [
@@ -375,6 +569,12 @@ mod tests {
                 3,
                 None,
             ),
+            (
+                r#"{"type":"cwa_eew","ID":1140514,"ReportTime":"2025-01-21 00:17:37","ReportNum":2,"OriginTime":"2025-01-21 00:17:26","HypoCenter":"臺南市楠西區","Latitude":23.22,"Longitude":120.53,"Magunitude":6.4,"Depth":10,"MaxIntensity":"6強"}"#,
+                "cwa_eew",
+                2,
+                Some(10.0),
+            ),
         ];
 
         for (json, source_type, report_num, depth) in cases {
@@ -387,12 +587,49 @@ mod tests {
             }
         }
 
+        let parsed = EarthquakeData::parse_to_common_info(cases[5].0);
+        assert!(
+            parsed.is_ok_and(|cwa| cwa.event_id == "1140514" && cwa.max_intensity == "6強"),
+            "failed to parse CWA identifiers"
+        );
+
         let parsed = EarthquakeData::parse_to_common_info(cases[0].0);
         assert!(parsed.is_ok(), "failed to parse JMA flags: {parsed:?}");
         if let Ok(jma) = parsed {
             assert!(jma.training);
             assert!(jma.final_report);
             assert!(jma.cancel);
+            assert_eq!(jma.official_warning, None);
+            assert!(!jma.assumed_hypocenter);
         }
+
+        let warning = cases[0]
+            .0
+            .replace("\"isTraining\"", "\"isWarn\":true,\"isTraining\"");
+        let parsed = EarthquakeData::parse_to_common_info(&warning);
+        assert!(parsed.is_ok_and(|jma| jma.official_warning == Some(true)));
+
+        for accuracy in [
+            r#""isAssumption":true,"#,
+            r#""Accuracy":{"Epicenter":"P波/S波レベル超え、IPF法（1点）、または仮定震源要素"},"#,
+        ] {
+            let assumed = cases[0]
+                .0
+                .replace("\"isTraining\"", &format!("{accuracy}\"isTraining\""));
+            let parsed = EarthquakeData::parse_to_common_info(&assumed);
+            assert!(parsed.is_ok_and(|jma| jma.assumed_hypocenter), "{accuracy}");
+        }
+
+        let warned = cases[0].0.replace(
+            "\"isTraining\"",
+            r#""WarnArea":[{"Chiiki":"石川県能登","Shindo1":"7","Shindo2":"5弱","Time":"16:10:10","Type":"警報","Arrive":true},{"Chiiki":"北海道道南","Type":"警報"},{"Chiiki":"新潟県上越","Type":"予報"}],"isTraining""#,
+        );
+        let parsed = EarthquakeData::parse_to_common_info(&warned);
+        assert!(
+            parsed.is_ok_and(
+                |jma| jma.warned_regions == ["北海道", "北海道道南", "石川县", "石川県能登"]
+            ),
+            "{warned}"
+        );
     }
 }
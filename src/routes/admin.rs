@@ -0,0 +1,656 @@
+use crate::config::TunableSettings;
+use crate::models::{
+    ApiResponse, DisasterEvent, FeltReport, RestoreSubscriptionRequest, UnsubscribeRequest,
+    epoch_millis_to_rfc3339, mask_device_key,
+};
+use crate::providers::CatalogFormat;
+use crate::runtime::ParseDiagnosticsSnapshot;
+use crate::storage::{SubscriptionBreakdown, SubscriptionDensity};
+use crate::subscriptions::{DeleteSubscriptionError, H3_RESOLUTIONS};
+use crate::utils::boundaries;
+use crate::utils::intensity::{
+    CalibrationSuggestion, IntensityModel, MIN_CALIBRATION_SAMPLES, UnknownIntensityModel,
+    suggest_calibration,
+};
+use aws_lc_rs::constant_time::verify_slices_are_equal;
+use axum::{
+    Json,
+    extract::{
+        Query, Request, State,
+        rejection::{JsonRejection, QueryRejection, StringRejection},
+    },
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// 订阅分布默认使用的 H3 分辨率，格子边长约 9 公里，适合按城市观察。
+const DEFAULT_DENSITY_RESOLUTION: u8 = 5;
+
+use super::AppState;
+use super::felt::observations;
+use super::subscribe::{parse_destination, restore_destination};
+
+/// 管理接口的 Bearer Token 校验。
+///
+/// 只保存 Token 的摘要，并以定长摘要做常量时间比较，避免按前缀逐字节试探。
+#[derive(Clone)]
+pub(crate) struct AdminAuth {
+    token_digest: Arc<[u8; 32]>,
+}
+
+impl AdminAuth {
+    pub(crate) fn new(token: &str) -> Self {
+        Self {
+            token_digest: Arc::new(Sha256::digest(token.as_bytes()).into()),
+        }
+    }
+
+    fn verify(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        let digest: [u8; 32] = Sha256::digest(token.trim().as_bytes()).into();
+        verify_slices_are_equal(&digest, self.token_digest.as_slice()).is_ok()
+    }
+}
+
+pub(crate) async fn require_admin(
+    State(auth): State<AdminAuth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if auth.verify(request.headers()) {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        event = "admin.unauthorized",
+        path = %request.uri().path(),
+        "admin.unauthorized"
+    );
+    let mut response = (
+        StatusCode::UNAUTHORIZED,
+        Json(ApiResponse::<()>::error("管理凭据无效")),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// 管理员按目的地彻底删除订阅和历史记录，效果等同于用户以 `purge=true` 取消订阅。
+pub(crate) async fn admin_purge_subscription_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<UnsubscribeRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let Json(payload) = match payload {
+        Ok(payload) => payload,
+        Err(_rejection) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::<()>::error("删除请求体无效")),
+            );
+        }
+    };
+    let destination = match parse_destination(&state, &payload.destination) {
+        Ok(value) => value,
+        Err((status, message)) => {
+            return (status, Json(ApiResponse::<()>::error(message)));
+        }
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("订阅存储繁忙，请稍后重试")),
+        );
+    };
+    let manager = state.subscriptions.clone();
+    let target = destination.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        manager.purge_subscription(&target)
+    })
+    .await
+    .map_err(|error| DeleteSubscriptionError::Storage(anyhow::Error::from(error)))
+    .and_then(|result| result);
+    match result {
+        Ok(()) => {
+            tracing::info!(
+                event = "admin.subscription_purged",
+                device_key = %mask_device_key(&destination.device_key),
+                "admin.subscription_purged"
+            );
+            (
+                StatusCode::OK,
+                Json(ApiResponse::<()>::success("订阅及历史记录已删除", None)),
+            )
+        }
+        Err(DeleteSubscriptionError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("订阅不存在")),
+        ),
+        Err(DeleteSubscriptionError::Storage(error)) => {
+            tracing::error!(
+                event = "admin.subscription_purge_failed",
+                device_key = %mask_device_key(&destination.device_key),
+                error = ?error,
+                "admin.subscription_purge_failed"
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("删除暂时无法完成，请稍后重试")),
+            )
+        }
+    }
+}
+
+/// 管理员恢复恢复期内取消的订阅，与用户自助恢复的规则相同；没有可恢复的订阅时如实返回 404。
+pub(crate) async fn admin_restore_subscription_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<RestoreSubscriptionRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let Ok(Json(payload)) = payload else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("恢复订阅请求体无效")),
+        );
+    };
+    match restore_destination(&state, &payload.destination).await {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("订阅已恢复", None)),
+        ),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error("订阅不存在或已超过恢复期")),
+        ),
+        Err((status, message)) => (status, Json(ApiResponse::error(message))),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DensityQuery {
+    #[serde(default = "default_density_resolution")]
+    resolution: u8,
+}
+
+const fn default_density_resolution() -> u8 {
+    DEFAULT_DENSITY_RESOLUTION
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct DensityResponse {
+    resolution: u8,
+    cells: Vec<DensityCell>,
+    regions: Vec<DensityRegion>,
+}
+
+#[derive(Debug, Serialize)]
+struct DensityCell {
+    cell: String,
+    latitude: f64,
+    longitude: f64,
+    subscriptions: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct DensityRegion {
+    region: &'static str,
+    subscriptions: usize,
+}
+
+/// 按 H3 格子汇总生效订阅的分布，用于绘制覆盖热力图和评估某条断裂带附近的推送量。
+pub(crate) async fn admin_subscription_density_handler(
+    State(state): State<AppState>,
+    query: Result<Query<DensityQuery>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<DensityResponse>>) {
+    let finest = u8::from(H3_RESOLUTIONS[H3_RESOLUTIONS.len() - 1]);
+    let resolution = query
+        .ok()
+        .and_then(|Query(query)| h3o::Resolution::try_from(query.resolution).ok())
+        .filter(|resolution| u8::from(*resolution) <= finest);
+    let Some(resolution) = resolution else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(format!("分辨率须为 0 到 {finest}"))),
+        );
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("订阅存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage.subscription_density(resolution)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(density) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "订阅分布获取成功",
+                Some(density_response(u8::from(resolution), density)),
+            )),
+        ),
+        Err(error) => {
+            tracing::error!(event = "admin.density_failed", error = ?error, "admin.density_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("订阅分布暂时无法统计，请稍后重试")),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct BreakdownResponse {
+    subscriptions: usize,
+    min_intensity: Vec<IntensityCount>,
+    channels: Vec<ChannelCount>,
+    created_weeks: Vec<WeekCount>,
+    regions: Vec<RegionCount>,
+}
+
+#[derive(Debug, Serialize)]
+struct IntensityCount {
+    min_intensity: u8,
+    subscriptions: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ChannelCount {
+    channel: &'static str,
+    subscriptions: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct WeekCount {
+    /// 该周周一的 UTC 日期。
+    week: String,
+    subscriptions: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct RegionCount {
+    /// 内置边界名称；不在任何内置边界内的坐标为空。
+    region: Option<&'static str>,
+    subscriptions: usize,
+}
+
+/// 按最低提醒烈度、推送渠道、创建周和大致地区汇总生效订阅，便于了解订阅人群而无需导出原始数据。
+pub(crate) async fn admin_subscription_breakdown_handler(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<BreakdownResponse>>) {
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("订阅存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage.subscription_breakdown()
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(breakdown) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "订阅构成获取成功",
+                Some(breakdown_response(breakdown)),
+            )),
+        ),
+        Err(error) => {
+            tracing::error!(
+                event = "admin.breakdown_failed",
+                error = ?error,
+                "admin.breakdown_failed"
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("订阅构成暂时无法统计，请稍后重试")),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CatalogImportQuery {
+    format: CatalogFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CatalogImportResponse {
+    source: &'static str,
+    /// 解析出的地震数。
+    parsed: usize,
+    /// 新写入历史记录的地震数。
+    imported: usize,
+    /// 历史记录中已有而跳过的地震数。
+    skipped: usize,
+    /// 缺少时间、坐标或编号而无法导入的记录数。
+    rejected: usize,
+}
+
+/// 把外部历史目录导入历史地震记录，供历史查询和活动统计使用；导入的地震不会推送。
+pub(crate) async fn admin_import_catalog_handler(
+    State(state): State<AppState>,
+    query: Result<Query<CatalogImportQuery>, QueryRejection>,
+    body: Result<String, StringRejection>,
+) -> (StatusCode, Json<ApiResponse<CatalogImportResponse>>) {
+    let Ok(Query(CatalogImportQuery { format })) = query else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(
+                "目录格式须为 usgs_csv、jma_json、wolfx_jma_eqlist 或 wolfx_cenc_eqlist",
+            )),
+        );
+    };
+    let Ok(body) = body else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(
+                "目录内容须为 UTF-8 文本且不超过大小上限",
+            )),
+        );
+    };
+    let parsed = match format.parse(&body) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            tracing::warn!(event = "admin.catalog_invalid", error = ?error, "admin.catalog_invalid");
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(format!("目录无法解析：{error}"))),
+            );
+        }
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage
+            .import_earthquake_history(&parsed.events)
+            .map(|imported| CatalogImportResponse {
+                source: format.source(),
+                parsed: parsed.events.len(),
+                imported,
+                skipped: parsed.events.len().saturating_sub(imported),
+                rejected: parsed.rejected,
+            })
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(response) => {
+            tracing::info!(
+                event = "admin.catalog_imported",
+                source = response.source,
+                imported = response.imported,
+                skipped = response.skipped,
+                rejected = response.rejected,
+                "admin.catalog_imported"
+            );
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success("历史目录导入成功", Some(response))),
+            )
+        }
+        Err(error) => {
+            tracing::error!(event = "admin.catalog_import_failed", error = ?error, "admin.catalog_import_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("历史目录暂时无法导入，请稍后重试")),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CalibrationQuery {
+    /// 要评估的震度模型，默认为当前生效的模型。
+    model: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CalibrationResponse {
+    model: IntensityModel,
+    /// 有震感报告且仍在历史记录中的地震数。
+    events: usize,
+    reports: usize,
+    /// 带坐标且所属地震有震中和震级、参与对照的报告数。
+    located: usize,
+    /// 报告数超过读取上限，只汇总了一部分。
+    truncated: bool,
+    /// 给出系数修正建议所需的最少报告数。
+    min_samples: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<CalibrationSuggestion>,
+}
+
+/// 汇总全部震感报告与震度模型估算的偏差，给出衰减式系数的修正建议，供调整模型时参考；
+/// 建议不会自动应用。
+pub(crate) async fn admin_felt_calibration_handler(
+    State(state): State<AppState>,
+    query: Result<Query<CalibrationQuery>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<CalibrationResponse>>) {
+    let model = match query {
+        Ok(Query(CalibrationQuery { model: None })) => {
+            Ok(state.config_reloader.current().intensity_model)
+        }
+        Ok(Query(CalibrationQuery { model: Some(model) })) => model.parse::<IntensityModel>(),
+        Err(_rejection) => Err(UnknownIntensityModel),
+    };
+    let Ok(model) = model else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("震度模型须为 blended 或 si_midorikawa")),
+        );
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage
+            .felt_report_events()
+            .map(|(groups, truncated)| calibration_response(model, &groups, truncated))
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("模型校准汇总成功", Some(response))),
+        ),
+        Err(error) => {
+            tracing::error!(event = "admin.calibration_failed", error = ?error, "admin.calibration_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("震感报告暂时无法汇总，请稍后重试")),
+            )
+        }
+    }
+}
+
+fn calibration_response(
+    model: IntensityModel,
+    groups: &[(DisasterEvent, Vec<FeltReport>)],
+    truncated: bool,
+) -> CalibrationResponse {
+    let observations = groups
+        .iter()
+        .flat_map(|(event, reports)| observations(event, reports, model))
+        .map(|(_epicentral_km, observation)| observation)
+        .collect::<Vec<_>>();
+    CalibrationResponse {
+        model,
+        events: groups.len(),
+        reports: groups.iter().map(|(_event, reports)| reports.len()).sum(),
+        located: observations.len(),
+        truncated,
+        min_samples: MIN_CALIBRATION_SAMPLES,
+        suggestion: suggest_calibration(&observations),
+    }
+}
+
+fn density_response(resolution: u8, density: SubscriptionDensity) -> DensityResponse {
+    DensityResponse {
+        resolution,
+        cells: density
+            .cells
+            .into_iter()
+            .filter_map(|(cell, subscriptions)| {
+                let cell = h3o::CellIndex::try_from(cell).ok()?;
+                let center = h3o::LatLng::from(cell);
+                Some(DensityCell {
+                    cell: cell.to_string(),
+                    latitude: center.lat(),
+                    longitude: center.lng(),
+                    subscriptions,
+                })
+            })
+            .collect(),
+        regions: density
+            .regions
+            .into_iter()
+            .filter_map(|(index, subscriptions)| {
+                Some(DensityRegion {
+                    region: boundaries::get(index)?.name,
+                    subscriptions,
+                })
+            })
+            .collect(),
+    }
+}
+
+fn breakdown_response(breakdown: SubscriptionBreakdown) -> BreakdownResponse {
+    BreakdownResponse {
+        subscriptions: breakdown.subscriptions,
+        min_intensity: breakdown
+            .min_intensity
+            .into_iter()
+            .map(|(min_intensity, subscriptions)| IntensityCount {
+                min_intensity,
+                subscriptions,
+            })
+            .collect(),
+        channels: breakdown
+            .channels
+            .into_iter()
+            .map(|(channel, subscriptions)| ChannelCount {
+                channel,
+                subscriptions,
+            })
+            .collect(),
+        created_weeks: breakdown
+            .created_weeks
+            .into_iter()
+            .filter_map(|(week, subscriptions)| {
+                let timestamp = epoch_millis_to_rfc3339(week).ok()?;
+                Some(WeekCount {
+                    week: timestamp.get(..10)?.to_string(),
+                    subscriptions,
+                })
+            })
+            .collect(),
+        regions: breakdown
+            .regions
+            .into_iter()
+            .map(|(index, subscriptions)| RegionCount {
+                region: index
+                    .and_then(boundaries::get)
+                    .map(|boundary| boundary.name),
+                subscriptions,
+            })
+            .collect(),
+    }
+}
+
+/// 各数据源的解析失败计数和最近几条失败消息，用于尽早发现上游格式变化。
+pub(crate) async fn admin_parse_diagnostics_handler(
+    State(state): State<AppState>,
+) -> Json<ApiResponse<ParseDiagnosticsSnapshot>> {
+    Json(ApiResponse::success(
+        "解析诊断获取成功",
+        Some(state.runtime_status.parse_diagnostics()),
+    ))
+}
+
+/// 重新读取配置文件并应用可热更新的设置，失败时返回原因且保留当前设置。
+pub(crate) async fn admin_reload_config_handler(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<TunableSettings>>) {
+    let reloader = state.config_reloader.clone();
+    let result = tokio::task::spawn_blocking(move || reloader.reload_logged("admin"))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    match result {
+        Ok(settings) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "配置已重新加载",
+                Some(TunableSettings::clone(&settings)),
+            )),
+        ),
+        Err(error) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ApiResponse::error(format!(
+                "配置重新加载失败，当前设置保持不变：{error:#}"
+            ))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(value).unwrap_or(HeaderValue::from_static("")),
+        );
+        headers
+    }
+
+    #[test]
+    fn admin_auth_accepts_only_the_configured_bearer_token() {
+        let token = "0123456789abcdef0123456789abcdef";
+        let auth = AdminAuth::new(token);
+        assert!(auth.verify(&headers(&format!("Bearer {token}"))));
+        assert!(!auth.verify(&headers(token)));
+        assert!(!auth.verify(&headers("Bearer 0123456789abcdef")));
+        assert!(!auth.verify(&HeaderMap::new()));
+    }
+}
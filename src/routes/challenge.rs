@@ -0,0 +1,340 @@
+use crate::config::{Config, LiveSettings};
+use crate::models::{ApiResponse, ChallengeSolution};
+use crate::storage::try_now_millis;
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::AppState;
+use super::signing::TokenSigner;
+
+const CHALLENGE_PURPOSE: &[u8] = b"disaster-alert:subscribe-challenge:v2";
+const CHALLENGE_TTL_MS: i64 = 5 * 60 * 1_000;
+const MAX_TOKEN_BYTES: usize = 96;
+const MAX_NONCE_BYTES: usize = 32;
+const MAX_REDEEMED_CHALLENGES: usize = 65_536;
+
+/// 订阅创建前的工作量证明。
+///
+/// 挑战由服务端密钥签发、无需存储；已兑换的挑战按签发时间和序号只在有效期内记入内存，
+/// 防止重复使用，令牌字段写法不同（如补零）也视为同一挑战。
+/// 难度为 0 时关闭校验，难度随配置重新加载即时生效。
+#[derive(Clone)]
+pub(crate) struct SubscriptionChallenges {
+    inner: Arc<ChallengeInner>,
+}
+
+struct ChallengeInner {
+    settings: LiveSettings,
+    signer: TokenSigner,
+    counter: AtomicU64,
+    redeemed: Mutex<HashMap<(i64, u64), i64>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ChallengeError {
+    Missing,
+    Invalid,
+    Expired,
+    Insufficient,
+    Replayed,
+    Busy,
+}
+
+impl ChallengeError {
+    pub(crate) fn status(&self) -> StatusCode {
+        match self {
+            Self::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Missing | Self::Invalid | Self::Expired | Self::Insufficient | Self::Replayed => {
+                StatusCode::FORBIDDEN
+            }
+        }
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            Self::Missing => "请先完成订阅验证",
+            Self::Invalid | Self::Insufficient => "订阅验证无效，请重试",
+            Self::Expired => "订阅验证已过期，请重试",
+            Self::Replayed => "订阅验证已使用，请重新提交",
+            Self::Busy => "订阅验证繁忙，请稍后重试",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChallengeResponse {
+    pub(crate) difficulty: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) expires_at_ms: Option<i64>,
+}
+
+impl SubscriptionChallenges {
+    pub(crate) fn new(config: &Config, settings: LiveSettings) -> Self {
+        Self::with_secret(settings, config.alert_signing_key.expose().as_bytes())
+    }
+
+    pub(crate) fn disabled() -> Self {
+        Self::with_secret(LiveSettings::default(), &[0; 32])
+    }
+
+    #[cfg(test)]
+    fn with_difficulty(difficulty: u8, secret: [u8; 32]) -> Self {
+        use crate::config::TunableSettings;
+        Self::with_secret(
+            LiveSettings::new(TunableSettings {
+                subscribe_pow_difficulty: difficulty,
+                ..TunableSettings::default()
+            }),
+            &secret,
+        )
+    }
+
+    fn with_secret(settings: LiveSettings, secret: &[u8]) -> Self {
+        Self {
+            inner: Arc::new(ChallengeInner {
+                settings,
+                signer: TokenSigner::derive(secret, CHALLENGE_PURPOSE),
+                counter: AtomicU64::new(0),
+                redeemed: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.difficulty() > 0
+    }
+
+    fn difficulty(&self) -> u8 {
+        self.inner.settings.load().subscribe_pow_difficulty
+    }
+
+    pub(crate) fn issue(&self, now_ms: i64) -> ChallengeResponse {
+        let difficulty = self.difficulty();
+        if difficulty == 0 {
+            return ChallengeResponse {
+                difficulty: 0,
+                token: None,
+                expires_at_ms: None,
+            };
+        }
+        let serial = self.inner.counter.fetch_add(1, Ordering::Relaxed);
+        let mac = self
+            .inner
+            .signer
+            .sign(&[&now_ms.to_be_bytes(), &serial.to_be_bytes()]);
+        ChallengeResponse {
+            difficulty,
+            token: Some(format!("{now_ms}.{serial}.{mac}")),
+            expires_at_ms: Some(now_ms.saturating_add(CHALLENGE_TTL_MS)),
+        }
+    }
+
+    pub(crate) fn redeem(
+        &self,
+        solution: Option<&ChallengeSolution>,
+        now_ms: i64,
+    ) -> Result<(), ChallengeError> {
+        let difficulty = self.difficulty();
+        if difficulty == 0 {
+            return Ok(());
+        }
+        let solution = solution.ok_or(ChallengeError::Missing)?;
+        let (issued_at_ms, serial) = self.verify_token(&solution.token)?;
+        if now_ms.saturating_sub(issued_at_ms) > CHALLENGE_TTL_MS || issued_at_ms > now_ms {
+            return Err(ChallengeError::Expired);
+        }
+        if solution.nonce.is_empty()
+            || solution.nonce.len() > MAX_NONCE_BYTES
+            || !solution
+                .nonce
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric())
+        {
+            return Err(ChallengeError::Invalid);
+        }
+        if leading_zero_bits(&solution.token, &solution.nonce) < u32::from(difficulty) {
+            return Err(ChallengeError::Insufficient);
+        }
+
+        let mut redeemed = self
+            .inner
+            .redeemed
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        redeemed.retain(|_, expires_at_ms| *expires_at_ms > now_ms);
+        if redeemed.contains_key(&(issued_at_ms, serial)) {
+            return Err(ChallengeError::Replayed);
+        }
+        if redeemed.len() >= MAX_REDEEMED_CHALLENGES {
+            return Err(ChallengeError::Busy);
+        }
+        redeemed.insert(
+            (issued_at_ms, serial),
+            issued_at_ms.saturating_add(CHALLENGE_TTL_MS),
+        );
+        Ok(())
+    }
+
+    fn verify_token(&self, token: &str) -> Result<(i64, u64), ChallengeError> {
+        if token.len() > MAX_TOKEN_BYTES {
+            return Err(ChallengeError::Invalid);
+        }
+        let mut parts = token.split('.');
+        let (Some(issued_at), Some(serial), Some(mac), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ChallengeError::Invalid);
+        };
+        let issued_at_ms = issued_at
+            .parse::<i64>()
+            .map_err(|_error| ChallengeError::Invalid)?;
+        let serial = serial
+            .parse::<u64>()
+            .map_err(|_error| ChallengeError::Invalid)?;
+        if !self
+            .inner
+            .signer
+            .verify(&[&issued_at_ms.to_be_bytes(), &serial.to_be_bytes()], mac)
+        {
+            return Err(ChallengeError::Invalid);
+        }
+        Ok((issued_at_ms, serial))
+    }
+}
+
+/// 客户端需找到 nonce，使 `SHA-256("{token}:{nonce}")` 至少有 `difficulty` 个前导零位。
+fn leading_zero_bits(token: &str, nonce: &str) -> u32 {
+    let mut hash = Sha256::new();
+    hash.update(token.as_bytes());
+    hash.update(b":");
+    hash.update(nonce.as_bytes());
+    let digest = hash.finalize();
+    let mut bits = 0;
+    for byte in digest {
+        if byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+pub(crate) async fn subscribe_challenge_handler(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match try_now_millis() {
+        Ok(now_ms) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "订阅验证获取成功",
+                Some(state.subscription_challenges.issue(now_ms)),
+            )),
+        ),
+        Err(error) => {
+            tracing::error!(event = "subscription.challenge_failed", error = ?error, "subscription.challenge_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("订阅验证暂时无法获取")),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(token: &str, difficulty: u8) -> String {
+        (0u64..)
+            .map(|nonce| nonce.to_string())
+            .find(|nonce| leading_zero_bits(token, nonce) >= u32::from(difficulty))
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn disabled_challenges_accept_missing_solutions() {
+        let challenges = SubscriptionChallenges::disabled();
+        assert!(challenges.issue(1_000).token.is_none());
+        assert_eq!(challenges.redeem(None, 1_000), Ok(()));
+    }
+
+    #[test]
+    fn solved_challenge_is_accepted_once() {
+        let challenges = SubscriptionChallenges::with_difficulty(8, [3; 32]);
+        let token = challenges.issue(1_000).token.unwrap_or_default();
+        let solution = ChallengeSolution {
+            nonce: solve(&token, 8),
+            token,
+        };
+        assert_eq!(challenges.redeem(Some(&solution), 2_000), Ok(()));
+        assert_eq!(
+            challenges.redeem(Some(&solution), 3_000),
+            Err(ChallengeError::Replayed)
+        );
+    }
+
+    #[test]
+    fn rewritten_token_fields_count_as_the_same_challenge() {
+        let challenges = SubscriptionChallenges::with_difficulty(8, [3; 32]);
+        let token = challenges.issue(1_000).token.unwrap_or_default();
+        let solution = ChallengeSolution {
+            nonce: solve(&token, 8),
+            token: token.clone(),
+        };
+        assert_eq!(challenges.redeem(Some(&solution), 2_000), Ok(()));
+
+        for variant in [format!("0{token}"), format!("+{token}")] {
+            let replay = ChallengeSolution {
+                nonce: solve(&variant, 8),
+                token: variant,
+            };
+            assert_eq!(
+                challenges.redeem(Some(&replay), 2_000),
+                Err(ChallengeError::Replayed)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_missing_forged_expired_and_unsolved_challenges() {
+        let challenges = SubscriptionChallenges::with_difficulty(8, [3; 32]);
+        assert_eq!(challenges.redeem(None, 1_000), Err(ChallengeError::Missing));
+
+        let token = challenges.issue(1_000).token.unwrap_or_default();
+        let forged = ChallengeSolution {
+            token: token.replacen("1000.", "1001.", 1),
+            nonce: solve(&token, 8),
+        };
+        assert_eq!(
+            challenges.redeem(Some(&forged), 2_000),
+            Err(ChallengeError::Invalid)
+        );
+
+        let solved = ChallengeSolution {
+            nonce: solve(&token, 8),
+            token: token.clone(),
+        };
+        assert_eq!(
+            challenges.redeem(Some(&solved), 1_000 + CHALLENGE_TTL_MS + 1),
+            Err(ChallengeError::Expired)
+        );
+
+        let nonce = (0u64..)
+            .map(|nonce| nonce.to_string())
+            .find(|nonce| leading_zero_bits(&token, nonce) < 8)
+            .unwrap_or_default();
+        let unsolved = ChallengeSolution { token, nonce };
+        assert_eq!(
+            challenges.redeem(Some(&unsolved), 2_000),
+            Err(ChallengeError::Insufficient)
+        );
+    }
+}
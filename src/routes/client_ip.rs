@@ -0,0 +1,189 @@
+use anyhow::{Context, Result, bail};
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+const MAX_FORWARDED_HOPS: usize = 16;
+
+/// 根据可信代理列表解析请求来源 IP。
+///
+/// 只有直连地址属于 `TRUSTED_PROXIES` 时才读取 `X-Forwarded-For`，并从右向左跳过
+/// 可信代理，取第一个不可信地址；否则直接使用 TCP 对端地址，防止客户端伪造请求头。
+/// 可信代理未发送 `X-Forwarded-For` 时才读取 `X-Real-IP`。
+#[derive(Clone, Default)]
+pub(crate) struct ClientIpResolver {
+    trusted: Arc<[IpNetwork]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpNetwork {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl ClientIpResolver {
+    pub(crate) fn new(trusted_proxies: &[String]) -> Result<Self> {
+        let trusted = trusted_proxies
+            .iter()
+            .map(|entry| {
+                IpNetwork::parse(entry)
+                    .with_context(|| format!("invalid TRUSTED_PROXIES entry {entry:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            trusted: trusted.into(),
+        })
+    }
+
+    pub(crate) fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = canonical(peer);
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        let mut client = peer;
+        for hop in forwarded.iter().rev().take(MAX_FORWARDED_HOPS) {
+            let Ok(address) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = canonical(address);
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+        // `X-Real-IP` 只在没有 `X-Forwarded-For` 时作为可信代理的单跳写法；转发链无法解析
+        // 或全是可信代理时保留对端地址，不能改用可能由客户端附带的 `X-Real-IP`。
+        if forwarded.is_empty()
+            && let Some(real_ip) = headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<IpAddr>().ok())
+        {
+            client = canonical(real_ip);
+        }
+        client
+    }
+
+    fn is_trusted(&self, address: IpAddr) -> bool {
+        self.trusted.iter().any(|network| network.contains(address))
+    }
+}
+
+impl IpNetwork {
+    fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value, None),
+        };
+        let address = canonical(address.parse::<IpAddr>().context("must be an IP or CIDR")?);
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().context("invalid CIDR prefix")?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            bail!("CIDR prefix must be <= {max_prefix}");
+        }
+        Ok(Self { address, prefix })
+    }
+
+    fn contains(&self, address: IpAddr) -> bool {
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => prefix_matches(
+                network.to_bits().into(),
+                address.to_bits().into(),
+                32,
+                self.prefix,
+            ),
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                prefix_matches(network.to_bits(), address.to_bits(), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: u128, address: u128, width: u32, prefix: u8) -> bool {
+    let host_bits = width.saturating_sub(u32::from(prefix));
+    if host_bits >= width {
+        return true;
+    }
+    (network >> host_bits) == (address >> host_bits)
+}
+
+/// IPv4 映射的 IPv6 地址按 IPv4 处理，避免同一来源出现两种表示。
+fn canonical(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4),
+        IpAddr::V4(_) => address,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(forwarded: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_str(forwarded).unwrap_or(HeaderValue::from_static("")),
+        );
+        headers
+    }
+
+    #[test]
+    fn ignores_forwarded_headers_from_untrusted_peers() -> Result<()> {
+        let resolver = ClientIpResolver::new(&["10.0.0.0/8".to_string()])?;
+        let peer = "203.0.113.9".parse::<IpAddr>()?;
+        anyhow::ensure!(resolver.resolve(peer, &headers("198.51.100.1")) == peer);
+        Ok(())
+    }
+
+    #[test]
+    fn walks_forwarded_chain_past_trusted_proxies() -> Result<()> {
+        let resolver = ClientIpResolver::new(&["10.0.0.0/8".to_string(), "::1".to_string()])?;
+        let peer = "10.0.0.2".parse::<IpAddr>()?;
+        anyhow::ensure!(
+            resolver.resolve(peer, &headers("198.51.100.7, 203.0.113.5, 10.1.2.3"))
+                == "203.0.113.5".parse::<IpAddr>()?
+        );
+        let mapped = "::ffff:10.0.0.2".parse::<IpAddr>()?;
+        anyhow::ensure!(
+            resolver.resolve(mapped, &headers("203.0.113.5")) == "203.0.113.5".parse::<IpAddr>()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn real_ip_is_used_only_without_a_forwarded_chain() -> Result<()> {
+        let resolver = ClientIpResolver::new(&["10.0.0.0/8".to_string()])?;
+        let peer = "10.0.0.2".parse::<IpAddr>()?;
+        let mut spoofed = headers("not-an-ip");
+        spoofed.insert("x-real-ip", HeaderValue::from_static("198.51.100.66"));
+        anyhow::ensure!(resolver.resolve(peer, &spoofed) == peer);
+
+        let mut single_hop = HeaderMap::new();
+        single_hop.insert("x-real-ip", HeaderValue::from_static("203.0.113.5"));
+        anyhow::ensure!(resolver.resolve(peer, &single_hop) == "203.0.113.5".parse::<IpAddr>()?);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_trusted_proxy_entries() {
+        for entry in ["not-an-ip", "10.0.0.0/33", "::/129", "10.0.0.0/x"] {
+            assert!(
+                ClientIpResolver::new(&[entry.to_string()]).is_err(),
+                "{entry}"
+            );
+        }
+    }
+}
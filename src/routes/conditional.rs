@@ -0,0 +1,201 @@
+use crate::models::{epoch_millis_to_rfc3339, rfc3339_to_epoch_millis};
+use crate::storage::try_now_millis;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest as _, Sha256};
+
+use super::AppState;
+
+/// 相对当前时间的查询窗口按分钟对齐，滑动窗口每分钟最多变化一次，期间轮询都能得到 304。
+const WINDOW_BUCKET_MS: i64 = 60_000;
+
+/// 历史地震读接口的条件请求。响应只取决于历史数据版本、请求路径和查询串、当前分钟和震度模型，
+/// 据此在调用处理函数前生成 ETag 和 Last-Modified；客户端的校验值仍然有效时直接返回 304，
+/// 不再查询存储。
+pub(crate) async fn conditional_history_read(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let model = state.config_reloader.current().intensity_model;
+    let validators = Validators::new(
+        state.storage.earthquake_history_changed_ms(),
+        try_now_millis().unwrap_or(0),
+        &[
+            request.uri().path().as_bytes(),
+            request.uri().query().unwrap_or_default().as_bytes(),
+            model.as_str().as_bytes(),
+        ],
+    );
+    if validators.matches(request.headers()) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        validators.apply(response.headers_mut());
+        return response;
+    }
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::OK {
+        validators.apply(response.headers_mut());
+    }
+    response
+}
+
+/// 相对当前时间的查询窗口的结束时间：当前分钟的最后一毫秒。同一分钟内的请求得到相同的窗口，
+/// 与 [`conditional_history_read`] 的校验值一致。
+pub(crate) fn window_end_ms(now_ms: i64) -> i64 {
+    now_ms - now_ms.rem_euclid(WINDOW_BUCKET_MS) + (WINDOW_BUCKET_MS - 1)
+}
+
+#[derive(Debug)]
+struct Validators {
+    etag: String,
+    last_modified_ms: i64,
+}
+
+impl Validators {
+    fn new(changed_ms: i64, now_ms: i64, parts: &[&[u8]]) -> Self {
+        let bucket_ms = now_ms - now_ms.rem_euclid(WINDOW_BUCKET_MS);
+        let mut hash = Sha256::new();
+        hash.update(b"disaster-alert:history-etag:v1\0");
+        hash.update(changed_ms.to_be_bytes());
+        hash.update(bucket_ms.to_be_bytes());
+        for part in parts {
+            hash.update(u64::try_from(part.len()).unwrap_or(u64::MAX).to_be_bytes());
+            hash.update(part);
+        }
+        Self {
+            etag: format!("\"{}\"", URL_SAFE_NO_PAD.encode(&hash.finalize()[..16])),
+            last_modified_ms: changed_ms.max(bucket_ms),
+        }
+    }
+
+    /// 按 RFC 9110：有 `If-None-Match` 时只比较 ETag（弱比较），否则比较 `If-Modified-Since`。
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        if let Some(value) = headers.get(header::IF_NONE_MATCH) {
+            return value.to_str().is_ok_and(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == self.etag)
+            });
+        }
+        headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_http_date)
+            .is_some_and(|since_ms| self.last_modified_ms.div_euclid(1_000) * 1_000 <= since_ms)
+    }
+
+    fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(last_modified) = format_http_date(self.last_modified_ms)
+            .and_then(|value| HeaderValue::from_str(&value).ok())
+        {
+            headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// 格式化为 HTTP 日期（IMF-fixdate），如 `Sun, 06 Nov 1994 08:49:37 GMT`。
+fn format_http_date(epoch_ms: i64) -> Option<String> {
+    let rfc3339 = epoch_millis_to_rfc3339(epoch_ms).ok()?;
+    let month = rfc3339.get(5..7)?.parse::<usize>().ok()?;
+    let weekday =
+        WEEKDAYS.get(usize::try_from(epoch_ms.div_euclid(86_400_000).rem_euclid(7)).ok()?)?;
+    Some(format!(
+        "{weekday}, {} {} {} {} GMT",
+        rfc3339.get(8..10)?,
+        MONTHS.get(month.checked_sub(1)?)?,
+        rfc3339.get(0..4)?,
+        rfc3339.get(11..19)?
+    ))
+}
+
+/// 解析 IMF-fixdate 格式的 HTTP 日期；其他已废弃的格式返回 `None`，等同于没有该请求头。
+fn parse_http_date(value: &str) -> Option<i64> {
+    let mut parts = value.split_ascii_whitespace().skip(1);
+    let (day, month, year, time, zone) = (
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+    );
+    if zone != "GMT" || parts.next().is_some() || day.len() != 2 || year.len() != 4 {
+        return None;
+    }
+    let month = MONTHS.iter().position(|name| *name == month)? + 1;
+    rfc3339_to_epoch_millis(&format!("{year}-{month:02}-{day}T{time}Z"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_dates_round_trip() {
+        assert_eq!(
+            format_http_date(784_111_777_000).as_deref(),
+            Some("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777_000)
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn validators_follow_data_version_minute_and_request() {
+        let now_ms = 1_800_000_030_000;
+        let base = Validators::new(1_000, now_ms, &[b"/api/earthquakes/search", b""]);
+        let same_minute =
+            Validators::new(1_000, now_ms + 20_000, &[b"/api/earthquakes/search", b""]);
+        let changed = Validators::new(1_001, now_ms, &[b"/api/earthquakes/search", b""]);
+        let next_minute =
+            Validators::new(1_000, now_ms + 40_000, &[b"/api/earthquakes/search", b""]);
+        let other_query = Validators::new(1_000, now_ms, &[b"/api/earthquakes/search", b"limit=5"]);
+        assert_eq!(base.etag, same_minute.etag);
+        for other in [&changed, &next_minute, &other_query] {
+            assert_ne!(base.etag, other.etag);
+        }
+
+        let mut headers = HeaderMap::new();
+        assert!(!base.matches(&headers));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Sun, 06 Nov 2099 08:49:37 GMT"),
+        );
+        assert!(base.matches(&headers));
+        // If-None-Match 优先于 If-Modified-Since。
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+        assert!(!base.matches(&headers));
+        let weak = format!("\"stale\", W/{}", base.etag);
+        if let Ok(value) = HeaderValue::from_str(&weak) {
+            headers.insert(header::IF_NONE_MATCH, value);
+        }
+        assert!(base.matches(&headers));
+    }
+
+    #[test]
+    fn window_end_is_the_last_millisecond_of_the_minute() {
+        assert_eq!(window_end_ms(1_800_000_030_000), 1_800_000_059_999);
+        assert_eq!(window_end_ms(1_800_000_000_000), 1_800_000_059_999);
+        assert_eq!(window_end_ms(1_800_000_059_999), 1_800_000_059_999);
+    }
+}
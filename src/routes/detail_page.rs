@@ -1,7 +1,10 @@
 use crate::delivery::{
     NotificationRuleSnapshot, NotificationSnapshot, NotificationSourcesSnapshot,
 };
-use crate::models::{DisasterEvent, IncidentRecord, IncidentReportSummary};
+use crate::models::{
+    DisasterEvent, IncidentRecord, IncidentReportSummary, display_event_time, display_source_time,
+};
+use crate::utils::places;
 use axum::{
     http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{Html, IntoResponse, Response},
@@ -44,7 +47,10 @@ pub(crate) fn render_incident_page(
     html.push_str(" · 第 ");
     html.push_str(&snapshot.event.report_num.to_string());
     html.push_str(" 报 · ");
-    escape_into(&snapshot.event.occurred_at, &mut html);
+    escape_into(
+        &display_source_time(&snapshot.event.occurred_at, &snapshot.event.source),
+        &mut html,
+    );
     html.push_str("</p><div class=\"hero-metrics\">");
     if let Some(magnitude) = snapshot.event.magnitude {
         hero_metric("震级", &format!("M{magnitude:.1}"), true, &mut html);
@@ -88,10 +94,7 @@ pub(crate) fn render_incident_page(
     .join(" / ");
     if region.is_empty() {
         escape_into(
-            &format!(
-                "{:.4}, {:.4}",
-                snapshot.target.latitude, snapshot.target.longitude
-            ),
+            &places::describe(snapshot.target.latitude, snapshot.target.longitude),
             &mut html,
         );
     } else {
@@ -170,6 +173,20 @@ pub(crate) fn render_incident_page(
     if let Some(radius) = snapshot.event.radius_km {
         fact("影响半径", &format!("{radius:.0} km"), &mut html);
     }
+    if !snapshot.event.isoseismal_radii.is_empty() {
+        fact(
+            "预计震度范围",
+            &snapshot
+                .event
+                .isoseismal_radii
+                .iter()
+                .rev()
+                .map(|radius| format!("{} 度 {:.0} km", radius.intensity, radius.radius_km))
+                .collect::<Vec<_>>()
+                .join(" · "),
+            &mut html,
+        );
+    }
     if let (Some(latitude), Some(longitude)) = (snapshot.event.latitude, snapshot.event.longitude) {
         fact(
             "事件位置",
@@ -348,6 +365,18 @@ fn render_map_data(
             html.push_str("\" data-radius-km=\"");
             html.push_str(&format!("{radius_km:.3}"));
         }
+        if point.role == "event" && !snapshot.event.isoseismal_radii.is_empty() {
+            html.push_str("\" data-isoseismal=\"");
+            html.push_str(
+                &snapshot
+                    .event
+                    .isoseismal_radii
+                    .iter()
+                    .map(|radius| format!("{}:{:.1}", radius.intensity, radius.radius_km))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
         html.push_str("\"></span>");
     }
     html.push_str("</div>");
@@ -464,7 +493,7 @@ fn render_event(event: &DisasterEvent, html: &mut String) {
     html.push_str("</span><h3>");
     escape_into(&event.title, html);
     html.push_str("</h3><p class=\"source-time\">");
-    escape_into(&event.occurred_at, html);
+    escape_into(&display_event_time(event), html);
     html.push_str("</p></div><div class=\"report-state\"><span>第 ");
     html.push_str(&event.report_num.to_string());
     html.push_str(" 报</span>");
@@ -507,7 +536,10 @@ fn render_snapshot_event(snapshot: &NotificationSnapshot, html: &mut String) {
     html.push_str("</span><h3>");
     escape_into(&snapshot.event.title, html);
     html.push_str("</h3><p class=\"source-time\">");
-    escape_into(&snapshot.event.occurred_at, html);
+    escape_into(
+        &display_source_time(&snapshot.event.occurred_at, &snapshot.event.source),
+        html,
+    );
     html.push_str("</p></div><div class=\"report-state\"><span>第 ");
     html.push_str(&snapshot.event.report_num.to_string());
     html.push_str(" 报</span>");
@@ -552,6 +584,7 @@ fn render_matched_rule(rule: &NotificationRuleSnapshot, html: &mut String) {
         NotificationRuleSnapshot::EarthquakeWarning {
             sources,
             intensity_bands,
+            max_depth_km,
         } => {
             row("灾害类别", "地震预警", html);
             row("来源范围", &format_sources(sources), html);
@@ -568,14 +601,21 @@ fn render_matched_rule(rule: &NotificationRuleSnapshot, html: &mut String) {
                 .collect::<Vec<_>>()
                 .join("；");
             row("烈度规则", &bands, html);
+            if let Some(depth) = max_depth_km {
+                row("最大震源深度", &format!("{depth:.0} km"), html);
+            }
         }
         NotificationRuleSnapshot::EarthquakeReport {
             sources,
             min_magnitude,
+            max_depth_km,
         } => {
             row("灾害类别", "地震速报", html);
             row("来源范围", &format_sources(sources), html);
             row("最低震级", &format!("M{min_magnitude:.1}"), html);
+            if let Some(depth) = max_depth_km {
+                row("最大震源深度", &format!("{depth:.0} km"), html);
+            }
         }
         NotificationRuleSnapshot::WeatherWarning {
             sources,
@@ -906,15 +946,15 @@ a{color:inherit}h1,h2,h3{overflow-wrap:anywhere}h1{margin:0;font-size:30px;line-
 "#;
 
 const DETAIL_SCRIPT: &str = r##"
-(()=>{const container=document.querySelector("#incident-map");const data=document.querySelector("#map-data");if(!container||!data||!window.L)return;const points=[...data.querySelectorAll(".map-point-data")].map(node=>({role:node.dataset.role,label:node.dataset.label||"",lat:Number(node.dataset.lat),lon:Number(node.dataset.lon),radius:Number(node.dataset.radiusKm||0)})).filter(point=>Number.isFinite(point.lat)&&Number.isFinite(point.lon));if(!points.length)return;const map=L.map(container,{attributionControl:false,zoomControl:false,scrollWheelZoom:true,minZoom:2,maxZoom:18});container.classList.add("map-enhanced");container.closest(".map-hero")?.classList.add("map-ready");const tiles=L.tileLayer("https://{s}.basemaps.cartocdn.com/light_all/{z}/{x}/{y}{r}.png",{subdomains:"abcd",maxZoom:19,crossOrigin:true});tiles.once("load",()=>container.classList.add("tiles-ready"));tiles.addTo(map);L.control.zoom({position:"topright"}).addTo(map);const bounds=L.latLngBounds([]);let eventPoint=null;let targetPoint=null;for(const point of points){const size=point.role==="current"?20:24;const icon=L.divIcon({className:"",html:`<span class="incident-marker ${point.role}"></span>`,iconSize:[size,size],iconAnchor:[size/2,size/2]});const marker=L.marker([point.lat,point.lon],{icon,zIndexOffset:point.role==="target"?300:point.role==="event"?200:100}).addTo(map);const label=document.createElement("span");label.textContent=point.label;marker.bindTooltip(label,{direction:"top",offset:[0,-14]});bounds.extend(marker.getLatLng());if(point.role==="event"&&!eventPoint)eventPoint=point;if(point.role==="target"&&!targetPoint)targetPoint=point;if(point.role==="event"&&point.radius>0){L.circle([point.lat,point.lon],{radius:point.radius*1000,color:"#df4b3f",weight:1.5,opacity:.7,fillColor:"#df4b3f",fillOpacity:.07,interactive:false}).addTo(map);bounds.extend(L.latLng(point.lat,point.lon).toBounds(point.radius*2000))}}if(eventPoint&&targetPoint)L.polyline([[eventPoint.lat,eventPoint.lon],[targetPoint.lat,targetPoint.lon]],{color:"#3e7063",weight:2,dashArray:"7 8",opacity:.7,interactive:false}).addTo(map);const fit=()=>{if(points.length===1&&!points[0].radius){map.setView([points[0].lat,points[0].lon],7);return}map.fitBounds(bounds.pad(.2),{paddingTopLeft:[48,72],paddingBottomRight:[48,72],maxZoom:9})};fit();document.querySelector("#map-fit-button")?.addEventListener("click",fit);requestAnimationFrame(()=>map.invalidateSize())})();
+(()=>{const container=document.querySelector("#incident-map");const data=document.querySelector("#map-data");if(!container||!data||!window.L)return;const points=[...data.querySelectorAll(".map-point-data")].map(node=>({role:node.dataset.role,label:node.dataset.label||"",lat:Number(node.dataset.lat),lon:Number(node.dataset.lon),radius:Number(node.dataset.radiusKm||0),rings:(node.dataset.isoseismal||"").split(" ").filter(Boolean).map(ring=>ring.split(":").map(Number)).filter(([intensity,radius])=>intensity>0&&radius>0)})).filter(point=>Number.isFinite(point.lat)&&Number.isFinite(point.lon));if(!points.length)return;const map=L.map(container,{attributionControl:false,zoomControl:false,scrollWheelZoom:true,minZoom:2,maxZoom:18});container.classList.add("map-enhanced");container.closest(".map-hero")?.classList.add("map-ready");const tiles=L.tileLayer("https://{s}.basemaps.cartocdn.com/light_all/{z}/{x}/{y}{r}.png",{subdomains:"abcd",maxZoom:19,crossOrigin:true});tiles.once("load",()=>container.classList.add("tiles-ready"));tiles.addTo(map);L.control.zoom({position:"topright"}).addTo(map);const bounds=L.latLngBounds([]);let eventPoint=null;let targetPoint=null;for(const point of points){const size=point.role==="current"?20:24;const icon=L.divIcon({className:"",html:`<span class="incident-marker ${point.role}"></span>`,iconSize:[size,size],iconAnchor:[size/2,size/2]});const marker=L.marker([point.lat,point.lon],{icon,zIndexOffset:point.role==="target"?300:point.role==="event"?200:100}).addTo(map);const label=document.createElement("span");label.textContent=point.label;marker.bindTooltip(label,{direction:"top",offset:[0,-14]});bounds.extend(marker.getLatLng());if(point.role==="event"&&!eventPoint)eventPoint=point;if(point.role==="target"&&!targetPoint)targetPoint=point;if(point.role==="event"&&point.radius>0){L.circle([point.lat,point.lon],{radius:point.radius*1000,color:"#df4b3f",weight:1.5,opacity:.7,fillColor:"#df4b3f",fillOpacity:.07,interactive:false}).addTo(map);bounds.extend(L.latLng(point.lat,point.lon).toBounds(point.radius*2000))}if(point.role==="event"){for(const[intensity,radius]of point.rings){const ring=L.circle([point.lat,point.lon],{radius:radius*1000,color:"#c7772f",weight:1,opacity:.6,dashArray:"4 6",fill:false}).addTo(map);ring.bindTooltip(`预计震度 ${intensity}`,{sticky:true})}}}if(eventPoint&&targetPoint)L.polyline([[eventPoint.lat,eventPoint.lon],[targetPoint.lat,targetPoint.lon]],{color:"#3e7063",weight:2,dashArray:"7 8",opacity:.7,interactive:false}).addTo(map);const fit=()=>{if(points.length===1&&!points[0].radius){map.setView([points[0].lat,points[0].lon],7);return}map.fitBounds(bounds.pad(.2),{paddingTopLeft:[48,72],paddingBottomRight:[48,72],maxZoom:9})};fit();document.querySelector("#map-fit-button")?.addEventListener("click",fit);requestAnimationFrame(()=>map.invalidateSize())})();
 "##;
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::delivery::{
-        NotificationEventSnapshot, NotificationIntensityBandSnapshot, NotificationTargetSnapshot,
-        NotificationTimingSnapshot,
+        NotificationEventSnapshot, NotificationIntensityBandSnapshot,
+        NotificationIsoseismalSnapshot, NotificationTargetSnapshot, NotificationTimingSnapshot,
     };
     use crate::models::InterruptionLevel;
     use crate::models::{DisasterCategory, IncidentId, ProviderChannel};
@@ -940,6 +980,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -967,6 +1010,16 @@ mod tests {
                 final_report: false,
                 cancel: false,
                 training: false,
+                isoseismal_radii: vec![
+                    NotificationIsoseismalSnapshot {
+                        intensity: 1,
+                        radius_km: 210.0,
+                    },
+                    NotificationIsoseismalSnapshot {
+                        intensity: 2,
+                        radius_km: 95.4,
+                    },
+                ],
             },
             target: NotificationTargetSnapshot {
                 label: "<住所>".to_string(),
@@ -991,6 +1044,7 @@ mod tests {
                     max: 7,
                     interruption_level: InterruptionLevel::Critical,
                 }],
+                max_depth_km: None,
             },
         }
     }
@@ -1128,6 +1182,8 @@ mod tests {
         assert!(html.contains("data-role=\"current\""));
         assert!(html.contains("data-role=\"target\""));
         assert!(html.contains("data-radius-km=\"120.000\""));
+        assert!(html.contains("data-isoseismal=\"1:210.0 2:95.4\""));
+        assert!(html.contains("2 度 95 km · 1 度 210 km"));
         assert!(html.contains("leaflet@1.9.4/dist/leaflet.js"));
         assert!(html.contains("basemaps.cartocdn.com/light_all"));
         assert!(html.contains("class=\"floating-panel event-panel\""));
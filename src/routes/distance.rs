@@ -0,0 +1,144 @@
+use crate::models::ApiResponse;
+use crate::utils::distance;
+use axum::{
+    Json,
+    extract::{Query, State, rejection::QueryRejection},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use super::AppState;
+
+/// 震源深度上限；已知最深的地震约 700 千米。
+const MAX_DEPTH_KM: f64 = 700.0;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DistanceParams {
+    from_lat: f64,
+    from_lon: f64,
+    to_lat: f64,
+    to_lon: f64,
+    /// 震源深度，默认为 0，即按震中距计算走时。
+    depth_km: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct DistanceResponse {
+    distance_km: f64,
+    bearing_degrees: f64,
+    depth_km: f64,
+    hypocentral_distance_km: f64,
+    p_wave_seconds: f64,
+    s_wave_seconds: f64,
+}
+
+/// 计算两点间的 Vincenty 距离、初始方位角和 P/S 波走时，算法和波速与推送时的估算相同，
+/// 前端显示的距离和倒计时因此与服务端筛选一致。
+pub(crate) async fn distance_handler(
+    State(state): State<AppState>,
+    params: Result<Query<DistanceParams>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<DistanceResponse>>) {
+    let Ok(Query(params)) = params else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("查询参数无效")),
+        );
+    };
+    let settings = state.config_reloader.current();
+    match measure(&params, settings.p_wave_km_s, settings.s_wave_km_s) {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("距离计算成功", Some(response))),
+        ),
+        Err((status, message)) => (status, Json(ApiResponse::error(message))),
+    }
+}
+
+fn measure(
+    params: &DistanceParams,
+    p_wave_km_s: f64,
+    s_wave_km_s: f64,
+) -> Result<DistanceResponse, (StatusCode, &'static str)> {
+    if !distance::validate_coordinates(params.from_lat, params.from_lon)
+        || !distance::validate_coordinates(params.to_lat, params.to_lon)
+    {
+        return Err((StatusCode::BAD_REQUEST, "坐标无效"));
+    }
+    let depth_km = params.depth_km.unwrap_or_default();
+    if !(0.0..=MAX_DEPTH_KM).contains(&depth_km) {
+        return Err((StatusCode::BAD_REQUEST, "深度须在 0 到 700 千米之间"));
+    }
+    let Some(distance_km) = distance::vincenty_distance(
+        params.from_lat,
+        params.from_lon,
+        params.to_lat,
+        params.to_lon,
+    ) else {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "两点接近对跖点，无法计算距离",
+        ));
+    };
+    let hypocentral_distance_km = distance_km.mul_add(distance_km, depth_km * depth_km).sqrt();
+    // 与推送时一样把走时取整到毫秒。
+    let travel_seconds =
+        |speed_km_s: f64| (hypocentral_distance_km / speed_km_s * 1_000.0).round() / 1_000.0;
+    Ok(DistanceResponse {
+        distance_km,
+        bearing_degrees: distance::initial_bearing(
+            params.from_lat,
+            params.from_lon,
+            params.to_lat,
+            params.to_lon,
+        ),
+        depth_km,
+        hypocentral_distance_km,
+        p_wave_seconds: travel_seconds(p_wave_km_s),
+        s_wave_seconds: travel_seconds(s_wave_km_s),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(depth_km: Option<f64>) -> DistanceParams {
+        DistanceParams {
+            from_lat: 39.9042,
+            from_lon: 116.4074,
+            to_lat: 31.2304,
+            to_lon: 121.4737,
+            depth_km,
+        }
+    }
+
+    #[test]
+    fn measures_distance_bearing_and_travel_times() -> anyhow::Result<()> {
+        let flat = measure(&params(None), 6.0, 3.5)
+            .map_err(|(_status, message)| anyhow::anyhow!(message))?;
+        anyhow::ensure!((flat.distance_km - 1067.0).abs() < 2.0);
+        anyhow::ensure!((150.0..160.0).contains(&flat.bearing_degrees));
+        anyhow::ensure!(flat.hypocentral_distance_km == flat.distance_km);
+        anyhow::ensure!(
+            flat.p_wave_seconds == (flat.distance_km / 6.0 * 1_000.0).round() / 1_000.0
+                && flat.s_wave_seconds > flat.p_wave_seconds
+        );
+
+        let deep = measure(&params(Some(600.0)), 6.0, 3.5);
+        anyhow::ensure!(deep.is_ok_and(|deep| deep.hypocentral_distance_km > flat.distance_km));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let status = |params: &DistanceParams| measure(params, 6.0, 3.5).err().map(|error| error.0);
+        let mut invalid = params(None);
+        invalid.to_lat = 91.0;
+        assert_eq!(status(&invalid), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(status(&params(Some(-1.0))), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            status(&params(Some(f64::NAN))),
+            Some(StatusCode::BAD_REQUEST)
+        );
+    }
+}
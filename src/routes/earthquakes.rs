@@ -0,0 +1,800 @@
+use crate::models::{
+    ApiResponse, DisasterCategory, DisasterEvent, epoch_millis_to_rfc3339, parse_event_epoch_ms,
+    rfc3339_to_epoch_millis,
+};
+use crate::providers::{CATALOG_SOURCES, catalog_label};
+use crate::source_registry;
+use crate::storage::{EarthquakeQuery, EarthquakeSearch, try_now_millis};
+use crate::utils::intensity::IntensityModel;
+use crate::utils::{boundaries, distance};
+use axum::{
+    Json,
+    extract::{Path, Query, State, rejection::QueryRejection},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::AppState;
+use super::conditional::window_end_ms;
+
+/// 未指定起始时间时向前查询的天数。
+const DEFAULT_SEARCH_DAYS: i64 = 30;
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_SEARCH_LIMIT: usize = 500;
+const DEFAULT_STATS_DAYS: u16 = 30;
+const MAX_STATS_DAYS: u16 = 365;
+/// 活动统计按北京时间划分日期和周。
+const BEIJING_OFFSET_MS: i64 = 8 * 3_600_000;
+const DAY_MS: i64 = 86_400_000;
+/// 震度网格默认的格点间距（度）。
+const DEFAULT_GRID_RESOLUTION: f64 = 0.25;
+const MIN_GRID_RESOLUTION: f64 = 0.05;
+const MAX_GRID_RESOLUTION: f64 = 2.0;
+/// 单次展开的格点数上限，超出时需改用更粗的分辨率。
+const MAX_GRID_POINTS: usize = 40_000;
+/// 每度纬度的千米数取偏小值，使网格范围略大于预计震度 1 的半径。
+const KM_PER_DEGREE: f64 = 110.0;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct EarthquakeSearchParams {
+    /// `西,南,东,北`，与 GeoJSON 的 bbox 顺序一致。
+    bbox: Option<String>,
+    min_magnitude: Option<f64>,
+    from: Option<String>,
+    to: Option<String>,
+    source: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SeismicityParams {
+    #[serde(default = "default_stats_days")]
+    days: u16,
+    source: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SeismicityResponse {
+    from: String,
+    to: String,
+    events: usize,
+    per_day: Vec<DailyCount>,
+    magnitude_histogram: Vec<MagnitudeBucket>,
+    most_active_region: Option<RegionActivity>,
+    largest_per_week: Vec<WeeklyLargest>,
+    truncated: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct DailyCount {
+    date: String,
+    events: usize,
+}
+
+/// `magnitude` 为震级下限，例如 4 表示 4.0 到 4.9 级。
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct MagnitudeBucket {
+    magnitude: u8,
+    events: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct RegionActivity {
+    region: &'static str,
+    events: usize,
+}
+
+#[derive(Serialize)]
+struct WeeklyLargest {
+    week_start: String,
+    event: EarthquakeSummary,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct IntensityGridParams {
+    /// 格点间距（度）。
+    resolution: Option<f64>,
+    source: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct IntensityGridResponse {
+    event: EarthquakeSummary,
+    model: IntensityModel,
+    resolution: f64,
+    /// 网格覆盖的震中距，即预计震度 1 的范围。
+    radius_km: f64,
+    cells: Vec<IntensityCell>,
+}
+
+/// 预计震度不低于 0.5 的格点。
+#[derive(Debug, PartialEq, Serialize)]
+struct IntensityCell {
+    latitude: f64,
+    longitude: f64,
+    intensity: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct EarthquakeSearchResponse {
+    events: Vec<EarthquakeSummary>,
+    truncated: bool,
+}
+
+#[derive(Serialize)]
+struct EarthquakeSummary {
+    category: DisasterCategory,
+    source: String,
+    source_label: Option<&'static str>,
+    event_id: String,
+    title: String,
+    occurred_at: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    magnitude: Option<f64>,
+    depth_km: Option<f64>,
+    affected_regions: Vec<String>,
+    report_num: u32,
+    final_report: bool,
+}
+
+/// 按时间、范围、震级和数据源查询已入库的历史地震，每个事件只返回最新一报。
+pub(crate) async fn earthquake_search_handler(
+    State(state): State<AppState>,
+    params: Result<Query<EarthquakeSearchParams>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<EarthquakeSearchResponse>>) {
+    let Ok(Query(params)) = params else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("查询参数无效")),
+        );
+    };
+    // 默认窗口截止到当前分钟末，同一分钟内的轮询结果相同，配合条件请求返回 304。
+    let query = match search_query(params, window_end_ms(try_now_millis().unwrap_or(0))) {
+        Ok(query) => query,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::error(message))),
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage.search_earthquakes(&query)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(search) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "历史地震查询成功",
+                Some(search_response(search)),
+            )),
+        ),
+        Err(error) => {
+            tracing::error!(event = "earthquakes.search_failed", error = ?error, "earthquakes.search_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("历史地震暂时无法查询，请稍后重试")),
+            )
+        }
+    }
+}
+
+/// 最近若干天的地震速报活动概况：每日数量、震级分布、最活跃的地区和每周最大地震。
+/// 只统计地震速报；不同机构对同一地震的速报分别计数，需要单一目录时按 `source` 筛选。
+pub(crate) async fn seismicity_stats_handler(
+    State(state): State<AppState>,
+    params: Result<Query<SeismicityParams>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<SeismicityResponse>>) {
+    let Ok(Query(params)) = params else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("查询参数无效")),
+        );
+    };
+    if !(1..=MAX_STATS_DAYS).contains(&params.days) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("统计天数须在 1 到 365 之间")),
+        );
+    }
+    if params
+        .source
+        .as_deref()
+        .is_some_and(|source| source_label(source, true).is_none())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("未知的地震速报数据源")),
+        );
+    }
+    let to_ms = window_end_ms(try_now_millis().unwrap_or(0));
+    let query = EarthquakeQuery {
+        from_ms: to_ms.saturating_sub(i64::from(params.days) * DAY_MS),
+        to_ms,
+        bbox: None,
+        min_magnitude: None,
+        category: Some(DisasterCategory::EarthquakeReport),
+        source: params.source,
+        limit: usize::MAX,
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        storage
+            .search_earthquakes(&query)
+            .map(|search| seismicity(search, query.from_ms, query.to_ms))
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(stats) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("地震活动统计获取成功", Some(stats))),
+        ),
+        Err(error) => {
+            tracing::error!(event = "earthquakes.stats_failed", error = ?error, "earthquakes.stats_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("地震活动统计暂时无法获取，请稍后重试")),
+            )
+        }
+    }
+}
+
+/// 按当前震度模型估算历史地震周边的网格震度，供前端绘制热力图。不指定数据源时在全部地震
+/// 数据源中按事件编号查找，多个数据源都有时取发生时间最晚的一条。
+pub(crate) async fn intensity_grid_handler(
+    State(state): State<AppState>,
+    Path(event_id): Path<String>,
+    params: Result<Query<IntensityGridParams>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<IntensityGridResponse>>) {
+    let Ok(Query(params)) = params else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("查询参数无效")),
+        );
+    };
+    if event_id.trim().is_empty() || event_id.len() > 128 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("事件编号无效")),
+        );
+    }
+    let resolution = params.resolution.unwrap_or(DEFAULT_GRID_RESOLUTION);
+    if !(MIN_GRID_RESOLUTION..=MAX_GRID_RESOLUTION).contains(&resolution) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("分辨率须在 0.05 到 2 度之间")),
+        );
+    }
+    let Some(sources) = lookup_sources(params.source) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("未知的地震数据源")),
+        );
+    };
+    let model = state.config_reloader.current().intensity_model;
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let sources = sources.iter().map(String::as_str).collect::<Vec<_>>();
+        storage
+            .earthquake_history_event(&sources, &event_id)
+            .map(|event| event.map(|event| intensity_grid(event, model, resolution)))
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(Some(Ok(grid))) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("震度网格生成成功", Some(grid))),
+        ),
+        Ok(Some(Err((status, message)))) => (status, Json(ApiResponse::error(message))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error("未找到该地震")),
+        ),
+        Err(error) => {
+            tracing::error!(event = "earthquakes.grid_failed", error = ?error, "earthquakes.grid_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("震度网格暂时无法生成，请稍后重试")),
+            )
+        }
+    }
+}
+
+/// 以震中为圆心、预计震度 1 的半径为范围展开经纬度网格，格点对齐到 `resolution` 的整数倍，
+/// 只返回预计震度不低于 0.5 的格点。
+fn intensity_grid(
+    event: DisasterEvent,
+    model: IntensityModel,
+    resolution: f64,
+) -> Result<IntensityGridResponse, (StatusCode, &'static str)> {
+    let (Some((latitude, longitude)), Some(magnitude)) =
+        (event.latitude.zip(event.longitude), event.magnitude)
+    else {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "该地震缺少震中或震级，无法估算震度",
+        ));
+    };
+    let depth_km = event.depth_km.unwrap_or_default().max(0.0);
+    let radius_km = model
+        .isoseismal_radii(magnitude, depth_km)
+        .first()
+        .map_or(0.0, |radius| radius.radius_km);
+    let mut cells = Vec::new();
+    if radius_km > 0.0 {
+        let latitude_span = radius_km / KM_PER_DEGREE;
+        let edge_latitude = (latitude.abs() + latitude_span).min(89.0);
+        let longitude_span =
+            (radius_km / (KM_PER_DEGREE * edge_latitude.to_radians().cos())).min(180.0);
+        let south = ((latitude - latitude_span).max(-90.0) / resolution).floor() as i64;
+        let north = ((latitude + latitude_span).min(90.0) / resolution).ceil() as i64;
+        let west = ((longitude - longitude_span) / resolution).floor() as i64;
+        let east = ((longitude + longitude_span) / resolution).ceil() as i64;
+        let points = (north - south + 1).saturating_mul(east - west + 1);
+        if !usize::try_from(points).is_ok_and(|points| points <= MAX_GRID_POINTS) {
+            return Err((StatusCode::BAD_REQUEST, "网格过大，请使用更粗的分辨率"));
+        }
+        for row in south..=north {
+            let cell_latitude = round_to(row as f64 * resolution, 1e4).clamp(-90.0, 90.0);
+            for column in west..=east {
+                let cell_longitude = round_to(
+                    (column as f64 * resolution + 180.0).rem_euclid(360.0) - 180.0,
+                    1e4,
+                );
+                let Some(distance_km) =
+                    distance::vincenty_distance(latitude, longitude, cell_latitude, cell_longitude)
+                else {
+                    continue;
+                };
+                if distance_km > radius_km {
+                    continue;
+                }
+                let intensity = model.estimate(magnitude, distance_km.hypot(depth_km));
+                if intensity >= 0.5 {
+                    cells.push(IntensityCell {
+                        latitude: cell_latitude,
+                        longitude: cell_longitude,
+                        intensity: round_to(intensity, 10.0),
+                    });
+                }
+            }
+        }
+    }
+    Ok(IntensityGridResponse {
+        event: summary(event),
+        model,
+        resolution,
+        radius_km,
+        cells,
+    })
+}
+
+fn round_to(value: f64, scale: f64) -> f64 {
+    (value * scale).round() / scale
+}
+
+const fn default_stats_days() -> u16 {
+    DEFAULT_STATS_DAYS
+}
+
+fn seismicity(search: EarthquakeSearch, from_ms: i64, to_ms: i64) -> SeismicityResponse {
+    let first_day = beijing_day(from_ms);
+    let last_day = beijing_day(to_ms);
+    let mut per_day = BTreeMap::new();
+    let mut magnitudes = BTreeMap::new();
+    let mut regions = BTreeMap::new();
+    let mut weekly = BTreeMap::<i64, DisasterEvent>::new();
+    let events = search.events.len();
+    for event in search.events {
+        let Some(occurred_ms) = parse_event_epoch_ms(&event) else {
+            continue;
+        };
+        let day = beijing_day(occurred_ms);
+        *per_day.entry(day).or_insert(0_usize) += 1;
+        if let Some(boundary) = event
+            .latitude
+            .zip(event.longitude)
+            .and_then(|(latitude, longitude)| boundaries::locate(latitude, longitude))
+        {
+            *regions.entry(boundary.name).or_insert(0_usize) += 1;
+        }
+        let Some(magnitude) = event.magnitude else {
+            continue;
+        };
+        *magnitudes
+            .entry(magnitude.floor().clamp(0.0, 9.0) as u8)
+            .or_insert(0_usize) += 1;
+        // 周一为一周的开始；1970-01-01 是周四。
+        let week_start = day - (day + 3).rem_euclid(7);
+        let largest = weekly
+            .get(&week_start)
+            .and_then(|largest| largest.magnitude)
+            .is_some_and(|largest| largest >= magnitude);
+        if !largest {
+            weekly.insert(week_start, event);
+        }
+    }
+    SeismicityResponse {
+        from: epoch_millis_to_rfc3339(from_ms).unwrap_or_default(),
+        to: epoch_millis_to_rfc3339(to_ms).unwrap_or_default(),
+        events,
+        per_day: (first_day..=last_day)
+            .map(|day| DailyCount {
+                date: beijing_date(day),
+                events: per_day.get(&day).copied().unwrap_or(0),
+            })
+            .collect(),
+        magnitude_histogram: magnitudes
+            .into_iter()
+            .map(|(magnitude, events)| MagnitudeBucket { magnitude, events })
+            .collect(),
+        most_active_region: regions
+            .into_iter()
+            .max_by_key(|(_region, events)| *events)
+            .map(|(region, events)| RegionActivity { region, events }),
+        largest_per_week: weekly
+            .into_iter()
+            .map(|(week_start, event)| WeeklyLargest {
+                week_start: beijing_date(week_start),
+                event: summary(event),
+            })
+            .collect(),
+        truncated: search.truncated,
+    }
+}
+
+/// 北京时间的日序号（自 1970-01-01 起）。
+fn beijing_day(epoch_ms: i64) -> i64 {
+    epoch_ms
+        .saturating_add(BEIJING_OFFSET_MS)
+        .div_euclid(DAY_MS)
+}
+
+fn beijing_date(day: i64) -> String {
+    epoch_millis_to_rfc3339(day.saturating_mul(DAY_MS))
+        .ok()
+        .and_then(|value| value.get(..10).map(ToOwned::to_owned))
+        .unwrap_or_default()
+}
+
+fn search_query(
+    params: EarthquakeSearchParams,
+    now_ms: i64,
+) -> Result<EarthquakeQuery, &'static str> {
+    let to_ms = match params.to.as_deref() {
+        Some(value) => {
+            rfc3339_to_epoch_millis(value).ok_or("结束时间须为带时区的 RFC 3339 时间")?
+        }
+        None => now_ms,
+    };
+    let from_ms = match params.from.as_deref() {
+        Some(value) => {
+            rfc3339_to_epoch_millis(value).ok_or("起始时间须为带时区的 RFC 3339 时间")?
+        }
+        None => to_ms.saturating_sub(DEFAULT_SEARCH_DAYS * 86_400_000),
+    };
+    if from_ms > to_ms {
+        return Err("起始时间不能晚于结束时间");
+    }
+    let bbox = params.bbox.as_deref().map(parse_bbox).transpose()?;
+    if params
+        .min_magnitude
+        .is_some_and(|magnitude| !(0.0..=10.0).contains(&magnitude))
+    {
+        return Err("最小震级须在 0 到 10 之间");
+    }
+    if params
+        .source
+        .as_deref()
+        .is_some_and(|source| source_label(source, false).is_none())
+    {
+        return Err("未知的地震数据源");
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return Err("返回数量须在 1 到 500 之间");
+    }
+    Ok(EarthquakeQuery {
+        from_ms,
+        to_ms,
+        bbox,
+        min_magnitude: params.min_magnitude,
+        category: None,
+        source: params.source,
+        limit,
+    })
+}
+
+fn parse_bbox(value: &str) -> Result<[f64; 4], &'static str> {
+    const INVALID: &str = "范围须为“西,南,东,北”四个经纬度";
+    let values = value
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_error| INVALID)?;
+    let [west, south, east, north] = <[f64; 4]>::try_from(values).map_err(|_error| INVALID)?;
+    if ![west, east]
+        .iter()
+        .all(|value| (-180.0..=180.0).contains(value))
+        || ![south, north]
+            .iter()
+            .all(|value| (-90.0..=90.0).contains(value))
+        || south > north
+    {
+        return Err(INVALID);
+    }
+    Ok([west, south, east, north])
+}
+
+/// 地震数据源或历史目录导入来源的显示名称，不是地震来源时返回 `None`；
+/// `reports_only` 时不接受地震预警来源。
+/// 按事件编号查找历史地震时要查的数据源：指定时只查该数据源，否则查全部地震数据源和导入目录。
+/// 指定了未知的数据源时返回 `None`。
+pub(super) fn lookup_sources(source: Option<String>) -> Option<Vec<String>> {
+    match source {
+        Some(source) if source_label(&source, false).is_none() => None,
+        Some(source) => Some(vec![source]),
+        None => Some(
+            source_registry::SOURCES
+                .iter()
+                .filter(|definition| {
+                    matches!(
+                        definition.category,
+                        DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+                    )
+                })
+                .map(|definition| definition.id)
+                .chain(CATALOG_SOURCES)
+                .map(str::to_string)
+                .collect(),
+        ),
+    }
+}
+
+fn source_label(source: &str, reports_only: bool) -> Option<&'static str> {
+    catalog_label(source).or_else(|| {
+        source_registry::find(source)
+            .filter(|definition| match definition.category {
+                DisasterCategory::EarthquakeReport => true,
+                DisasterCategory::EarthquakeWarning => !reports_only,
+                _ => false,
+            })
+            .map(|definition| definition.label)
+    })
+}
+
+fn search_response(search: EarthquakeSearch) -> EarthquakeSearchResponse {
+    EarthquakeSearchResponse {
+        events: search.events.into_iter().map(summary).collect(),
+        truncated: search.truncated,
+    }
+}
+
+fn summary(event: DisasterEvent) -> EarthquakeSummary {
+    let occurred_at =
+        parse_event_epoch_ms(&event).and_then(|epoch_ms| epoch_millis_to_rfc3339(epoch_ms).ok());
+    EarthquakeSummary {
+        category: event.category,
+        source_label: source_label(&event.source, false),
+        source: event.source,
+        event_id: event.event_id,
+        title: event.title,
+        occurred_at,
+        latitude: event.latitude,
+        longitude: event.longitude,
+        magnitude: event.magnitude,
+        depth_km: event.depth_km,
+        affected_regions: event.affected_regions,
+        report_num: event.report_num,
+        final_report: event.final_report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intensity_grid_covers_the_felt_area_around_the_epicenter() -> anyhow::Result<()> {
+        let mut event = DisasterEvent {
+            category: DisasterCategory::EarthquakeReport,
+            channel: crate::models::ProviderChannel::FanStudio,
+            source: "fanstudio.cenc".to_string(),
+            event_id: "grid".to_string(),
+            revision: "1".to_string(),
+            report_num: 1,
+            title: String::new(),
+            description: String::new(),
+            latitude: Some(30.0),
+            longitude: Some(179.9),
+            magnitude: Some(5.0),
+            depth_km: Some(10.0),
+            affected_regions: Vec::new(),
+            radius_km: None,
+            level: 2,
+            occurred_at: "2026-07-12T00:00:00Z".to_string(),
+            final_report: true,
+            cancel: false,
+            training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
+        };
+        let model = IntensityModel::Blended;
+        let grid = intensity_grid(event.clone(), model, 0.5)
+            .map_err(|(_status, message)| anyhow::anyhow!(message))?;
+        anyhow::ensure!(grid.radius_km > 0.0);
+        let strongest = grid
+            .cells
+            .iter()
+            .map(|cell| cell.intensity)
+            .fold(0.0, f64::max);
+        anyhow::ensure!(strongest >= 3.0, "{strongest}");
+        anyhow::ensure!(
+            grid.cells
+                .iter()
+                .all(|cell| { (-180.0..180.0).contains(&cell.longitude) && cell.intensity >= 0.5 })
+        );
+        // 跨越 180° 经线的格点归一到西经。
+        anyhow::ensure!(grid.cells.iter().any(|cell| cell.longitude < 0.0));
+
+        let too_fine = intensity_grid(event.clone(), model, MIN_GRID_RESOLUTION);
+        anyhow::ensure!(
+            too_fine.is_err_and(|(status, _message)| status == StatusCode::BAD_REQUEST)
+        );
+        event.magnitude = None;
+        let unlocated = intensity_grid(event, model, 0.5);
+        anyhow::ensure!(
+            unlocated.is_err_and(|(status, _message)| status == StatusCode::UNPROCESSABLE_ENTITY)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn search_parameters_are_validated_and_defaulted() {
+        let now_ms = 1_800_000_000_000;
+        let query = search_query(EarthquakeSearchParams::default(), now_ms);
+        assert_eq!(
+            query.map(|query| (query.from_ms, query.to_ms, query.limit)),
+            Ok((now_ms - 30 * 86_400_000, now_ms, 100))
+        );
+
+        let query = search_query(
+            EarthquakeSearchParams {
+                bbox: Some("170, -50, -170, -30".to_string()),
+                min_magnitude: Some(5.5),
+                from: Some("2026-07-10T00:00:00+08:00".to_string()),
+                to: Some("2026-07-11T00:00:00Z".to_string()),
+                source: Some("wolfx.fj_eew".to_string()),
+                limit: Some(20),
+            },
+            now_ms,
+        );
+        assert!(
+            query.is_ok_and(|query| query.bbox == Some([170.0, -50.0, -170.0, -30.0])
+                && query.from_ms == 1_783_612_800_000
+                && query.to_ms == 1_783_728_000_000)
+        );
+
+        for params in [
+            EarthquakeSearchParams {
+                bbox: Some("100,30,110".to_string()),
+                ..EarthquakeSearchParams::default()
+            },
+            EarthquakeSearchParams {
+                from: Some("2026-07-10 00:00:00".to_string()),
+                ..EarthquakeSearchParams::default()
+            },
+            EarthquakeSearchParams {
+                source: Some("fanstudio.weatheralarm".to_string()),
+                ..EarthquakeSearchParams::default()
+            },
+            EarthquakeSearchParams {
+                limit: Some(0),
+                ..EarthquakeSearchParams::default()
+            },
+        ] {
+            assert!(search_query(params, now_ms).is_err());
+        }
+    }
+
+    fn report(event_id: &str, occurred_at: &str, magnitude: f64, latitude: f64) -> DisasterEvent {
+        DisasterEvent {
+            category: DisasterCategory::EarthquakeReport,
+            channel: crate::models::ProviderChannel::FanStudio,
+            source: "fanstudio.cenc".to_string(),
+            event_id: event_id.to_string(),
+            revision: "1".to_string(),
+            report_num: 1,
+            title: event_id.to_string(),
+            description: String::new(),
+            latitude: Some(latitude),
+            longitude: Some(103.0),
+            magnitude: Some(magnitude),
+            depth_km: Some(10.0),
+            affected_regions: Vec::new(),
+            radius_km: None,
+            level: 0,
+            occurred_at: occurred_at.to_string(),
+            final_report: true,
+            cancel: false,
+            training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
+        }
+    }
+
+    #[test]
+    fn seismicity_groups_by_beijing_day_week_magnitude_and_region() {
+        let from_ms = rfc3339_to_epoch_millis("2026-07-05T16:00:00Z").unwrap_or_default();
+        let to_ms = rfc3339_to_epoch_millis("2026-07-08T12:00:00Z").unwrap_or_default();
+        let search = EarthquakeSearch {
+            events: vec![
+                // 北京时间 7 月 8 日，周三。
+                report("a", "2026-07-07T17:00:00Z", 4.6, 30.5),
+                report("b", "2026-07-07 10:00:00", 3.2, 25.0),
+                // 北京时间 7 月 6 日，周一。
+                report("c", "2026-07-06 08:00:00", 5.1, 31.0),
+            ],
+            truncated: false,
+        };
+
+        let stats = seismicity(search, from_ms, to_ms);
+        assert_eq!(stats.events, 3);
+        assert_eq!(
+            stats.per_day,
+            [("2026-07-06", 1), ("2026-07-07", 1), ("2026-07-08", 1)].map(|(date, events)| {
+                DailyCount {
+                    date: date.to_string(),
+                    events,
+                }
+            })
+        );
+        assert_eq!(
+            stats.magnitude_histogram,
+            [(3, 1), (4, 1), (5, 1)]
+                .map(|(magnitude, events)| MagnitudeBucket { magnitude, events })
+        );
+        assert_eq!(
+            stats.most_active_region,
+            Some(RegionActivity {
+                region: "四川省",
+                events: 2
+            })
+        );
+        assert_eq!(stats.largest_per_week.len(), 1);
+        assert!(
+            stats
+                .largest_per_week
+                .first()
+                .is_some_and(|week| week.week_start == "2026-07-06" && week.event.event_id == "c")
+        );
+    }
+}
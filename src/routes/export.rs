@@ -0,0 +1,322 @@
+use crate::config::Config;
+use crate::models::{ApiResponse, UnsubscribeRequest, mask_device_key};
+use crate::storage::try_now_millis;
+use crate::subscriptions::SubscriptionId;
+use axum::{
+    Json,
+    extract::{Path, State, rejection::JsonRejection},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use super::AppState;
+use super::signing::TokenSigner;
+use super::subscribe::parse_destination;
+
+const EXPORT_PURPOSE: &[u8] = b"disaster-alert:subscription-export:v2";
+const EXPORT_LINK_TTL_MS: i64 = 15 * 60 * 1_000;
+const MAX_TOKEN_BYTES: usize = 128;
+const INVALID_LINK_MESSAGE: &str = "导出链接无效或已过期";
+
+/// 设备数据导出链接。
+///
+/// Bark Key 只是推送地址，不能作为读取凭据；导出请求只会把签名链接推送到该设备本身，
+/// 持有设备的人才能打开。链接绑定订阅 ID 和版本号，15 分钟后失效，订阅被覆盖后也随之失效。
+#[derive(Clone)]
+pub(crate) struct SubscriptionExports {
+    inner: Option<Arc<ExportInner>>,
+}
+
+struct ExportInner {
+    base_url: String,
+    signer: TokenSigner,
+}
+
+impl SubscriptionExports {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self::with_secret(
+            &config.alert_detail_base_url,
+            config.alert_signing_key.expose().as_bytes(),
+        )
+    }
+
+    pub(crate) fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    fn with_secret(base_url: &str, secret: &[u8]) -> Self {
+        Self {
+            inner: Some(Arc::new(ExportInner {
+                base_url: base_url.trim_end_matches('/').to_string(),
+                signer: TokenSigner::derive(secret, EXPORT_PURPOSE),
+            })),
+        }
+    }
+
+    fn link(&self, id: SubscriptionId, generation: u64, now_ms: i64) -> Option<String> {
+        let inner = self.inner.as_ref()?;
+        let expires_at_ms = now_ms.saturating_add(EXPORT_LINK_TTL_MS);
+        let mac = inner.signer.sign(&[
+            &id.0.to_be_bytes(),
+            &generation.to_be_bytes(),
+            &expires_at_ms.to_be_bytes(),
+        ]);
+        Some(format!(
+            "{}/api/subscription-export/{}.{generation}.{expires_at_ms}.{mac}",
+            inner.base_url, id.0
+        ))
+    }
+
+    fn verify(&self, token: &str, now_ms: i64) -> Option<(SubscriptionId, u64)> {
+        let inner = self.inner.as_ref()?;
+        if token.len() > MAX_TOKEN_BYTES {
+            return None;
+        }
+        let mut parts = token.split('.');
+        let (Some(id), Some(generation), Some(expires_at), Some(mac), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return None;
+        };
+        let id = SubscriptionId(id.parse().ok()?);
+        let generation = generation.parse::<u64>().ok()?;
+        let expires_at_ms = expires_at.parse::<i64>().ok()?;
+        let signed = inner.signer.verify(
+            &[
+                &id.0.to_be_bytes(),
+                &generation.to_be_bytes(),
+                &expires_at_ms.to_be_bytes(),
+            ],
+            mac,
+        );
+        if !signed || expires_at_ms <= now_ms {
+            return None;
+        }
+        Some((id, generation))
+    }
+}
+
+pub(crate) async fn subscription_export_request_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<UnsubscribeRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    if !state.subscription_exports.enabled() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("数据导出暂不可用")),
+        );
+    }
+    let Json(payload) = match payload {
+        Ok(payload) => payload,
+        Err(_rejection) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::<()>::error("数据导出请求体无效")),
+            );
+        }
+    };
+    let destination = match parse_destination(&state, &payload.destination) {
+        Ok(value) => value,
+        Err((status, message)) => {
+            return (status, Json(ApiResponse::<()>::error(message)));
+        }
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("订阅存储繁忙，请稍后重试")),
+        );
+    };
+    let manager = state.subscriptions.clone();
+    let lookup = destination.clone();
+    let subject = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        manager.export_subject(&lookup)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    let (id, generation, subscription) = match subject {
+        Ok(Some(subject)) => subject,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::<()>::error("订阅不存在")),
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                event = "subscription.export_lookup_failed",
+                device_key = %mask_device_key(&destination.device_key),
+                error = ?error,
+                "subscription.export_lookup_failed"
+            );
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("数据导出暂时无法完成，请稍后重试")),
+            );
+        }
+    };
+    let link = try_now_millis()
+        .ok()
+        .and_then(|now_ms| state.subscription_exports.link(id, generation, now_ms));
+    let Some(link) = link else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("数据导出暂不可用")),
+        );
+    };
+    if let Err(error) = state
+        .bark_notifier
+        .send_data_export_link(&subscription, &link)
+        .await
+    {
+        tracing::warn!(
+            event = "subscription.export_link_failed",
+            device_key = %mask_device_key(&destination.device_key),
+            error = ?error,
+            "subscription.export_link_failed"
+        );
+        return (
+            StatusCode::BAD_GATEWAY,
+            Json(ApiResponse::<()>::error("导出链接推送失败，请稍后重试")),
+        );
+    }
+    tracing::info!(
+        event = "subscription.export_link_sent",
+        device_key = %mask_device_key(&destination.device_key),
+        "subscription.export_link_sent"
+    );
+    (
+        StatusCode::ACCEPTED,
+        Json(ApiResponse::<()>::success(
+            "导出链接已推送到该设备，15 分钟内有效",
+            None,
+        )),
+    )
+}
+
+pub(crate) async fn subscription_export_handler(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Response {
+    let verified = try_now_millis()
+        .ok()
+        .and_then(|now_ms| state.subscription_exports.verify(&token, now_ms));
+    let Some((id, generation)) = verified else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(INVALID_LINK_MESSAGE)),
+        )
+            .into_response();
+    };
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("订阅存储繁忙，请稍后重试")),
+        )
+            .into_response();
+    };
+    let manager = state.subscriptions.clone();
+    let export = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        manager.export(id, generation)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    let mut response = match export {
+        Ok(Some(export)) => {
+            tracing::info!(
+                event = "subscription.exported",
+                subscription_id = id.0,
+                "subscription.exported"
+            );
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success("数据导出成功", Some(export))),
+            )
+                .into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(INVALID_LINK_MESSAGE)),
+        )
+            .into_response(),
+        Err(error) => {
+            tracing::error!(
+                event = "subscription.export_failed",
+                subscription_id = id.0,
+                error = ?error,
+                "subscription.export_failed"
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("数据导出暂时无法完成，请稍后重试")),
+            )
+                .into_response()
+        }
+    };
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exports() -> SubscriptionExports {
+        SubscriptionExports::with_secret("https://alert.example.com/", &[7; 32])
+    }
+
+    fn token(link: &str) -> &str {
+        link.rsplit('/').next().unwrap_or_default()
+    }
+
+    #[test]
+    fn export_link_round_trips_until_expiry() -> anyhow::Result<()> {
+        let exports = exports();
+        let link = exports
+            .link(SubscriptionId(42), 3, 1_000)
+            .ok_or_else(|| anyhow::anyhow!("export link missing"))?;
+        anyhow::ensure!(
+            link.starts_with("https://alert.example.com/api/subscription-export/42.3.")
+        );
+        anyhow::ensure!(exports.verify(token(&link), 1_000) == Some((SubscriptionId(42), 3)));
+        anyhow::ensure!(
+            exports
+                .verify(token(&link), 1_000 + EXPORT_LINK_TTL_MS)
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn export_link_rejects_tampering_and_foreign_secrets() -> anyhow::Result<()> {
+        let exports = exports();
+        let link = exports
+            .link(SubscriptionId(42), 3, 1_000)
+            .ok_or_else(|| anyhow::anyhow!("export link missing"))?;
+        let tampered = token(&link).replacen("42.3.", "43.3.", 1);
+        anyhow::ensure!(exports.verify(&tampered, 1_000).is_none());
+        let foreign = SubscriptionExports::with_secret("https://alert.example.com", &[8; 32]);
+        anyhow::ensure!(foreign.verify(token(&link), 1_000).is_none());
+        anyhow::ensure!(
+            SubscriptionExports::disabled()
+                .link(SubscriptionId(42), 3, 1_000)
+                .is_none()
+        );
+        Ok(())
+    }
+}
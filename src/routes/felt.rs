@@ -0,0 +1,473 @@
+use crate::models::{
+    ApiResponse, DisasterEvent, FeltReport, FeltReportRequest, MAX_FELT_INTENSITY,
+    parse_event_epoch_ms,
+};
+use crate::storage::{FeltReportOutcome, try_now_millis};
+use crate::utils::distance;
+use crate::utils::intensity::{IntensityModel, IntensityObservation, suggest_calibration};
+use axum::{
+    Json,
+    extract::{
+        ConnectInfo, Path, Query, State,
+        rejection::{JsonRejection, QueryRejection},
+    },
+    http::{HeaderMap, StatusCode},
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+use super::AppState;
+use super::earthquakes::lookup_sources;
+use super::quota::source_network;
+
+/// 地震发生后接收震感报告的天数；更晚的回忆不可靠，也限制了可刷报告的事件范围。
+const FELT_REPORT_WINDOW_DAYS: i64 = 7;
+const DAY_MS: i64 = 86_400_000;
+
+/// 观测与估算对照的震中距分段下限（千米），最后一段不设上限。
+const DISTANCE_BANDS_KM: [f64; 6] = [0.0, 25.0, 50.0, 100.0, 200.0, 400.0];
+
+type LookupResult = Result<(DisasterEvent, FeltReportOutcome), (StatusCode, &'static str)>;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct FeltReportReceipt {
+    source: String,
+    event_id: String,
+    intensity: u8,
+    /// 同一网络此前已报告过这次地震，本次替换了旧报告。
+    replaced: bool,
+}
+
+/// 接收用户对某次历史地震的震感报告。
+///
+/// 只接受已入库、未取消且发生在最近 7 天内的地震；同一网络（IPv4 地址或 IPv6 /64 前缀）
+/// 对同一地震只保留最新一份，每次地震的报告数也有上限。不保存来源 IP，坐标取整到两位小数。
+pub(crate) async fn felt_report_handler(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    payload: Result<Json<FeltReportRequest>, JsonRejection>,
+) -> (StatusCode, Json<ApiResponse<FeltReportReceipt>>) {
+    let Ok(Json(request)) = payload else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("请求格式无效")),
+        );
+    };
+    let now_ms = match try_now_millis() {
+        Ok(now_ms) => now_ms,
+        Err(error) => {
+            tracing::error!(event = "felt.report_failed", error = ?error, "felt.report_failed");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("震感报告保存失败")),
+            );
+        }
+    };
+    let report = match validate(&request, now_ms) {
+        Ok(report) => report,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::error(message))),
+    };
+    let Some(sources) = lookup_sources(request.source) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("未知的地震数据源")),
+        );
+    };
+    let network = source_network(state.client_ip.resolve(peer.ip(), &headers));
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let event_id = request.event_id;
+    let intensity = report.intensity;
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<LookupResult> {
+        let _permit = permit;
+        let sources = sources.iter().map(String::as_str).collect::<Vec<_>>();
+        let Some(event) = storage.earthquake_history_event(&sources, &event_id)? else {
+            return Ok(Err((StatusCode::NOT_FOUND, "未找到该地震")));
+        };
+        if !accepts_reports(&event, now_ms) {
+            return Ok(Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "该地震已超过 7 天，不再接收震感报告",
+            )));
+        }
+        storage
+            .record_felt_report(&event.event_key(), &network, &report)
+            .map(|outcome| Ok((event, outcome)))
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(Ok((_event, FeltReportOutcome::EventFull))) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ApiResponse::error("该地震的震感报告已达上限")),
+        ),
+        Ok(Ok((event, outcome))) => {
+            let replaced = outcome == FeltReportOutcome::Replaced;
+            tracing::info!(
+                event = "felt.report_recorded",
+                source = %event.source,
+                event_id = %event.event_id,
+                intensity,
+                replaced,
+                "felt.report_recorded"
+            );
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "震感报告已记录",
+                    Some(FeltReportReceipt {
+                        source: event.source,
+                        event_id: event.event_id,
+                        intensity,
+                        replaced,
+                    }),
+                )),
+            )
+        }
+        Ok(Err((status, message))) => (status, Json(ApiResponse::error(message))),
+        Err(error) => {
+            tracing::error!(event = "felt.report_failed", error = ?error, "felt.report_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("震感报告保存失败")),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FeltSummaryParams {
+    source: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct FeltSummaryResponse {
+    source: String,
+    event_id: String,
+    model: IntensityModel,
+    reports: usize,
+    /// 各震度（0 到 7）的报告数。
+    intensities: [usize; 8],
+    /// 带坐标、可与估算对照的报告数。
+    located: usize,
+    /// 按震中距分段的观测与估算震度，没有报告的分段不列出。
+    comparisons: Vec<FeltComparison>,
+    /// 带坐标报告的观测减估算平均值，为正表示模型低估；没有带坐标的报告时为 `None`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mean_residual: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct FeltComparison {
+    min_distance_km: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_distance_km: Option<f64>,
+    reports: usize,
+    observed_mean: f64,
+    estimated_mean: f64,
+}
+
+/// 汇总某次地震的震感报告，并按当前震度模型逐段对照观测和估算震度。
+pub(crate) async fn felt_summary_handler(
+    State(state): State<AppState>,
+    Path(event_id): Path<String>,
+    params: Result<Query<FeltSummaryParams>, QueryRejection>,
+) -> (StatusCode, Json<ApiResponse<FeltSummaryResponse>>) {
+    let Ok(Query(params)) = params else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("查询参数无效")),
+        );
+    };
+    if event_id.trim().is_empty() || event_id.len() > 128 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("事件编号无效")),
+        );
+    }
+    let Some(sources) = lookup_sources(params.source) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("未知的地震数据源")),
+        );
+    };
+    let model = state.config_reloader.current().intensity_model;
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("事件存储繁忙，请稍后重试")),
+        );
+    };
+    let storage = state.storage.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Option<FeltSummaryResponse>> {
+            let _permit = permit;
+            let sources = sources.iter().map(String::as_str).collect::<Vec<_>>();
+            let Some(event) = storage.earthquake_history_event(&sources, &event_id)? else {
+                return Ok(None);
+            };
+            let reports = storage.felt_reports(&event.event_key())?;
+            Ok(Some(summarize(&event, &reports, model)))
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    match result {
+        Ok(Some(summary)) => (
+            StatusCode::OK,
+            Json(ApiResponse::success("震感报告汇总成功", Some(summary))),
+        ),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error("未找到该地震")),
+        ),
+        Err(error) => {
+            tracing::error!(event = "felt.summary_failed", error = ?error, "felt.summary_failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error("震感报告暂时无法汇总，请稍后重试")),
+            )
+        }
+    }
+}
+
+fn summarize(
+    event: &DisasterEvent,
+    reports: &[FeltReport],
+    model: IntensityModel,
+) -> FeltSummaryResponse {
+    let mut intensities = [0; 8];
+    for report in reports {
+        if let Some(count) = intensities.get_mut(usize::from(report.intensity)) {
+            *count += 1;
+        }
+    }
+    let compared = observations(event, reports, model);
+    let mut comparisons = Vec::new();
+    for (index, min_distance_km) in DISTANCE_BANDS_KM.iter().copied().enumerate() {
+        let max_distance_km = DISTANCE_BANDS_KM.get(index + 1).copied();
+        let band = compared
+            .iter()
+            .filter(|(epicentral_km, _observation)| {
+                *epicentral_km >= min_distance_km
+                    && max_distance_km.is_none_or(|max| *epicentral_km < max)
+            })
+            .map(|(_epicentral_km, observation)| observation)
+            .collect::<Vec<_>>();
+        if band.is_empty() {
+            continue;
+        }
+        let count = band.len() as f64;
+        comparisons.push(FeltComparison {
+            min_distance_km,
+            max_distance_km,
+            reports: band.len(),
+            observed_mean: round_to_hundredths(
+                band.iter()
+                    .map(|observation| observation.observed)
+                    .sum::<f64>()
+                    / count,
+            ),
+            estimated_mean: round_to_hundredths(
+                band.iter()
+                    .map(|observation| observation.estimated)
+                    .sum::<f64>()
+                    / count,
+            ),
+        });
+    }
+    let located = compared
+        .into_iter()
+        .map(|(_epicentral_km, observation)| observation)
+        .collect::<Vec<_>>();
+    FeltSummaryResponse {
+        source: event.source.clone(),
+        event_id: event.event_id.clone(),
+        model,
+        reports: reports.len(),
+        intensities,
+        located: located.len(),
+        comparisons,
+        mean_residual: suggest_calibration(&located)
+            .map(|suggestion| round_to_hundredths(suggestion.mean_residual)),
+    }
+}
+
+/// 将带坐标的震感报告与模型估算逐一对照，返回震中距和对照样本。
+/// 地震缺少震中或震级时无法估算，返回空列表。
+pub(super) fn observations(
+    event: &DisasterEvent,
+    reports: &[FeltReport],
+    model: IntensityModel,
+) -> Vec<(f64, IntensityObservation)> {
+    let (Some((latitude, longitude)), Some(magnitude)) =
+        (event.latitude.zip(event.longitude), event.magnitude)
+    else {
+        return Vec::new();
+    };
+    let depth_km = event.depth_km.unwrap_or_default().max(0.0);
+    reports
+        .iter()
+        .filter_map(|report| {
+            let epicentral_km = distance::vincenty_distance(
+                latitude,
+                longitude,
+                report.latitude?,
+                report.longitude?,
+            )?;
+            let hypocentral_distance_km = epicentral_km
+                .mul_add(epicentral_km, depth_km * depth_km)
+                .sqrt();
+            Some((
+                epicentral_km,
+                IntensityObservation {
+                    magnitude,
+                    hypocentral_distance_km,
+                    observed: f64::from(report.intensity),
+                    estimated: model.estimate(magnitude, hypocentral_distance_km),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn round_to_hundredths(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn validate(request: &FeltReportRequest, now_ms: i64) -> Result<FeltReport, &'static str> {
+    if request.event_id.trim().is_empty() || request.event_id.len() > 128 {
+        return Err("事件编号无效");
+    }
+    if request.intensity > MAX_FELT_INTENSITY {
+        return Err("震度须在 0 到 7 之间");
+    }
+    let (latitude, longitude) = match (request.latitude, request.longitude) {
+        (None, None) => (None, None),
+        (Some(latitude), Some(longitude))
+            if distance::validate_coordinates(latitude, longitude) =>
+        {
+            (
+                Some(round_to_hundredths(latitude)),
+                Some(round_to_hundredths(longitude)),
+            )
+        }
+        _ => return Err("坐标无效"),
+    };
+    Ok(FeltReport {
+        intensity: request.intensity,
+        latitude,
+        longitude,
+        reported_at_ms: now_ms,
+    })
+}
+
+fn accepts_reports(event: &DisasterEvent, now_ms: i64) -> bool {
+    parse_event_epoch_ms(event).is_some_and(|occurred_ms| {
+        now_ms.saturating_sub(occurred_ms) <= FELT_REPORT_WINDOW_DAYS * DAY_MS
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DisasterCategory, ProviderChannel};
+
+    fn request(intensity: u8, latitude: Option<f64>, longitude: Option<f64>) -> FeltReportRequest {
+        FeltReportRequest {
+            event_id: "us7000abcd".to_string(),
+            source: None,
+            intensity,
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn validates_and_coarsens_reports() {
+        assert_eq!(
+            validate(&request(3, Some(35.681_236), Some(139.767_125)), 1_000),
+            Ok(FeltReport {
+                intensity: 3,
+                latitude: Some(35.68),
+                longitude: Some(139.77),
+                reported_at_ms: 1_000,
+            })
+        );
+        assert!(validate(&request(0, None, None), 1_000).is_ok());
+        assert!(validate(&request(8, None, None), 1_000).is_err());
+        assert!(validate(&request(3, Some(35.0), None), 1_000).is_err());
+        assert!(validate(&request(3, Some(91.0), Some(0.0)), 1_000).is_err());
+        let mut blank = request(3, None, None);
+        blank.event_id = " ".to_string();
+        assert!(validate(&blank, 1_000).is_err());
+    }
+
+    #[test]
+    fn summaries_compare_observed_and_estimated_intensity_by_distance() {
+        let event = DisasterEvent {
+            category: DisasterCategory::EarthquakeReport,
+            channel: ProviderChannel::FanStudio,
+            source: "catalog.usgs".to_string(),
+            event_id: "us7000abcd".to_string(),
+            revision: "1".to_string(),
+            report_num: 1,
+            title: String::new(),
+            description: String::new(),
+            latitude: Some(35.0),
+            longitude: Some(139.0),
+            magnitude: Some(6.0),
+            depth_km: Some(10.0),
+            affected_regions: Vec::new(),
+            radius_km: None,
+            level: 2,
+            occurred_at: "2026-07-12T00:00:00Z".to_string(),
+            final_report: true,
+            cancel: false,
+            training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
+        };
+        let report = |intensity, latitude: Option<f64>| FeltReport {
+            intensity,
+            latitude,
+            longitude: latitude.map(|_latitude| 139.0),
+            reported_at_ms: 0,
+        };
+        let reports = [
+            report(5, Some(35.1)),
+            report(4, Some(35.15)),
+            report(2, Some(36.0)),
+            report(3, None),
+        ];
+        let summary = summarize(&event, &reports, IntensityModel::Blended);
+        assert_eq!(summary.reports, 4);
+        assert_eq!(summary.located, 3);
+        assert_eq!(summary.intensities, [0, 0, 1, 1, 1, 1, 0, 0]);
+        assert_eq!(
+            summary
+                .comparisons
+                .iter()
+                .map(|band| (band.min_distance_km, band.reports))
+                .collect::<Vec<_>>(),
+            [(0.0, 2), (100.0, 1)]
+        );
+        assert!(summary.comparisons[0].observed_mean == 4.5);
+        assert!(summary.mean_residual.is_some());
+
+        let unlocated = DisasterEvent {
+            latitude: None,
+            ..event
+        };
+        let summary = summarize(&unlocated, &reports, IntensityModel::Blended);
+        assert!(summary.comparisons.is_empty() && summary.mean_residual.is_none());
+    }
+}
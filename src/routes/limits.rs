@@ -0,0 +1,115 @@
+use crate::models::ApiResponse;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// 一组路由共享的处理时限和并发上限。
+///
+/// 超过上限的请求立即返回 503，而不是排队等待，避免慢查询或大量历史请求占满
+/// 运行时，拖慢订阅和健康检查等与推送相关的接口。
+#[derive(Clone)]
+pub(crate) struct RouteLimits {
+    tier: &'static str,
+    timeout: Duration,
+    in_flight: Arc<Semaphore>,
+}
+
+impl RouteLimits {
+    pub(crate) fn new(tier: &'static str, max_in_flight: usize, timeout: Duration) -> Self {
+        Self {
+            tier,
+            timeout,
+            in_flight: Arc::new(Semaphore::new(max_in_flight)),
+        }
+    }
+}
+
+/// 按 [`RouteLimits`] 限制并发并为请求设置处理时限。
+///
+/// 超时后放弃等待响应；已经交给阻塞线程的存储操作会继续执行完毕，但不再占用并发名额。
+pub(crate) async fn enforce_route_limits(
+    State(limits): State<RouteLimits>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Ok(_permit) = limits.in_flight.try_acquire() else {
+        tracing::warn!(
+            event = "http.overloaded",
+            tier = limits.tier,
+            "http.overloaded"
+        );
+        return unavailable("服务繁忙，请稍后重试");
+    };
+    match tokio::time::timeout(limits.timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_elapsed) => {
+            tracing::warn!(
+                event = "http.timed_out",
+                tier = limits.tier,
+                timeout_ms = u64::try_from(limits.timeout.as_millis()).unwrap_or(u64::MAX),
+                "http.timed_out"
+            );
+            unavailable("请求处理超时，请稍后重试")
+        }
+    }
+}
+
+fn unavailable(message: &'static str) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, HeaderValue::from_static("1"))],
+        Json(ApiResponse::<()>::error(message)),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware, routing::get};
+
+    #[tokio::test]
+    async fn rejects_requests_over_the_cap_and_past_the_deadline() -> anyhow::Result<()> {
+        async fn slow() -> &'static str {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            "done"
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let app =
+            Router::new()
+                .route("/slow", get(slow))
+                .route_layer(middleware::from_fn_with_state(
+                    RouteLimits::new("test", 1, Duration::from_millis(300)),
+                    enforce_route_limits,
+                ));
+        let server = tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+        let url = format!("http://{address}/slow");
+
+        let first = tokio::spawn(client.get(&url).send());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let busy = client.get(&url).send().await?;
+        anyhow::ensure!(busy.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        anyhow::ensure!(
+            busy.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .is_some_and(|value| value == "1")
+        );
+
+        let timed_out = first.await??;
+        anyhow::ensure!(timed_out.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = timed_out.json().await?;
+        anyhow::ensure!(body["message"] == "请求处理超时，请稍后重试");
+        server.abort();
+        Ok(())
+    }
+}
@@ -1,11 +1,51 @@
+mod access_log;
+mod admin;
+mod challenge;
+mod client_ip;
+mod conditional;
 mod detail_page;
+mod distance;
+mod earthquakes;
+mod export;
+mod felt;
+mod limits;
+mod preview;
+mod quota;
+mod request_id;
 mod reverse_geocoder;
+mod signing;
 mod subscribe;
 mod web;
 
+pub(crate) use access_log::log_http_request;
+pub(crate) use admin::{
+    AdminAuth, admin_felt_calibration_handler, admin_import_catalog_handler,
+    admin_parse_diagnostics_handler, admin_purge_subscription_handler, admin_reload_config_handler,
+    admin_restore_subscription_handler, admin_subscription_breakdown_handler,
+    admin_subscription_density_handler, require_admin,
+};
+pub(crate) use challenge::{SubscriptionChallenges, subscribe_challenge_handler};
+pub(crate) use client_ip::ClientIpResolver;
+pub(crate) use conditional::conditional_history_read;
+pub(crate) use distance::distance_handler;
+pub(crate) use earthquakes::{
+    earthquake_search_handler, intensity_grid_handler, seismicity_stats_handler,
+};
+pub(crate) use export::{
+    SubscriptionExports, subscription_export_handler, subscription_export_request_handler,
+};
+pub(crate) use felt::{felt_report_handler, felt_summary_handler};
+pub(crate) use limits::{RouteLimits, enforce_route_limits};
+pub(crate) use preview::preview_handler;
+pub(crate) use quota::{QuotaExceeded, QuotaSettings, Reservation, SubscriptionQuota};
+pub(crate) use request_id::{REQUEST_ID_HEADER, assign_request_id};
 pub(crate) use reverse_geocoder::{ReverseGeocodeResult, ReverseGeocoder};
 pub(crate) use subscribe::{
-    AppState, bark_urls_handler, health_handler, reverse_geocode_handler, status_handler,
-    subscribe_handler, subscription_options_handler, unsubscribe_handler,
+    AppState, bark_urls_handler, health_handler, liveness_handler, metrics_handler,
+    public_config_handler, readiness_handler, restore_subscription_handler,
+    reverse_geocode_handler, status_handler, subscribe_handler, subscription_options_handler,
+    unsubscribe_handler,
+};
+pub(crate) use web::{
+    incident_detail_handler, index_handler, notification_open_handler, web_fallback_handler,
 };
-pub(crate) use web::{incident_detail_handler, index_handler};
@@ -0,0 +1,167 @@
+use crate::delivery::PreparedAlert;
+use crate::models::{
+    ApiResponse, DisasterEvent, NotificationPreviewRequest, PreviewEvent, ProviderChannel,
+    epoch_millis_to_rfc3339,
+};
+use crate::runtime::{estimate_alert_timing, sanitize_event};
+use crate::storage::try_now_millis;
+use crate::utils::distance;
+use axum::{Json, extract::State, extract::rejection::JsonRejection, http::StatusCode};
+use serde::Serialize;
+
+use super::AppState;
+use super::subscribe::normalize_targets;
+
+#[derive(Serialize)]
+pub(crate) struct PreviewResponse {
+    notifications: Vec<PreviewNotification>,
+}
+
+/// 一个监测地点在一个推送渠道上的通知内容，与实际推送一致（已按长度上限裁剪）。
+#[derive(Serialize)]
+struct PreviewNotification {
+    channel: &'static str,
+    target: String,
+    title: String,
+    subtitle: String,
+    body: String,
+}
+
+/// 按当前生效的模板渲染假设事件对每个监测地点的通知，供前端检查模板和文案；不读取订阅，也不推送。
+pub(crate) async fn preview_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<NotificationPreviewRequest>, JsonRejection>,
+) -> (StatusCode, Json<ApiResponse<PreviewResponse>>) {
+    let Ok(Json(payload)) = payload else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error("预览请求体无效")),
+        );
+    };
+    let targets = match normalize_targets(payload.targets) {
+        Ok(targets) => targets,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::error(message))),
+    };
+    let now_ms = try_now_millis().unwrap_or(0);
+    let event = match preview_event(payload.event, now_ms) {
+        Ok(event) => event,
+        Err(reason) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(format!("预览事件无效：{reason}"))),
+            );
+        }
+    };
+
+    let settings = state.config_reloader.current();
+    let alert = PreparedAlert::new(&event, &settings.message_templates);
+    let mut notifications = Vec::with_capacity(targets.len());
+    for target in &targets {
+        // 按地区订阅没有具体位置，与实际推送一样不估算震波到达时间。
+        let distance_km = event
+            .latitude
+            .zip(event.longitude)
+            .zip(target.point)
+            .and_then(|((latitude, longitude), point)| {
+                distance::vincenty_distance(latitude, longitude, point.latitude, point.longitude)
+            });
+        let timing = match distance_km
+            .map(|distance_km| estimate_alert_timing(&event, distance_km, &settings))
+            .transpose()
+        {
+            Ok(timing) => timing.flatten(),
+            Err(error) => {
+                tracing::error!(event = "preview.failed", error = ?error, "preview.failed");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error("预览暂时无法生成，请稍后重试")),
+                );
+            }
+        };
+        let content = state
+            .bark_notifier
+            .render_alert(&alert, target, timing.as_ref(), now_ms);
+        notifications.push(PreviewNotification {
+            channel: "bark",
+            target: target.label.clone(),
+            title: content.title,
+            subtitle: content.subtitle,
+            body: content.body,
+        });
+    }
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "预览生成成功",
+            Some(PreviewResponse { notifications }),
+        )),
+    )
+}
+
+fn preview_event(event: PreviewEvent, now_ms: i64) -> Result<DisasterEvent, String> {
+    let occurred_at = match event.occurred_at {
+        Some(occurred_at) => occurred_at,
+        None => epoch_millis_to_rfc3339(now_ms).map_err(|error| error.to_string())?,
+    };
+    let source = if event.source.trim().is_empty() {
+        "preview".to_string()
+    } else {
+        event.source.trim().to_string()
+    };
+    let channel = crate::source_registry::find(&source)
+        .map_or(ProviderChannel::FanStudio, |definition| definition.channel);
+    sanitize_event(DisasterEvent {
+        category: event.category,
+        channel,
+        source,
+        event_id: "preview".to_string(),
+        revision: "preview".to_string(),
+        report_num: event.report_num,
+        title: event.title,
+        description: event.description,
+        latitude: event.latitude,
+        longitude: event.longitude,
+        magnitude: event.magnitude,
+        depth_km: event.depth_km,
+        affected_regions: event.affected_regions,
+        radius_km: event.radius_km,
+        level: event.level,
+        occurred_at,
+        final_report: event.final_report,
+        cancel: event.cancel,
+        training: event.training,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
+    })
+    .map_err(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DisasterCategory;
+
+    #[test]
+    fn preview_event_fills_defaults_and_rejects_half_coordinates() -> anyhow::Result<()> {
+        let request = serde_json::from_str::<NotificationPreviewRequest>(
+            r#"{
+                "event": {"category": "earthquake_warning", "source": "wolfx.cenc_eew", "latitude": 29.6, "longitude": 102.1, "magnitude": 6.2},
+                "targets": [{"point": {"latitude": 31.2, "longitude": 121.5}}]
+            }"#,
+        )?;
+        let event = preview_event(request.event, 1_735_689_600_000)
+            .map_err(|reason| anyhow::anyhow!(reason))?;
+
+        anyhow::ensure!(event.category == DisasterCategory::EarthquakeWarning);
+        anyhow::ensure!(event.channel == ProviderChannel::Wolfx);
+        anyhow::ensure!(event.report_num == 1);
+        anyhow::ensure!(event.occurred_at == "2025-01-01T00:00:00.000Z");
+
+        let half = serde_json::from_str::<PreviewEvent>(
+            r#"{"category": "earthquake_report", "latitude": 29.6}"#,
+        )?;
+        anyhow::ensure!(preview_event(half, 0).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use crate::config::LiveSettings;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+const MAX_TRACKED_SOURCES: usize = 100_000;
+
+/// 按来源 IP 统计订阅创建次数的滑动窗口配额。
+///
+/// IPv6 按 /64 前缀归并（家庭宽带通常分配整个前缀），IPv4 按单个地址计数；
+/// 配额为 0 时关闭。记录只保存在内存中，重启后清空；配额和窗口随配置重新加载即时生效。
+#[derive(Clone)]
+pub(crate) struct SubscriptionQuota {
+    inner: Arc<QuotaInner>,
+}
+
+struct QuotaInner {
+    settings: LiveSettings,
+    sources: Mutex<HashMap<QuotaKey, VecDeque<i64>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum QuotaKey {
+    V4(u32),
+    V6Prefix(u64),
+}
+
+/// 公开给前端的配额设置；`limit` 为 0 表示不限制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct QuotaSettings {
+    pub(crate) limit: usize,
+    pub(crate) window_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct QuotaExceeded {
+    pub(crate) limit: usize,
+    pub(crate) retry_after_seconds: u64,
+}
+
+impl SubscriptionQuota {
+    #[cfg(test)]
+    pub(crate) fn new(limit: usize, window_seconds: u64) -> Self {
+        use crate::config::TunableSettings;
+        Self::with_settings(LiveSettings::new(TunableSettings {
+            subscribe_quota_per_ip: limit,
+            subscribe_quota_window_seconds: window_seconds,
+            ..TunableSettings::default()
+        }))
+    }
+
+    pub(crate) fn with_settings(settings: LiveSettings) -> Self {
+        Self {
+            inner: Arc::new(QuotaInner {
+                settings,
+                sources: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub(crate) fn disabled() -> Self {
+        Self::with_settings(LiveSettings::default())
+    }
+
+    pub(crate) fn settings(&self) -> QuotaSettings {
+        let settings = self.inner.settings.load();
+        QuotaSettings {
+            limit: settings.subscribe_quota_per_ip,
+            window_seconds: settings.subscribe_quota_window_seconds,
+        }
+    }
+
+    fn limits(&self) -> (usize, i64) {
+        let settings = self.settings();
+        (
+            settings.limit,
+            i64::try_from(settings.window_seconds.saturating_mul(1_000)).unwrap_or(i64::MAX),
+        )
+    }
+
+    /// 检查来源的剩余配额并在同一把锁内占用一次；返回的 [`Reservation`] 未调用
+    /// [`Reservation::keep`] 就被丢弃时退还配额，请求在校验或存储阶段失败不会计入。
+    pub(crate) fn try_reserve(
+        &self,
+        source: IpAddr,
+        now_ms: i64,
+    ) -> Result<Reservation, QuotaExceeded> {
+        let (limit, window_ms) = self.limits();
+        if limit == 0 {
+            return Ok(Reservation { held: None });
+        }
+        let key = QuotaKey::from(source);
+        let mut sources = self
+            .inner
+            .sources
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(creations) = sources.get_mut(&key) {
+            expire(creations, window_ms, now_ms);
+            if creations.len() >= limit {
+                let oldest = creations.front().copied().unwrap_or(now_ms);
+                let retry_after_ms = oldest
+                    .saturating_add(window_ms)
+                    .saturating_sub(now_ms)
+                    .max(0);
+                return Err(QuotaExceeded {
+                    limit,
+                    retry_after_seconds: u64::try_from(retry_after_ms.saturating_add(999) / 1_000)
+                        .unwrap_or(0),
+                });
+            }
+        } else if sources.len() >= MAX_TRACKED_SOURCES {
+            sources.retain(|_, creations| {
+                creations
+                    .back()
+                    .is_some_and(|latest| now_ms.saturating_sub(*latest) < window_ms)
+            });
+            if sources.len() >= MAX_TRACKED_SOURCES {
+                tracing::warn!(
+                    event = "subscription.quota_table_full",
+                    tracked_sources = sources.len(),
+                    "subscription.quota_table_full"
+                );
+                return Ok(Reservation { held: None });
+            }
+        }
+        sources.entry(key).or_default().push_back(now_ms);
+        Ok(Reservation {
+            held: Some((Arc::clone(&self.inner), key, now_ms)),
+        })
+    }
+}
+
+/// 已占用的一次订阅创建配额。
+#[must_use]
+pub(crate) struct Reservation {
+    held: Option<(Arc<QuotaInner>, QuotaKey, i64)>,
+}
+
+impl Reservation {
+    /// 订阅请求写入存储后保留占用，直到滑动窗口过期。
+    pub(crate) fn keep(mut self) {
+        self.held = None;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let Some((inner, key, reserved_at_ms)) = self.held.take() else {
+            return;
+        };
+        let mut sources = inner
+            .sources
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let Some(creations) = sources.get_mut(&key) else {
+            return;
+        };
+        if let Some(position) = creations
+            .iter()
+            .rposition(|created_at| *created_at == reserved_at_ms)
+        {
+            creations.remove(position);
+        }
+        if creations.is_empty() {
+            sources.remove(&key);
+        }
+    }
+}
+
+fn expire(creations: &mut VecDeque<i64>, window_ms: i64, now_ms: i64) {
+    while creations
+        .front()
+        .is_some_and(|created_at| now_ms.saturating_sub(*created_at) >= window_ms)
+    {
+        creations.pop_front();
+    }
+}
+
+/// 来源所在网络的字节表示，归并方式与配额相同：IPv4 为单个地址，IPv6 为 /64 前缀。
+pub(crate) fn source_network(address: IpAddr) -> Vec<u8> {
+    let mut network = Vec::with_capacity(9);
+    match QuotaKey::from(address) {
+        QuotaKey::V4(address) => {
+            network.push(4);
+            network.extend_from_slice(&address.to_be_bytes());
+        }
+        QuotaKey::V6Prefix(prefix) => {
+            network.push(6);
+            network.extend_from_slice(&prefix.to_be_bytes());
+        }
+    }
+    network
+}
+
+impl From<IpAddr> for QuotaKey {
+    fn from(address: IpAddr) -> Self {
+        match address {
+            IpAddr::V4(address) => Self::V4(address.to_bits()),
+            IpAddr::V6(address) => match address.to_ipv4_mapped() {
+                Some(address) => Self::V4(address.to_bits()),
+                None => Self::V6Prefix(u64::try_from(address.to_bits() >> 64).unwrap_or(0)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_after_limit_until_window_slides() -> anyhow::Result<()> {
+        let quota = SubscriptionQuota::new(2, 60);
+        let source = "203.0.113.10".parse::<IpAddr>()?;
+        anyhow::ensure!(quota.try_reserve(source, 0).map(Reservation::keep).is_ok());
+        anyhow::ensure!(
+            quota
+                .try_reserve(source, 10_000)
+                .map(Reservation::keep)
+                .is_ok()
+        );
+        anyhow::ensure!(
+            quota.try_reserve(source, 20_000).err()
+                == Some(QuotaExceeded {
+                    limit: 2,
+                    retry_after_seconds: 40,
+                })
+        );
+        anyhow::ensure!(quota.try_reserve("203.0.113.11".parse()?, 20_000).is_ok());
+        anyhow::ensure!(quota.try_reserve(source, 60_000).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn outstanding_reservations_count_until_released() -> anyhow::Result<()> {
+        let quota = SubscriptionQuota::new(2, 60);
+        let source = "203.0.113.10".parse::<IpAddr>()?;
+        let first = quota.try_reserve(source, 0);
+        let second = quota.try_reserve(source, 0);
+        anyhow::ensure!(first.is_ok() && second.is_ok());
+        anyhow::ensure!(quota.try_reserve(source, 0).is_err());
+        drop(second);
+        let third = quota.try_reserve(source, 1_000);
+        anyhow::ensure!(third.is_ok());
+        anyhow::ensure!(quota.try_reserve(source, 1_000).is_err());
+        drop((first, third));
+        anyhow::ensure!(
+            quota
+                .inner
+                .sources
+                .lock()
+                .map_err(|_error| anyhow::anyhow!("quota lock poisoned"))?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groups_ipv6_sources_by_prefix() -> anyhow::Result<()> {
+        let quota = SubscriptionQuota::new(1, 60);
+        quota
+            .try_reserve("2001:db8:1:2::10".parse()?, 0)
+            .map(Reservation::keep)
+            .map_err(|_exceeded| anyhow::anyhow!("first reservation rejected"))?;
+        anyhow::ensure!(
+            quota
+                .try_reserve("2001:db8:1:2::20".parse()?, 1_000)
+                .is_err()
+        );
+        anyhow::ensure!(
+            quota
+                .try_reserve("2001:db8:1:3::10".parse()?, 1_000)
+                .is_ok()
+        );
+        anyhow::ensure!(
+            source_network("2001:db8:1:2::10".parse()?)
+                == source_network("2001:db8:1:2::20".parse()?)
+        );
+        anyhow::ensure!(
+            source_network("::ffff:203.0.113.10".parse()?)
+                == source_network("203.0.113.10".parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_quota_never_rejects() -> anyhow::Result<()> {
+        let quota = SubscriptionQuota::disabled();
+        let source = "203.0.113.10".parse::<IpAddr>()?;
+        anyhow::ensure!(quota.try_reserve(source, 0).map(Reservation::keep).is_ok());
+        anyhow::ensure!(quota.try_reserve(source, 0).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::Instrument;
+
+use crate::storage::try_now_millis;
+
+pub(crate) static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// 进程内唯一的请求编号：启动时间戳加自增序号，重启后不会与之前的编号重复。
+static REQUEST_ID_PREFIX: LazyLock<String> =
+    LazyLock::new(|| format!("{:x}", try_now_millis().unwrap_or(0)));
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 为每个请求分配或沿用 `X-Request-Id`，在 `http.request` span 中处理请求，
+/// 使路由日志都带上该编号，并在响应（包括错误响应）中回传。
+///
+/// 上游代理传入的编号只在由可打印的安全字符组成且不超过 64 个字符时沿用，避免日志注入。
+pub(crate) async fn assign_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map_or_else(generate_request_id, str::to_string);
+    let header_value = HeaderValue::from_str(&request_id).ok();
+    let span = tracing::info_span!(
+        "http.request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Some(value) = header_value {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+fn is_valid_request_id(value: &str) -> bool {
+    (1..=MAX_REQUEST_ID_LEN).contains(&value.len())
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b':'))
+}
+
+fn generate_request_id() -> String {
+    format!(
+        "{}-{:x}",
+        REQUEST_ID_PREFIX.as_str(),
+        NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_safe_request_ids() {
+        assert!(is_valid_request_id("0b1f6c2e-9d4a-4c1e-8f00-7a1b2c3d4e5f"));
+        assert!(is_valid_request_id("trace:abc.123_x"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("bad id"));
+        assert!(!is_valid_request_id("line\nbreak"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[test]
+    fn generated_request_ids_are_unique_and_valid() {
+        let first = generate_request_id();
+        let second = generate_request_id();
+        assert_ne!(first, second);
+        assert!(is_valid_request_id(&first));
+    }
+}
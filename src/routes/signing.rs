@@ -0,0 +1,49 @@
+use aws_lc_rs::hmac;
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use zeroize::Zeroizing;
+
+/// 无状态令牌和链接的 HMAC-SHA256 签名密钥。
+///
+/// 每种用途以 `HMAC(根密钥, 用途标签)` 派生独立密钥，同一根密钥签发的不同令牌不能互相冒用。
+/// 签名字段按顺序直接拼接，调用方只传入定长编码的字段。
+pub(super) struct TokenSigner {
+    key: hmac::Key,
+}
+
+impl TokenSigner {
+    pub(super) fn derive(root_secret: &[u8], purpose: &[u8]) -> Self {
+        let root = hmac::Key::new(hmac::HMAC_SHA256, root_secret);
+        let derived = Zeroizing::new(hmac::sign(&root, purpose).as_ref().to_vec());
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, &derived),
+        }
+    }
+
+    pub(super) fn sign(&self, fields: &[&[u8]]) -> String {
+        URL_SAFE_NO_PAD.encode(hmac::sign(&self.key, &fields.concat()))
+    }
+
+    pub(super) fn verify(&self, fields: &[&[u8]], tag: &str) -> bool {
+        let Ok(tag) = URL_SAFE_NO_PAD.decode(tag) else {
+            return false;
+        };
+        hmac::verify(&self.key, &fields.concat(), &tag).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_bound_to_purpose_and_fields() {
+        let signer = TokenSigner::derive(&[7; 32], b"disaster-alert:test:v1");
+        let tag = signer.sign(&[&1_i64.to_be_bytes(), &2_u64.to_be_bytes()]);
+        assert!(signer.verify(&[&1_i64.to_be_bytes(), &2_u64.to_be_bytes()], &tag));
+        assert!(!signer.verify(&[&1_i64.to_be_bytes(), &3_u64.to_be_bytes()], &tag));
+        assert!(!signer.verify(&[&1_i64.to_be_bytes(), &2_u64.to_be_bytes()], "not-a-tag!"));
+
+        let other = TokenSigner::derive(&[7; 32], b"disaster-alert:other:v1");
+        assert!(!other.verify(&[&1_i64.to_be_bytes(), &2_u64.to_be_bytes()], &tag));
+    }
+}
@@ -1,43 +1,56 @@
-use crate::config::normalize_bark_url;
+use crate::config::{ConfigReloader, normalize_bark_url};
 use crate::delivery::{BarkNotifier, NotificationLinkService};
 use crate::models::{
-    ApiResponse, MonitoringTarget, NotificationDestination, SubscribeRequest, Subscription,
-    UnsubscribeRequest, mask_device_key,
+    ApiResponse, DestinationId, MAX_DEVICE_LABEL_CHARS, MonitoringTarget, NotificationDestination,
+    RestoreSubscriptionRequest, SUBSCRIPTION_LIMITS, SubscribeRequest, Subscription,
+    SubscriptionLimits, UNKNOWN_REGION_MESSAGE, UnsubscribeRequest, mask_device_key,
+};
+use crate::routes::{
+    ClientIpResolver, QuotaExceeded, QuotaSettings, Reservation, ReverseGeocodeResult,
+    ReverseGeocoder, SubscriptionChallenges, SubscriptionExports, SubscriptionQuota,
+};
+use crate::runtime::{
+    DurableBacklogSnapshot, NotifierHealth, RuntimeReadiness, RuntimeStatus, RuntimeStatusSnapshot,
+    write_labeled_family,
 };
-use crate::routes::{ReverseGeocodeResult, ReverseGeocoder};
-use crate::runtime::{DurableBacklogSnapshot, RuntimeStatus, RuntimeStatusSnapshot};
 use crate::source_registry::{CategoryOption, category_options};
-use crate::storage::Storage;
+use crate::storage::{Storage, try_now_millis};
 use crate::subscriptions::{
     DeleteSubscriptionError, SubscriptionConfirmationOutcome, SubscriptionConfirmationService,
     SubscriptionManager,
 };
-use crate::utils::distance;
+use crate::utils::{boundaries, distance, places};
 use axum::{
     Json,
     extract::{
-        Query, State,
+        ConnectInfo, Query, State,
         rejection::{JsonRejection, QueryRejection},
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, RETRY_AFTER},
+    },
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 const MAX_LOCATIONS: usize = 3;
 const MAX_LOCATION_NAME_CHARS: usize = 80;
 const INSTANCE_TERMS_REQUIRED_MESSAGE: &str = "当前实例尚未确认部署责任，暂不接受新增或覆盖订阅";
+const DEFAULT_SUBSCRIPTION_RESTORE_DAYS: u64 = 30;
 
 #[derive(Clone)]
 pub(crate) struct AppState {
     pub(crate) instance_terms_accepted: bool,
     pub(crate) storage: Storage,
-    subscriptions: SubscriptionManager,
-    bark_notifier: BarkNotifier,
+    pub(crate) subscriptions: SubscriptionManager,
+    pub(crate) bark_notifier: BarkNotifier,
     bark_urls: Vec<String>,
-    runtime_status: RuntimeStatus,
+    pub(crate) runtime_status: RuntimeStatus,
     reverse_geocoder: ReverseGeocoder,
     pub(crate) notification_links: NotificationLinkService,
     pub(crate) detail_concurrency: Arc<Semaphore>,
@@ -45,6 +58,13 @@ pub(crate) struct AppState {
     pub(crate) storage_concurrency: Arc<Semaphore>,
     subscription_concurrency: Arc<Semaphore>,
     subscription_confirmations: SubscriptionConfirmationService,
+    pub(crate) subscription_challenges: SubscriptionChallenges,
+    pub(crate) client_ip: ClientIpResolver,
+    subscription_quota: SubscriptionQuota,
+    pub(crate) subscription_exports: SubscriptionExports,
+    subscription_restore_days: u64,
+    readiness_grace: Duration,
+    pub(crate) config_reloader: ConfigReloader,
 }
 
 impl AppState {
@@ -77,6 +97,13 @@ impl AppState {
             storage_concurrency: Arc::new(Semaphore::new(32)),
             subscription_concurrency: Arc::new(Semaphore::new(16)),
             subscription_confirmations,
+            subscription_challenges: SubscriptionChallenges::disabled(),
+            client_ip: ClientIpResolver::default(),
+            subscription_quota: SubscriptionQuota::disabled(),
+            subscription_exports: SubscriptionExports::disabled(),
+            subscription_restore_days: DEFAULT_SUBSCRIPTION_RESTORE_DAYS,
+            readiness_grace: Duration::ZERO,
+            config_reloader: ConfigReloader::default(),
         }
     }
 
@@ -84,6 +111,44 @@ impl AppState {
         self.instance_terms_accepted = accepted;
         self
     }
+
+    pub(crate) fn with_subscription_challenges(
+        mut self,
+        challenges: SubscriptionChallenges,
+    ) -> Self {
+        self.subscription_challenges = challenges;
+        self
+    }
+
+    pub(crate) fn with_subscription_quota(
+        mut self,
+        client_ip: ClientIpResolver,
+        quota: SubscriptionQuota,
+    ) -> Self {
+        self.client_ip = client_ip;
+        self.subscription_quota = quota;
+        self
+    }
+
+    pub(crate) fn with_subscription_exports(mut self, exports: SubscriptionExports) -> Self {
+        self.subscription_exports = exports;
+        self
+    }
+
+    pub(crate) fn with_config_reloader(mut self, reloader: ConfigReloader) -> Self {
+        self.config_reloader = reloader;
+        self
+    }
+
+    pub(crate) fn with_subscription_restore_days(mut self, days: u64) -> Self {
+        self.subscription_restore_days = days;
+        self
+    }
+
+    pub(crate) fn with_readiness_grace(mut self, grace: Duration) -> Self {
+        self.readiness_grace = grace;
+        self
+    }
 }
 
 #[derive(Deserialize)]
@@ -118,8 +183,7 @@ pub(crate) async fn reverse_geocode_handler(
         Err(error) => {
             tracing::warn!(
                 event = "reverse_geocode.failed",
-                latitude = query.latitude,
-                longitude = query.longitude,
+                place = %places::describe(query.latitude, query.longitude),
                 error = ?error,
                 "reverse_geocode.failed"
             );
@@ -138,11 +202,66 @@ fn parse_reverse_geocode_query(
 ) -> Result<ReverseGeocodeQuery, ApiResponse<ReverseGeocodeResult>> {
     query
         .map(|Query(query)| query)
-        .map_err(|_| ApiResponse::error("坐标参数无效"))
+        .map_err(|_rejection| ApiResponse::error("坐标参数无效"))
 }
 
 pub(crate) async fn subscribe_handler(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    payload: Result<Json<SubscribeRequest>, JsonRejection>,
+) -> Response {
+    let now_ms = match try_now_millis() {
+        Ok(now_ms) => now_ms,
+        Err(error) => {
+            tracing::error!(event = "subscription.clock_failed", error = ?error, "subscription.clock_failed");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<SubscribeResponse>::error(
+                    "订阅暂时无法保存，请稍后重试",
+                )),
+            )
+                .into_response();
+        }
+    };
+    let client_ip = state.client_ip.resolve(peer.ip(), &headers);
+    let reservation = match state.subscription_quota.try_reserve(client_ip, now_ms) {
+        Ok(reservation) => reservation,
+        Err(exceeded) => {
+            tracing::info!(
+                event = "subscription.quota_exceeded",
+                limit = exceeded.limit,
+                retry_after_seconds = exceeded.retry_after_seconds,
+                "subscription.quota_exceeded"
+            );
+            return quota_exceeded_response(exceeded);
+        }
+    };
+    create_subscription(state, reservation, now_ms, payload)
+        .await
+        .into_response()
+}
+
+fn quota_exceeded_response(exceeded: QuotaExceeded) -> Response {
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ApiResponse {
+            success: false,
+            message: "该网络的订阅次数已达上限，请稍后重试".to_string(),
+            data: Some(exceeded),
+        }),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(exceeded.retry_after_seconds));
+    response
+}
+
+async fn create_subscription(
+    state: AppState,
+    reservation: Reservation,
+    now_ms: i64,
     payload: Result<Json<SubscribeRequest>, JsonRejection>,
 ) -> impl IntoResponse {
     if let Err(response) = require_subscription_creation_enabled(state.instance_terms_accepted) {
@@ -194,6 +313,15 @@ pub(crate) async fn subscribe_handler(
             );
         }
     };
+    let label = match normalize_device_label(payload.label) {
+        Ok(label) => label,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::<SubscribeResponse>::error(message)),
+            );
+        }
+    };
     let subscription = Subscription::new(
         NotificationDestination::Bark {
             base_url: bark_url,
@@ -201,7 +329,8 @@ pub(crate) async fn subscribe_handler(
         },
         targets,
         payload.alerts,
-    );
+    )
+    .with_label(label);
     if let Err(message) = subscription.validate() {
         return (
             StatusCode::BAD_REQUEST,
@@ -213,6 +342,7 @@ pub(crate) async fn subscribe_handler(
         event = "subscription.requested",
         device_key = %mask_device_key(subscription.device_key()),
         target_count = subscription.targets.len(),
+        places = ?subscription.targets.iter().map(places::describe_target).collect::<Vec<_>>(),
         alert_count = subscription.alerts.len(),
         "subscription.requested"
     );
@@ -235,6 +365,21 @@ pub(crate) async fn subscribe_handler(
         );
     };
 
+    if let Err(error) = state
+        .subscription_challenges
+        .redeem(payload.challenge.as_ref(), now_ms)
+    {
+        tracing::info!(
+            event = "subscription.challenge_rejected",
+            reason = ?error,
+            "subscription.challenge_rejected"
+        );
+        return (
+            error.status(),
+            Json(ApiResponse::<SubscribeResponse>::error(error.message())),
+        );
+    }
+
     let confirmation = match state.subscription_confirmations.begin(subscription).await {
         Ok(confirmation) => confirmation,
         Err(error) => {
@@ -253,6 +398,7 @@ pub(crate) async fn subscribe_handler(
         }
     };
     drop(database_permit);
+    reservation.keep();
     let outcome = state.subscription_confirmations.attempt(confirmation).await;
     drop(request_permit);
     match outcome {
@@ -330,6 +476,37 @@ pub(crate) struct SubscriptionOptionsResponse {
     pub(crate) categories: Vec<CategoryOption>,
 }
 
+/// 前端据此调整表单校验和功能开关；只包含不涉密的生效配置。
+#[derive(Serialize)]
+pub(crate) struct PublicConfigResponse {
+    instance_terms_accepted: bool,
+    channels: &'static [&'static str],
+    bark_urls: Vec<String>,
+    limits: SubscriptionLimits,
+    subscription_quota: QuotaSettings,
+    challenge_required: bool,
+    subscription_export: bool,
+    subscription_restore_days: u64,
+    categories: Vec<CategoryOption>,
+}
+
+pub(crate) async fn public_config_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(ApiResponse::success(
+        "实例配置获取成功",
+        Some(PublicConfigResponse {
+            instance_terms_accepted: state.instance_terms_accepted,
+            channels: &["bark"],
+            bark_urls: state.bark_urls,
+            limits: SUBSCRIPTION_LIMITS,
+            subscription_quota: state.subscription_quota.settings(),
+            challenge_required: state.subscription_challenges.enabled(),
+            subscription_export: state.subscription_exports.enabled(),
+            subscription_restore_days: state.subscription_restore_days,
+            categories: category_options(),
+        }),
+    ))
+}
+
 pub(crate) async fn subscription_options_handler() -> impl IntoResponse {
     Json(ApiResponse::success(
         "订阅选项获取成功",
@@ -352,35 +529,18 @@ pub(crate) async fn unsubscribe_handler(
             );
         }
     };
-    let device_key = match validate_device_key(payload.destination.bark_device_key()) {
+    let destination_id = match parse_destination(&state, &payload.destination) {
         Ok(value) => value,
         Err((status, message)) => {
             return (status, Json(ApiResponse::<()>::error(message)));
         }
     };
-    let base_url = match normalize_bark_url(payload.destination.bark_base_url()) {
-        Ok(value) if state.bark_notifier.allows_bark_url(&value) => value,
-        Ok(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ApiResponse::<()>::error("Bark URL 不在允许列表中")),
-            );
-        }
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ApiResponse::<()>::error("Bark URL 无效")),
-            );
-        }
-    };
-    let destination_id = crate::models::DestinationId {
-        base_url,
-        device_key,
-    };
 
+    let purge = payload.purge;
     tracing::info!(
         event = "subscription.delete_requested",
         device_key = %mask_device_key(&destination_id.device_key),
+        purge,
         "subscription.delete_requested"
     );
 
@@ -393,7 +553,11 @@ pub(crate) async fn unsubscribe_handler(
         );
     };
     match run_store(permit, move || {
-        manager.delete_subscription(&destination_to_delete)
+        if purge {
+            manager.purge_subscription(&destination_to_delete)
+        } else {
+            manager.delete_subscription(&destination_to_delete)
+        }
     })
     .await
     {
@@ -401,11 +565,19 @@ pub(crate) async fn unsubscribe_handler(
             tracing::info!(
                 event = "subscription.delete_completed",
                 device_key = %mask_device_key(&destination_id.device_key),
+                purge,
                 "subscription.delete_completed"
             );
             (
                 StatusCode::OK,
-                Json(ApiResponse::<()>::success("已取消订阅", None)),
+                Json(ApiResponse::<()>::success(
+                    if purge {
+                        "已取消订阅并删除全部记录"
+                    } else {
+                        "已取消订阅"
+                    },
+                    None,
+                )),
             )
         }
         Err(e) => {
@@ -430,22 +602,127 @@ pub(crate) async fn unsubscribe_handler(
     }
 }
 
+pub(crate) async fn restore_subscription_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<RestoreSubscriptionRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let Ok(Json(payload)) = payload else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("恢复订阅请求体无效")),
+        );
+    };
+    // 有无可恢复的订阅都返回相同结果，不能凭 Bark Key 探测订阅是否存在；恢复结果只推送给设备本身。
+    match restore_destination(&state, &payload.destination).await {
+        Ok(_restored) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "如果该设备在恢复期内取消过订阅，订阅已恢复，设备会收到 Bark 通知",
+                None,
+            )),
+        ),
+        Err((status, message)) => (status, Json(ApiResponse::error(message))),
+    }
+}
+
+/// 恢复期内取消的订阅可以原样恢复，并通过 Bark 告知该设备；用户和管理接口共用。
+/// 返回是否恢复了订阅，由调用方决定是否向请求方透露。
+pub(super) async fn restore_destination(
+    state: &AppState,
+    destination: &NotificationDestination,
+) -> std::result::Result<bool, (StatusCode, String)> {
+    if !state.instance_terms_accepted {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            INSTANCE_TERMS_REQUIRED_MESSAGE.to_string(),
+        ));
+    }
+    let destination_id = parse_destination(state, destination)?;
+    let Ok(now_ms) = try_now_millis() else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "恢复订阅暂时无法完成，请稍后重试".to_string(),
+        ));
+    };
+    let window_ms = i64::try_from(state.subscription_restore_days.saturating_mul(86_400_000))
+        .unwrap_or(i64::MAX);
+    let deactivated_after_ms = now_ms.saturating_sub(window_ms);
+    let Ok(permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "订阅存储繁忙，请稍后重试".to_string(),
+        ));
+    };
+    let manager = state.subscriptions.clone();
+    let lookup = destination_id.clone();
+    let restored = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        manager.restore_subscription(&lookup, deactivated_after_ms)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    let subscription = match restored {
+        Ok(Some(subscription)) => subscription,
+        Ok(None) => return Ok(false),
+        Err(error) => {
+            tracing::error!(
+                event = "subscription.restore_failed",
+                device_key = %mask_device_key(&destination_id.device_key),
+                error = ?error,
+                "subscription.restore_failed"
+            );
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "恢复订阅暂时无法完成，请稍后重试".to_string(),
+            ));
+        }
+    };
+    tracing::info!(
+        event = "subscription.restored",
+        device_key = %mask_device_key(&destination_id.device_key),
+        "subscription.restored"
+    );
+    // 通知在后台发送，响应耗时不随是否恢复了订阅而变化。
+    let bark_notifier = state.bark_notifier.clone();
+    tokio::spawn(async move {
+        if let Err(error) = bark_notifier
+            .send_subscription_restored(&subscription)
+            .await
+        {
+            tracing::warn!(
+                event = "subscription.restore_notice_failed",
+                device_key = %mask_device_key(&destination_id.device_key),
+                error = ?error,
+                "subscription.restore_notice_failed"
+            );
+        }
+    });
+    Ok(true)
+}
+
 #[derive(Serialize)]
 pub(crate) struct SubscribeResponse {
     pub(crate) saved: bool,
 }
 
-fn normalize_targets(mut targets: Vec<MonitoringTarget>) -> Result<Vec<MonitoringTarget>, String> {
+pub(super) fn normalize_targets(
+    mut targets: Vec<MonitoringTarget>,
+) -> Result<Vec<MonitoringTarget>, String> {
     if targets.is_empty() {
         return Err("请至少添加一个有效监测地点".to_string());
     }
     if targets.len() > MAX_LOCATIONS {
         return Err(format!("监测地点最多 {MAX_LOCATIONS} 个"));
     }
-    if targets.iter().any(|target| {
-        !distance::validate_coordinates(target.point.latitude, target.point.longitude)
-    }) {
-        return Err("监测地点坐标无效".to_string());
+    for target in &mut targets {
+        if let Some(point) = &mut target.point {
+            let (latitude, longitude) =
+                distance::normalize_coordinates(point.latitude, point.longitude)
+                    .map_err(|error| error.message().to_string())?;
+            point.latitude = latitude;
+            point.longitude = longitude;
+        }
     }
     for target in &mut targets {
         for (label, value) in [
@@ -462,10 +739,49 @@ fn normalize_targets(mut targets: Vec<MonitoringTarget>) -> Result<Vec<Monitorin
             }
             *value = trimmed.to_string();
         }
+        if target.point.is_none() {
+            let Some(boundary) = boundaries::find(&target.region.province) else {
+                return Err(UNKNOWN_REGION_MESSAGE.to_string());
+            };
+            target.region.province = boundary.name.to_string();
+        }
     }
     Ok(targets)
 }
 
+fn normalize_device_label(label: Option<String>) -> Result<Option<String>, String> {
+    let Some(label) = label else {
+        return Ok(None);
+    };
+    let trimmed = label.trim();
+    if trimmed.chars().count() > MAX_DEVICE_LABEL_CHARS {
+        return Err(format!("设备备注最多 {MAX_DEVICE_LABEL_CHARS} 个字符"));
+    }
+    Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+}
+
+/// 校验并规范化请求中的推送目的地，供取消订阅和数据导出共用。
+pub(super) fn parse_destination(
+    state: &AppState,
+    destination: &NotificationDestination,
+) -> std::result::Result<DestinationId, (StatusCode, String)> {
+    let device_key = validate_device_key(destination.bark_device_key())?;
+    let base_url = match normalize_bark_url(destination.bark_base_url()) {
+        Ok(value) if state.bark_notifier.allows_bark_url(&value) => value,
+        Ok(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Bark URL 不在允许列表中".to_string(),
+            ));
+        }
+        Err(_error) => return Err((StatusCode::BAD_REQUEST, "Bark URL 无效".to_string())),
+    };
+    Ok(DestinationId {
+        base_url,
+        device_key,
+    })
+}
+
 fn validate_device_key(raw: &str) -> std::result::Result<String, (StatusCode, String)> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -523,10 +839,105 @@ pub(crate) async fn bark_urls_handler(State(state): State<AppState>) -> impl Int
     ))
 }
 
-pub(crate) async fn health_handler() -> impl IntoResponse {
+pub(crate) async fn liveness_handler() -> impl IntoResponse {
     (StatusCode::OK, Json(ApiResponse::<()>::success("OK", None)))
 }
 
+#[derive(Serialize)]
+struct HealthResponse {
+    storage: bool,
+    notifier: NotifierHealth,
+    bark_delivery_permits_available: usize,
+}
+
+/// 依赖健康检查：写入并读回数据库探测键，并报告 Bark 推送是否持续失败。
+/// 任一依赖异常时返回 503 与逐项结果，便于监控区分存储故障和推送降级。
+pub(crate) async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let storage = check_storage(&state, "health", Storage::ping).await;
+    let health = HealthResponse {
+        storage,
+        notifier: state.runtime_status.notifier_health(),
+        bark_delivery_permits_available: state.bark_notifier.available_delivery_permits(),
+    };
+    if health.storage && !health.notifier.degraded {
+        (
+            StatusCode::OK,
+            Json(ApiResponse::success("OK", Some(health))),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse {
+                success: false,
+                message: if health.storage {
+                    "Bark 推送连续失败".to_string()
+                } else {
+                    "数据库读写检查失败".to_string()
+                },
+                data: Some(health),
+            }),
+        )
+    }
+}
+
+/// 在阻塞线程中执行存储检查。状态查询并发已满说明进程仍在正常服务，此时不判定为失败。
+async fn check_storage(
+    state: &AppState,
+    probe: &'static str,
+    check: fn(&Storage) -> anyhow::Result<()>,
+) -> bool {
+    let Ok(permit) = state.status_concurrency.clone().try_acquire_owned() else {
+        return true;
+    };
+    let storage = state.storage.clone();
+    match tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        check(&storage)
+    })
+    .await
+    {
+        Ok(Ok(())) => true,
+        Ok(Err(error)) => {
+            tracing::error!(event = "probe.storage_failed", probe, error = ?error, "probe.storage_failed");
+            false
+        }
+        Err(error) => {
+            tracing::error!(event = "probe.task_failed", probe, error = ?error, "probe.task_failed");
+            false
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReadinessResponse {
+    storage: bool,
+    #[serde(flatten)]
+    runtime: RuntimeReadiness,
+}
+
+/// 就绪探针：存储可读、启动恢复完成，且至少一个数据源已连接（启动宽限期内不要求连接）。
+/// 存活探针见 [`liveness_handler`]，只表示进程能响应请求。
+pub(crate) async fn readiness_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let runtime = state.runtime_status.readiness(state.readiness_grace);
+    let storage = check_storage(&state, "readiness", Storage::probe).await;
+    let readiness = ReadinessResponse { storage, runtime };
+    if storage && runtime.is_ready() {
+        (
+            StatusCode::OK,
+            Json(ApiResponse::success("服务已就绪", Some(readiness))),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse {
+                success: false,
+                message: "服务尚未就绪".to_string(),
+                data: Some(readiness),
+            }),
+        )
+    }
+}
+
 pub(crate) async fn status_handler(State(state): State<AppState>) -> impl IntoResponse {
     let Ok(permit) = state.status_concurrency.clone().try_acquire_owned() else {
         return (
@@ -584,6 +995,37 @@ pub(crate) async fn status_handler(State(state): State<AppState>) -> impl IntoRe
     }
 }
 
+/// Prometheus 抓取端点；只导出内存计数，不占用存储并发配额。
+pub(crate) async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let mut output = String::new();
+    state.runtime_status.render_prometheus(&mut output);
+    write_labeled_family(
+        &mut output,
+        "disaster_alert_semaphore_available_permits",
+        "gauge",
+        "各并发限制器剩余的许可数，归零表示已饱和",
+        "pool",
+        [
+            (
+                "bark_delivery",
+                state.bark_notifier.available_delivery_permits(),
+            ),
+            ("detail", state.detail_concurrency.available_permits()),
+            ("status", state.status_concurrency.available_permits()),
+            ("storage", state.storage_concurrency.available_permits()),
+            (
+                "subscription",
+                state.subscription_concurrency.available_permits(),
+            ),
+        ]
+        .map(|(pool, permits)| (pool, u64::try_from(permits).unwrap_or(u64::MAX))),
+    );
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        output,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -596,15 +1038,17 @@ mod tests {
             },
             targets: vec![MonitoringTarget {
                 label: "home".to_string(),
-                point: crate::models::GeoPoint {
+                point: Some(crate::models::GeoPoint {
                     latitude: 35.0,
                     longitude: 105.0,
-                },
+                }),
                 region: crate::models::AdministrativeRegion::default(),
             }],
             alerts: vec![crate::models::AlertRule::default_for(
                 crate::models::DisasterCategory::WeatherWarning,
             )],
+            label: None,
+            challenge: None,
         }
     }
 
@@ -615,6 +1059,49 @@ mod tests {
         assert!(subscription.validate().is_ok());
     }
 
+    #[test]
+    fn target_points_are_normalized_before_indexing() -> anyhow::Result<()> {
+        let mut targets = request().targets;
+        if let Some(point) = &mut targets[0].point {
+            point.longitude = 465.0;
+        }
+        let normalized = normalize_targets(targets).map_err(anyhow::Error::msg)?;
+        anyhow::ensure!(
+            normalized[0]
+                .point
+                .is_some_and(|point| point.longitude == 105.0)
+        );
+
+        let mut swapped = request().targets;
+        swapped[0].point = Some(crate::models::GeoPoint {
+            latitude: 105.0,
+            longitude: 35.0,
+        });
+        anyhow::ensure!(
+            normalize_targets(swapped).err().as_deref()
+                == Some(distance::CoordinateError::Swapped.message())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn device_label_is_trimmed_and_blank_labels_are_dropped() -> anyhow::Result<()> {
+        anyhow::ensure!(
+            normalize_device_label(Some("  妈妈的手机 ".to_string()))
+                == Ok(Some("妈妈的手机".to_string()))
+        );
+        anyhow::ensure!(normalize_device_label(Some("   ".to_string())) == Ok(None));
+        anyhow::ensure!(normalize_device_label(None) == Ok(None));
+        anyhow::ensure!(
+            normalize_device_label(Some("机".repeat(MAX_DEVICE_LABEL_CHARS + 1))).is_err()
+        );
+        let payload = request();
+        let subscription = Subscription::new(payload.destination, payload.targets, payload.alerts)
+            .with_label(Some("Office\niPad".to_string()));
+        anyhow::ensure!(subscription.validate().is_err());
+        Ok(())
+    }
+
     #[test]
     fn administrative_fields_obey_location_length_limit() {
         let mut payload = request();
@@ -623,17 +1110,36 @@ mod tests {
     }
 
     #[test]
-    fn reverse_geocode_query_rejection_uses_the_api_envelope() {
+    fn region_only_targets_require_a_built_in_region() -> anyhow::Result<()> {
+        let mut payload = request();
+        payload.targets[0].point = None;
+        payload.targets[0].region.province = " 东京 ".to_string();
+        let targets = normalize_targets(payload.targets.clone())
+            .map_err(|message| anyhow::anyhow!(message))?;
+        anyhow::ensure!(targets[0].region.province == "東京都");
+        anyhow::ensure!(
+            Subscription::new(payload.destination, targets, payload.alerts)
+                .validate()
+                .is_ok()
+        );
+
+        payload.targets[0].region.province = "成都市".to_string();
+        anyhow::ensure!(normalize_targets(payload.targets).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_geocode_query_rejection_uses_the_api_envelope() -> anyhow::Result<()> {
         let uri = axum::http::Uri::from_static("/api/reverse-geocode?latitude=31.2");
         let query = Query::<ReverseGeocodeQuery>::try_from_uri(&uri);
-        let response = match parse_reverse_geocode_query(query) {
-            Ok(_) => panic!("missing longitude should be rejected"),
-            Err(response) => response,
+        let Err(response) = parse_reverse_geocode_query(query) else {
+            anyhow::bail!("missing longitude should be rejected");
         };
 
-        assert!(!response.success);
-        assert_eq!(response.message, "坐标参数无效");
-        assert!(response.data.is_none());
+        anyhow::ensure!(!response.success);
+        anyhow::ensure!(response.message == "坐标参数无效");
+        anyhow::ensure!(response.data.is_none());
+        Ok(())
     }
 
     #[test]
@@ -661,19 +1167,38 @@ mod tests {
     }
 
     #[test]
-    fn status_response_flattens_subscription_count_and_runtime_metrics() {
+    fn quota_rejection_carries_retry_after() {
+        let response = quota_exceeded_response(QuotaExceeded {
+            limit: 5,
+            retry_after_seconds: 42,
+        });
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers().get(RETRY_AFTER),
+            Some(&HeaderValue::from_static("42"))
+        );
+    }
+
+    #[test]
+    fn status_response_flattens_subscription_count_and_runtime_metrics() -> anyhow::Result<()> {
         let response = StatusResponse {
             total_subscriptions: 12,
             runtime: RuntimeStatus::default().snapshot(DurableBacklogSnapshot::default()),
         };
-        let value = serde_json::to_value(response).expect("status response should serialize");
-
-        assert_eq!(value["total_subscriptions"], 12);
-        assert!(value.get("wolfx").is_some());
-        assert!(value.get("fanstudio").is_some());
-        assert!(value.get("huania").is_some());
-        assert!(value.get("durable").is_some());
-        assert!(value.get("ready_queues").is_some());
-        assert!(value.get("runtime").is_none());
+        let value = serde_json::to_value(response)?;
+
+        anyhow::ensure!(value["total_subscriptions"] == 12);
+        for field in [
+            "wolfx",
+            "fanstudio",
+            "huania",
+            "p2pquake",
+            "durable",
+            "ready_queues",
+        ] {
+            anyhow::ensure!(value.get(field).is_some(), "missing {field}");
+        }
+        anyhow::ensure!(value.get("runtime").is_none());
+        Ok(())
     }
 }
@@ -1,17 +1,20 @@
 use crate::delivery::NotificationVerifyError;
-use crate::models::IncidentId;
+use crate::models::{ApiResponse, IncidentId};
 use crate::routes::AppState;
 use crate::routes::detail_page::{
     detail_error, detail_not_found, detail_response, detail_unavailable, render_incident_page,
 };
 use axum::{
+    Json,
     extract::{Path, State},
-    http::{HeaderValue, header},
-    response::{Html, IntoResponse, Response},
+    http::{HeaderValue, Method, StatusCode, Uri, header},
+    response::{Html, IntoResponse, Redirect, Response},
 };
 use std::sync::OnceLock;
 
 const INDEX_HTML: &str = include_str!(concat!(env!("OUT_DIR"), "/index.min.html"));
+/// `web/` 下除首页外的静态文件，由构建脚本按请求路径排序生成。
+const WEB_ASSETS: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/web_assets.rs"));
 const INSTANCE_NOTICE_MARKER: &str = "__DISASTER_ALERT_INSTANCE_NOTICE__";
 const INSTANCE_TERMS_NOTICE: &str = r#"
 <dialog id="instance-terms-dialog" class="instance-terms-dialog" aria-labelledby="instance-terms-title" aria-describedby="instance-terms-summary" open>
@@ -56,6 +59,69 @@ fn index_response(instance_terms_accepted: bool) -> Response {
     response
 }
 
+/// 未匹配任何路由的请求。`web/` 中的静态文件直接返回；`/api/` 下返回 JSON 格式的 404；
+/// 其余不带扩展名的 GET 路径视为前端路由，返回首页由前端处理。
+pub(crate) async fn web_fallback_handler(
+    State(state): State<AppState>,
+    method: Method,
+    uri: Uri,
+) -> Response {
+    fallback_response(&method, uri.path(), state.instance_terms_accepted)
+}
+
+fn fallback_response(method: &Method, path: &str, instance_terms_accepted: bool) -> Response {
+    if path == "/api" || path.starts_with("/api/") {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("接口不存在")),
+        )
+            .into_response();
+    }
+    if method != Method::GET && method != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    if let Ok(index) = WEB_ASSETS.binary_search_by(|(asset_path, _body)| (*asset_path).cmp(path)) {
+        let (asset_path, body) = WEB_ASSETS[index];
+        return (
+            [
+                (header::CONTENT_TYPE, content_type(asset_path)),
+                (header::CACHE_CONTROL, "public, max-age=3600"),
+            ],
+            body,
+        )
+            .into_response();
+    }
+    if path
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'))
+    {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    index_response(instance_terms_accepted)
+}
+
+fn content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_stem, extension)| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("webmanifest") => "application/manifest+json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
 fn render_index_html(instance_terms_accepted: bool) -> &'static str {
     let (rendered, notice) = if instance_terms_accepted {
         (&ACCEPTED_INDEX_HTML, "")
@@ -141,10 +207,88 @@ pub(crate) async fn incident_detail_handler(
     detail_response(render_incident_page(&snapshot, incident.as_deref()))
 }
 
+/// 推送中带打开回执的链接：校验回执后记录打开，再跳转到详情页。
+/// 记录失败或服务繁忙时照常跳转，打开统计不影响用户查看详情。
+pub(crate) async fn notification_open_handler(
+    State(state): State<AppState>,
+    Path((incident_id, token, receipt)): Path<(String, String, String)>,
+) -> Response {
+    let Some(incident_id) = IncidentId::parse(&incident_id) else {
+        return detail_not_found();
+    };
+    // 用相对地址跳转，`ALERT_DETAIL_BASE_URL` 带路径前缀时同样适用。
+    let detail = Redirect::to(&format!("../../{token}"));
+    let Ok(database_permit) = state.storage_concurrency.clone().try_acquire_owned() else {
+        tracing::warn!(
+            event = "notification.open_overloaded",
+            incident_id = %incident_id.as_str(),
+            "notification.open_overloaded"
+        );
+        return detail.into_response();
+    };
+    let links = state.notification_links.clone();
+    let storage = state.storage.clone();
+    let open_incident_id = incident_id.clone();
+    let recorded = tokio::task::spawn_blocking(move || {
+        let _database_permit = database_permit;
+        let (snapshot, destination_id) = links
+            .verify_open(&open_incident_id, &token, &receipt)
+            .map_err(|error| match error {
+                NotificationVerifyError::Invalid(error) => DetailLoadError::InvalidLink(error),
+                NotificationVerifyError::Storage(error) => DetailLoadError::Storage(error),
+            })?;
+        storage
+            .record_notification_open(
+                destination_id,
+                &open_incident_id,
+                &snapshot.event.source,
+                &snapshot.event.source_event_id,
+            )
+            .map_err(DetailLoadError::Storage)
+    })
+    .await;
+    match recorded {
+        Ok(Ok(first)) => {
+            if first {
+                state.runtime_status.record_notification_opened();
+            }
+        }
+        Ok(Err(DetailLoadError::InvalidLink(error))) => {
+            tracing::warn!(
+                event = "notification.invalid_open_link",
+                incident_id = %incident_id.as_str(),
+                error = %error,
+                "notification.invalid_open_link"
+            );
+            return detail_not_found();
+        }
+        Ok(Err(DetailLoadError::Storage(error))) => {
+            tracing::error!(
+                event = "notification.open_record_failed",
+                incident_id = %incident_id.as_str(),
+                error = ?error,
+                "notification.open_record_failed"
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                event = "notification.open_task_failed",
+                incident_id = %incident_id.as_str(),
+                error = ?error,
+                "notification.open_task_failed"
+            );
+        }
+    }
+    detail.into_response()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{INSTANCE_NOTICE_MARKER, index_response, render_index_html};
-    use axum::http::header;
+    use super::{
+        INSTANCE_NOTICE_MARKER, WEB_ASSETS, content_type, fallback_response, index_response,
+        render_index_html,
+    };
+    use axum::http::{Method, StatusCode, header};
 
     #[test]
     fn accepted_instance_has_no_terms_dialog() {
@@ -163,6 +307,25 @@ mod tests {
         assert!(html.contains("新增和覆盖订阅已在服务端禁用"));
     }
 
+    #[test]
+    fn fallback_serves_the_frontend_for_client_routes_only() {
+        let status = |method: &Method, path: &str| fallback_response(method, path, true).status();
+        assert_eq!(status(&Method::GET, "/history/2026"), StatusCode::OK);
+        assert_eq!(status(&Method::HEAD, "/settings"), StatusCode::OK);
+        assert_eq!(status(&Method::GET, "/api/unknown"), StatusCode::NOT_FOUND);
+        assert_eq!(status(&Method::GET, "/missing.js"), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(&Method::POST, "/settings"),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert!(WEB_ASSETS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            content_type("/assets/app.JS"),
+            "text/javascript; charset=utf-8"
+        );
+        assert_eq!(content_type("/LICENSE"), "application/octet-stream");
+    }
+
     #[test]
     fn index_response_is_not_cached() {
         let response = index_response(false);
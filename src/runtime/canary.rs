@@ -0,0 +1,255 @@
+use super::{EventRuntime, RuntimeStatus};
+use crate::models::{
+    AdministrativeRegion, AlertRule, DisasterCategory, DisasterEvent, GeoPoint, IncidentId,
+    MonitoringTarget, NotificationDestination, ProviderChannel, SourceSelection, Subscription,
+    epoch_millis_to_rfc3339,
+};
+use crate::storage::Storage;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// 等待送达记录时的轮询间隔。
+const CANARY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 链路自检：定期向专用订阅注入一条合成气象预警，确认事件写入、匹配和 Bark 推送全程可用。
+///
+/// 自检订阅只监测一个由设备 Key 派生的行政区名称，自检事件只列出该行政区且不带坐标，
+/// 因此只会命中自检订阅，不会推送给其他订阅者。
+#[derive(Clone)]
+pub(crate) struct CanaryMonitor {
+    storage: Storage,
+    event_runtime: EventRuntime,
+    runtime_status: RuntimeStatus,
+    subscription: Subscription,
+    region: String,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl CanaryMonitor {
+    pub(crate) fn new(
+        storage: Storage,
+        event_runtime: EventRuntime,
+        runtime_status: RuntimeStatus,
+        bark_url: String,
+        device_key: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Self {
+        let region = canary_region(&bark_url, device_key);
+        let subscription = Subscription::new(
+            NotificationDestination::Bark {
+                base_url: bark_url,
+                device_key: device_key.to_string(),
+            },
+            vec![MonitoringTarget {
+                label: "链路自检".to_string(),
+                // 远离陆地的公海坐标，配合 1 公里回退半径，避免真实气象预警按距离命中。
+                point: Some(GeoPoint {
+                    latitude: 0.0,
+                    longitude: -160.0,
+                }),
+                region: AdministrativeRegion {
+                    district: region.clone(),
+                    ..AdministrativeRegion::default()
+                },
+            }],
+            vec![AlertRule::WeatherWarning {
+                sources: SourceSelection::All,
+                min_severity: 1,
+                fallback_radius_km: 1.0,
+            }],
+        );
+        Self {
+            storage,
+            event_runtime,
+            runtime_status,
+            subscription,
+            region,
+            interval,
+            timeout,
+        }
+    }
+
+    /// 启动后立即自检一次，之后按间隔重复；失败只记录日志和指标，不影响服务运行。
+    pub(crate) fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match self.check().await {
+                    Ok(elapsed) => {
+                        self.runtime_status.record_canary(true);
+                        tracing::info!(
+                            event = "canary.delivered",
+                            elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+                            "canary.delivered"
+                        );
+                    }
+                    Err(error) => {
+                        self.runtime_status.record_canary(false);
+                        tracing::error!(event = "canary.failed", error = ?error, "canary.failed");
+                    }
+                }
+            }
+        })
+    }
+
+    /// 注入一次自检事件，返回从提交到送达记录出现的耗时。
+    pub(crate) async fn check(&self) -> Result<Duration> {
+        let storage = self.storage.clone();
+        let subscription = self.subscription.clone();
+        let destination_id = tokio::task::spawn_blocking(move || {
+            let destination = subscription.destination_id();
+            storage
+                .subscription_manager()
+                .upsert_subscription(subscription)?;
+            storage
+                .inner()
+                .stored_subscription_by_destination(&destination)?
+                .map(|record| record.destination_id)
+                .context("canary subscription was not stored")
+        })
+        .await
+        .context("canary subscription task failed")??;
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| {
+                i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+            });
+        let event = canary_event(&self.region, now_ms)?;
+        let incident_id = IncidentId::derive(&event.event_key());
+        let started = Instant::now();
+        anyhow::ensure!(
+            self.event_runtime.submit_canary(event).await,
+            "event pipeline rejected the canary event"
+        );
+        loop {
+            let storage = self.storage.clone();
+            let incident = incident_id.clone();
+            let delivered = tokio::task::spawn_blocking(move || {
+                storage
+                    .inner()
+                    .delivered_rows(&incident, DisasterCategory::WeatherWarning)
+            })
+            .await
+            .context("canary delivery lookup task failed")??
+            .iter()
+            .any(|row| row.destination_id == destination_id);
+            if delivered {
+                return Ok(started.elapsed());
+            }
+            anyhow::ensure!(
+                started.elapsed() < self.timeout,
+                "canary was not delivered within {} seconds",
+                self.timeout.as_secs()
+            );
+            tokio::time::sleep(CANARY_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// 由自检订阅的 Bark 地址和设备 Key 派生的行政区名称，普通订阅者无法猜到。
+fn canary_region(bark_url: &str, device_key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"disaster-alert:canary-region:v1\0");
+    hasher.update(bark_url.as_bytes());
+    hasher.update([0]);
+    hasher.update(device_key.as_bytes());
+    let digest = hasher.finalize();
+    let suffix = digest[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("canary-{suffix}")
+}
+
+fn canary_event(region: &str, now_ms: i64) -> Result<DisasterEvent> {
+    Ok(DisasterEvent {
+        category: DisasterCategory::WeatherWarning,
+        channel: ProviderChannel::FanStudio,
+        source: "fanstudio.weatheralarm".to_string(),
+        event_id: format!("canary-{now_ms}"),
+        revision: "1".to_string(),
+        report_num: 1,
+        title: "链路自检".to_string(),
+        description: "灾害预警服务定时自检，收到此消息说明推送链路正常，无需处理。".to_string(),
+        latitude: None,
+        longitude: None,
+        magnitude: None,
+        depth_km: None,
+        affected_regions: vec![region.to_string()],
+        radius_km: None,
+        level: 1,
+        occurred_at: epoch_millis_to_rfc3339(now_ms)?,
+        final_report: false,
+        cancel: false,
+        training: false,
+        max_intensity: None,
+        official_warning: None,
+        assumed_hypocenter: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::{BarkNotifier, BarkPushConfig, NotificationLinkService};
+    use crate::matching::{MatchEngine, MatchPlan};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn canary_event_only_matches_the_canary_subscription() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = Storage::open(directory.path())?;
+        let notifier = BarkNotifier::new(
+            vec!["https://api.day.app".to_string()],
+            1,
+            1,
+            BarkPushConfig::new(None, 10, "test".to_string(), false),
+        )?;
+        let links = NotificationLinkService::for_test(&storage);
+        let event_runtime = EventRuntime::for_test(storage.clone(), notifier, links)?;
+        let canary = CanaryMonitor::new(
+            storage.clone(),
+            event_runtime,
+            RuntimeStatus::default(),
+            "https://api.day.app".to_string(),
+            "canarydevice",
+            Duration::from_secs(3_600),
+            Duration::from_secs(60),
+        );
+        let mut neighbour = canary.subscription.clone();
+        let NotificationDestination::Bark { device_key, .. } = &mut neighbour.destination;
+        *device_key = "neighbourdevice".to_string();
+        neighbour.targets[0].region = AdministrativeRegion {
+            province: "四川省".to_string(),
+            ..AdministrativeRegion::default()
+        };
+        let subscriptions = storage.subscription_manager();
+        subscriptions.upsert_subscription(canary.subscription.clone())?;
+        subscriptions.upsert_subscription(neighbour)?;
+
+        let event = canary_event(&canary.region, 1_735_689_600_000)?;
+        let plan = MatchPlan::for_event(&event)?;
+        let blocks = storage.inner().posting_blocks(&plan)?;
+        let compiled = storage.inner().load_compiled_blocks(&blocks)?;
+        let rows = MatchEngine::new(1)?.match_blocks(Arc::new(event), blocks, &compiled);
+        let canary_destination = storage
+            .inner()
+            .stored_subscription_by_destination(&canary.subscription.destination_id())?
+            .context("missing canary subscription")?
+            .destination_id;
+
+        anyhow::ensure!(rows.len() == 1);
+        anyhow::ensure!(
+            rows.iter()
+                .all(|row| row.destination_id == canary_destination)
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,14 @@
+mod canary;
+mod operator;
 mod pipeline;
 mod ready_queue;
 mod status;
 
-pub(crate) use pipeline::EventRuntime;
-pub(crate) use status::DurableBacklogSnapshot;
-pub(crate) use status::{RuntimeStatus, RuntimeStatusSnapshot};
+pub(crate) use canary::CanaryMonitor;
+pub(crate) use operator::{OperatorAlerts, OperatorThresholds};
+pub(crate) use pipeline::{EventRuntime, estimate_alert_timing, sanitize_event};
+pub(crate) use status::{DurableBacklogSnapshot, write_labeled_family};
+pub(crate) use status::{
+    NotifierHealth, ParseDiagnosticsSnapshot, RuntimeReadiness, RuntimeStatus,
+    RuntimeStatusSnapshot,
+};
@@ -0,0 +1,284 @@
+use super::RuntimeStatus;
+use crate::delivery::BarkNotifier;
+use crate::models::ProviderChannel;
+use crate::storage::Storage;
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// 检查运行状况的间隔。
+const OPERATOR_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// 一个检查周期内失败推送少于该数时不视为失败率突增，避免零星失败触发告警。
+const MIN_FAILED_NOTIFICATIONS: u64 = 10;
+
+/// 触发运维告警的阈值。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OperatorThresholds {
+    /// 上游连接断开超过该时长时告警。
+    pub(crate) disconnect: Duration,
+    /// 一个检查周期内推送失败占比达到该比例时告警。
+    pub(crate) failure_ratio: f64,
+    /// 待重试推送积压达到该数时告警。
+    pub(crate) retry_backlog: usize,
+    /// 一个检查周期内新增死信达到该数时告警。
+    pub(crate) dead_letter_growth: usize,
+}
+
+/// 需要运维人员介入的异常状况。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Condition {
+    UpstreamDisconnected(ProviderChannel),
+    DeliveryFailures,
+    RetryBacklog,
+    DeadLetterGrowth,
+    NotifierDegraded,
+}
+
+impl Condition {
+    fn describe(self) -> String {
+        match self {
+            Self::UpstreamDisconnected(channel) => {
+                format!("上游数据源 {} 连接中断", channel.as_str())
+            }
+            Self::DeliveryFailures => "推送失败率突增".to_string(),
+            Self::RetryBacklog => "待重试推送积压".to_string(),
+            Self::DeadLetterGrowth => "死信持续增加".to_string(),
+            Self::NotifierDegraded => "Bark 服务连续请求失败".to_string(),
+        }
+    }
+}
+
+/// 一个检查周期观察到的运行状况。
+#[derive(Debug, Clone, Default)]
+struct Observation {
+    /// 正处于断线中的上游及其断线时长。
+    disconnected: Vec<(ProviderChannel, Duration)>,
+    /// 本周期内成功和失败的推送数。
+    succeeded: u64,
+    failed: u64,
+    retries_pending: usize,
+    /// 本周期内新增的死信数。
+    dead_letters_added: usize,
+    notifier_degraded: bool,
+}
+
+fn evaluate(observation: &Observation, thresholds: &OperatorThresholds) -> BTreeSet<Condition> {
+    let mut conditions = BTreeSet::new();
+    for (channel, duration) in &observation.disconnected {
+        if *duration >= thresholds.disconnect {
+            conditions.insert(Condition::UpstreamDisconnected(*channel));
+        }
+    }
+    let attempted = observation.succeeded.saturating_add(observation.failed);
+    if observation.failed >= MIN_FAILED_NOTIFICATIONS
+        && observation.failed as f64 >= attempted as f64 * thresholds.failure_ratio
+    {
+        conditions.insert(Condition::DeliveryFailures);
+    }
+    if observation.retries_pending >= thresholds.retry_backlog {
+        conditions.insert(Condition::RetryBacklog);
+    }
+    if observation.dead_letters_added >= thresholds.dead_letter_growth {
+        conditions.insert(Condition::DeadLetterGrowth);
+    }
+    if observation.notifier_degraded {
+        conditions.insert(Condition::NotifierDegraded);
+    }
+    conditions
+}
+
+/// 运维告警：定期检查上游连接、推送失败率、重试积压和 Bark 可达性，状况出现和解除时
+/// 各向运维人员的 Bark 设备推送一次，持续期间不重复推送。
+#[derive(Clone)]
+pub(crate) struct OperatorAlerts {
+    storage: Storage,
+    runtime_status: RuntimeStatus,
+    bark_notifier: BarkNotifier,
+    bark_url: String,
+    device_key: String,
+    thresholds: OperatorThresholds,
+}
+
+impl OperatorAlerts {
+    pub(crate) fn new(
+        storage: Storage,
+        runtime_status: RuntimeStatus,
+        bark_notifier: BarkNotifier,
+        bark_url: String,
+        device_key: &str,
+        thresholds: OperatorThresholds,
+    ) -> Self {
+        Self {
+            storage,
+            runtime_status,
+            bark_notifier,
+            bark_url,
+            device_key: device_key.to_string(),
+            thresholds,
+        }
+    }
+
+    pub(crate) fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(OPERATOR_CHECK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut active = BTreeSet::new();
+            let mut totals = self.runtime_status.notification_totals();
+            let mut dead_letters = None;
+            loop {
+                ticker.tick().await;
+                let observation = match self.observe(&mut totals, &mut dead_letters).await {
+                    Ok(observation) => observation,
+                    Err(error) => {
+                        tracing::error!(
+                            event = "operator.check_failed",
+                            error = ?error,
+                            "operator.check_failed"
+                        );
+                        continue;
+                    }
+                };
+                let current = evaluate(&observation, &self.thresholds);
+                for condition in current.difference(&active) {
+                    tracing::warn!(
+                        event = "operator.condition_raised",
+                        condition = ?condition,
+                        "operator.condition_raised"
+                    );
+                    self.notify(&format!("运维告警：{}", condition.describe()), &observation)
+                        .await;
+                }
+                for condition in active.difference(&current) {
+                    tracing::info!(
+                        event = "operator.condition_cleared",
+                        condition = ?condition,
+                        "operator.condition_cleared"
+                    );
+                    self.notify(&format!("已恢复：{}", condition.describe()), &observation)
+                        .await;
+                }
+                active = current;
+            }
+        })
+    }
+
+    /// `dead_letters` 记录上一周期的死信总数；首次检查只建立基线，不计新增。
+    async fn observe(
+        &self,
+        totals: &mut (u64, u64),
+        dead_letters: &mut Option<usize>,
+    ) -> Result<Observation> {
+        let storage = self.storage.clone();
+        let backlog = tokio::task::spawn_blocking(move || storage.backlog_counts())
+            .await
+            .context("operator backlog task failed")??;
+        let (succeeded, failed) = self.runtime_status.notification_totals();
+        let observation = Observation {
+            disconnected: [
+                ProviderChannel::Wolfx,
+                ProviderChannel::FanStudio,
+                ProviderChannel::Huania,
+                ProviderChannel::P2pquake,
+            ]
+            .into_iter()
+            .filter_map(|channel| {
+                self.runtime_status
+                    .channel(channel)
+                    .current_disconnect()
+                    .map(|duration| (channel, duration))
+            })
+            .collect(),
+            succeeded: succeeded.saturating_sub(totals.0),
+            failed: failed.saturating_sub(totals.1),
+            retries_pending: backlog.retries,
+            dead_letters_added: dead_letters
+                .map_or(0, |previous| backlog.dead_letters.saturating_sub(previous)),
+            notifier_degraded: self.runtime_status.notifier_health().degraded,
+        };
+        *totals = (succeeded, failed);
+        *dead_letters = Some(backlog.dead_letters);
+        Ok(observation)
+    }
+
+    async fn notify(&self, title: &str, observation: &Observation) {
+        let body = format!(
+            "近 {} 秒推送成功 {} 条、失败 {} 条，新增死信 {} 条；待重试 {} 条",
+            OPERATOR_CHECK_INTERVAL.as_secs(),
+            observation.succeeded,
+            observation.failed,
+            observation.dead_letters_added,
+            observation.retries_pending
+        );
+        if let Err(error) = self
+            .bark_notifier
+            .send_operator_alert(&self.bark_url, &self.device_key, title, &body)
+            .await
+        {
+            tracing::error!(
+                event = "operator.notify_failed",
+                error = ?error,
+                "operator.notify_failed"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thresholds() -> OperatorThresholds {
+        OperatorThresholds {
+            disconnect: Duration::from_secs(300),
+            failure_ratio: 0.5,
+            retry_backlog: 100,
+            dead_letter_growth: 20,
+        }
+    }
+
+    #[test]
+    fn raises_each_condition_only_past_its_threshold() {
+        assert!(evaluate(&Observation::default(), &thresholds()).is_empty());
+
+        let observation = Observation {
+            disconnected: vec![
+                (ProviderChannel::Wolfx, Duration::from_secs(301)),
+                (ProviderChannel::FanStudio, Duration::from_secs(30)),
+            ],
+            succeeded: 10,
+            failed: 12,
+            retries_pending: 100,
+            dead_letters_added: 20,
+            notifier_degraded: true,
+        };
+        assert_eq!(
+            evaluate(&observation, &thresholds()),
+            BTreeSet::from([
+                Condition::UpstreamDisconnected(ProviderChannel::Wolfx),
+                Condition::DeliveryFailures,
+                Condition::RetryBacklog,
+                Condition::DeadLetterGrowth,
+                Condition::NotifierDegraded,
+            ])
+        );
+
+        let sporadic = Observation {
+            succeeded: 0,
+            failed: MIN_FAILED_NOTIFICATIONS - 1,
+            ..Observation::default()
+        };
+        assert!(evaluate(&sporadic, &thresholds()).is_empty());
+        let mostly_delivered = Observation {
+            succeeded: 1_000,
+            failed: 20,
+            ..Observation::default()
+        };
+        assert!(evaluate(&mostly_delivered, &thresholds()).is_empty());
+        let slow_dead_letters = Observation {
+            dead_letters_added: 19,
+            ..Observation::default()
+        };
+        assert!(evaluate(&slow_dead_letters, &thresholds()).is_empty());
+    }
+}
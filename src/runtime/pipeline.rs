@@ -1,29 +1,30 @@
-use crate::config::Config;
+use crate::config::{FeatureFlags, LiveSettings, TunableSettings};
 use crate::delivery::{
     AlertRecipient, AlertTiming, BarkDeliveryError, BarkNotifier, CountdownRecipient,
-    DeadLetterItem, DeliverySuccess, NotificationContextInput, NotificationLinkService,
-    remaining_seconds,
+    DeadLetterItem, DeliverySuccess, MessageTemplates, NotificationContextInput,
+    NotificationLinkService, PreparedAlert, isoseismal_radii, remaining_seconds,
 };
 use crate::delivery::{DeliveryBatch, DeliveryRow, RetryItem};
-use crate::events::{EventCoordinator, EventPolicy};
+use crate::events::{DispatchMode, EventCoordinator};
 use crate::matching::{MatchEngine, MatchPlan};
 use crate::models::{
-    DisasterCategory, DisasterEvent, IncidentId, InterruptionLevel, ProviderChannel,
-    parse_event_epoch,
+    AlertRule, DisasterCategory, DisasterEvent, IncidentId, InterruptionLevel, ProviderChannel,
+    parse_event_epoch_ms,
 };
 use crate::providers::ProviderCursor;
 use crate::runtime::RuntimeStatus;
 use crate::runtime::ready_queue::ReadyQueue;
-use crate::storage::Storage;
 use crate::storage::{FjallStorage, try_now_millis};
+use crate::storage::{PruneStats, RetentionPolicy, Storage};
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::sync::{
     Arc, Mutex, Weak,
     atomic::{AtomicBool, AtomicU64, Ordering},
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex as AsyncMutex, mpsc, watch};
+use tracing::Instrument;
 
 const SCAN_INTERVAL: Duration = Duration::from_millis(25);
 const READY_QUEUE_CAPACITY: usize = 4_096;
@@ -36,6 +37,7 @@ const DELIVERY_SHARDS: u64 = 64;
 const MAX_RETRY_ATTEMPTS: u16 = 12;
 const MAX_RETRY_AGE_MS: i64 = 24 * 60 * 60 * 1_000;
 const COUNTDOWN_COMMAND_CAPACITY: usize = 4_096;
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[derive(Clone)]
 pub(crate) struct EventRuntime {
@@ -49,8 +51,7 @@ struct RuntimeInner {
     notifier: BarkNotifier,
     notification_links: NotificationLinkService,
     runtime_status: RuntimeStatus,
-    p_wave_km_s: f64,
-    s_wave_km_s: f64,
+    settings: LiveSettings,
     closing: AtomicBool,
     event_stopped: AtomicBool,
     match_stopped: AtomicBool,
@@ -63,6 +64,15 @@ struct RuntimeInner {
     countdown_receiver: Mutex<Option<mpsc::Receiver<CountdownCommand>>>,
     countdown_shutdown: watch::Sender<bool>,
     next_countdown_id: AtomicU64,
+    countdown_enabled: bool,
+    maintenance: Option<StorageMaintenance>,
+}
+
+/// 每日运行的保留清理：自动取消无法送达的订阅，并删除过期数据。启动时已清理过一次。
+#[derive(Clone)]
+struct StorageMaintenance {
+    storage: Storage,
+    policy: RetentionPolicy,
 }
 
 #[derive(Clone, Copy)]
@@ -86,13 +96,30 @@ struct EarthquakeCountdown {
     subscription_id: crate::subscriptions::SubscriptionId,
     generation: u64,
     recipient: CountdownRecipient,
-    event: Arc<DisasterEvent>,
+    channel: ProviderChannel,
+    alert: Arc<PreparedAlert>,
     timing: AlertTiming,
     detail_url: String,
 }
 
+/// 一个投递批次内所有订阅者共享的事件和预先渲染的消息片段。
+#[derive(Clone)]
+struct DeliveryEvent {
+    event: Arc<DisasterEvent>,
+    alert: Arc<PreparedAlert>,
+}
+
+impl DeliveryEvent {
+    fn new(event: DisasterEvent, templates: &MessageTemplates) -> Self {
+        Self {
+            alert: Arc::new(PreparedAlert::new(&event, templates)),
+            event: Arc::new(event),
+        }
+    }
+}
+
 enum CountdownCommand {
-    Schedule(EarthquakeCountdown),
+    Schedule(Box<EarthquakeCountdown>),
     Cancel(CountdownKey),
 }
 
@@ -113,11 +140,17 @@ struct DeliveryLaneOutcome {
 impl EventRuntime {
     pub(crate) fn new(
         storage: Storage,
-        config: &Config,
+        settings: LiveSettings,
+        features: FeatureFlags,
         notifier: BarkNotifier,
         notification_links: NotificationLinkService,
         runtime_status: RuntimeStatus,
+        retention_policy: RetentionPolicy,
     ) -> Result<Self> {
+        let maintenance = StorageMaintenance {
+            storage: storage.clone(),
+            policy: retention_policy,
+        };
         let storage = storage.inner();
         let (countdown_commands, countdown_receiver) = mpsc::channel(COUNTDOWN_COMMAND_CAPACITY);
         let (countdown_shutdown, _countdown_shutdown_receiver) = watch::channel(false);
@@ -127,23 +160,13 @@ impl EventRuntime {
         let delivery_ready_metrics = runtime_status.delivery_ready_metrics();
         Ok(Self {
             inner: Arc::new(RuntimeInner {
-                coordinator: EventCoordinator::with_policy(
-                    storage.clone(),
-                    EventPolicy {
-                        push_updates: config.push_updates,
-                        update_min_report_gap: config.update_min_report_gap,
-                        ignore_training: config.ignore_training,
-                        ignore_cancel: config.ignore_cancel,
-                        stale_origin_seconds: config.stale_origin_seconds,
-                    },
-                ),
+                coordinator: EventCoordinator::with_settings(storage.clone(), settings.clone()),
                 matcher: Arc::new(MatchEngine::new(match_threads)?),
                 storage,
                 notifier,
                 notification_links,
                 runtime_status,
-                p_wave_km_s: config.p_wave_km_s,
-                s_wave_km_s: config.s_wave_km_s,
+                settings,
                 closing: AtomicBool::new(false),
                 event_stopped: AtomicBool::new(false),
                 match_stopped: AtomicBool::new(false),
@@ -168,6 +191,8 @@ impl EventRuntime {
                 countdown_receiver: Mutex::new(Some(countdown_receiver)),
                 countdown_shutdown,
                 next_countdown_id: AtomicU64::new(1),
+                countdown_enabled: features.countdown,
+                maintenance: Some(maintenance),
             }),
         })
     }
@@ -177,6 +202,27 @@ impl EventRuntime {
         storage: Storage,
         notifier: BarkNotifier,
         notification_links: NotificationLinkService,
+    ) -> Result<Self> {
+        Self::without_maintenance(storage, notifier, notification_links, 1)
+    }
+
+    /// 与生产环境相同的匹配线程数，但不运行存储清理，供 `disaster-alert bench` 压测使用。
+    #[cfg(feature = "benchmarks")]
+    pub(crate) fn for_benchmark(
+        storage: Storage,
+        notifier: BarkNotifier,
+        notification_links: NotificationLinkService,
+    ) -> Result<Self> {
+        let match_threads = std::thread::available_parallelism().map_or(1, |value| value.get());
+        Self::without_maintenance(storage, notifier, notification_links, match_threads)
+    }
+
+    #[cfg(any(test, feature = "benchmarks"))]
+    fn without_maintenance(
+        storage: Storage,
+        notifier: BarkNotifier,
+        notification_links: NotificationLinkService,
+        match_threads: usize,
     ) -> Result<Self> {
         let storage = storage.inner();
         let runtime_status = RuntimeStatus::default();
@@ -184,14 +230,16 @@ impl EventRuntime {
         let (countdown_shutdown, _countdown_shutdown_receiver) = watch::channel(false);
         Ok(Self {
             inner: Arc::new(RuntimeInner {
-                coordinator: EventCoordinator::with_policy(storage.clone(), EventPolicy::default()),
-                matcher: Arc::new(MatchEngine::new(1)?),
+                coordinator: EventCoordinator::with_policy(
+                    storage.clone(),
+                    crate::events::EventPolicy::default(),
+                ),
+                matcher: Arc::new(MatchEngine::new(match_threads)?),
                 storage,
                 notifier,
                 notification_links,
                 runtime_status: runtime_status.clone(),
-                p_wave_km_s: 6.0,
-                s_wave_km_s: 3.5,
+                settings: LiveSettings::default(),
                 closing: AtomicBool::new(false),
                 event_stopped: AtomicBool::new(false),
                 match_stopped: AtomicBool::new(false),
@@ -216,6 +264,8 @@ impl EventRuntime {
                 countdown_receiver: Mutex::new(Some(countdown_receiver)),
                 countdown_shutdown,
                 next_countdown_id: AtomicU64::new(1),
+                countdown_enabled: true,
+                maintenance: None,
             }),
         })
     }
@@ -229,7 +279,14 @@ impl EventRuntime {
     }
 
     pub(crate) async fn submit_nonblocking(&self, event: DisasterEvent) -> bool {
-        self.submit_provider_batch_inner(event.channel, vec![event], None)
+        self.submit_provider_batch_inner(event.channel, vec![event], None, true)
+            .await
+    }
+
+    /// 提交链路自检事件；与数据源事件走同一流水线，但不计入数据源的最近事件状态，
+    /// 以免掩盖数据源本身已经停止推送。
+    pub(crate) async fn submit_canary(&self, event: DisasterEvent) -> bool {
+        self.submit_provider_batch_inner(event.channel, vec![event], None, false)
             .await
     }
 
@@ -239,7 +296,7 @@ impl EventRuntime {
         events: Vec<DisasterEvent>,
         cursor: ProviderCursor,
     ) -> bool {
-        self.submit_provider_batch_inner(provider, events, Some(cursor))
+        self.submit_provider_batch_inner(provider, events, Some(cursor), true)
             .await
     }
 
@@ -249,7 +306,7 @@ impl EventRuntime {
         events: Vec<DisasterEvent>,
         cursor: Option<ProviderCursor>,
     ) -> bool {
-        self.submit_provider_batch_inner(provider, events, cursor)
+        self.submit_provider_batch_inner(provider, events, cursor, true)
             .await
     }
 
@@ -258,10 +315,12 @@ impl EventRuntime {
         provider: ProviderChannel,
         events: Vec<DisasterEvent>,
         cursor: Option<ProviderCursor>,
+        record_channel_event: bool,
     ) -> bool {
         if self.inner.closing.load(Ordering::Acquire) {
             return false;
         }
+        let submitted = Instant::now();
         let events = match events
             .into_iter()
             .enumerate()
@@ -280,7 +339,19 @@ impl EventRuntime {
                 return false;
             }
         };
+        let latest = events.last().cloned();
+        let sources = if record_channel_event {
+            events.iter().map(|event| event.source.clone()).collect()
+        } else {
+            Vec::new()
+        };
         let storage = self.inner.storage.clone();
+        let span = tracing::info_span!(
+            "event.ingest",
+            provider = provider.as_str(),
+            events = events.len()
+        );
+        let started = Instant::now();
         let committed = tokio::task::spawn_blocking(move || {
             storage.ingest_with_cursor(
                 provider,
@@ -288,9 +359,22 @@ impl EventRuntime {
                 cursor.as_ref().map(|value| (value.stream(), value.value())),
             )
         })
+        .instrument(span)
         .await;
+        self.inner
+            .runtime_status
+            .record_storage_commit_latency(started.elapsed());
         match committed {
             Ok(Ok(ids)) => {
+                if let Some(event) = latest.filter(|_event| record_channel_event) {
+                    self.inner
+                        .runtime_status
+                        .channel(event.channel)
+                        .record_event(&event);
+                }
+                self.inner
+                    .runtime_status
+                    .record_source_events(sources.iter().map(String::as_str), submitted.elapsed());
                 for id in ids {
                     let _queued = self.inner.inbox_ready.try_push(AcceptedEvent(id));
                 }
@@ -307,6 +391,15 @@ impl EventRuntime {
         }
     }
 
+    /// 把数据源推送的地震列表写入历史记录；只补充尚无记录的地震，不触发匹配和推送。
+    /// 返回新写入的地震数。
+    pub(crate) async fn record_history(&self, events: Vec<DisasterEvent>) -> Result<usize> {
+        let storage = self.inner.storage.clone();
+        tokio::task::spawn_blocking(move || storage.import_earthquake_history(&events))
+            .await
+            .context("history import task failed")?
+    }
+
     pub(crate) async fn provider_cursors(
         &self,
         provider: ProviderChannel,
@@ -401,6 +494,15 @@ impl EventRuntime {
         workers.spawn(async move { ("retry engine", runtime.run_retry_engine().await) });
         let runtime = self.clone();
         workers.spawn(async move { ("countdown engine", runtime.run_countdown_engine().await) });
+        if let Some(maintenance) = self.inner.maintenance.clone() {
+            let runtime = self.clone();
+            workers.spawn(async move {
+                (
+                    "storage maintenance",
+                    runtime.run_storage_maintenance(maintenance).await,
+                )
+            });
+        }
         while let Some(joined) = workers.join_next().await {
             match joined {
                 Ok((name, Ok(()))) if !self.inner.closing.load(Ordering::Acquire) => {
@@ -427,6 +529,35 @@ impl EventRuntime {
         Ok(())
     }
 
+    async fn run_storage_maintenance(&self, maintenance: StorageMaintenance) -> Result<()> {
+        let mut shutdown = self.inner.countdown_shutdown.subscribe();
+        loop {
+            if self.inner.closing.load(Ordering::Acquire) || *shutdown.borrow() {
+                return Ok(());
+            }
+            tokio::select! {
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                () = tokio::time::sleep(MAINTENANCE_INTERVAL) => {}
+            }
+            let storage = maintenance.storage.clone();
+            let policy = maintenance.policy;
+            match tokio::task::spawn_blocking(move || storage.prune_retained_data(policy)).await {
+                Ok(Ok(stats)) => log_maintenance(stats),
+                Ok(Err(error)) => {
+                    tracing::error!(event = "database.maintenance_failed", error = ?error, "database.maintenance_failed");
+                }
+                Err(error) => {
+                    tracing::error!(event = "database.maintenance_task_failed", error = ?error, "database.maintenance_task_failed");
+                }
+            }
+        }
+    }
+
     async fn run_event_coordinator(&self) -> Result<()> {
         loop {
             if let Some(AcceptedEvent(_notified_id)) = self.inner.inbox_ready.pop() {
@@ -503,19 +634,92 @@ impl EventRuntime {
     async fn process_match_job(&self, job: crate::events::MatchJob) -> Result<Vec<u64>> {
         let storage = self.inner.storage.clone();
         let matcher = Arc::clone(&self.inner.matcher);
+        let runtime_status = self.inner.runtime_status.clone();
+        let settings = self.inner.settings.load();
+        let span = tracing::info_span!(
+            "match.job",
+            job_id = job.id,
+            incident_id = job.incident_id.as_str(),
+            event_revision = job.event_revision,
+            rows = tracing::field::Empty,
+            batches = tracing::field::Empty
+        );
         tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
             let event = storage
                 .event(job.event_revision)?
                 .context("MatchJob references missing event")?;
             let category = event.category;
-            let mut rows = if event.cancel {
+            let mode = crate::events::dispatch_mode(
+                job.stage,
+                job.later_stream,
+                &event,
+                &settings.event_policy,
+            );
+            let mut rows = if mode == DispatchMode::Cancellation {
                 cancellation_rows(storage.delivered_rows(&job.incident_id, event.category)?)
+            } else if mode == DispatchMode::FinalFollowUp {
+                final_followup_rows(
+                    &storage,
+                    &event,
+                    storage.delivered_rows(&job.incident_id, event.category)?,
+                )?
             } else {
                 let plan = MatchPlan::for_event(&event)?;
-                let blocks = storage.posting_blocks(&plan)?;
-                let subscriptions = storage.load_compiled_blocks(&blocks)?;
-                matcher.match_blocks(Arc::new(event), blocks, &subscriptions)
+                // 最外圈等震线以内的订阅都可能达到推送震度，候选检索半径必须覆盖它。
+                if let Some(outermost) = isoseismal_radii(&event, settings.intensity_model).first()
+                    && !plan.covers_radius(outermost.radius_km)
+                {
+                    tracing::warn!(
+                        event = "match.isoseismal_radius_uncovered",
+                        incident_id = job.incident_id.as_str(),
+                        event_revision = job.event_revision,
+                        intensity = outermost.intensity,
+                        radius_km = outermost.radius_km,
+                        "match.isoseismal_radius_uncovered"
+                    );
+                }
+                let (blocks, subscriptions) = {
+                    let _lookup = tracing::info_span!("match.candidate_lookup").entered();
+                    let blocks = storage.posting_blocks(&plan)?;
+                    let subscriptions = storage.load_compiled_blocks(&blocks)?;
+                    (blocks, subscriptions)
+                };
+                let _filter = tracing::info_span!("match.filter").entered();
+                let (rows, shadow) = matcher.match_blocks_with_models(
+                    Arc::new(event),
+                    blocks,
+                    &subscriptions,
+                    settings.intensity_model,
+                    settings.shadow_intensity_model,
+                );
+                if let Some(comparison) = shadow {
+                    runtime_status.record_shadow_comparison(&comparison);
+                    if comparison.diverged() > 0 {
+                        tracing::info!(
+                            event = "match.shadow_intensity_diverged",
+                            incident_id = job.incident_id.as_str(),
+                            event_revision = job.event_revision,
+                            model = settings.intensity_model.as_str(),
+                            shadow_model =
+                                settings.shadow_intensity_model.map(|model| model.as_str()),
+                            evaluated = comparison.evaluated,
+                            extra = comparison.extra,
+                            missed = comparison.missed,
+                            level_changed = comparison.level_changed,
+                            "match.shadow_intensity_diverged"
+                        );
+                    }
+                }
+                rows
             };
+            if mode == DispatchMode::EscalationOnly {
+                rows = crate::events::escalated_rows(
+                    rows,
+                    &storage.delivered_rows(&job.incident_id, category)?,
+                );
+            }
+            span.record("rows", rows.len());
             rows.sort_unstable_by_key(|row| {
                 (
                     delivery_shard(row.destination_id.0),
@@ -523,7 +727,9 @@ impl EventRuntime {
                     row.subscription_id.0,
                 )
             });
+            let _commit = tracing::info_span!("match.commit").entered();
             let batches = build_delivery_batches(&storage, &job, category, &rows)?;
+            span.record("batches", batches.len());
             let ids = batches.iter().map(|batch| batch.id).collect();
             storage.commit_match_batches(job.id, &batches)?;
             Ok::<_, anyhow::Error>(ids)
@@ -570,9 +776,22 @@ impl EventRuntime {
                     let batch_id = batch.id;
                     active.insert(batch_id);
                     let runtime = self.clone();
-                    attempts.spawn(async move {
-                        (batch_id, runtime.process_delivery_batch(batch).await)
-                    });
+                    let span = tracing::info_span!(
+                        "delivery.batch",
+                        batch_id,
+                        incident_id = batch.incident_id.as_str(),
+                        event_revision = batch.event_revision,
+                        shard = batch.shard,
+                        rows = batch.rows.len()
+                    );
+                    let in_flight = self.inner.runtime_status.track_delivery_batch();
+                    attempts.spawn(
+                        async move {
+                            let _in_flight = in_flight;
+                            (batch_id, runtime.process_delivery_batch(batch).await)
+                        }
+                        .instrument(span),
+                    );
                     continue;
                 }
             }
@@ -611,7 +830,7 @@ impl EventRuntime {
         })
         .await
         .context("delivery batch read task failed")??;
-        let event = Arc::new(event);
+        let event = DeliveryEvent::new(event, &self.inner.settings.load().message_templates);
         let batch = Arc::new(batch);
         let mut lanes = HashMap::<u64, Vec<(usize, DeliveryRow)>>::new();
         for (row_index, row) in pending_rows {
@@ -623,10 +842,12 @@ impl EventRuntime {
         let mut attempts = tokio::task::JoinSet::new();
         for rows in lanes.into_values() {
             let runtime = self.clone();
-            let event = Arc::clone(&event);
+            let event = event.clone();
             let batch = Arc::clone(&batch);
-            attempts
-                .spawn(async move { runtime.process_destination_lane(&event, &batch, rows).await });
+            attempts.spawn(
+                async move { runtime.process_destination_lane(&event, &batch, rows).await }
+                    .in_current_span(),
+            );
         }
         while let Some(result) = attempts.join_next().await {
             result.context("delivery destination lane task failed")??;
@@ -636,7 +857,7 @@ impl EventRuntime {
 
     async fn process_destination_lane(
         &self,
-        event: &Arc<DisasterEvent>,
+        event: &DeliveryEvent,
         batch: &DeliveryBatch,
         rows: Vec<(usize, DeliveryRow)>,
     ) -> Result<DeliveryLaneOutcome> {
@@ -849,11 +1070,24 @@ impl EventRuntime {
                     active_retries.insert(retry.id);
                     active_destinations.insert(retry.destination_id.0);
                     let runtime = self.clone();
-                    attempts.spawn(async move {
-                        let retry_id = retry.id;
-                        let destination_id = retry.destination_id.0;
-                        (retry_id, destination_id, runtime.process_retry(retry).await)
-                    });
+                    let span = tracing::info_span!(
+                        "delivery.retry",
+                        retry_id = retry.id,
+                        batch_id = retry.batch_id,
+                        attempts = retry.attempts,
+                        incident_id = tracing::field::Empty,
+                        event_revision = tracing::field::Empty
+                    );
+                    let in_flight = self.inner.runtime_status.track_retry();
+                    attempts.spawn(
+                        async move {
+                            let _in_flight = in_flight;
+                            let retry_id = retry.id;
+                            let destination_id = retry.destination_id.0;
+                            (retry_id, destination_id, runtime.process_retry(retry).await)
+                        }
+                        .instrument(span),
+                    );
                 }
             }
 
@@ -948,7 +1182,7 @@ impl EventRuntime {
 
     async fn run_earthquake_countdown(
         &self,
-        countdown: EarthquakeCountdown,
+        countdown: Box<EarthquakeCountdown>,
         mut cancel: watch::Receiver<bool>,
         mut shutdown: watch::Receiver<bool>,
     ) {
@@ -1030,7 +1264,7 @@ impl EventRuntime {
         let result = tokio::select! {
             result = self.inner.notifier.send_disaster_countdown(
                 &countdown.recipient,
-                &countdown.event,
+                &countdown.alert,
                 &countdown.timing,
                 &countdown.detail_url,
             ) => Some(result),
@@ -1047,7 +1281,7 @@ impl EventRuntime {
         if let Some(result) = result {
             self.inner
                 .runtime_status
-                .channel(countdown.event.channel)
+                .channel(countdown.channel)
                 .record_notification(result.is_ok());
             if let Err(error) = result {
                 tracing::warn!(
@@ -1121,7 +1355,10 @@ impl EventRuntime {
                 return Ok(());
             }
         };
-        let event = Arc::from(event);
+        let span = tracing::Span::current();
+        span.record("incident_id", batch.incident_id.as_str());
+        span.record("event_revision", batch.event_revision);
+        let event = DeliveryEvent::new(*event, &self.inner.settings.load().message_templates);
         let result = self
             .deliver_row_locked(&event, &row, &batch, retry.row_index)
             .await;
@@ -1177,11 +1414,12 @@ impl EventRuntime {
 
     async fn deliver_row_locked(
         &self,
-        event: &Arc<DisasterEvent>,
+        delivery: &DeliveryEvent,
         row: &DeliveryRow,
         batch: &DeliveryBatch,
         row_index: u32,
     ) -> std::result::Result<Option<DeliverySuccess>, BarkDeliveryError> {
+        let event = &delivery.event;
         let storage = self.inner.storage.clone();
         let incident_id = batch.incident_id.clone();
         let category = batch.category;
@@ -1196,6 +1434,22 @@ impl EventRuntime {
         {
             return Ok(None);
         }
+        if try_now_millis().is_ok_and(|now_ms| {
+            deadline_exceeded(
+                self.inner.settings.load().dispatch_deadline_seconds,
+                event,
+                batch.created_at_ms,
+                now_ms,
+            )
+        }) {
+            self.inner.runtime_status.record_deadline_exceeded();
+            tracing::warn!(
+                event = "delivery.deadline_exceeded",
+                destination_id = row.destination_id.0,
+                "delivery.deadline_exceeded"
+            );
+            return Ok(None);
+        }
         let storage = self.inner.storage.clone();
         let subscription_id = row.subscription_id;
         let record =
@@ -1215,14 +1469,21 @@ impl EventRuntime {
             .ok_or_else(|| {
                 BarkDeliveryError::permanent(anyhow::anyhow!("compiled target ordinal is invalid"))
             })?;
-        let rule = record.subscription.alert(batch.category).ok_or_else(|| {
-            BarkDeliveryError::permanent(anyhow::anyhow!(
-                "subscription no longer has matching rule"
-            ))
-        })?;
-        let timing = self
-            .alert_timing(event, row)
-            .map_err(BarkDeliveryError::transient)?;
+        let rule = record
+            .subscription
+            .alert_for_source(batch.category, &event.source)
+            .ok_or_else(|| {
+                BarkDeliveryError::permanent(anyhow::anyhow!(
+                    "subscription no longer has matching rule"
+                ))
+            })?;
+        // 按地区订阅没有具体位置，估算的震波到达时间没有意义。
+        let timing = if target.point.is_some() {
+            self.alert_timing(event, row)
+                .map_err(BarkDeliveryError::transient)?
+        } else {
+            None
+        };
         let context = self
             .inner
             .notification_links
@@ -1234,6 +1495,7 @@ impl EventRuntime {
                 interruption_level: row.interruption_level.as_str(),
                 matched_rule: rule,
                 issued_at_ms: batch.created_at_ms,
+                intensity_model: self.inner.settings.load().intensity_model,
             })
             .map_err(BarkDeliveryError::transient)?;
         let links = self.inner.notification_links.clone();
@@ -1242,6 +1504,11 @@ impl EventRuntime {
             .await
             .map_err(|error| BarkDeliveryError::transient(anyhow::anyhow!(error)))?
             .map_err(BarkDeliveryError::transient)?;
+        let detail_url = self
+            .inner
+            .notification_links
+            .delivery_url(&context, row.destination_id.0)
+            .map_err(BarkDeliveryError::transient)?;
         let recipient = AlertRecipient::new(&record.subscription, target);
         let countdown_key = CountdownKey {
             incident_id: batch.incident_id.clone(),
@@ -1252,38 +1519,49 @@ impl EventRuntime {
             self.queue_countdown_command(CountdownCommand::Cancel(countdown_key.clone()))
                 .await;
         }
+        let in_flight = self.inner.runtime_status.track_notification();
+        let started = Instant::now();
         let result = self
             .inner
             .notifier
             .send_disaster_alert(
                 &recipient,
                 row.interruption_level.as_str(),
-                event,
+                &delivery.alert,
                 timing.as_ref(),
-                &context.url,
+                &detail_url,
             )
             .await;
+        drop(in_flight);
+        self.inner
+            .runtime_status
+            .record_dispatch_latency(started.elapsed());
+        self.inner.runtime_status.record_notifier_reachable(
+            result
+                .as_ref()
+                .map_or_else(BarkDeliveryError::is_permanent, |()| true),
+        );
         self.inner
             .runtime_status
             .channel(event.channel)
             .record_notification(result.is_ok());
         result?;
-        if event.category == DisasterCategory::EarthquakeWarning && !event.cancel {
-            if let Some(timing) = timing.filter(|timing| {
-                try_now_millis()
-                    .is_ok_and(|now_ms| remaining_seconds(timing.s_arrival_at_ms, now_ms) > 0)
-            }) {
-                self.queue_countdown_command(CountdownCommand::Schedule(EarthquakeCountdown {
+        if let Some(timing) = try_now_millis().ok().and_then(|now_ms| {
+            countdown_timing(self.inner.countdown_enabled, event, timing, now_ms)
+        }) {
+            self.queue_countdown_command(CountdownCommand::Schedule(Box::new(
+                EarthquakeCountdown {
                     key: countdown_key,
                     subscription_id: row.subscription_id,
                     generation: row.generation,
                     recipient: recipient.to_countdown_recipient(),
-                    event: Arc::clone(event),
+                    channel: event.channel,
+                    alert: Arc::clone(&delivery.alert),
                     timing,
-                    detail_url: context.url,
-                }))
-                .await;
-            }
+                    detail_url,
+                },
+            )))
+            .await;
         }
         Ok(Some(DeliverySuccess {
             row_index,
@@ -1296,33 +1574,15 @@ impl EventRuntime {
         event: &DisasterEvent,
         row: &DeliveryRow,
     ) -> Result<Option<AlertTiming>> {
-        if !matches!(
-            event.category,
-            DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
-        ) {
-            return Ok(None);
-        }
+        let settings = self.inner.settings.load();
         let distance_km = f64::from(row.distance_m) / 1_000.0;
-        let depth = event.depth_km.unwrap_or_default().max(0.0);
-        let hypocentral_km = distance_km.mul_add(distance_km, depth * depth).sqrt();
-        let estimated_intensity = event.magnitude.map_or(0.0, |magnitude| {
-            crate::utils::intensity::estimate_intensity(magnitude, hypocentral_km)
-        });
-        let occurred_at_ms = match parse_event_epoch(event) {
-            Some(seconds) => seconds.saturating_mul(1_000),
-            None => try_now_millis()?,
+        let Some(mut timing) = estimate_alert_timing(event, distance_km, &settings)? else {
+            return Ok(None);
         };
-        Ok(Some(AlertTiming {
-            distance_km,
-            hypocentral_km,
-            estimated_intensity: if event.category == DisasterCategory::EarthquakeWarning {
-                f64::from(row.intensity_cent) / 100.0
-            } else {
-                estimated_intensity
-            },
-            p_arrival_at_ms: arrival_at(occurred_at_ms, hypocentral_km, self.inner.p_wave_km_s),
-            s_arrival_at_ms: arrival_at(occurred_at_ms, hypocentral_km, self.inner.s_wave_km_s),
-        }))
+        if event.category == DisasterCategory::EarthquakeWarning {
+            timing.estimated_intensity = f64::from(row.intensity_cent) / 100.0;
+        }
+        Ok(Some(timing))
     }
 
     fn destination_lock(&self, destination_id: u64) -> Arc<AsyncMutex<()>> {
@@ -1357,6 +1617,68 @@ async fn wait_for_countdown_delay(
     }
 }
 
+/// 按震中距估算监测点的预计烈度和震波到达时间，非地震类事件返回 `None`；发生时间无法解析时
+/// 以当前时间为起点。投递时地震预警改用匹配阶段记录的烈度。
+pub(crate) fn estimate_alert_timing(
+    event: &DisasterEvent,
+    distance_km: f64,
+    settings: &TunableSettings,
+) -> Result<Option<AlertTiming>> {
+    if !matches!(
+        event.category,
+        DisasterCategory::EarthquakeWarning | DisasterCategory::EarthquakeReport
+    ) {
+        return Ok(None);
+    }
+    let depth = event.depth_km.unwrap_or_default().max(0.0);
+    let hypocentral_km = distance_km.mul_add(distance_km, depth * depth).sqrt();
+    let estimated_intensity = event.magnitude.map_or(0.0, |magnitude| {
+        settings.intensity_model.estimate(magnitude, hypocentral_km)
+    });
+    let occurred_at_ms = match parse_event_epoch_ms(event) {
+        Some(value) => value,
+        None => try_now_millis()?,
+    };
+    Ok(Some(AlertTiming {
+        distance_km,
+        hypocentral_km,
+        estimated_intensity,
+        p_arrival_at_ms: arrival_at(occurred_at_ms, hypocentral_km, settings.p_wave_km_s),
+        s_arrival_at_ms: arrival_at(occurred_at_ms, hypocentral_km, settings.s_wave_km_s),
+    }))
+}
+
+/// 地震预警投递积压时，超过推送时限仍未发出的通知已失去预警意义，直接丢弃；解除消息和其他
+/// 灾种不受限制，`deadline_seconds` 为 0 时关闭。
+fn deadline_exceeded(
+    deadline_seconds: u64,
+    event: &DisasterEvent,
+    created_at_ms: i64,
+    now_ms: i64,
+) -> bool {
+    if deadline_seconds == 0
+        || event.category != DisasterCategory::EarthquakeWarning
+        || event.cancel
+    {
+        return false;
+    }
+    let deadline_ms = i64::try_from(deadline_seconds.saturating_mul(1_000)).unwrap_or(i64::MAX);
+    now_ms.saturating_sub(created_at_ms) > deadline_ms
+}
+
+/// 地震预警送达后，S 波尚未到达监测点时才安排倒计时；`FEATURES_COUNTDOWN` 关闭时不安排。
+fn countdown_timing(
+    enabled: bool,
+    event: &DisasterEvent,
+    timing: Option<AlertTiming>,
+    now_ms: i64,
+) -> Option<AlertTiming> {
+    if !enabled || event.category != DisasterCategory::EarthquakeWarning || event.cancel {
+        return None;
+    }
+    timing.filter(|timing| remaining_seconds(timing.s_arrival_at_ms, now_ms) > 0)
+}
+
 fn countdown_tick_delay_ms(arrival_at_ms: i64, now_ms: i64) -> i64 {
     let delta_ms = arrival_at_ms.saturating_sub(now_ms).max(0);
     let seconds = remaining_seconds(arrival_at_ms, now_ms);
@@ -1367,7 +1689,9 @@ fn countdown_tick_delay_ms(arrival_at_ms: i64, now_ms: i64) -> i64 {
     }
 }
 
-fn sanitize_event(mut event: DisasterEvent) -> std::result::Result<DisasterEvent, &'static str> {
+pub(crate) fn sanitize_event(
+    mut event: DisasterEvent,
+) -> std::result::Result<DisasterEvent, &'static str> {
     if event.source.is_empty()
         || event.source.len() > 128
         || event.event_id.is_empty()
@@ -1406,6 +1730,33 @@ fn cancellation_rows(mut rows: Vec<DeliveryRow>) -> Vec<DeliveryRow> {
     rows
 }
 
+/// 终报回顾不重新匹配：只发给此前收到过该事件、且规则中开启了终报回顾的订阅，
+/// 即使确认后的震级已低于推送门槛也照样告知，并改用较温和的中断级别。
+fn final_followup_rows(
+    storage: &FjallStorage,
+    event: &DisasterEvent,
+    rows: Vec<DeliveryRow>,
+) -> Result<Vec<DeliveryRow>> {
+    let mut followups = Vec::new();
+    for mut row in rows {
+        let opted_in = storage
+            .stored_subscription(row.subscription_id)?
+            .is_some_and(|record| {
+                record.active
+                    && record.generation == row.generation
+                    && record
+                        .subscription
+                        .alert_for_source(event.category, &event.source)
+                        .is_some_and(AlertRule::final_followup)
+            });
+        if opted_in {
+            row.interruption_level = InterruptionLevel::Passive;
+            followups.push(row);
+        }
+    }
+    Ok(followups)
+}
+
 fn truncate(value: &str, max_bytes: usize) -> String {
     if value.len() <= max_bytes {
         return value.to_string();
@@ -1475,6 +1826,23 @@ fn delivery_shard(destination_id: u64) -> u16 {
     u16::try_from(destination_id % DELIVERY_SHARDS).unwrap_or(0)
 }
 
+fn log_maintenance(stats: PruneStats) {
+    if stats.total() > 0 {
+        tracing::info!(
+            event = "database.records_pruned",
+            incidents = stats.incidents,
+            delivery_records = stats.delivery_records,
+            events = stats.events,
+            earthquake_history = stats.earthquake_history,
+            felt_reports = stats.felt_reports,
+            notification_opens = stats.notification_opens,
+            subscriptions = stats.subscriptions,
+            undeliverable_subscriptions = stats.undeliverable_subscriptions,
+            "database.records_pruned"
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1494,6 +1862,45 @@ mod tests {
         assert_eq!(countdown_tick_delay_ms(5_719_500, 0), 500);
     }
 
+    #[test]
+    fn countdowns_follow_the_feature_flag_and_arrival_time() {
+        let timing = AlertTiming {
+            distance_km: 80.0,
+            hypocentral_km: 81.0,
+            estimated_intensity: 3.0,
+            p_arrival_at_ms: 5_000,
+            s_arrival_at_ms: 10_000,
+        };
+        let mut warning = test_delivery_event(1, "countdown");
+        warning.category = DisasterCategory::EarthquakeWarning;
+        let mut cancel = warning.clone();
+        cancel.cancel = true;
+        let report = test_delivery_event(1, "countdown");
+
+        assert!(countdown_timing(true, &warning, Some(timing.clone()), 0).is_some());
+        assert!(countdown_timing(false, &warning, Some(timing.clone()), 0).is_none());
+        assert!(countdown_timing(true, &warning, Some(timing.clone()), 10_000).is_none());
+        assert!(countdown_timing(true, &warning, None, 0).is_none());
+        assert!(countdown_timing(true, &cancel, Some(timing.clone()), 0).is_none());
+        assert!(countdown_timing(true, &report, Some(timing), 0).is_none());
+    }
+
+    #[test]
+    fn dispatch_deadline_only_drops_late_earthquake_warnings() {
+        let mut warning = test_delivery_event(1, "deadline");
+        warning.category = DisasterCategory::EarthquakeWarning;
+        let mut report = warning.clone();
+        report.category = DisasterCategory::EarthquakeReport;
+        let mut cancel = warning.clone();
+        cancel.cancel = true;
+
+        assert!(!deadline_exceeded(30, &warning, 1_000, 31_000));
+        assert!(deadline_exceeded(30, &warning, 1_000, 31_001));
+        assert!(!deadline_exceeded(0, &warning, 1_000, 600_000));
+        assert!(!deadline_exceeded(30, &report, 1_000, 600_000));
+        assert!(!deadline_exceeded(30, &cancel, 1_000, 600_000));
+    }
+
     #[test]
     fn cancellation_preserves_historical_generation() {
         let historical = DeliveryRow {
@@ -1609,6 +2016,8 @@ mod tests {
             incident_id: IncidentId::derive("batch-test"),
             event_revision: 1,
             created_at_ms: 1,
+            stage: crate::events::EventStage::First,
+            later_stream: false,
         };
         let mut rows = (0..2_000_u64)
             .rev()
@@ -1787,10 +2196,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "target".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 35.0,
                     longitude: 105.0,
-                },
+                }),
                 region: Default::default(),
             }],
             vec![AlertRule::default_for(DisasterCategory::EarthquakeReport)],
@@ -1828,6 +2237,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -1,37 +1,218 @@
-use crate::models::ProviderChannel;
+use crate::matching::ShadowComparison;
+use crate::models::{DisasterCategory, DisasterEvent, ProviderChannel};
+use crate::source_registry;
 use serde::Serialize;
-use std::sync::Arc;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 每个数据源保留的最近解析失败样本数。
+const RECENT_PARSE_FAILURES: usize = 5;
+const MAX_PARSE_FAILURE_PAYLOAD_BYTES: usize = 2_048;
+const MAX_PARSE_FAILURE_ERROR_BYTES: usize = 512;
+
+/// 连续这么多次推送未到达 Bark 服务时，健康检查报告推送降级。
+const NOTIFIER_DEGRADED_FAILURES: u64 = 10;
+
+/// 延迟直方图的桶上界（秒），覆盖从存储提交到 Bark 超时的区间。
+const LATENCY_BUCKETS_SECONDS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
 
 #[derive(Clone, Default)]
 pub(crate) struct RuntimeStatus {
     wolfx: Arc<ChannelMetrics>,
     fanstudio: Arc<ChannelMetrics>,
     huania: Arc<ChannelMetrics>,
+    p2pquake: Arc<ChannelMetrics>,
     inbox_ready: Arc<ReadyQueueMetrics>,
     match_ready: Arc<ReadyQueueMetrics>,
     delivery_ready: Arc<ReadyQueueMetrics>,
+    dispatch_latency: Arc<LatencyHistogram>,
+    storage_commit_latency: Arc<LatencyHistogram>,
+    active_delivery_batches: Arc<AtomicUsize>,
+    active_retries: Arc<AtomicUsize>,
+    deadline_exceeded: Arc<AtomicU64>,
+    notifications_opened: Arc<AtomicU64>,
+    notifications_in_flight: Arc<AtomicUsize>,
+    last_dispatch_epoch_ms: Arc<AtomicU64>,
+    recovered_at_epoch_ms: Arc<AtomicU64>,
+    http_requests: Arc<Mutex<BTreeMap<HttpRouteKey, LatencyHistogram>>>,
+    notifier_consecutive_failures: Arc<AtomicU64>,
+    notifier_last_reachable_epoch_ms: Arc<AtomicU64>,
+    shadow_intensity: Arc<ShadowIntensityMetrics>,
+    canary: Arc<CanaryMetrics>,
+    sources: Arc<Mutex<BTreeMap<&'static str, SourceMetrics>>>,
+    started: Arc<ProcessStart>,
+}
+
+/// 进程启动时刻；随 [`RuntimeStatus`] 一同创建。
+struct ProcessStart {
+    epoch_ms: u64,
+    instant: Instant,
+}
+
+impl Default for ProcessStart {
+    fn default() -> Self {
+        Self {
+            epoch_ms: current_epoch_ms(),
+            instant: Instant::now(),
+        }
+    }
+}
+
+/// 单个数据源（如 `wolfx.fj_eew`）的入库统计，自进程启动起计数。
+#[derive(Default)]
+struct SourceMetrics {
+    events: u64,
+    last_event_epoch_ms: u64,
+    parse_errors: u64,
+    processing_micros: u64,
+}
+
+/// 影子震度模型与生效模型的累计分歧，自进程启动起计数。
+#[derive(Default)]
+struct ShadowIntensityMetrics {
+    divergent_jobs: AtomicU64,
+    agreed: AtomicU64,
+    extra: AtomicU64,
+    missed: AtomicU64,
+    level_changed: AtomicU64,
+}
+
+/// 链路自检结果，自进程启动起计数。
+#[derive(Default)]
+struct CanaryMetrics {
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    last_success_epoch_ms: AtomicU64,
 }
 
 #[derive(Default)]
 pub(crate) struct ChannelMetrics {
     connected: AtomicBool,
     last_message_epoch_ms: AtomicU64,
+    /// 相邻两条上游消息之间的最长间隔。
+    longest_message_gap_ms: AtomicU64,
     reconnects: AtomicU64,
+    /// 已结束的断线时长之和；首次连上之前的时间不计入。
+    disconnected_ms: AtomicU64,
+    /// 当前这次断线的开始时间，连接中或从未连上时为 0。
+    disconnected_since_epoch_ms: AtomicU64,
     messages: AtomicU64,
     parse_errors: AtomicU64,
     notifications_succeeded: AtomicU64,
     notifications_failed: AtomicU64,
+    last_event: Mutex<Option<LastEventSnapshot>>,
+    parse_failures: Mutex<ParseFailures>,
+}
+
+#[derive(Default)]
+struct ParseFailures {
+    by_kind: BTreeMap<&'static str, u64>,
+    recent: VecDeque<ParseFailureSample>,
+}
+
+/// 一次解析失败的样本；原始消息按字节截断，用于排查上游格式变化。
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ParseFailureSample {
+    pub(crate) kind: &'static str,
+    pub(crate) error: String,
+    pub(crate) payload: Option<String>,
+    pub(crate) payload_truncated: bool,
+    pub(crate) failed_at_ms: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ParseDiagnosticsSnapshot {
+    pub(crate) wolfx: ChannelParseDiagnostics,
+    pub(crate) fanstudio: ChannelParseDiagnostics,
+    pub(crate) huania: ChannelParseDiagnostics,
+    pub(crate) p2pquake: ChannelParseDiagnostics,
 }
 
+#[derive(Serialize)]
+pub(crate) struct ChannelParseDiagnostics {
+    pub(crate) parse_errors: u64,
+    pub(crate) by_kind: BTreeMap<&'static str, u64>,
+    /// 按时间倒序排列。
+    pub(crate) recent: Vec<ParseFailureSample>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct HttpRouteKey {
+    method: &'static str,
+    route: String,
+    status: u16,
+}
+
+/// 进行中的推送工作计数；持有期间计入，释放时自动扣除。
+pub(super) struct InFlight(Arc<AtomicUsize>);
+
 #[derive(Serialize)]
 pub(crate) struct RuntimeStatusSnapshot {
     pub(crate) wolfx: ChannelSnapshot,
     pub(crate) fanstudio: ChannelSnapshot,
     pub(crate) huania: ChannelSnapshot,
+    pub(crate) p2pquake: ChannelSnapshot,
     pub(crate) durable: DurableBacklogSnapshot,
     pub(crate) ready_queues: ReadyQueuesSnapshot,
+    pub(crate) dispatch: DispatchSnapshot,
+    pub(crate) sources: Vec<SourceSnapshot>,
+    pub(crate) process: ProcessSnapshot,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct ProcessSnapshot {
+    pub(crate) started_at_epoch_ms: u64,
+    pub(crate) uptime_seconds: u64,
+}
+
+/// 按数据源细分的入库统计；内置数据源总会列出，便于发现长时间没有事件的数据源。
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SourceSnapshot {
+    pub(crate) source: &'static str,
+    pub(crate) label: &'static str,
+    pub(crate) channel: &'static str,
+    pub(crate) events_received: u64,
+    pub(crate) last_event_epoch_ms: Option<u64>,
+    pub(crate) seconds_since_last_event: Option<u64>,
+    pub(crate) parse_errors: u64,
+    /// 从数据源交给流水线到持久化完成的平均耗时（毫秒）。
+    pub(crate) average_processing_ms: Option<f64>,
+}
+
+/// Bark 服务的可达性：连续多次网络错误或 5xx 时视为降级。
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct NotifierHealth {
+    pub(crate) degraded: bool,
+    pub(crate) consecutive_failures: u64,
+    pub(crate) last_reachable_epoch_ms: Option<u64>,
+}
+
+/// 就绪探针关心的运行时条件；存储可读性由调用方另行检查。
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct RuntimeReadiness {
+    /// 启动恢复已完成，持久化的待办任务已重新入队。
+    pub(crate) recovered: bool,
+    pub(crate) upstream_connected: bool,
+    pub(crate) within_grace_period: bool,
+}
+
+impl RuntimeReadiness {
+    pub(crate) fn is_ready(self) -> bool {
+        self.recovered && (self.upstream_connected || self.within_grace_period)
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct DispatchSnapshot {
+    pub(crate) active_delivery_batches: usize,
+    pub(crate) active_retries: usize,
+    pub(crate) notifications_in_flight: usize,
+    pub(crate) last_dispatch_epoch_ms: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -48,6 +229,14 @@ pub(super) struct ReadyQueueMetrics {
     backpressure: AtomicU64,
 }
 
+/// 固定桶的延迟直方图；桶内计数不累加，导出时再按 Prometheus 语义累加。
+#[derive(Default)]
+pub(crate) struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_SECONDS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
 #[derive(Debug, Clone, Copy, Serialize)]
 pub(crate) struct ReadyQueueSnapshot {
     pub(crate) depth: usize,
@@ -64,6 +253,22 @@ pub(crate) struct ChannelSnapshot {
     pub(crate) parse_errors: u64,
     pub(crate) notifications_succeeded: u64,
     pub(crate) notifications_failed: u64,
+    pub(crate) seconds_since_last_message: Option<u64>,
+    /// 断线累计秒数，包括仍在进行中的这次断线。
+    pub(crate) disconnected_seconds: u64,
+    /// 上游消息之间的最长间隔秒数，包括距最近一条消息的当前间隔。
+    pub(crate) longest_message_gap_seconds: u64,
+    pub(crate) last_event: Option<LastEventSnapshot>,
+}
+
+/// 该数据源最近一次成功入库的事件。
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LastEventSnapshot {
+    pub(crate) category: DisasterCategory,
+    pub(crate) source: String,
+    pub(crate) event_id: String,
+    pub(crate) title: String,
+    pub(crate) received_at_ms: u64,
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize)]
@@ -81,6 +286,7 @@ impl RuntimeStatus {
             ProviderChannel::Wolfx => &self.wolfx,
             ProviderChannel::FanStudio => &self.fanstudio,
             ProviderChannel::Huania => &self.huania,
+            ProviderChannel::P2pquake => &self.p2pquake,
         }
     }
 
@@ -96,20 +302,192 @@ impl RuntimeStatus {
         &self.huania
     }
 
+    pub(crate) fn p2pquake(&self) -> &ChannelMetrics {
+        &self.p2pquake
+    }
+
+    /// 所有上游通道累计的推送成功数和失败数。
+    pub(crate) fn notification_totals(&self) -> (u64, u64) {
+        [&self.wolfx, &self.fanstudio, &self.huania, &self.p2pquake]
+            .into_iter()
+            .fold((0, 0), |(succeeded, failed), metrics| {
+                (
+                    succeeded
+                        .saturating_add(metrics.notifications_succeeded.load(Ordering::Relaxed)),
+                    failed.saturating_add(metrics.notifications_failed.load(Ordering::Relaxed)),
+                )
+            })
+    }
+
     pub(crate) fn snapshot(&self, durable: DurableBacklogSnapshot) -> RuntimeStatusSnapshot {
         RuntimeStatusSnapshot {
             wolfx: self.wolfx.snapshot(),
             fanstudio: self.fanstudio.snapshot(),
             huania: self.huania.snapshot(),
+            p2pquake: self.p2pquake.snapshot(),
             durable,
             ready_queues: ReadyQueuesSnapshot {
                 inbox: self.inbox_ready.snapshot(),
                 matching: self.match_ready.snapshot(),
                 delivery: self.delivery_ready.snapshot(),
             },
+            dispatch: self.dispatch_snapshot(),
+            sources: self.source_snapshots(),
+            process: self.process_snapshot(),
+        }
+    }
+
+    fn process_snapshot(&self) -> ProcessSnapshot {
+        ProcessSnapshot {
+            started_at_epoch_ms: self.started.epoch_ms,
+            uptime_seconds: self.started.instant.elapsed().as_secs(),
+        }
+    }
+
+    /// 记录一批成功入库的事件；`elapsed` 为整批的处理耗时，计入批内每个事件。
+    pub(crate) fn record_source_events<'a>(
+        &self,
+        sources: impl IntoIterator<Item = &'a str>,
+        elapsed: Duration,
+    ) {
+        let now = current_epoch_ms();
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let mut metrics = self
+            .sources
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        for source in sources {
+            let Some(definition) = source_registry::find(source) else {
+                continue;
+            };
+            let entry = metrics.entry(definition.id).or_default();
+            entry.events = entry.events.saturating_add(1);
+            entry.last_event_epoch_ms = now;
+            entry.processing_micros = entry.processing_micros.saturating_add(micros);
         }
     }
 
+    /// 记录一次能归属到具体数据源的解析失败；通道级的计数仍由
+    /// [`ChannelMetrics::record_parse_error`] 负责。
+    pub(crate) fn record_source_parse_error(&self, channel: ProviderChannel, provider_key: &str) {
+        let Some(definition) = source_registry::find_provider(channel, provider_key) else {
+            return;
+        };
+        let mut metrics = self
+            .sources
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let entry = metrics.entry(definition.id).or_default();
+        entry.parse_errors = entry.parse_errors.saturating_add(1);
+    }
+
+    fn source_snapshots(&self) -> Vec<SourceSnapshot> {
+        let now = current_epoch_ms();
+        let metrics = self
+            .sources
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        source_registry::SOURCES
+            .iter()
+            .map(|definition| {
+                let source = metrics.get(definition.id);
+                let events = source.map_or(0, |source| source.events);
+                let last_event = source
+                    .map(|source| source.last_event_epoch_ms)
+                    .filter(|epoch_ms| *epoch_ms != 0);
+                SourceSnapshot {
+                    source: definition.id,
+                    label: definition.label,
+                    channel: definition.channel.as_str(),
+                    events_received: events,
+                    last_event_epoch_ms: last_event,
+                    seconds_since_last_event: last_event
+                        .map(|epoch_ms| now.saturating_sub(epoch_ms) / 1_000),
+                    parse_errors: source.map_or(0, |source| source.parse_errors),
+                    average_processing_ms: source
+                        .filter(|_source| events > 0)
+                        .map(|source| source.processing_micros as f64 / events as f64 / 1_000.0),
+                }
+            })
+            .collect()
+    }
+
+    fn dispatch_snapshot(&self) -> DispatchSnapshot {
+        let last_dispatch = self.last_dispatch_epoch_ms.load(Ordering::Relaxed);
+        DispatchSnapshot {
+            active_delivery_batches: self.active_delivery_batches.load(Ordering::Relaxed),
+            active_retries: self.active_retries.load(Ordering::Relaxed),
+            notifications_in_flight: self.notifications_in_flight.load(Ordering::Relaxed),
+            last_dispatch_epoch_ms: (last_dispatch != 0).then_some(last_dispatch),
+        }
+    }
+
+    pub(crate) fn parse_diagnostics(&self) -> ParseDiagnosticsSnapshot {
+        ParseDiagnosticsSnapshot {
+            wolfx: self.wolfx.parse_diagnostics(),
+            fanstudio: self.fanstudio.parse_diagnostics(),
+            huania: self.huania.parse_diagnostics(),
+            p2pquake: self.p2pquake.parse_diagnostics(),
+        }
+    }
+
+    pub(crate) fn mark_recovered(&self) {
+        self.recovered_at_epoch_ms
+            .store(current_epoch_ms().max(1), Ordering::Relaxed);
+    }
+
+    pub(crate) fn readiness(&self, grace: Duration) -> RuntimeReadiness {
+        let recovered_at = self.recovered_at_epoch_ms.load(Ordering::Relaxed);
+        let grace_ms = u64::try_from(grace.as_millis()).unwrap_or(u64::MAX);
+        RuntimeReadiness {
+            recovered: recovered_at != 0,
+            upstream_connected: [&self.wolfx, &self.fanstudio, &self.huania, &self.p2pquake]
+                .iter()
+                .any(|metrics| metrics.connected.load(Ordering::Relaxed)),
+            within_grace_period: recovered_at != 0
+                && current_epoch_ms().saturating_sub(recovered_at) <= grace_ms,
+        }
+    }
+
+    /// 记录一次推送是否到达 Bark 服务；Bark 明确拒绝（永久错误）也算可达。
+    pub(crate) fn record_notifier_reachable(&self, reachable: bool) {
+        if reachable {
+            self.notifier_consecutive_failures
+                .store(0, Ordering::Relaxed);
+            self.notifier_last_reachable_epoch_ms
+                .store(current_epoch_ms(), Ordering::Relaxed);
+        } else {
+            self.notifier_consecutive_failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn notifier_health(&self) -> NotifierHealth {
+        let consecutive_failures = self.notifier_consecutive_failures.load(Ordering::Relaxed);
+        let last_reachable = self
+            .notifier_last_reachable_epoch_ms
+            .load(Ordering::Relaxed);
+        NotifierHealth {
+            degraded: consecutive_failures >= NOTIFIER_DEGRADED_FAILURES,
+            consecutive_failures,
+            last_reachable_epoch_ms: (last_reachable != 0).then_some(last_reachable),
+        }
+    }
+
+    pub(super) fn track_delivery_batch(&self) -> InFlight {
+        InFlight::start(&self.active_delivery_batches)
+    }
+
+    pub(super) fn track_retry(&self) -> InFlight {
+        InFlight::start(&self.active_retries)
+    }
+
+    pub(super) fn track_notification(&self) -> InFlight {
+        self.last_dispatch_epoch_ms
+            .store(current_epoch_ms(), Ordering::Relaxed);
+        InFlight::start(&self.notifications_in_flight)
+    }
+
     pub(super) fn inbox_ready_metrics(&self) -> Arc<ReadyQueueMetrics> {
         Arc::clone(&self.inbox_ready)
     }
@@ -121,6 +499,376 @@ impl RuntimeStatus {
     pub(super) fn delivery_ready_metrics(&self) -> Arc<ReadyQueueMetrics> {
         Arc::clone(&self.delivery_ready)
     }
+
+    pub(crate) fn record_dispatch_latency(&self, elapsed: Duration) {
+        self.dispatch_latency.record(elapsed);
+    }
+
+    pub(crate) fn record_deadline_exceeded(&self) {
+        self.deadline_exceeded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 某个目的地首次打开某次事件的通知。
+    pub(crate) fn record_notification_opened(&self) {
+        self.notifications_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_storage_commit_latency(&self, elapsed: Duration) {
+        self.storage_commit_latency.record(elapsed);
+    }
+
+    pub(crate) fn record_shadow_comparison(&self, comparison: &ShadowComparison) {
+        let metrics = &self.shadow_intensity;
+        if comparison.diverged() > 0 {
+            metrics.divergent_jobs.fetch_add(1, Ordering::Relaxed);
+        }
+        metrics.agreed.fetch_add(
+            comparison.evaluated.saturating_sub(comparison.diverged()),
+            Ordering::Relaxed,
+        );
+        metrics.extra.fetch_add(comparison.extra, Ordering::Relaxed);
+        metrics
+            .missed
+            .fetch_add(comparison.missed, Ordering::Relaxed);
+        metrics
+            .level_changed
+            .fetch_add(comparison.level_changed, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_canary(&self, succeeded: bool) {
+        if succeeded {
+            self.canary.succeeded.fetch_add(1, Ordering::Relaxed);
+            self.canary
+                .last_success_epoch_ms
+                .store(current_epoch_ms(), Ordering::Relaxed);
+        } else {
+            self.canary.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 以 Prometheus 文本格式导出运行时指标；只读取内存中的原子计数，不访问存储。
+    pub(crate) fn render_prometheus(&self, output: &mut String) {
+        let name = "disaster_alert_start_time_seconds";
+        output.push_str(&format!(
+            "# HELP {name} 进程启动的 Unix 时间\n# TYPE {name} gauge\n{name} {}\n",
+            self.started.epoch_ms / 1_000
+        ));
+        let name = "disaster_alert_uptime_seconds";
+        output.push_str(&format!(
+            "# HELP {name} 进程已运行的秒数\n# TYPE {name} gauge\n{name} {}\n",
+            self.started.instant.elapsed().as_secs()
+        ));
+        let channels = [
+            ("wolfx", &self.wolfx),
+            ("fanstudio", &self.fanstudio),
+            ("huania", &self.huania),
+            ("p2pquake", &self.p2pquake),
+        ];
+        write_labeled_family(
+            output,
+            "disaster_alert_source_connected",
+            "gauge",
+            "数据源 WebSocket 当前是否已连接",
+            "source",
+            channels.map(|(name, metrics)| {
+                (name, u64::from(metrics.connected.load(Ordering::Relaxed)))
+            }),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_source_messages_total",
+            "counter",
+            "各数据源收到的消息数",
+            "source",
+            channels.map(|(name, metrics)| (name, metrics.messages.load(Ordering::Relaxed))),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_source_parse_errors_total",
+            "counter",
+            "各数据源解析失败的消息数",
+            "source",
+            channels.map(|(name, metrics)| (name, metrics.parse_errors.load(Ordering::Relaxed))),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_source_reconnects_total",
+            "counter",
+            "各数据源 WebSocket 重连次数",
+            "source",
+            channels.map(|(name, metrics)| (name, metrics.reconnects.load(Ordering::Relaxed))),
+        );
+        let now = current_epoch_ms();
+        write_labeled_family(
+            output,
+            "disaster_alert_source_disconnected_seconds_total",
+            "counter",
+            "各数据源首次连上后累计断线的秒数",
+            "source",
+            channels.map(|(name, metrics)| (name, metrics.disconnected_ms(now) / 1_000)),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_source_longest_gap_seconds",
+            "gauge",
+            "各数据源相邻上游消息之间的最长间隔秒数",
+            "source",
+            channels.map(|(name, metrics)| (name, metrics.longest_message_gap_ms(now) / 1_000)),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_notifications_sent_total",
+            "counter",
+            "按数据源统计的 Bark 推送成功数",
+            "source",
+            channels.map(|(name, metrics)| {
+                (
+                    name,
+                    metrics.notifications_succeeded.load(Ordering::Relaxed),
+                )
+            }),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_notifications_failed_total",
+            "counter",
+            "按数据源统计的 Bark 推送失败数",
+            "source",
+            channels.map(|(name, metrics)| {
+                (name, metrics.notifications_failed.load(Ordering::Relaxed))
+            }),
+        );
+
+        let queues = [
+            ("inbox", self.inbox_ready.snapshot()),
+            ("matching", self.match_ready.snapshot()),
+            ("delivery", self.delivery_ready.snapshot()),
+        ];
+        write_labeled_family(
+            output,
+            "disaster_alert_ready_queue_depth",
+            "gauge",
+            "内存就绪队列中的条目数",
+            "queue",
+            queues
+                .map(|(name, snapshot)| (name, u64::try_from(snapshot.depth).unwrap_or(u64::MAX))),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_ready_queue_bytes",
+            "gauge",
+            "内存就绪队列占用的估算字节数",
+            "queue",
+            queues
+                .map(|(name, snapshot)| (name, u64::try_from(snapshot.bytes).unwrap_or(u64::MAX))),
+        );
+        write_labeled_family(
+            output,
+            "disaster_alert_ready_queue_backpressure_total",
+            "counter",
+            "就绪队列已满而回退到持久化扫描的次数",
+            "queue",
+            queues.map(|(name, snapshot)| (name, snapshot.backpressure)),
+        );
+
+        self.dispatch_latency.render(
+            output,
+            "disaster_alert_dispatch_duration_seconds",
+            "单次 Bark 推送请求的耗时",
+        );
+        let name = "disaster_alert_delivery_deadline_exceeded_total";
+        output.push_str(&format!(
+            "# HELP {name} 超过推送时限而丢弃的地震预警推送数\n# TYPE {name} counter\n{name} {}\n",
+            self.deadline_exceeded.load(Ordering::Relaxed)
+        ));
+        let name = "disaster_alert_notifications_opened_total";
+        output.push_str(&format!(
+            "# HELP {name} 被订阅者打开的通知数，同一目的地的同一事件只计一次\n# TYPE {name} counter\n{name} {}\n",
+            self.notifications_opened.load(Ordering::Relaxed)
+        ));
+        self.storage_commit_latency.render(
+            output,
+            "disaster_alert_storage_commit_duration_seconds",
+            "数据源事件写入持久化存储的耗时",
+        );
+
+        let shadow = &self.shadow_intensity;
+        write_labeled_family(
+            output,
+            "disaster_alert_shadow_intensity_decisions_total",
+            "counter",
+            "影子震度模型与生效模型对候选订阅推送决定的比较结果",
+            "outcome",
+            [
+                ("agreed", shadow.agreed.load(Ordering::Relaxed)),
+                ("extra", shadow.extra.load(Ordering::Relaxed)),
+                ("missed", shadow.missed.load(Ordering::Relaxed)),
+                (
+                    "level_changed",
+                    shadow.level_changed.load(Ordering::Relaxed),
+                ),
+            ],
+        );
+        let name = "disaster_alert_shadow_intensity_divergent_jobs_total";
+        output.push_str(&format!(
+            "# HELP {name} 影子震度模型给出不同推送决定的匹配任务数\n# TYPE {name} counter\n{name} {}\n",
+            shadow.divergent_jobs.load(Ordering::Relaxed)
+        ));
+
+        write_labeled_family(
+            output,
+            "disaster_alert_canary_checks_total",
+            "counter",
+            "链路自检结果",
+            "outcome",
+            [
+                ("succeeded", self.canary.succeeded.load(Ordering::Relaxed)),
+                ("failed", self.canary.failed.load(Ordering::Relaxed)),
+            ],
+        );
+        let name = "disaster_alert_canary_last_success_timestamp_seconds";
+        output.push_str(&format!(
+            "# HELP {name} 最近一次链路自检成功的 Unix 时间，0 表示尚未成功\n# TYPE {name} gauge\n{name} {}\n",
+            self.canary.last_success_epoch_ms.load(Ordering::Relaxed) / 1_000
+        ));
+
+        let name = "disaster_alert_source_parse_errors_by_kind_total";
+        output.push_str(&format!(
+            "# HELP {name} 各数据源按失败环节统计的解析失败数\n# TYPE {name} counter\n"
+        ));
+        for (source, metrics) in channels {
+            let failures = metrics
+                .parse_failures
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            for (kind, count) in &failures.by_kind {
+                output.push_str(&format!(
+                    "{name}{{source=\"{source}\",kind=\"{kind}\"}} {count}\n"
+                ));
+            }
+        }
+
+        let name = "disaster_alert_http_request_duration_seconds";
+        output.push_str(&format!(
+            "# HELP {name} HTTP 请求按方法、路由模板和状态码统计的耗时\n# TYPE {name} histogram\n"
+        ));
+        let http_requests = self
+            .http_requests
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        for (key, histogram) in http_requests.iter() {
+            histogram.render_samples(
+                output,
+                name,
+                &format!(
+                    "method=\"{}\",route=\"{}\",status=\"{}\"",
+                    key.method,
+                    escape_label_value(&key.route),
+                    key.status
+                ),
+            );
+        }
+    }
+
+    /// 记录一次 HTTP 请求。`route` 应为路由模板而非原始路径，以限制时间序列数量。
+    pub(crate) fn record_http_request(
+        &self,
+        method: &'static str,
+        route: &str,
+        status: u16,
+        elapsed: Duration,
+    ) {
+        let key = HttpRouteKey {
+            method,
+            route: route.to_string(),
+            status,
+        };
+        self.http_requests
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .entry(key)
+            .or_default()
+            .record(elapsed);
+    }
+}
+
+fn truncate(value: &str, max_bytes: usize) -> String {
+    if value.len() <= max_bytes {
+        return value.to_string();
+    }
+    let mut end = max_bytes;
+    while !value.is_char_boundary(end) {
+        end = end.saturating_sub(1);
+    }
+    value[..end].to_string()
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl LatencyHistogram {
+    fn record(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        if let Some(index) = LATENCY_BUCKETS_SECONDS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            && let Some(bucket) = self.buckets.get(index)
+        {
+            bucket.fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(
+            u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn render(&self, output: &mut String, name: &str, help: &str) {
+        output.push_str(&format!("# HELP {name} {help}\n# TYPE {name} histogram\n"));
+        self.render_samples(output, name, "");
+    }
+
+    /// `labels` 为已格式化的 `key="value"` 列表，空字符串表示无标签。
+    fn render_samples(&self, output: &mut String, name: &str, labels: &str) {
+        let (series, bucket_prefix) = if labels.is_empty() {
+            (String::new(), String::new())
+        } else {
+            (format!("{{{labels}}}"), format!("{labels},"))
+        };
+        let mut cumulative = 0_u64;
+        for (bound, bucket) in LATENCY_BUCKETS_SECONDS.iter().zip(&self.buckets) {
+            cumulative = cumulative.saturating_add(bucket.load(Ordering::Relaxed));
+            output.push_str(&format!(
+                "{name}_bucket{{{bucket_prefix}le=\"{bound}\"}} {cumulative}\n"
+            ));
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum_micros = self.sum_micros.load(Ordering::Relaxed);
+        output.push_str(&format!(
+            "{name}_bucket{{{bucket_prefix}le=\"+Inf\"}} {count}\n{name}_sum{series} {}.{:06}\n{name}_count{series} {count}\n",
+            sum_micros / 1_000_000,
+            sum_micros % 1_000_000,
+        ));
+    }
+}
+
+/// 写出一个带单一标签的指标族；标签值均为内部常量，无需转义。
+pub(crate) fn write_labeled_family<const N: usize>(
+    output: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    label: &str,
+    values: [(&str, u64); N],
+) {
+    output.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+    for (value_label, value) in values {
+        output.push_str(&format!("{name}{{{label}=\"{value_label}\"}} {value}\n"));
+    }
 }
 
 impl ReadyQueueMetrics {
@@ -144,21 +892,120 @@ impl ReadyQueueMetrics {
 
 impl ChannelMetrics {
     pub(crate) fn set_connected(&self, connected: bool) {
-        self.connected.store(connected, Ordering::Relaxed);
+        let was_connected = self.connected.swap(connected, Ordering::Relaxed);
+        let now = current_epoch_ms();
+        if connected && !was_connected {
+            let since = self.disconnected_since_epoch_ms.swap(0, Ordering::Relaxed);
+            if since != 0 {
+                self.disconnected_ms
+                    .fetch_add(now.saturating_sub(since), Ordering::Relaxed);
+            }
+        } else if !connected && was_connected {
+            self.disconnected_since_epoch_ms
+                .store(now, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
     }
 
     pub(crate) fn record_message(&self) {
         self.messages.fetch_add(1, Ordering::Relaxed);
-        self.last_message_epoch_ms
-            .store(current_epoch_ms(), Ordering::Relaxed);
+        let now = current_epoch_ms();
+        let previous = self.last_message_epoch_ms.swap(now, Ordering::Relaxed);
+        if previous != 0 {
+            self.longest_message_gap_ms
+                .fetch_max(now.saturating_sub(previous), Ordering::Relaxed);
+        }
+    }
+
+    /// 当前这次断线已持续的时长；连接正常或从未断线时返回 `None`。
+    pub(crate) fn current_disconnect(&self) -> Option<Duration> {
+        let since = self.disconnected_since_epoch_ms.load(Ordering::Relaxed);
+        (since != 0).then(|| Duration::from_millis(current_epoch_ms().saturating_sub(since)))
+    }
+
+    fn disconnected_ms(&self, now: u64) -> u64 {
+        let since = self.disconnected_since_epoch_ms.load(Ordering::Relaxed);
+        let ongoing = if since == 0 {
+            0
+        } else {
+            now.saturating_sub(since)
+        };
+        self.disconnected_ms
+            .load(Ordering::Relaxed)
+            .saturating_add(ongoing)
+    }
+
+    fn longest_message_gap_ms(&self, now: u64) -> u64 {
+        let last_message = self.last_message_epoch_ms.load(Ordering::Relaxed);
+        let current = if last_message == 0 {
+            0
+        } else {
+            now.saturating_sub(last_message)
+        };
+        self.longest_message_gap_ms
+            .load(Ordering::Relaxed)
+            .max(current)
     }
 
     pub(crate) fn record_reconnect(&self) {
         self.reconnects.fetch_add(1, Ordering::Relaxed);
     }
 
-    pub(crate) fn record_parse_error(&self) {
+    /// 记录一次解析失败。`kind` 区分失败环节，`payload` 为导致失败的原始消息（若仍可用）。
+    pub(crate) fn record_parse_error(
+        &self,
+        kind: &'static str,
+        error: &dyn Display,
+        payload: Option<&str>,
+    ) {
         self.parse_errors.fetch_add(1, Ordering::Relaxed);
+        let sample = ParseFailureSample {
+            kind,
+            error: truncate(&error.to_string(), MAX_PARSE_FAILURE_ERROR_BYTES),
+            payload: payload.map(|payload| truncate(payload, MAX_PARSE_FAILURE_PAYLOAD_BYTES)),
+            payload_truncated: payload
+                .is_some_and(|payload| payload.len() > MAX_PARSE_FAILURE_PAYLOAD_BYTES),
+            failed_at_ms: current_epoch_ms(),
+        };
+        let mut failures = self
+            .parse_failures
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let count = failures.by_kind.entry(kind).or_default();
+        *count = count.saturating_add(1);
+        if failures.recent.len() >= RECENT_PARSE_FAILURES {
+            failures.recent.pop_back();
+        }
+        failures.recent.push_front(sample);
+    }
+
+    fn parse_diagnostics(&self) -> ChannelParseDiagnostics {
+        let failures = self
+            .parse_failures
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        ChannelParseDiagnostics {
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+            by_kind: failures.by_kind.clone(),
+            recent: failures.recent.iter().cloned().collect(),
+        }
+    }
+
+    pub(crate) fn record_event(&self, event: &DisasterEvent) {
+        let last_event = LastEventSnapshot {
+            category: event.category,
+            source: event.source.clone(),
+            event_id: event.event_id.clone(),
+            title: event.title.clone(),
+            received_at_ms: current_epoch_ms(),
+        };
+        *self
+            .last_event
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = Some(last_event);
     }
 
     pub(crate) fn record_notification(&self, succeeded: bool) {
@@ -170,22 +1017,251 @@ impl ChannelMetrics {
     }
 
     fn snapshot(&self) -> ChannelSnapshot {
+        let now = current_epoch_ms();
         let last_message = self.last_message_epoch_ms.load(Ordering::Relaxed);
+        let last_message = (last_message != 0).then_some(last_message);
         ChannelSnapshot {
             connected: self.connected.load(Ordering::Relaxed),
-            last_message_epoch_ms: (last_message != 0).then_some(last_message),
+            last_message_epoch_ms: last_message,
             reconnects: self.reconnects.load(Ordering::Relaxed),
             messages: self.messages.load(Ordering::Relaxed),
             parse_errors: self.parse_errors.load(Ordering::Relaxed),
             notifications_succeeded: self.notifications_succeeded.load(Ordering::Relaxed),
             notifications_failed: self.notifications_failed.load(Ordering::Relaxed),
+            seconds_since_last_message: last_message
+                .map(|epoch_ms| now.saturating_sub(epoch_ms) / 1_000),
+            disconnected_seconds: self.disconnected_ms(now) / 1_000,
+            longest_message_gap_seconds: self.longest_message_gap_ms(now) / 1_000,
+            last_event: self
+                .last_event
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .clone(),
         }
     }
 }
 
+impl InFlight {
+    fn start(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(Arc::clone(counter))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 fn current_epoch_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let status = RuntimeStatus::default();
+        status.record_dispatch_latency(Duration::from_millis(3));
+        status.record_dispatch_latency(Duration::from_millis(40));
+        status.record_dispatch_latency(Duration::from_secs(30));
+        status.wolfx().record_message();
+
+        let mut output = String::new();
+        status.render_prometheus(&mut output);
+
+        assert!(output.contains("disaster_alert_source_messages_total{source=\"wolfx\"} 1"));
+        assert!(output.contains("disaster_alert_dispatch_duration_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(output.contains("disaster_alert_dispatch_duration_seconds_bucket{le=\"0.05\"} 2"));
+        assert!(output.contains("disaster_alert_dispatch_duration_seconds_bucket{le=\"10\"} 2"));
+        assert!(output.contains("disaster_alert_dispatch_duration_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(output.contains("disaster_alert_dispatch_duration_seconds_sum 30.043000"));
+        assert!(output.contains("disaster_alert_storage_commit_duration_seconds_count 0"));
+    }
+
+    #[test]
+    fn http_histograms_are_labeled_by_route_template() {
+        let status = RuntimeStatus::default();
+        status.record_http_request("GET", "/api/status", 200, Duration::from_millis(2));
+        status.record_http_request("GET", "/api/status", 200, Duration::from_millis(80));
+
+        let mut output = String::new();
+        status.render_prometheus(&mut output);
+
+        assert!(output.contains(
+            "disaster_alert_http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/status\",status=\"200\",le=\"0.005\"} 1"
+        ));
+        assert!(output.contains(
+            "disaster_alert_http_request_duration_seconds_count{method=\"GET\",route=\"/api/status\",status=\"200\"} 2"
+        ));
+    }
+
+    #[test]
+    fn shadow_intensity_comparisons_are_accumulated() {
+        let status = RuntimeStatus::default();
+        status.record_shadow_comparison(&ShadowComparison {
+            evaluated: 5,
+            extra: 1,
+            missed: 0,
+            level_changed: 1,
+        });
+        status.record_shadow_comparison(&ShadowComparison {
+            evaluated: 2,
+            ..ShadowComparison::default()
+        });
+
+        let mut output = String::new();
+        status.render_prometheus(&mut output);
+
+        assert!(
+            output
+                .contains("disaster_alert_shadow_intensity_decisions_total{outcome=\"agreed\"} 5")
+        );
+        assert!(
+            output.contains("disaster_alert_shadow_intensity_decisions_total{outcome=\"extra\"} 1")
+        );
+        assert!(output.contains("disaster_alert_shadow_intensity_divergent_jobs_total 1"));
+    }
+
+    #[test]
+    fn readiness_requires_recovery_and_a_connected_source_after_grace() {
+        let status = RuntimeStatus::default();
+        assert!(!status.readiness(Duration::from_secs(60)).is_ready());
+
+        status.mark_recovered();
+        assert!(status.readiness(Duration::from_secs(60)).is_ready());
+
+        status.huania().set_connected(true);
+        assert!(status.readiness(Duration::ZERO).is_ready());
+    }
+
+    #[test]
+    fn notifier_degrades_after_consecutive_failures() {
+        let status = RuntimeStatus::default();
+        for _ in 0..NOTIFIER_DEGRADED_FAILURES {
+            status.record_notifier_reachable(false);
+        }
+        assert!(status.notifier_health().degraded);
+
+        status.record_notifier_reachable(true);
+        let health = status.notifier_health();
+        assert!(!health.degraded);
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_reachable_epoch_ms.is_some());
+    }
+
+    #[test]
+    fn parse_failures_keep_recent_truncated_samples() {
+        let status = RuntimeStatus::default();
+        let payload = "震".repeat(MAX_PARSE_FAILURE_PAYLOAD_BYTES);
+        for _ in 0..=RECENT_PARSE_FAILURES {
+            status
+                .wolfx()
+                .record_parse_error("parse_failed", &"missing field", Some(&payload));
+        }
+        status
+            .wolfx()
+            .record_parse_error("missing_type", &"no type", None);
+
+        let diagnostics = status.parse_diagnostics();
+        assert_eq!(diagnostics.wolfx.parse_errors, 7);
+        assert_eq!(diagnostics.wolfx.by_kind.get("parse_failed"), Some(&6));
+        assert_eq!(diagnostics.wolfx.recent.len(), RECENT_PARSE_FAILURES);
+        assert_eq!(
+            diagnostics.wolfx.recent.first().map(|sample| sample.kind),
+            Some("missing_type")
+        );
+        let truncated = diagnostics.wolfx.recent.get(1);
+        assert!(truncated.is_some_and(|sample| {
+            sample.payload_truncated
+                && sample
+                    .payload
+                    .as_ref()
+                    .is_some_and(|payload| payload.len() <= MAX_PARSE_FAILURE_PAYLOAD_BYTES)
+        }));
+        assert!(diagnostics.fanstudio.recent.is_empty());
+    }
+
+    #[test]
+    fn source_snapshots_list_every_source_with_its_own_counters() {
+        let status = RuntimeStatus::default();
+        status.record_source_events(
+            ["wolfx.fj_eew", "wolfx.fj_eew", "unknown.source"],
+            Duration::from_millis(30),
+        );
+        status.record_source_parse_error(ProviderChannel::Wolfx, "fj_eew");
+        status.record_source_parse_error(ProviderChannel::Wolfx, "unknown");
+
+        let sources = status.source_snapshots();
+        assert_eq!(sources.len(), source_registry::SOURCES.len());
+        let fujian = sources
+            .iter()
+            .find(|source| source.source == "wolfx.fj_eew");
+        assert!(fujian.is_some_and(|source| source.events_received == 2
+            && source.parse_errors == 1
+            && source.last_event_epoch_ms.is_some()
+            && source.average_processing_ms == Some(30.0)));
+        let quiet = sources
+            .iter()
+            .find(|source| source.source == "wolfx.jma_eew");
+        assert!(quiet.is_some_and(|source| source.events_received == 0
+            && source.last_event_epoch_ms.is_none()
+            && source.average_processing_ms.is_none()));
+    }
+
+    #[test]
+    fn disconnections_and_message_gaps_are_accumulated() {
+        let metrics = ChannelMetrics::default();
+        // 从未连上时不计断线时长。
+        metrics.set_connected(false);
+        assert_eq!(metrics.disconnected_ms(current_epoch_ms() + 60_000), 0);
+
+        metrics.set_connected(true);
+        metrics.set_connected(false);
+        let since = metrics.disconnected_since_epoch_ms.load(Ordering::Relaxed);
+        assert_ne!(since, 0);
+        metrics
+            .disconnected_since_epoch_ms
+            .store(since - 5_000, Ordering::Relaxed);
+        metrics.set_connected(true);
+        let disconnected = metrics.disconnected_ms(current_epoch_ms());
+        assert!((5_000..10_000).contains(&disconnected));
+        assert_eq!(
+            metrics.disconnected_since_epoch_ms.load(Ordering::Relaxed),
+            0
+        );
+
+        metrics.record_message();
+        let last = metrics.last_message_epoch_ms.load(Ordering::Relaxed);
+        metrics
+            .last_message_epoch_ms
+            .store(last - 7_000, Ordering::Relaxed);
+        metrics.record_message();
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.longest_message_gap_seconds, 7);
+        assert_eq!(snapshot.disconnected_seconds, 5);
+    }
+
+    #[test]
+    fn dispatch_snapshot_tracks_in_flight_work() {
+        let status = RuntimeStatus::default();
+        let batch = status.track_delivery_batch();
+        let notification = status.track_notification();
+        let snapshot = status.dispatch_snapshot();
+        assert_eq!(snapshot.active_delivery_batches, 1);
+        assert_eq!(snapshot.notifications_in_flight, 1);
+        assert!(snapshot.last_dispatch_epoch_ms.is_some());
+
+        drop(notification);
+        drop(batch);
+        let snapshot = status.dispatch_snapshot();
+        assert_eq!(snapshot.active_delivery_batches, 0);
+        assert_eq!(snapshot.notifications_in_flight, 0);
+    }
+}
@@ -104,6 +104,16 @@ pub(crate) const SOURCES: &[SourceDefinition] = &[
         "Wolfx 重庆地震局",
         Some(8 * 3600)
     ),
+    source!(
+        "wolfx.cwa_eew",
+        "cwa_eew",
+        Wolfx,
+        EarthquakeWarning,
+        "wolfx-earthquake-warning",
+        "Wolfx 地震预警",
+        "Wolfx 台湾气象署",
+        Some(8 * 3600)
+    ),
     source!(
         "fanstudio.cea",
         "cea",
@@ -324,6 +334,16 @@ pub(crate) const SOURCES: &[SourceDefinition] = &[
         "FSSN CMT",
         None
     ),
+    source!(
+        "p2pquake.jma_quake",
+        "jma_quake",
+        P2pquake,
+        EarthquakeReport,
+        "p2pquake-earthquake-report",
+        "P2P地震情報",
+        "日本气象厅地震情报",
+        Some(9 * 3600)
+    ),
     source!(
         "fanstudio.weatheralarm",
         "weatheralarm",
@@ -1,7 +1,7 @@
-use super::{FjallStorage, try_now_millis};
+use super::{FeltReportGroups, FeltReportOutcome, FjallStorage, StorageProtection, try_now_millis};
 #[cfg(feature = "migration")]
 use crate::models::Subscription;
-use crate::models::{IncidentId, IncidentRecord};
+use crate::models::{DisasterCategory, DisasterEvent, FeltReport, IncidentId, IncidentRecord};
 use crate::subscriptions::SubscriptionManager;
 use anyhow::{Context, Result};
 use std::path::Path;
@@ -17,13 +17,24 @@ pub(crate) struct RetentionPolicy {
     pub(crate) incident_days: u64,
     pub(crate) delivery_ledger_days: u64,
     pub(crate) operation_days: u64,
+    pub(crate) subscription_restore_days: u64,
+    /// 近期永久失败达到该次数且没有成功送达的订阅会被自动取消；0 表示不清理。
+    pub(crate) undeliverable_failure_threshold: u32,
 }
 
+/// 统计永久失败的时间窗口。
+const UNDELIVERABLE_WINDOW_DAYS: u64 = 14;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub(crate) struct PruneStats {
     pub(crate) incidents: usize,
     pub(crate) delivery_records: usize,
     pub(crate) events: usize,
+    pub(crate) earthquake_history: usize,
+    pub(crate) felt_reports: usize,
+    pub(crate) notification_opens: usize,
+    pub(crate) subscriptions: usize,
+    pub(crate) undeliverable_subscriptions: usize,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -32,6 +43,101 @@ pub(crate) struct BacklogCounts {
     pub(crate) match_jobs: usize,
     pub(crate) delivery_batches: usize,
     pub(crate) retries: usize,
+    pub(crate) dead_letters: usize,
+}
+
+/// 启动预热的统计结果。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct WarmupReport {
+    pub(crate) subscriptions: usize,
+    pub(crate) targets: usize,
+    pub(crate) posting_keys: usize,
+    /// 每个 H3 二级格子中的监测点数量，按数量从多到少排列。
+    pub(crate) coarse_cells: Vec<(u64, usize)>,
+}
+
+/// 订阅在地图上的分布，供管理员绘制覆盖热力图。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SubscriptionDensity {
+    /// 每个 H3 格子中有监测点的订阅数，同一订阅的多个监测点落在同一格子只算一次；
+    /// 按数量从多到少排列。
+    pub(crate) cells: Vec<(u64, usize)>,
+    /// 按地区订阅的订阅数，按内置边界下标统计，排序同上。
+    pub(crate) regions: Vec<(u16, usize)>,
+}
+
+/// 生效订阅按提醒阈值、推送渠道、创建时间和大致地区的分布，供管理员了解订阅人群。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SubscriptionBreakdown {
+    pub(crate) subscriptions: usize,
+    /// 地震预警规则中最低的提醒烈度，没有地震预警规则的订阅不计入；按烈度从低到高排列。
+    pub(crate) min_intensity: Vec<(u8, usize)>,
+    /// 推送渠道类型，按数量从多到少排列。
+    pub(crate) channels: Vec<(&'static str, usize)>,
+    /// 创建时间所在周（UTC 周一零点，Unix 毫秒），按时间先后排列。
+    pub(crate) created_weeks: Vec<(i64, usize)>,
+    /// 监测点所在的内置边界下标，同一订阅的多个监测点在同一地区只算一次；
+    /// 不在任何内置边界内的坐标记为 `None`。按数量从多到少排列。
+    pub(crate) regions: Vec<(Option<u16>, usize)>,
+}
+
+/// 历史地震查询条件；时间范围按发生时间（Unix 毫秒）取闭区间。
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EarthquakeQuery {
+    pub(crate) from_ms: i64,
+    pub(crate) to_ms: i64,
+    /// 经纬度范围 `[西, 南, 东, 北]`，西经度大于东经度时表示跨越 180° 经线。
+    pub(crate) bbox: Option<[f64; 4]>,
+    pub(crate) min_magnitude: Option<f64>,
+    pub(crate) category: Option<DisasterCategory>,
+    pub(crate) source: Option<String>,
+    pub(crate) limit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EarthquakeSearch {
+    /// 按发生时间从新到旧排列。
+    pub(crate) events: Vec<DisasterEvent>,
+    /// 结果超过 `limit` 或扫描量达到上限而未查完时为 true，应缩小范围后再查。
+    pub(crate) truncated: bool,
+}
+
+impl EarthquakeQuery {
+    /// 是否满足时间以外的条件；设置了范围或震级时，缺少坐标或震级的事件不算满足。
+    pub(crate) fn matches(&self, event: &DisasterEvent) -> bool {
+        if self
+            .category
+            .is_some_and(|category| category != event.category)
+        {
+            return false;
+        }
+        if self
+            .source
+            .as_ref()
+            .is_some_and(|source| *source != event.source)
+        {
+            return false;
+        }
+        if let Some(min_magnitude) = self.min_magnitude
+            && !event
+                .magnitude
+                .is_some_and(|magnitude| magnitude >= min_magnitude)
+        {
+            return false;
+        }
+        let Some([west, south, east, north]) = self.bbox else {
+            return true;
+        };
+        let (Some(latitude), Some(longitude)) = (event.latitude, event.longitude) else {
+            return false;
+        };
+        let within_longitude = if west <= east {
+            (west..=east).contains(&longitude)
+        } else {
+            longitude >= west || longitude <= east
+        };
+        (south..=north).contains(&latitude) && within_longitude
+    }
 }
 
 impl PruneStats {
@@ -40,16 +146,31 @@ impl PruneStats {
         self.incidents
             .saturating_add(self.delivery_records)
             .saturating_add(self.events)
+            .saturating_add(self.earthquake_history)
+            .saturating_add(self.felt_reports)
+            .saturating_add(self.notification_opens)
+            .saturating_add(self.subscriptions)
+            .saturating_add(self.undeliverable_subscriptions)
     }
 }
 
 impl Storage {
+    #[cfg(any(test, feature = "benchmarks", feature = "migration"))]
     pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
         Ok(Self {
             inner: FjallStorage::open(path)?,
         })
     }
 
+    pub(crate) fn open_with_protection(
+        path: impl AsRef<Path>,
+        protection: StorageProtection,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: FjallStorage::open_with_protection(path, protection)?,
+        })
+    }
+
     #[must_use]
     pub(crate) fn subscription_manager(&self) -> SubscriptionManager {
         SubscriptionManager::new(self.inner.clone())
@@ -59,21 +180,113 @@ impl Storage {
         Ok(self.inner.incident(id)?.map(Arc::new))
     }
 
+    pub(crate) fn probe(&self) -> Result<()> {
+        self.inner.probe()
+    }
+
+    pub(crate) fn ping(&self) -> Result<()> {
+        self.inner.ping(try_now_millis()?)
+    }
+
     pub(crate) fn backlog_counts(&self) -> Result<BacklogCounts> {
         self.inner.backlog_counts()
     }
 
+    pub(crate) fn warm_up_subscriptions(&self) -> Result<WarmupReport> {
+        self.inner.warm_up_subscriptions()
+    }
+
+    pub(crate) fn subscription_density(
+        &self,
+        resolution: h3o::Resolution,
+    ) -> Result<SubscriptionDensity> {
+        self.inner.subscription_density(resolution)
+    }
+
+    pub(crate) fn subscription_breakdown(&self) -> Result<SubscriptionBreakdown> {
+        self.inner.subscription_breakdown()
+    }
+
+    pub(crate) fn import_earthquake_history(&self, events: &[DisasterEvent]) -> Result<usize> {
+        self.inner.import_earthquake_history(events)
+    }
+
+    pub(crate) fn search_earthquakes(&self, query: &EarthquakeQuery) -> Result<EarthquakeSearch> {
+        self.inner.search_earthquakes(query)
+    }
+
+    pub(crate) fn earthquake_history_changed_ms(&self) -> i64 {
+        self.inner.earthquake_history_changed_ms()
+    }
+
+    pub(crate) fn earthquake_history_event(
+        &self,
+        sources: &[&str],
+        event_id: &str,
+    ) -> Result<Option<DisasterEvent>> {
+        self.inner.earthquake_history_event(sources, event_id)
+    }
+
+    pub(crate) fn record_felt_report(
+        &self,
+        event_key: &str,
+        network: &[u8],
+        report: &FeltReport,
+    ) -> Result<FeltReportOutcome> {
+        self.inner.record_felt_report(event_key, network, report)
+    }
+
+    pub(crate) fn felt_reports(&self, event_key: &str) -> Result<Vec<FeltReport>> {
+        self.inner.felt_reports(event_key)
+    }
+
+    pub(crate) fn felt_report_events(&self) -> Result<(FeltReportGroups, bool)> {
+        self.inner.felt_report_events()
+    }
+
+    pub(crate) fn record_notification_open(
+        &self,
+        destination_id: u64,
+        incident_id: &IncidentId,
+        source: &str,
+        event_id: &str,
+    ) -> Result<bool> {
+        self.inner.record_notification_open(
+            destination_id,
+            incident_id,
+            source,
+            event_id,
+            try_now_millis()?,
+        )
+    }
+
     pub(crate) fn prune_retained_data(&self, policy: RetentionPolicy) -> Result<PruneStats> {
         let now = try_now_millis()?;
+        let undeliverable_subscriptions = if policy.undeliverable_failure_threshold > 0 {
+            self.inner.deactivate_undeliverable_subscriptions(
+                now.saturating_sub(days_ms(UNDELIVERABLE_WINDOW_DAYS)),
+                policy.undeliverable_failure_threshold,
+            )?
+        } else {
+            0
+        };
         let stats = self.inner.prune(
             now.saturating_sub(days_ms(policy.incident_days)),
             now.saturating_sub(days_ms(policy.delivery_ledger_days)),
             now.saturating_sub(days_ms(policy.operation_days)),
         )?;
+        let subscriptions = self.inner.purge_expired_subscriptions(
+            now.saturating_sub(days_ms(policy.subscription_restore_days)),
+        )?;
         Ok(PruneStats {
             incidents: stats.incidents,
             delivery_records: stats.delivery_records,
             events: stats.events,
+            earthquake_history: stats.earthquake_history,
+            felt_reports: stats.felt_reports,
+            notification_opens: stats.notification_opens,
+            subscriptions,
+            undeliverable_subscriptions,
         })
     }
 
@@ -1,28 +1,44 @@
-use super::{decode_record, encode_record};
+use super::protection::{is_sealed_record, unkeyed_destination_digest};
+use super::{StorageProtection, decode_record, encode_record};
 use crate::delivery::{DeadLetterItem, DeliveryBatch, DeliverySuccess, RetryItem};
 use crate::events::MatchJob;
 use crate::matching::{MatchPlan, MatchScope, PostingBlock};
 use crate::models::{
-    DisasterCategory, DisasterEvent, IncidentCapacity, IncidentId, IncidentRecord, ProviderChannel,
-    Subscription, parse_event_epoch,
+    AlertRule, DisasterCategory, DisasterEvent, FeltReport, IncidentCapacity, IncidentId,
+    IncidentRecord, ProviderChannel, Subscription, event_key_for, parse_event_epoch,
+    parse_event_epoch_ms,
 };
 use crate::subscriptions::{
-    CompiledSubscription, DestinationNumericId, MatchPostingKey, SubscriptionCompiler,
-    SubscriptionId,
+    CompiledSubscription, DestinationNumericId, H3_RESOLUTIONS, MatchPostingKey,
+    SubscriptionCompiler, SubscriptionId,
 };
 use anyhow::{Context, Result};
 use fjall::{Database, Keyspace, KeyspaceCreateOptions, PersistMode};
 use roaring::RoaringBitmap;
 use std::io::Cursor;
 use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 
 const FORMAT_VERSION: &[u8] = b"1";
+const SUBSCRIPTIONS: &str = "subscriptions";
+const COMPILED_SUBSCRIPTIONS: &str = "compiled_subscriptions";
+const META: &str = "meta";
 const MAX_RECORD_BYTES: usize = 512 * 1024;
 const CORRELATION_WINDOW_SECONDS: i64 = 120;
 const CORRELATION_DISTANCE_KM: f64 = 100.0;
 const CORRELATION_MAGNITUDE_DELTA: f64 = 1.0;
 const MAX_CORRELATION_CANDIDATES: usize = 1_024;
+/// 单次历史地震查询最多扫描的记录数，避免过宽的时间范围拖慢存储。
+const MAX_EARTHQUAKE_HISTORY_SCAN: usize = 20_000;
+/// 每次地震最多保存的震感报告数，超过后不再接收，限制刷报告占用的存储。
+const MAX_FELT_REPORTS_PER_EVENT: usize = 10_000;
+/// 汇总全部震感报告时最多读取的报告数。
+const MAX_FELT_REPORT_SCAN: usize = 200_000;
+/// 震感报告键末尾报告者摘要的长度。
+const FELT_REPORTER_DIGEST_LEN: usize = 32;
+/// 启用静态保护时每个写批次改写的记录数上限。
+const PROTECTION_BATCH_RECORDS: usize = 1_024;
 
 #[derive(Clone)]
 pub(crate) struct FjallStorage {
@@ -31,6 +47,8 @@ pub(crate) struct FjallStorage {
     subscription_lock: Arc<Mutex<()>>,
     match_lock: Arc<Mutex<()>>,
     retry_lock: Arc<Mutex<()>>,
+    felt_lock: Arc<Mutex<()>>,
+    open_lock: Arc<Mutex<()>>,
     inbox: Keyspace,
     rejected_inbox: Keyspace,
     incidents: Keyspace,
@@ -39,6 +57,9 @@ pub(crate) struct FjallStorage {
     incident_correlation: Keyspace,
     incident_correlation_by_incident: Keyspace,
     events: Keyspace,
+    earthquake_history: Keyspace,
+    earthquake_history_by_event: Keyspace,
+    felt_reports: Keyspace,
     match_jobs: Keyspace,
     subscriptions: Keyspace,
     subscriptions_by_destination: Keyspace,
@@ -53,7 +74,12 @@ pub(crate) struct FjallStorage {
     dead_letters: Keyspace,
     ledger: Keyspace,
     contexts: Keyspace,
+    notification_opens: Keyspace,
     meta: Keyspace,
+    protection: StorageProtection,
+    /// 历史地震最近一次变更的时间（Unix 毫秒），严格递增，供读接口生成缓存校验值。
+    /// 只保存在内存中，启动时取当前时间。
+    history_changed_ms: Arc<AtomicI64>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -64,6 +90,9 @@ pub(crate) struct StoredSubscription {
     pub(crate) generation: u64,
     pub(crate) active: bool,
     pub(crate) subscription: Subscription,
+    /// 取消订阅的时间；恢复期结束后由保留清理彻底删除。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) deactivated_at_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -102,15 +131,88 @@ struct StoredDelivery {
     row: crate::delivery::DeliveryRow,
 }
 
+/// 某个目的地打开某次事件通知的记录，键为目的地编号和事件编号。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NotificationOpen {
+    pub(crate) incident_id: String,
+    pub(crate) source: String,
+    pub(crate) event_id: String,
+    pub(crate) first_opened_at_ms: i64,
+    pub(crate) last_opened_at_ms: i64,
+    pub(crate) opens: u64,
+}
+
+/// 某个订阅在库中的全部数据，供订阅者导出。
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct SubscriptionExport {
+    pub(crate) subscription_id: SubscriptionId,
+    pub(crate) active: bool,
+    pub(crate) generation: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deactivated_at_ms: Option<i64>,
+    pub(crate) subscription: Subscription,
+    /// 各监测地点的大致位置，与 `subscription.targets` 一一对应，由内置地名索引离线生成。
+    pub(crate) places: Vec<String>,
+    pub(crate) deliveries: Vec<ExportedDelivery>,
+    pub(crate) pending_deliveries: Vec<ExportedPendingDelivery>,
+    pub(crate) retries: Vec<RetryItem>,
+    pub(crate) dead_letters: Vec<DeadLetterItem>,
+    /// 通知被打开的记录。
+    pub(crate) notification_opens: Vec<NotificationOpen>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ExportedDelivery {
+    pub(crate) incident_id: String,
+    pub(crate) category: DisasterCategory,
+    pub(crate) event_revision: u64,
+    pub(crate) delivered_at_ms: i64,
+    pub(crate) row: crate::delivery::DeliveryRow,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ExportedPendingDelivery {
+    pub(crate) batch_id: u64,
+    pub(crate) incident_id: String,
+    pub(crate) category: DisasterCategory,
+    pub(crate) created_at_ms: i64,
+    pub(crate) row: crate::delivery::DeliveryRow,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub(crate) struct StoragePruneStats {
     pub(crate) incidents: usize,
     pub(crate) delivery_records: usize,
     pub(crate) events: usize,
+    pub(crate) earthquake_history: usize,
+    pub(crate) felt_reports: usize,
+    pub(crate) notification_opens: usize,
+}
+
+/// 按地震分组的震感报告。
+pub(crate) type FeltReportGroups = Vec<(DisasterEvent, Vec<FeltReport>)>;
+
+/// 保存震感报告的结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FeltReportOutcome {
+    Recorded,
+    /// 同一网络此前已报告过这次地震，旧报告被替换。
+    Replaced,
+    /// 这次地震的报告数已达上限。
+    EventFull,
 }
 
 impl FjallStorage {
+    #[cfg(any(test, feature = "benchmarks", feature = "migration"))]
     pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_protection(path, StorageProtection::disabled())
+    }
+
+    pub(crate) fn open_with_protection(
+        path: impl AsRef<Path>,
+        protection: StorageProtection,
+    ) -> Result<Self> {
         let db = Database::builder(path)
             .open()
             .context("failed to open Fjall database")?;
@@ -123,6 +225,8 @@ impl FjallStorage {
             subscription_lock: Arc::new(Mutex::new(())),
             match_lock: Arc::new(Mutex::new(())),
             retry_lock: Arc::new(Mutex::new(())),
+            felt_lock: Arc::new(Mutex::new(())),
+            open_lock: Arc::new(Mutex::new(())),
             inbox: keyspace("inbox")?,
             rejected_inbox: keyspace("rejected_inbox")?,
             incidents: keyspace("incidents")?,
@@ -131,10 +235,13 @@ impl FjallStorage {
             incident_correlation: keyspace("incident_correlation")?,
             incident_correlation_by_incident: keyspace("incident_correlation_by_incident")?,
             events: keyspace("events")?,
+            earthquake_history: keyspace("earthquake_history")?,
+            earthquake_history_by_event: keyspace("earthquake_history_by_event")?,
+            felt_reports: keyspace("felt_reports")?,
             match_jobs: keyspace("match_jobs")?,
-            subscriptions: keyspace("subscriptions")?,
+            subscriptions: keyspace(SUBSCRIPTIONS)?,
             subscriptions_by_destination: keyspace("subscriptions_by_destination")?,
-            compiled_subscriptions: keyspace("compiled_subscriptions")?,
+            compiled_subscriptions: keyspace(COMPILED_SUBSCRIPTIONS)?,
             postings: keyspace("postings")?,
             delivery_batches: keyspace("delivery_batches")?,
             delivery_progress: keyspace("delivery_progress")?,
@@ -145,13 +252,38 @@ impl FjallStorage {
             dead_letters: keyspace("dead_letters")?,
             ledger: keyspace("ledger")?,
             contexts: keyspace("contexts")?,
-            meta: keyspace("meta")?,
+            notification_opens: keyspace("notification_opens")?,
+            meta: keyspace(META)?,
+            protection,
+            history_changed_ms: Arc::new(AtomicI64::new(super::try_now_millis()?)),
             db,
         };
         storage.initialize()?;
         Ok(storage)
     }
 
+    /// 读取格式标记，确认数据库仍可读；供就绪探针使用。
+    pub(crate) fn probe(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.meta.get(b"format_version")?.is_some(),
+            "Fjall database format marker is missing"
+        );
+        Ok(())
+    }
+
+    /// 写入并读回探测键，确认数据库仍可写；供健康检查使用。
+    pub(crate) fn ping(&self, now_ms: i64) -> Result<()> {
+        let value = now_ms.to_be_bytes();
+        self.meta.insert(b"health_ping", value)?;
+        anyhow::ensure!(
+            self.meta
+                .get(b"health_ping")?
+                .is_some_and(|stored| stored.as_ref() == value.as_slice()),
+            "Fjall health ping read back a different value"
+        );
+        Ok(())
+    }
+
     fn initialize(&self) -> Result<()> {
         match self.meta.get(b"format_version")? {
             Some(value) => anyhow::ensure!(
@@ -160,9 +292,131 @@ impl FjallStorage {
             ),
             None => self.meta.insert(b"format_version", FORMAT_VERSION)?,
         }
+        if let Some(started) = self.meta.get(b"storage_protection_pending")? {
+            match self.protection.fingerprint() {
+                Some(fingerprint) => anyhow::ensure!(
+                    started.as_ref() == fingerprint,
+                    "STORAGE_ENCRYPTION_KEY does not match the key an interrupted protection run started with"
+                ),
+                None => anyhow::bail!(
+                    "database protection was interrupted; STORAGE_ENCRYPTION_KEY is required to finish it"
+                ),
+            }
+        }
+        let marker = self.meta.get(b"storage_protection")?;
+        match (self.protection.fingerprint(), marker) {
+            (Some(fingerprint), Some(marker)) => anyhow::ensure!(
+                marker.as_ref() == fingerprint,
+                "STORAGE_ENCRYPTION_KEY does not match the key this database was protected with"
+            ),
+            (Some(fingerprint), None) => self.protect_existing_subscriptions(fingerprint)?,
+            (None, Some(_marker)) => {
+                anyhow::bail!("database is protected; STORAGE_ENCRYPTION_KEY is required")
+            }
+            (None, None) => {}
+        }
         Ok(())
     }
 
+    /// 首次启用静态保护时，把无密钥摘要索引改写为 HMAC 索引，并加密订阅记录、编译记录和
+    /// 待确认的订阅。按批提交以限制内存占用；已加密的记录直接跳过，中断后重新启动会从剩余
+    /// 记录继续，全部完成后才写入密钥指纹。开始前先记下本次使用的密钥指纹，中断后只能以同一
+    /// 密钥继续，不会因未配置密钥或换用其他密钥而留下无法读取的记录。
+    fn protect_existing_subscriptions(&self, fingerprint: [u8; 32]) -> Result<()> {
+        #[derive(serde::Deserialize)]
+        struct PendingConfirmation {
+            subscription: Subscription,
+        }
+
+        self.meta
+            .insert(b"storage_protection_pending", fingerprint)
+            .context("failed to record the storage protection run")?;
+        let mut batch = self.db.batch();
+        let mut pending = 0usize;
+        for item in self.subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            if is_sealed_record(&value) {
+                continue;
+            }
+            let record: StoredSubscription = decode(&value)?;
+            let destination = record.subscription.destination_id();
+            let legacy = unkeyed_destination_digest(&destination.base_url, &destination.device_key);
+            if self
+                .subscriptions_by_destination
+                .get(legacy)?
+                .is_some_and(|value| value.as_ref() == record.id.0.to_be_bytes())
+            {
+                batch.remove(&self.subscriptions_by_destination, legacy);
+                batch.insert(
+                    &self.subscriptions_by_destination,
+                    self.destination_digest(&destination),
+                    record.id.0.to_be_bytes(),
+                );
+            }
+            batch.insert(&self.subscriptions, key, self.encode_subscription(&record)?);
+            self.commit_protection_batch(&mut batch, &mut pending, false)?;
+        }
+        for item in self.compiled_subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            if is_sealed_record(&value) {
+                continue;
+            }
+            let sealed =
+                self.protection
+                    .seal_record(COMPILED_SUBSCRIPTIONS, &key, value.to_vec())?;
+            batch.insert(&self.compiled_subscriptions, key, sealed);
+            self.commit_protection_batch(&mut batch, &mut pending, false)?;
+        }
+        for item in self.meta.prefix(b"confirmation:") {
+            let (key, value) = item.into_inner()?;
+            if is_sealed_record(&value) {
+                continue;
+            }
+            let id = decode_u64(key.get(13..).unwrap_or_default())?;
+            let destination = decode::<PendingConfirmation>(&value)?
+                .subscription
+                .destination_id();
+            let legacy = confirmation_destination_index(&unkeyed_destination_digest(
+                &destination.base_url,
+                &destination.device_key,
+            ));
+            if self
+                .meta
+                .get(&legacy)?
+                .is_some_and(|value| value.as_ref() == id.to_be_bytes())
+            {
+                batch.remove(&self.meta, legacy);
+                batch.insert(
+                    &self.meta,
+                    self.confirmation_destination_key(&destination),
+                    id.to_be_bytes(),
+                );
+            }
+            batch.insert(&self.meta, key, self.seal_confirmation(id, value.to_vec())?);
+            self.commit_protection_batch(&mut batch, &mut pending, false)?;
+        }
+        batch.insert(&self.meta, b"storage_protection", fingerprint);
+        batch.remove(&self.meta, b"storage_protection_pending");
+        self.commit_protection_batch(&mut batch, &mut pending, true)
+    }
+
+    /// 攒满 [`PROTECTION_BATCH_RECORDS`] 条记录或 `last` 时提交当前批次并换上新批次。
+    fn commit_protection_batch(
+        &self,
+        batch: &mut fjall::OwnedWriteBatch,
+        pending: &mut usize,
+        last: bool,
+    ) -> Result<()> {
+        *pending = pending.saturating_add(1);
+        if !last && *pending < PROTECTION_BATCH_RECORDS {
+            return Ok(());
+        }
+        *pending = 0;
+        std::mem::replace(batch, self.db.batch())
+            .commit()
+            .context("failed to protect existing subscription records")
+    }
+
     pub(crate) fn persist(&self) -> Result<()> {
         self.db
             .persist(PersistMode::SyncAll)
@@ -259,6 +513,7 @@ impl FjallStorage {
         }
         let mut batch = self.db.batch();
         let mut ids = Vec::with_capacity(events.len());
+        let mut history = std::collections::HashMap::new();
         for event in events {
             let source = crate::source_registry::find(&event.source);
             anyhow::ensure!(
@@ -268,6 +523,9 @@ impl FjallStorage {
                     }),
                 "Inbox event source, category, and provider do not agree"
             );
+            if is_earthquake(event.category) && !event.training {
+                self.record_earthquake_history(&mut batch, &mut history, &event)?;
+            }
             let id = self.next_id("inbox")?;
             let item = InboxItem {
                 id,
@@ -284,9 +542,318 @@ impl FjallStorage {
         batch
             .commit()
             .context("failed to atomically commit Inbox and cursor")?;
+        if !history.is_empty() {
+            self.touch_earthquake_history();
+        }
         Ok(ids)
     }
 
+    /// 把地震事件写入按发生时间排序的历史索引，每个事件只保留报数最大的一报；
+    /// `written` 记录本批次已写入的键，使同一批次里的多报也只留一条。
+    fn record_earthquake_history(
+        &self,
+        write: &mut fjall::OwnedWriteBatch,
+        written: &mut std::collections::HashMap<String, (Vec<u8>, u32)>,
+        event: &DisasterEvent,
+    ) -> Result<()> {
+        let Some(occurred_ms) = parse_event_epoch_ms(event) else {
+            return Ok(());
+        };
+        let event_key = event.event_key();
+        let previous = match written.get(&event_key) {
+            Some(previous) => Some(previous.clone()),
+            None => match self.earthquake_history_by_event.get(&event_key)? {
+                Some(key) => {
+                    let existing: Option<DisasterEvent> =
+                        get_record(&self.earthquake_history, &key)?;
+                    Some((key.to_vec(), existing.map_or(0, |event| event.report_num)))
+                }
+                None => None,
+            },
+        };
+        if let Some((key, report_num)) = previous {
+            if report_num > event.report_num {
+                return Ok(());
+            }
+            write.remove(&self.earthquake_history, key);
+        }
+        let key = earthquake_history_key(occurred_ms, &event_key);
+        write.insert(&self.earthquake_history, &key, encode(event)?);
+        write.insert(&self.earthquake_history_by_event, event_key.as_str(), &key);
+        written.insert(event_key, (key, event.report_num));
+        Ok(())
+    }
+
+    /// 把多家机构合并后的震中、震级和深度写回该报的历史记录，使历史查询与推送采用同一组参数。
+    /// 历史中已是更新的一报或参数没有变化时不写入。
+    fn merge_earthquake_history(
+        &self,
+        write: &mut fjall::OwnedWriteBatch,
+        event: &DisasterEvent,
+    ) -> Result<bool> {
+        if !is_earthquake(event.category) || event.training {
+            return Ok(false);
+        }
+        let Some(key) = self.earthquake_history_by_event.get(event.event_key())? else {
+            return Ok(false);
+        };
+        let Some(mut recorded) = get_record::<DisasterEvent>(&self.earthquake_history, &key)?
+        else {
+            return Ok(false);
+        };
+        if recorded.report_num != event.report_num
+            || (
+                recorded.latitude,
+                recorded.longitude,
+                recorded.magnitude,
+                recorded.depth_km,
+            ) == (
+                event.latitude,
+                event.longitude,
+                event.magnitude,
+                event.depth_km,
+            )
+        {
+            return Ok(false);
+        }
+        recorded.latitude = event.latitude;
+        recorded.longitude = event.longitude;
+        recorded.magnitude = event.magnitude;
+        recorded.depth_km = event.depth_km;
+        write.insert(&self.earthquake_history, key, encode(&recorded)?);
+        Ok(true)
+    }
+
+    /// 导入外部目录中的历史地震，只写入历史记录；已有记录的事件保持不变。
+    /// 返回新写入的事件数。
+    pub(crate) fn import_earthquake_history(&self, events: &[DisasterEvent]) -> Result<usize> {
+        let mut imported = 0_usize;
+        for chunk in events.chunks(1_000) {
+            let mut write = self.db.batch();
+            let mut written = std::collections::HashSet::new();
+            for event in chunk {
+                anyhow::ensure!(
+                    is_earthquake(event.category),
+                    "only earthquakes can be imported into the history"
+                );
+                let occurred_ms = parse_event_epoch_ms(event)
+                    .context("imported earthquake has no parseable occurrence time")?;
+                let event_key = event.event_key();
+                if written.contains(&event_key)
+                    || self.earthquake_history_by_event.get(&event_key)?.is_some()
+                {
+                    continue;
+                }
+                let key = earthquake_history_key(occurred_ms, &event_key);
+                write.insert(&self.earthquake_history, &key, encode(event)?);
+                write.insert(&self.earthquake_history_by_event, event_key.as_str(), &key);
+                written.insert(event_key);
+                imported = imported.saturating_add(1);
+            }
+            write
+                .commit()
+                .context("failed to commit imported earthquake history")?;
+        }
+        if imported > 0 {
+            self.touch_earthquake_history();
+        }
+        Ok(imported)
+    }
+
+    /// 历史地震最近一次变更的时间（Unix 毫秒）；每次变更都严格大于上一次，可直接作为版本号。
+    pub(crate) fn earthquake_history_changed_ms(&self) -> i64 {
+        self.history_changed_ms.load(Ordering::Acquire)
+    }
+
+    fn touch_earthquake_history(&self) {
+        let now_ms = super::try_now_millis().unwrap_or(0);
+        let _previous =
+            self.history_changed_ms
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |previous| {
+                    Some(now_ms.max(previous.saturating_add(1)))
+                });
+    }
+
+    /// 在给定数据源中按事件编号查找历史地震，多个数据源都有时取发生时间最晚的一条；
+    /// 已取消的预警视为不存在。
+    pub(crate) fn earthquake_history_event(
+        &self,
+        sources: &[&str],
+        event_id: &str,
+    ) -> Result<Option<DisasterEvent>> {
+        let mut found: Option<(Vec<u8>, DisasterEvent)> = None;
+        for source in sources {
+            for category in [
+                DisasterCategory::EarthquakeReport,
+                DisasterCategory::EarthquakeWarning,
+            ] {
+                let event_key = event_key_for(category, source, event_id);
+                let Some(key) = self.earthquake_history_by_event.get(&event_key)? else {
+                    continue;
+                };
+                let Some(event) = get_record::<DisasterEvent>(&self.earthquake_history, &key)?
+                else {
+                    continue;
+                };
+                if !event.cancel
+                    && found
+                        .as_ref()
+                        .is_none_or(|(current, _event)| key.as_ref() > current.as_slice())
+                {
+                    found = Some((key.to_vec(), event));
+                }
+            }
+        }
+        Ok(found.map(|(_key, event)| event))
+    }
+
+    /// 保存一份震感报告。`network` 为报告者所在网络，同一网络对同一地震只保留最新一份。
+    pub(crate) fn record_felt_report(
+        &self,
+        event_key: &str,
+        network: &[u8],
+        report: &FeltReport,
+    ) -> Result<FeltReportOutcome> {
+        let _lock = self
+            .felt_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let prefix = felt_report_prefix(event_key);
+        let mut key = prefix.clone();
+        key.extend_from_slice(&self.protection.felt_reporter_digest(event_key, network));
+        let outcome = if self.felt_reports.get(&key)?.is_some() {
+            FeltReportOutcome::Replaced
+        } else if self
+            .felt_reports
+            .prefix(&prefix)
+            .take(MAX_FELT_REPORTS_PER_EVENT)
+            .count()
+            >= MAX_FELT_REPORTS_PER_EVENT
+        {
+            return Ok(FeltReportOutcome::EventFull);
+        } else {
+            FeltReportOutcome::Recorded
+        };
+        self.felt_reports.insert(key, encode(report)?)?;
+        Ok(outcome)
+    }
+
+    /// 某次地震的全部震感报告。
+    pub(crate) fn felt_reports(&self, event_key: &str) -> Result<Vec<FeltReport>> {
+        self.felt_reports
+            .prefix(felt_report_prefix(event_key))
+            .map(|item| decode(&item.value()?))
+            .collect()
+    }
+
+    /// 按地震分组读取全部震感报告，供模型校准汇总；所属地震已不在历史记录中的报告跳过。
+    /// 读取的报告数超过上限时截断，第二个返回值为 `true`。
+    pub(crate) fn felt_report_events(&self) -> Result<(FeltReportGroups, bool)> {
+        let mut groups: FeltReportGroups = Vec::new();
+        let mut current: Option<(Vec<u8>, Option<DisasterEvent>, Vec<FeltReport>)> = None;
+        let mut truncated = false;
+        for (scanned, item) in self.felt_reports.iter().enumerate() {
+            if scanned >= MAX_FELT_REPORT_SCAN {
+                truncated = true;
+                break;
+            }
+            let (key, value) = item.into_inner()?;
+            let event_key = key
+                .len()
+                .checked_sub(FELT_REPORTER_DIGEST_LEN + 1)
+                .and_then(|end| key.get(..end))
+                .context("felt report key is too short")?;
+            if current
+                .as_ref()
+                .is_none_or(|(current_key, _event, _reports)| current_key.as_slice() != event_key)
+            {
+                if let Some((_key, Some(event), reports)) = current.take() {
+                    groups.push((event, reports));
+                }
+                let event = match self.earthquake_history_by_event.get(event_key)? {
+                    Some(history_key) => {
+                        get_record::<DisasterEvent>(&self.earthquake_history, &history_key)?
+                    }
+                    None => None,
+                };
+                current = Some((event_key.to_vec(), event, Vec::new()));
+            }
+            if let Some((_key, Some(_event), reports)) = current.as_mut() {
+                reports.push(decode(&value)?);
+            }
+        }
+        if let Some((_key, Some(event), reports)) = current {
+            groups.push((event, reports));
+        }
+        Ok((groups, truncated))
+    }
+
+    /// 记录目的地打开了某次事件的通知；同一目的地重复打开同一事件只累加次数。
+    /// 返回是否为首次打开。
+    pub(crate) fn record_notification_open(
+        &self,
+        destination_id: u64,
+        incident_id: &IncidentId,
+        source: &str,
+        event_id: &str,
+        opened_at_ms: i64,
+    ) -> Result<bool> {
+        let _lock = self
+            .open_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let key = notification_open_key(destination_id, incident_id);
+        let existing = get_record::<NotificationOpen>(&self.notification_opens, &key)?;
+        let first = existing.is_none();
+        let open = match existing {
+            Some(open) => NotificationOpen {
+                last_opened_at_ms: open.last_opened_at_ms.max(opened_at_ms),
+                opens: open.opens.saturating_add(1),
+                ..open
+            },
+            None => NotificationOpen {
+                incident_id: incident_id.as_str().to_string(),
+                source: source.to_string(),
+                event_id: event_id.to_string(),
+                first_opened_at_ms: opened_at_ms,
+                last_opened_at_ms: opened_at_ms,
+                opens: 1,
+            },
+        };
+        self.notification_opens.insert(key, encode(&open)?)?;
+        Ok(first)
+    }
+
+    /// 按发生时间从新到旧查询历史地震，已取消的预警不返回。
+    pub(crate) fn search_earthquakes(
+        &self,
+        query: &super::EarthquakeQuery,
+    ) -> Result<super::EarthquakeSearch> {
+        anyhow::ensure!(
+            query.from_ms <= query.to_ms,
+            "earthquake query range is reversed"
+        );
+        let start = query.from_ms.max(0).to_be_bytes();
+        let end = query.to_ms.max(0).saturating_add(1).to_be_bytes();
+        let mut search = super::EarthquakeSearch::default();
+        for (scanned, item) in self.earthquake_history.range(start..end).rev().enumerate() {
+            if scanned >= MAX_EARTHQUAKE_HISTORY_SCAN {
+                search.truncated = true;
+                break;
+            }
+            let event: DisasterEvent = decode(&item.value()?)?;
+            if event.cancel || !query.matches(&event) {
+                continue;
+            }
+            if search.events.len() >= query.limit {
+                search.truncated = true;
+                break;
+            }
+            search.events.push(event);
+        }
+        Ok(search)
+    }
+
     pub(crate) fn provider_cursors(
         &self,
         provider: ProviderChannel,
@@ -325,6 +892,7 @@ impl FjallStorage {
             match_jobs: self.match_jobs.len()?,
             delivery_batches,
             retries: self.retries.len()?,
+            dead_letters: self.dead_letters.len()?,
         })
     }
 
@@ -470,6 +1038,7 @@ impl FjallStorage {
         }
         let mut batch = self.db.batch();
         batch.insert(&self.incidents, incident.id.as_str(), encode(&incident)?);
+        let history_changed = self.merge_earthquake_history(&mut batch, event)?;
         if let Some(job) = job {
             batch.insert(
                 &self.events,
@@ -502,7 +1071,11 @@ impl FjallStorage {
         batch.remove(&self.inbox, inbox_id.to_be_bytes());
         batch
             .commit()
-            .context("failed to commit Incident transition")
+            .context("failed to commit Incident transition")?;
+        if history_changed {
+            self.touch_earthquake_history();
+        }
+        Ok(())
     }
 
     pub(crate) fn commit_incident_without_match(
@@ -561,7 +1134,6 @@ impl FjallStorage {
         get_record(&self.match_jobs, &id.to_be_bytes())
     }
 
-    #[cfg(any(test, feature = "benchmarks"))]
     pub(crate) fn store_subscription(
         &self,
         mut subscription: Subscription,
@@ -583,14 +1155,13 @@ impl FjallStorage {
             .subscription_lock
             .lock()
             .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
-        let key = confirmation_key(confirmation_id);
-        let Some(current) = self.meta.get(key)? else {
+        let Some(current) = self.confirmation_record(confirmation_id)? else {
             return Ok(false);
         };
-        if current.as_ref() != expected_confirmation {
+        if current != expected_confirmation {
             return Ok(false);
         }
-        let destination_index = confirmation_destination_key(&subscription.destination_id());
+        let destination_index = self.confirmation_destination_key(&subscription.destination_id());
         let expected_id = confirmation_id.to_be_bytes();
         if !self
             .meta
@@ -611,14 +1182,14 @@ impl FjallStorage {
         subscription
             .validate()
             .map_err(|error| anyhow::anyhow!("invalid subscription: {error}"))?;
-        let destination_key = destination_key(subscription);
+        let destination_key = self.destination_key(subscription);
         let existing_id = self
             .subscriptions_by_destination
             .get(destination_key)?
             .map(|value| decode_u64(&value))
             .transpose()?;
         let previous = existing_id
-            .map(|id| get_record::<StoredSubscription>(&self.subscriptions, &id.to_be_bytes()))
+            .map(|id| self.stored_subscription(SubscriptionId(id)))
             .transpose()?
             .flatten();
         let id = previous
@@ -639,6 +1210,7 @@ impl FjallStorage {
             generation,
             active: true,
             subscription: subscription.clone(),
+            deactivated_at_ms: None,
         };
         let compiled = SubscriptionCompiler::compile(
             record.id,
@@ -665,16 +1237,81 @@ impl FjallStorage {
             .subscription_lock
             .lock()
             .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
-        let Some(mut record) = get_record::<StoredSubscription>(
-            &self.subscriptions,
-            &subscription_id.0.to_be_bytes(),
-        )?
-        else {
+        let Some(record) = self.stored_subscription(subscription_id)? else {
             return Ok(false);
         };
-        let old = self.compiled_subscription(subscription_id)?;
+        self.deactivate_record(record, super::try_now_millis()?)?;
+        Ok(true)
+    }
+
+    /// 将近期投递全部永久失败的订阅标记为已取消，返回取消的订阅数。
+    ///
+    /// 只统计 `since_ms` 之后的死信：同一目的地至少 `min_failures` 次永久失败，期间没有成功送达，
+    /// 且订阅没有在最后一次失败后被重新提交。取消后的订阅在恢复期结束后由保留清理彻底删除。
+    pub(crate) fn deactivate_undeliverable_subscriptions(
+        &self,
+        since_ms: i64,
+        min_failures: u32,
+    ) -> Result<usize> {
+        let mut failures = std::collections::HashMap::<u64, (u32, i64)>::new();
+        for item in self.dead_letters.range(since_ms.max(0).to_be_bytes()..) {
+            let dead_letter: DeadLetterItem = decode(&item.value()?)?;
+            if dead_letter.permanent {
+                let entry = failures
+                    .entry(dead_letter.destination_id.0)
+                    .or_insert((0, dead_letter.failed_at_ms));
+                entry.0 = entry.0.saturating_add(1);
+                entry.1 = entry.1.max(dead_letter.failed_at_ms);
+            }
+        }
+        failures.retain(|_destination, (count, _last_failed_at_ms)| *count >= min_failures);
+        if failures.is_empty() {
+            return Ok(0);
+        }
+        for item in self.ledger.iter() {
+            let (key, value) = item.into_inner()?;
+            let Some((_incident_id, _category, destination_id)) = parse_ledger_key(&key) else {
+                continue;
+            };
+            if failures.contains_key(&destination_id) {
+                let delivery: StoredDelivery = decode(&value)?;
+                if delivery.delivered_at_ms >= since_ms {
+                    failures.remove(&destination_id);
+                }
+            }
+        }
+
+        let _lock = self
+            .subscription_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let mut undeliverable = Vec::new();
+        for item in self.subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            let record = self.decode_subscription(&key, &value)?;
+            let failed = failures.get(&record.destination_id.0);
+            if record.active
+                && failed.is_some_and(|(_count, last_failed_at_ms)| {
+                    record.subscription.updated_at <= *last_failed_at_ms
+                })
+            {
+                undeliverable.push(record);
+            }
+        }
+        let now_ms = super::try_now_millis()?;
+        let deactivated = undeliverable.len();
+        for record in undeliverable {
+            self.deactivate_record(record, now_ms)?;
+        }
+        Ok(deactivated)
+    }
+
+    /// 调用方必须持有 `subscription_lock`。
+    fn deactivate_record(&self, mut record: StoredSubscription, now_ms: i64) -> Result<()> {
+        let old = self.compiled_subscription(record.id)?;
         record.active = false;
         record.generation = record.generation.saturating_add(1);
+        record.deactivated_at_ms = Some(now_ms);
         let mut batch = self.db.batch();
         if let Some(old) = old.as_ref() {
             remove_postings(&self.postings, &mut batch, old)?;
@@ -682,11 +1319,11 @@ impl FjallStorage {
         batch.insert(
             &self.subscriptions,
             record.id.0.to_be_bytes(),
-            encode(&record)?,
+            self.encode_subscription(&record)?,
         );
         batch.remove(&self.compiled_subscriptions, record.id.0.to_be_bytes());
         let confirmation_destination =
-            confirmation_destination_key(&record.subscription.destination_id());
+            self.confirmation_destination_key(&record.subscription.destination_id());
         if let Some(id) = self
             .meta
             .get(&confirmation_destination)?
@@ -697,7 +1334,201 @@ impl FjallStorage {
             batch.remove(&self.meta, confirmation_destination);
         }
         batch.commit()?;
-        Ok(true)
+        Ok(())
+    }
+
+    /// 在恢复期内重新启用已取消的订阅，重新编译匹配索引并递增版本号。
+    ///
+    /// 订阅仍有效时直接返回当前记录；记录不存在或取消时间早于 `deactivated_after_ms` 时返回 `None`。
+    pub(crate) fn reactivate_subscription(
+        &self,
+        destination: &crate::models::DestinationId,
+        deactivated_after_ms: i64,
+    ) -> Result<Option<StoredSubscription>> {
+        let _lock = self
+            .subscription_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let Some(mut record) = self.stored_subscription_by_destination(destination)? else {
+            return Ok(None);
+        };
+        if record.active {
+            return Ok(Some(record));
+        }
+        if deactivated_at(&record) <= deactivated_after_ms {
+            return Ok(None);
+        }
+        record
+            .subscription
+            .validate()
+            .map_err(|error| anyhow::anyhow!("invalid subscription: {error}"))?;
+        record.active = true;
+        record.generation = record.generation.saturating_add(1);
+        record.deactivated_at_ms = None;
+        let compiled = SubscriptionCompiler::compile(
+            record.id,
+            record.destination_id,
+            record.generation,
+            &record.subscription,
+        )?;
+        self.commit_subscription_change(&record, &compiled, None, None)?;
+        Ok(Some(record))
+    }
+
+    /// 彻底删除目的地的订阅、匹配索引、待确认请求、送达账本、重试和死信记录。
+    ///
+    /// 尚未发送的投递行仍留在批次中，发送前会因订阅不存在而跳过，并按正常流程移出投递队列。
+    pub(crate) fn purge_destination(
+        &self,
+        destination: &crate::models::DestinationId,
+    ) -> Result<bool> {
+        let _subscription_lock = self
+            .subscription_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let _retry_lock = self
+            .retry_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let mut batch = self.db.batch();
+        let mut found = false;
+
+        let confirmation_destination = self.confirmation_destination_key(destination);
+        if let Some(id) = self
+            .meta
+            .get(&confirmation_destination)?
+            .map(|value| decode_u64(&value))
+            .transpose()?
+        {
+            batch.remove(&self.meta, confirmation_key(id));
+            batch.remove(&self.meta, confirmation_destination);
+            found = true;
+        }
+
+        if let Some(record) = self.stored_subscription_by_destination(destination)? {
+            found = true;
+            self.purge_subscription_record(&mut batch, &record)?;
+        }
+
+        if found {
+            batch
+                .commit()
+                .context("failed to atomically purge destination")?;
+        }
+        Ok(found)
+    }
+
+    /// 彻底删除取消时间不晚于 `cutoff_ms` 的订阅及其历史记录；待确认的新订阅请求不受影响。
+    pub(crate) fn purge_expired_subscriptions(&self, cutoff_ms: i64) -> Result<usize> {
+        let _subscription_lock = self
+            .subscription_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let _retry_lock = self
+            .retry_lock
+            .lock()
+            .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
+        let mut expired = Vec::new();
+        for item in self.subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            let record = self.decode_subscription(&key, &value)?;
+            if !record.active && deactivated_at(&record) <= cutoff_ms {
+                expired.push(record);
+            }
+        }
+        // 每个订阅单独提交，重试清理时才能看到前一个订阅已删除的同批次重试。
+        for record in &expired {
+            let mut batch = self.db.batch();
+            self.purge_subscription_record(&mut batch, record)?;
+            batch
+                .commit()
+                .context("failed to purge expired subscription")?;
+        }
+        Ok(expired.len())
+    }
+
+    fn purge_subscription_record(
+        &self,
+        batch: &mut fjall::OwnedWriteBatch,
+        record: &StoredSubscription,
+    ) -> Result<()> {
+        if let Some(compiled) = self.compiled_subscription(record.id)? {
+            remove_postings(&self.postings, batch, &compiled)?;
+        }
+        batch.remove(&self.subscriptions, record.id.0.to_be_bytes());
+        batch.remove(&self.compiled_subscriptions, record.id.0.to_be_bytes());
+        batch.remove(
+            &self.subscriptions_by_destination,
+            self.destination_key(&record.subscription),
+        );
+        self.purge_destination_history(batch, record.destination_id)
+    }
+
+    fn purge_destination_history(
+        &self,
+        batch: &mut fjall::OwnedWriteBatch,
+        destination_id: DestinationNumericId,
+    ) -> Result<()> {
+        for item in self.ledger.iter() {
+            let key = item.key()?;
+            if parse_ledger_key(&key)
+                .is_some_and(|(_, _, ledger_destination)| ledger_destination == destination_id.0)
+            {
+                batch.remove(&self.ledger, key);
+            }
+        }
+
+        let mut retries = Vec::new();
+        for item in self
+            .retries_by_destination
+            .prefix(destination_id.0.to_be_bytes())
+        {
+            let retry_key_value = item.value()?;
+            let Some(value) = self.retries.get(&retry_key_value)? else {
+                continue;
+            };
+            retries.push(decode::<RetryItem>(&value)?);
+        }
+        let purged = retries
+            .iter()
+            .map(retry_batch_key)
+            .collect::<std::collections::HashSet<_>>();
+        let mut checked_batches = std::collections::HashSet::new();
+        for retry in &retries {
+            remove_retry_indexes(self, batch, retry);
+            batch.remove(
+                &self.delivery_by_destination,
+                delivery_destination_key(retry.destination_id, retry.batch_id, retry.row_index),
+            );
+            if !checked_batches.insert(retry.batch_id) {
+                continue;
+            }
+            let has_other = self
+                .retries_by_batch
+                .prefix(retry.batch_id.to_be_bytes())
+                .any(|item| item.key().is_ok_and(|key| !purged.contains(key.as_ref())));
+            if !has_other {
+                batch.remove(
+                    &self.delivery_batches,
+                    delivery_batch_key(1, retry.batch_id),
+                );
+            }
+        }
+
+        for item in self.dead_letters.iter() {
+            let (key, value) = item.into_inner()?;
+            if decode::<DeadLetterItem>(&value)?.destination_id == destination_id {
+                batch.remove(&self.dead_letters, key);
+            }
+        }
+
+        for item in self
+            .notification_opens
+            .prefix(destination_id.0.to_be_bytes())
+        {
+            batch.remove(&self.notification_opens, item.key()?);
+        }
+        Ok(())
     }
 
     fn commit_subscription_change(
@@ -715,23 +1546,23 @@ impl FjallStorage {
         batch.insert(
             &self.subscriptions,
             record.id.0.to_be_bytes(),
-            encode(record)?,
+            self.encode_subscription(record)?,
         );
         batch.insert(
             &self.subscriptions_by_destination,
-            destination_key(&record.subscription),
+            self.destination_key(&record.subscription),
             record.id.0.to_be_bytes(),
         );
         batch.insert(
             &self.compiled_subscriptions,
             record.id.0.to_be_bytes(),
-            encode(compiled)?,
+            self.encode_compiled(compiled)?,
         );
         if let Some(id) = remove_confirmation_id {
             batch.remove(&self.meta, confirmation_key(id));
             batch.remove(
                 &self.meta,
-                confirmation_destination_key(&record.subscription.destination_id()),
+                self.confirmation_destination_key(&record.subscription.destination_id()),
             );
         }
         batch
@@ -743,21 +1574,35 @@ impl FjallStorage {
         &self,
         id: SubscriptionId,
     ) -> Result<Option<CompiledSubscription>> {
-        get_record(&self.compiled_subscriptions, &id.0.to_be_bytes())
+        let key = id.0.to_be_bytes();
+        self.compiled_subscriptions
+            .get(key)?
+            .map(|value| {
+                decode(
+                    &self
+                        .protection
+                        .open_record(COMPILED_SUBSCRIPTIONS, &key, &value)?,
+                )
+            })
+            .transpose()
     }
 
     pub(crate) fn stored_subscription(
         &self,
         id: SubscriptionId,
     ) -> Result<Option<StoredSubscription>> {
-        get_record(&self.subscriptions, &id.0.to_be_bytes())
+        let key = id.0.to_be_bytes();
+        self.subscriptions
+            .get(key)?
+            .map(|value| self.decode_subscription(&key, &value))
+            .transpose()
     }
 
     pub(crate) fn stored_subscription_by_destination(
         &self,
         destination: &crate::models::DestinationId,
     ) -> Result<Option<StoredSubscription>> {
-        let key = destination_digest(destination);
+        let key = self.destination_digest(destination);
         let Some(id) = self
             .subscriptions_by_destination
             .get(key)?
@@ -769,11 +1614,261 @@ impl FjallStorage {
         self.stored_subscription(SubscriptionId(id))
     }
 
+    /// 导出按事件、投递和重试分区扫描，只用于低频的数据访问请求。
+    pub(crate) fn subscription_export(
+        &self,
+        id: SubscriptionId,
+    ) -> Result<Option<SubscriptionExport>> {
+        let Some(record) = self.stored_subscription(id)? else {
+            return Ok(None);
+        };
+        let destination_id = record.destination_id;
+
+        let mut deliveries = Vec::new();
+        for item in self.ledger.iter() {
+            let (key, value) = item.into_inner()?;
+            let Some((incident_id, category, ledger_destination)) = parse_ledger_key(&key) else {
+                continue;
+            };
+            if ledger_destination != destination_id.0 {
+                continue;
+            }
+            let delivery: StoredDelivery = decode(&value)?;
+            deliveries.push(ExportedDelivery {
+                incident_id,
+                category,
+                event_revision: delivery.event_revision,
+                delivered_at_ms: delivery.delivered_at_ms,
+                row: delivery.row,
+            });
+        }
+
+        let mut pending_deliveries = Vec::new();
+        for item in self
+            .delivery_by_destination
+            .prefix(destination_id.0.to_be_bytes())
+        {
+            let key = item.key()?;
+            let batch_id = key
+                .get(8..16)
+                .map(decode_u64)
+                .transpose()?
+                .context("invalid delivery destination index key")?;
+            let row_index = key
+                .get(16..20)
+                .and_then(|value| <[u8; 4]>::try_from(value).ok())
+                .map(u32::from_be_bytes)
+                .context("invalid delivery destination index key")?;
+            let Some(batch) = self.delivery_batch(batch_id)? else {
+                continue;
+            };
+            let Some(row) = usize::try_from(row_index)
+                .ok()
+                .and_then(|index| batch.rows.get(index))
+            else {
+                continue;
+            };
+            pending_deliveries.push(ExportedPendingDelivery {
+                batch_id,
+                incident_id: batch.incident_id.as_str().to_string(),
+                category: batch.category,
+                created_at_ms: batch.created_at_ms,
+                row: *row,
+            });
+        }
+
+        let mut retries = Vec::new();
+        for item in self.retries.iter() {
+            let retry: RetryItem = decode(&item.value()?)?;
+            if retry.destination_id == destination_id {
+                retries.push(retry);
+            }
+        }
+        let mut dead_letters = Vec::new();
+        for item in self.dead_letters.iter() {
+            let dead_letter: DeadLetterItem = decode(&item.value()?)?;
+            if dead_letter.destination_id == destination_id {
+                dead_letters.push(dead_letter);
+            }
+        }
+
+        let notification_opens = self
+            .notification_opens
+            .prefix(destination_id.0.to_be_bytes())
+            .map(|item| decode(&item.value()?))
+            .collect::<Result<Vec<NotificationOpen>>>()?;
+
+        Ok(Some(SubscriptionExport {
+            subscription_id: record.id,
+            active: record.active,
+            generation: record.generation,
+            deactivated_at_ms: record.deactivated_at_ms,
+            places: record
+                .subscription
+                .targets
+                .iter()
+                .map(crate::utils::places::describe_target)
+                .collect(),
+            subscription: record.subscription,
+            deliveries,
+            pending_deliveries,
+            retries,
+            dead_letters,
+            notification_opens,
+        }))
+    }
+
+    /// 顺序读一遍编译后的订阅和倒排索引，使其进入数据库块缓存和系统页缓存，
+    /// 同时按 H3 二级格子统计监测点分布。
+    pub(crate) fn warm_up_subscriptions(&self) -> Result<super::WarmupReport> {
+        let mut report = super::WarmupReport::default();
+        let mut coarse_cells = std::collections::HashMap::<u64, usize>::new();
+        for item in self.compiled_subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            let compiled: CompiledSubscription = decode(&self.protection.open_record(
+                COMPILED_SUBSCRIPTIONS,
+                &key,
+                &value,
+            )?)?;
+            report.subscriptions = report.subscriptions.saturating_add(1);
+            for target in &compiled.targets {
+                report.targets = report.targets.saturating_add(1);
+                let count = coarse_cells.entry(target.h3_cells[0]).or_default();
+                *count = count.saturating_add(1);
+            }
+        }
+        for item in self.postings.iter() {
+            drop(item.into_inner()?);
+            report.posting_keys = report.posting_keys.saturating_add(1);
+        }
+        report.coarse_cells = coarse_cells.into_iter().collect();
+        report
+            .coarse_cells
+            .sort_unstable_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+        Ok(report)
+    }
+
+    /// 按给定 H3 分辨率统计生效订阅的分布；分辨率不能细于编译时保存的最细格子。
+    pub(crate) fn subscription_density(
+        &self,
+        resolution: h3o::Resolution,
+    ) -> Result<super::SubscriptionDensity> {
+        let finest = H3_RESOLUTIONS.len() - 1;
+        anyhow::ensure!(
+            u8::from(resolution) <= u8::from(H3_RESOLUTIONS[finest]),
+            "density resolution is finer than the stored H3 cells"
+        );
+        let mut cells = std::collections::HashMap::<u64, usize>::new();
+        let mut regions = std::collections::HashMap::<u16, usize>::new();
+        let mut seen_cells = std::collections::HashSet::new();
+        let mut seen_regions = std::collections::HashSet::new();
+        for item in self.compiled_subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            let compiled: CompiledSubscription = decode(&self.protection.open_record(
+                COMPILED_SUBSCRIPTIONS,
+                &key,
+                &value,
+            )?)?;
+            seen_cells.clear();
+            seen_regions.clear();
+            for target in &compiled.targets {
+                if let Some(boundary) = target.boundary {
+                    if seen_regions.insert(boundary) {
+                        let count = regions.entry(boundary).or_default();
+                        *count = count.saturating_add(1);
+                    }
+                    continue;
+                }
+                let cell = h3o::CellIndex::try_from(target.h3_cells[finest])
+                    .context("compiled target has an invalid H3 cell")?
+                    .parent(resolution)
+                    .context("failed to coarsen compiled target H3 cell")?;
+                let cell = u64::from(cell);
+                if seen_cells.insert(cell) {
+                    let count = cells.entry(cell).or_default();
+                    *count = count.saturating_add(1);
+                }
+            }
+        }
+        let mut density = super::SubscriptionDensity {
+            cells: cells.into_iter().collect(),
+            regions: regions.into_iter().collect(),
+        };
+        density
+            .cells
+            .sort_unstable_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+        density
+            .regions
+            .sort_unstable_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+        Ok(density)
+    }
+
+    /// 顺序读一遍订阅，按最低提醒烈度、推送渠道、创建周和监测点所在地区统计生效订阅。
+    pub(crate) fn subscription_breakdown(&self) -> Result<super::SubscriptionBreakdown> {
+        const WEEK_MS: i64 = 7 * 86_400_000;
+        // 1970-01-01 是星期四，向前偏移三天后按周取整即为周一零点。
+        const MONDAY_OFFSET_MS: i64 = 3 * 86_400_000;
+        let mut breakdown = super::SubscriptionBreakdown::default();
+        let mut min_intensity = std::collections::BTreeMap::<u8, usize>::new();
+        let mut channels = std::collections::HashMap::<&'static str, usize>::new();
+        let mut weeks = std::collections::BTreeMap::<i64, usize>::new();
+        let mut regions = std::collections::HashMap::<Option<u16>, usize>::new();
+        let mut seen_regions = std::collections::HashSet::new();
+        for item in self.subscriptions.iter() {
+            let (key, value) = item.into_inner()?;
+            let stored = self.decode_subscription(&key, &value)?;
+            if !stored.active {
+                continue;
+            }
+            let subscription = &stored.subscription;
+            breakdown.subscriptions = breakdown.subscriptions.saturating_add(1);
+            if let Some(AlertRule::EarthquakeWarning {
+                estimated_intensity_bands,
+                ..
+            }) = subscription.alert(DisasterCategory::EarthquakeWarning)
+                && let Some(lowest) = estimated_intensity_bands.iter().map(|band| band.min).min()
+            {
+                let count = min_intensity.entry(lowest).or_default();
+                *count = count.saturating_add(1);
+            }
+            let count = channels.entry(subscription.destination.kind()).or_default();
+            *count = count.saturating_add(1);
+            let week = (subscription.created_at + MONDAY_OFFSET_MS).div_euclid(WEEK_MS) * WEEK_MS
+                - MONDAY_OFFSET_MS;
+            let count = weeks.entry(week).or_default();
+            *count = count.saturating_add(1);
+            seen_regions.clear();
+            for target in &subscription.targets {
+                let region = match target.point {
+                    Some(point) => {
+                        crate::utils::boundaries::locate_position(point.latitude, point.longitude)
+                    }
+                    None => target.boundary(),
+                };
+                if seen_regions.insert(region) {
+                    let count = regions.entry(region).or_default();
+                    *count = count.saturating_add(1);
+                }
+            }
+        }
+        breakdown.min_intensity = min_intensity.into_iter().collect();
+        breakdown.channels = channels.into_iter().collect();
+        breakdown
+            .channels
+            .sort_unstable_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(right.0)));
+        breakdown.created_weeks = weeks.into_iter().collect();
+        breakdown.regions = regions.into_iter().collect();
+        breakdown
+            .regions
+            .sort_unstable_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+        Ok(breakdown)
+    }
+
     pub(crate) fn active_subscription_count(&self) -> Result<usize> {
         let mut count = 0usize;
         for item in self.subscriptions.iter() {
-            let record: StoredSubscription = decode(&item.value()?)?;
-            if record.active {
+            let (key, value) = item.into_inner()?;
+            if self.decode_subscription(&key, &value)?.active {
                 count = count.saturating_add(1);
             }
         }
@@ -799,7 +1894,7 @@ impl FjallStorage {
             subscription
                 .validate()
                 .map_err(|error| anyhow::anyhow!("invalid migrated subscription: {error}"))?;
-            let destination_key = destination_key(&subscription);
+            let destination_key = self.destination_key(&subscription);
             anyhow::ensure!(
                 destinations.insert(destination_key),
                 "migration batch contains duplicate destinations"
@@ -818,6 +1913,7 @@ impl FjallStorage {
                 generation: 1,
                 active: true,
                 subscription,
+                deactivated_at_ms: None,
             };
             let compiled = SubscriptionCompiler::compile(
                 id,
@@ -854,7 +1950,7 @@ impl FjallStorage {
             batch.insert(
                 &self.subscriptions,
                 record.id.0.to_be_bytes(),
-                encode(&record)?,
+                self.encode_subscription(&record)?,
             );
             batch.insert(
                 &self.subscriptions_by_destination,
@@ -864,7 +1960,7 @@ impl FjallStorage {
             batch.insert(
                 &self.compiled_subscriptions,
                 record.id.0.to_be_bytes(),
-                encode(&compiled)?,
+                self.encode_compiled(&compiled)?,
             );
         }
         for (key, bitmap) in posting_updates {
@@ -880,7 +1976,8 @@ impl FjallStorage {
     pub(crate) fn active_subscriptions(&self) -> Result<Vec<StoredSubscription>> {
         let mut records = Vec::new();
         for item in self.subscriptions.iter() {
-            let record: StoredSubscription = decode(&item.value()?)?;
+            let (key, value) = item.into_inner()?;
+            let record = self.decode_subscription(&key, &value)?;
             if record.active {
                 records.push(record);
             }
@@ -932,7 +2029,7 @@ impl FjallStorage {
                 .iter()
                 .filter_map(|subscription| {
                     crate::matching::match_subscription(subscription, &event)
-                        .map(|matched| (destination_key(subscription), matched))
+                        .map(|matched| (self.destination_key(subscription), matched))
                 })
                 .collect::<std::collections::BTreeMap<_, _>>();
             let plan = MatchPlan::for_event(&event)?;
@@ -945,7 +2042,7 @@ impl FjallStorage {
                     .stored_subscription(row.subscription_id)?
                     .context("sample match references a missing subscription")?;
                 actual.insert(
-                    destination_key(&record.subscription),
+                    self.destination_key(&record.subscription),
                     crate::matching::ReferenceMatch {
                         target_ordinal: row.target_ordinal,
                         match_kind: row.match_kind,
@@ -971,7 +2068,7 @@ impl FjallStorage {
             .subscription_lock
             .lock()
             .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
-        let destination_key = confirmation_destination_key(destination);
+        let destination_key = self.confirmation_destination_key(destination);
         let previous = self
             .meta
             .get(&destination_key)?
@@ -981,7 +2078,11 @@ impl FjallStorage {
         if let Some(previous) = previous {
             batch.remove(&self.meta, confirmation_key(previous));
         }
-        batch.insert(&self.meta, confirmation_key(id), value);
+        batch.insert(
+            &self.meta,
+            confirmation_key(id),
+            self.seal_confirmation(id, value)?,
+        );
         batch.insert(&self.meta, destination_key, id.to_be_bytes());
         batch
             .commit()
@@ -1001,19 +2102,19 @@ impl FjallStorage {
             .lock()
             .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
         let key = confirmation_key(id);
-        let Some(current) = self.meta.get(key)? else {
+        let Some(current) = self.confirmation_record(id)? else {
             return Ok(false);
         };
-        if current.as_ref() != expected {
+        if current != expected {
             return Ok(false);
         }
         let mut batch = self.db.batch();
         if let Some(replacement) = replacement {
-            batch.insert(&self.meta, key, replacement);
+            batch.insert(&self.meta, key, self.seal_confirmation(id, replacement)?);
         } else {
             batch.remove(&self.meta, key);
             if let Some(destination) = destination {
-                let destination_key = confirmation_destination_key(destination);
+                let destination_key = self.confirmation_destination_key(destination);
                 let expected_id = id.to_be_bytes();
                 anyhow::ensure!(
                     self.meta
@@ -1031,16 +2132,28 @@ impl FjallStorage {
     }
 
     pub(crate) fn confirmation_record(&self, id: u64) -> Result<Option<Vec<u8>>> {
-        Ok(self
-            .meta
-            .get(confirmation_key(id))?
-            .map(|value| value.to_vec()))
+        let key = confirmation_key(id);
+        self.meta
+            .get(key)?
+            .map(|value| {
+                Ok(self
+                    .protection
+                    .open_record(META, &key, &value)?
+                    .into_owned())
+            })
+            .transpose()
     }
 
     pub(crate) fn confirmation_records(&self) -> Result<Vec<Vec<u8>>> {
         self.meta
             .prefix(b"confirmation:")
-            .map(|item| Ok(item.value()?.to_vec()))
+            .map(|item| {
+                let (key, value) = item.into_inner()?;
+                Ok(self
+                    .protection
+                    .open_record(META, &key, &value)?
+                    .into_owned())
+            })
             .collect()
     }
 
@@ -1052,7 +2165,7 @@ impl FjallStorage {
             .subscription_lock
             .lock()
             .map_err(|error| anyhow::anyhow!("Fjall mutation lock poisoned: {error}"))?;
-        let destination_key = confirmation_destination_key(destination);
+        let destination_key = self.confirmation_destination_key(destination);
         let Some(id) = self
             .meta
             .get(&destination_key)?
@@ -1104,7 +2217,7 @@ impl FjallStorage {
                     resolution_index,
                     cells,
                 } => {
-                    for cell in cells {
+                    for cell in cells.iter() {
                         self.extend_posting_blocks(
                             plan.category,
                             plan.source_id,
@@ -1854,6 +2967,22 @@ impl FjallStorage {
         let mut stats = StoragePruneStats::default();
         let mut write = self.db.batch();
 
+        // 历史地震与事件记录保留同样久；键按发生时间排序，遇到未过期的即可停止。
+        let history_end = incident_cutoff_ms.max(0).saturating_add(1).to_be_bytes();
+        for item in self.earthquake_history.range(..history_end) {
+            let (key, value) = item.into_inner()?;
+            let event: DisasterEvent = decode(&value)?;
+            write.remove(&self.earthquake_history, key);
+            let event_key = event.event_key();
+            // 震感报告随所属地震一起清理。
+            for report in self.felt_reports.prefix(felt_report_prefix(&event_key)) {
+                write.remove(&self.felt_reports, report.key()?);
+                stats.felt_reports = stats.felt_reports.saturating_add(1);
+            }
+            write.remove(&self.earthquake_history_by_event, event_key);
+            stats.earthquake_history = stats.earthquake_history.saturating_add(1);
+        }
+
         for item in self.ledger.iter() {
             let (key, value) = item.into_inner()?;
             let delivery: StoredDelivery = decode(&value)?;
@@ -1863,6 +2992,16 @@ impl FjallStorage {
             }
         }
 
+        // 打开记录与送达账本保留同样久。
+        for item in self.notification_opens.iter() {
+            let (key, value) = item.into_inner()?;
+            let open: NotificationOpen = decode(&value)?;
+            if open.last_opened_at_ms <= ledger_cutoff_ms {
+                write.remove(&self.notification_opens, key);
+                stats.notification_opens = stats.notification_opens.saturating_add(1);
+            }
+        }
+
         let mut referenced_incidents = std::collections::HashSet::new();
         let mut referenced_events = std::collections::HashSet::new();
         for item in self.match_jobs.iter() {
@@ -1904,28 +3043,71 @@ impl FjallStorage {
         write
             .commit()
             .context("failed to commit retention pruning")?;
+        if stats.earthquake_history > 0 {
+            self.touch_earthquake_history();
+        }
         Ok(stats)
     }
 }
 
-fn destination_key(subscription: &Subscription) -> [u8; 32] {
-    use sha2::{Digest, Sha256};
-    let mut hash = Sha256::new();
-    hash.update(b"disaster-alert:destination:v1\0");
-    hash.update(subscription.bark_base_url().as_bytes());
-    hash.update([0]);
-    hash.update(subscription.device_key().as_bytes());
-    hash.finalize().into()
+impl FjallStorage {
+    fn destination_key(&self, subscription: &Subscription) -> [u8; 32] {
+        self.protection
+            .destination_digest(subscription.bark_base_url(), subscription.device_key())
+    }
+
+    fn destination_digest(&self, destination: &crate::models::DestinationId) -> [u8; 32] {
+        self.protection
+            .destination_digest(&destination.base_url, &destination.device_key)
+    }
+
+    fn confirmation_destination_key(&self, destination: &crate::models::DestinationId) -> Vec<u8> {
+        confirmation_destination_index(&self.destination_digest(destination))
+    }
+
+    fn encode_subscription(&self, record: &StoredSubscription) -> Result<Vec<u8>> {
+        self.protection
+            .seal_record(SUBSCRIPTIONS, &record.id.0.to_be_bytes(), encode(record)?)
+    }
+
+    fn decode_subscription(&self, key: &[u8], value: &[u8]) -> Result<StoredSubscription> {
+        decode(&self.protection.open_record(SUBSCRIPTIONS, key, value)?)
+    }
+
+    /// 待确认的订阅含 Bark Key 和监测点坐标，与订阅记录一样加密后写入 `meta`。
+    fn seal_confirmation(&self, id: u64, value: Vec<u8>) -> Result<Vec<u8>> {
+        self.protection
+            .seal_record(META, &confirmation_key(id), value)
+    }
+
+    fn encode_compiled(&self, compiled: &CompiledSubscription) -> Result<Vec<u8>> {
+        self.protection.seal_record(
+            COMPILED_SUBSCRIPTIONS,
+            &compiled.subscription_id.0.to_be_bytes(),
+            encode(compiled)?,
+        )
+    }
+}
+
+fn notification_open_key(destination_id: u64, incident_id: &IncidentId) -> Vec<u8> {
+    let mut key = Vec::with_capacity(8 + incident_id.as_str().len());
+    key.extend_from_slice(&destination_id.to_be_bytes());
+    key.extend_from_slice(incident_id.as_str().as_bytes());
+    key
+}
+
+fn felt_report_prefix(event_key: &str) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(event_key.len() + 1 + FELT_REPORTER_DIGEST_LEN);
+    prefix.extend_from_slice(event_key.as_bytes());
+    prefix.push(0);
+    prefix
 }
 
-fn destination_digest(destination: &crate::models::DestinationId) -> [u8; 32] {
-    use sha2::{Digest, Sha256};
-    let mut hash = Sha256::new();
-    hash.update(b"disaster-alert:destination:v1\0");
-    hash.update(destination.base_url.as_bytes());
-    hash.update([0]);
-    hash.update(destination.device_key.as_bytes());
-    hash.finalize().into()
+fn confirmation_destination_index(digest: &[u8; 32]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(25 + 32);
+    key.extend_from_slice(b"confirmation-destination:");
+    key.extend_from_slice(digest);
+    key
 }
 
 fn confirmation_key(id: u64) -> [u8; 21] {
@@ -1935,13 +3117,6 @@ fn confirmation_key(id: u64) -> [u8; 21] {
     key
 }
 
-fn confirmation_destination_key(destination: &crate::models::DestinationId) -> Vec<u8> {
-    let mut key = Vec::with_capacity(25 + 32);
-    key.extend_from_slice(b"confirmation-destination:");
-    key.extend_from_slice(&destination_digest(destination));
-    key
-}
-
 fn retry_key(retry: &RetryItem) -> [u8; 16] {
     let mut key = [0; 16];
     key[..8].copy_from_slice(&retry.due_at_ms.max(0).to_be_bytes());
@@ -2031,6 +3206,7 @@ fn cursor_key(provider: ProviderChannel, stream: &str) -> Vec<u8> {
         ProviderChannel::Wolfx => 1,
         ProviderChannel::FanStudio => 2,
         ProviderChannel::Huania => 3,
+        ProviderChannel::P2pquake => 4,
     });
     key.push(b':');
     key.extend_from_slice(stream.as_bytes());
@@ -2044,6 +3220,13 @@ fn ledger_prefix(incident_id: &IncidentId, category: crate::models::DisasterCate
     key
 }
 
+/// 早于本字段引入的已取消记录没有取消时间，以最后更新时间作为下限。
+fn deactivated_at(record: &StoredSubscription) -> i64 {
+    record
+        .deactivated_at_ms
+        .unwrap_or(record.subscription.updated_at)
+}
+
 fn ledger_key(
     incident_id: &IncidentId,
     category: crate::models::DisasterCategory,
@@ -2054,6 +3237,17 @@ fn ledger_key(
     key
 }
 
+/// 账本键为 22 字节事件 ID、1 字节灾种编码和 8 字节目的地 ID。
+fn parse_ledger_key(key: &[u8]) -> Option<(String, DisasterCategory, u64)> {
+    let incident_id = std::str::from_utf8(key.get(..22)?).ok()?;
+    let code = *key.get(22)?;
+    let category = DisasterCategory::ALL
+        .into_iter()
+        .find(|category| category_code(*category) == code)?;
+    let destination_id = u64::from_be_bytes(key.get(23..31)?.try_into().ok()?);
+    Some((incident_id.to_string(), category, destination_id))
+}
+
 fn category_code(category: crate::models::DisasterCategory) -> u8 {
     match category {
         crate::models::DisasterCategory::EarthquakeWarning => 1,
@@ -2093,6 +3287,13 @@ fn correlation_key(epoch: i64, id: &str, stream: &[u8; 32]) -> Vec<u8> {
     key
 }
 
+fn earthquake_history_key(occurred_ms: i64, event_key: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(8 + event_key.len());
+    key.extend_from_slice(&occurred_ms.max(0).to_be_bytes());
+    key.extend_from_slice(event_key.as_bytes());
+    key
+}
+
 fn correlation_epoch(key: &[u8]) -> Result<i64> {
     Ok(i64::from_be_bytes(
         key.get(..8)
@@ -2275,10 +3476,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "home".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 31.2,
                     longitude: 121.5,
-                },
+                }),
                 region: Default::default(),
             }],
             vec![AlertRule::default_for(DisasterCategory::EarthquakeReport)],
@@ -2310,6 +3511,413 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn enabling_protection_rehashes_indexes_and_encrypts_records() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let stored = {
+            let storage = FjallStorage::open(directory.path())?;
+            let stored = storage.store_subscription(subscription())?;
+            storage.persist()?;
+            stored
+        };
+        let protection = StorageProtection::from_secret(&[9; 32])?;
+        let destination = subscription().destination_id();
+        {
+            let storage = FjallStorage::open_with_protection(directory.path(), protection.clone())?;
+            let raw = storage
+                .subscriptions
+                .get(stored.id.0.to_be_bytes())?
+                .context("missing subscription record")?;
+            anyhow::ensure!(is_sealed_record(&raw));
+            anyhow::ensure!(!raw.windows(7).any(|window| window == b"device1"));
+            let compiled = storage
+                .compiled_subscriptions
+                .get(stored.id.0.to_be_bytes())?
+                .context("missing compiled record")?;
+            anyhow::ensure!(is_sealed_record(&compiled));
+            anyhow::ensure!(storage.compiled_subscription(stored.id)?.is_some());
+            anyhow::ensure!(
+                storage
+                    .subscriptions_by_destination
+                    .get(unkeyed_destination_digest(
+                        &destination.base_url,
+                        &destination.device_key
+                    ))?
+                    .is_none()
+            );
+            let found = storage
+                .stored_subscription_by_destination(&destination)?
+                .context("protected index lost the subscription")?;
+            anyhow::ensure!(found.id == stored.id && found.subscription.device_key() == "device1");
+            storage.persist()?;
+        }
+        anyhow::ensure!(FjallStorage::open(directory.path()).is_err());
+        anyhow::ensure!(
+            FjallStorage::open_with_protection(
+                directory.path(),
+                StorageProtection::from_secret(&[10; 32])?
+            )
+            .is_err()
+        );
+        let storage = FjallStorage::open_with_protection(directory.path(), protection)?;
+        anyhow::ensure!(storage.deactivate_subscription(stored.id)?);
+        anyhow::ensure!(
+            storage
+                .stored_subscription(stored.id)?
+                .is_some_and(|record| record.subscription.device_key() == "device1")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn enabling_protection_keeps_matching_and_pending_confirmations() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let mut pending = subscription();
+        pending.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        let pending_destination = pending.destination_id();
+        let stored = {
+            let storage = FjallStorage::open(directory.path())?;
+            let stored = storage.store_subscription(subscription())?;
+            storage.begin_confirmation(
+                7,
+                &pending_destination,
+                encode(&std::collections::BTreeMap::from([(
+                    "subscription",
+                    pending,
+                )]))?,
+            )?;
+            storage.persist()?;
+            stored
+        };
+        let protection = StorageProtection::from_secret(&[9; 32])?;
+        {
+            let storage = FjallStorage::open_with_protection(directory.path(), protection.clone())?;
+            let found = storage
+                .stored_subscription_by_destination(&subscription().destination_id())?
+                .context("protected index lost the subscription")?;
+            anyhow::ensure!(found.id == stored.id);
+
+            let source = test_source(DisasterCategory::EarthquakeReport, 0);
+            let event = DisasterEvent {
+                category: DisasterCategory::EarthquakeReport,
+                channel: source.channel,
+                source: source.id.to_string(),
+                event_id: "protected-match".to_string(),
+                revision: "1".to_string(),
+                report_num: 1,
+                title: String::new(),
+                description: String::new(),
+                latitude: Some(31.2),
+                longitude: Some(121.5),
+                magnitude: Some(6.5),
+                depth_km: Some(10.0),
+                affected_regions: Vec::new(),
+                radius_km: None,
+                level: 1,
+                occurred_at: "2026-07-13T00:00:00Z".to_string(),
+                final_report: false,
+                cancel: false,
+                training: false,
+                max_intensity: None,
+                official_warning: None,
+                assumed_hypocenter: false,
+            };
+            let plan = MatchPlan::for_event(&event)?;
+            let blocks = storage.posting_blocks(&plan)?;
+            let compiled = storage.load_compiled_blocks(&blocks)?;
+            let rows = crate::matching::MatchEngine::new(4)?.match_blocks(
+                Arc::new(event),
+                blocks,
+                &compiled,
+            );
+            anyhow::ensure!(rows.iter().any(|row| row.subscription_id == stored.id));
+
+            let raw = storage
+                .meta
+                .get(confirmation_key(7))?
+                .context("pending confirmation is missing")?;
+            anyhow::ensure!(is_sealed_record(&raw));
+            anyhow::ensure!(!raw.windows(7).any(|window| window == b"device2"));
+            anyhow::ensure!(storage.confirmation_record(7)?.is_some());
+            anyhow::ensure!(storage.remove_confirmation_for_destination(&pending_destination)?);
+            anyhow::ensure!(storage.confirmation_record(7)?.is_none());
+            storage.persist()?;
+        }
+        anyhow::ensure!(FjallStorage::open(directory.path()).is_err());
+        anyhow::ensure!(
+            FjallStorage::open_with_protection(
+                directory.path(),
+                StorageProtection::from_secret(&[10; 32])?
+            )
+            .is_err()
+        );
+        anyhow::ensure!(FjallStorage::open_with_protection(directory.path(), protection).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_protection_resumes_from_unsealed_records() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let protection = StorageProtection::from_secret(&[9; 32])?;
+        let mut second = subscription();
+        second.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        {
+            let storage = FjallStorage::open(directory.path())?;
+            let first = storage.store_subscription(subscription())?;
+            storage.store_subscription(second.clone())?;
+            // 模拟上次升级只完成了第一条订阅：记录已加密、索引已改写，但尚未写入指纹。
+            interrupt_protection(&storage, &protection)?;
+            let key = first.id.0.to_be_bytes();
+            let value = storage
+                .subscriptions
+                .get(key)?
+                .context("first subscription is missing")?;
+            let destination = first.subscription.destination_id();
+            let mut batch = storage.db.batch();
+            batch.insert(
+                &storage.subscriptions,
+                key,
+                protection.seal_record(SUBSCRIPTIONS, &key, value.to_vec())?,
+            );
+            batch.remove(
+                &storage.subscriptions_by_destination,
+                unkeyed_destination_digest(&destination.base_url, &destination.device_key),
+            );
+            batch.insert(
+                &storage.subscriptions_by_destination,
+                protection.destination_digest(&destination.base_url, &destination.device_key),
+                key,
+            );
+            batch.commit()?;
+            storage.persist()?;
+        }
+        let storage = FjallStorage::open_with_protection(directory.path(), protection)?;
+        anyhow::ensure!(storage.meta.get(b"storage_protection_pending")?.is_none());
+        for destination in [subscription().destination_id(), second.destination_id()] {
+            anyhow::ensure!(
+                storage
+                    .stored_subscription_by_destination(&destination)?
+                    .is_some()
+            );
+        }
+        anyhow::ensure!(
+            storage
+                .subscriptions
+                .iter()
+                .map(|item| item.value())
+                .collect::<std::result::Result<Vec<_>, _>>()?
+                .iter()
+                .all(|value| is_sealed_record(value))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_protection_requires_the_same_key() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let protection = StorageProtection::from_secret(&[9; 32])?;
+        {
+            let storage = FjallStorage::open(directory.path())?;
+            storage.store_subscription(subscription())?;
+            interrupt_protection(&storage, &protection)?;
+            storage.persist()?;
+        }
+        anyhow::ensure!(FjallStorage::open(directory.path()).is_err());
+        anyhow::ensure!(
+            FjallStorage::open_with_protection(
+                directory.path(),
+                StorageProtection::from_secret(&[8; 32])?,
+            )
+            .is_err()
+        );
+        let storage = FjallStorage::open_with_protection(directory.path(), protection)?;
+        anyhow::ensure!(
+            storage
+                .stored_subscription_by_destination(&subscription().destination_id())?
+                .is_some()
+        );
+        Ok(())
+    }
+
+    /// 写入保护开始时的标记，模拟在第一批提交后中断的升级。
+    fn interrupt_protection(storage: &FjallStorage, protection: &StorageProtection) -> Result<()> {
+        let fingerprint = protection
+            .fingerprint()
+            .context("protection fingerprint is missing")?;
+        storage
+            .meta
+            .insert(b"storage_protection_pending", fingerprint)?;
+        Ok(())
+    }
+
+    #[test]
+    fn pending_confirmations_are_sealed_in_meta() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open_with_protection(
+            directory.path(),
+            StorageProtection::from_secret(&[9; 32])?,
+        )?;
+        let destination = subscription().destination_id();
+        let value = encode(&std::collections::BTreeMap::from([(
+            "subscription",
+            subscription(),
+        )]))?;
+        storage.begin_confirmation(3, &destination, value.clone())?;
+
+        let raw = storage
+            .meta
+            .get(confirmation_key(3))?
+            .context("pending confirmation is missing")?;
+        anyhow::ensure!(is_sealed_record(&raw));
+        anyhow::ensure!(!raw.windows(7).any(|window| window == b"device1"));
+        anyhow::ensure!(storage.confirmation_record(3)?.as_deref() == Some(value.as_slice()));
+        anyhow::ensure!(storage.confirmation_records()?.len() == 1);
+        Ok(())
+    }
+
+    #[test]
+    fn purge_removes_history_of_one_destination_only() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let purged = storage.store_subscription(subscription())?;
+        let mut other = subscription();
+        other.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        let kept = storage.store_subscription(other)?;
+        let incident_id = IncidentId::derive("purge-test");
+        for destination_id in [purged.destination_id, kept.destination_id] {
+            storage.ledger.insert(
+                ledger_key(
+                    &incident_id,
+                    DisasterCategory::EarthquakeReport,
+                    destination_id.0,
+                ),
+                encode(&StoredDelivery {
+                    delivered_at_ms: 1,
+                    event_revision: 1,
+                    row: delivery_row(destination_id.0),
+                })?,
+            )?;
+            let mut retry = retry(destination_id.0, 71, u32::try_from(destination_id.0)?, 10);
+            retry.destination_id = destination_id;
+            let mut batch = storage.db.batch();
+            insert_retry_indexes(&storage, &mut batch, &retry)?;
+            batch.commit()?;
+            let dead_letter = DeadLetterItem {
+                id: destination_id.0,
+                batch_id: 70,
+                row_index: 0,
+                destination_id,
+                attempts: 3,
+                created_at_ms: 1,
+                failed_at_ms: 2,
+                permanent: true,
+                last_error: "rejected".to_string(),
+            };
+            storage
+                .dead_letters
+                .insert(dead_letter_key(&dead_letter), encode(&dead_letter)?)?;
+        }
+        storage
+            .delivery_batches
+            .insert(delivery_batch_key(1, 71), b"retained")?;
+
+        anyhow::ensure!(storage.purge_destination(&subscription().destination_id())?);
+        anyhow::ensure!(storage.stored_subscription(purged.id)?.is_none());
+        anyhow::ensure!(storage.compiled_subscription(purged.id)?.is_none());
+        anyhow::ensure!(
+            storage
+                .stored_subscription_by_destination(&subscription().destination_id())?
+                .is_none()
+        );
+        anyhow::ensure!(
+            storage
+                .retries_by_destination
+                .prefix(purged.destination_id.0.to_be_bytes())
+                .next()
+                .is_none()
+        );
+        anyhow::ensure!(
+            storage
+                .delivery_batches
+                .get(delivery_batch_key(1, 71))?
+                .is_some()
+        );
+        anyhow::ensure!(storage.ledger.len()? == 1 && storage.dead_letters.len()? == 1);
+        let export = storage
+            .subscription_export(kept.id)?
+            .context("kept subscription disappeared")?;
+        anyhow::ensure!(export.deliveries.len() == 1);
+        anyhow::ensure!(export.retries.len() == 1 && export.dead_letters.len() == 1);
+        anyhow::ensure!(export.places.len() == export.subscription.targets.len());
+        anyhow::ensure!(storage.compiled_subscription(kept.id)?.is_some());
+        anyhow::ensure!(!storage.purge_destination(&subscription().destination_id())?);
+        Ok(())
+    }
+
+    #[test]
+    fn undeliverable_subscriptions_are_deactivated_unless_recently_delivered() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let failing = storage.store_subscription(subscription())?;
+        let mut other = subscription();
+        other.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        let delivered = storage.store_subscription(other)?;
+        let failed_at_ms = crate::storage::try_now_millis()?.saturating_add(1_000);
+        for destination_id in [failing.destination_id, delivered.destination_id] {
+            for attempt in 0..3_u64 {
+                let dead_letter = DeadLetterItem {
+                    id: destination_id.0 * 10 + attempt,
+                    batch_id: 70,
+                    row_index: 0,
+                    destination_id,
+                    attempts: 1,
+                    created_at_ms: failed_at_ms,
+                    failed_at_ms,
+                    permanent: true,
+                    last_error: "device key rejected".to_string(),
+                };
+                storage
+                    .dead_letters
+                    .insert(dead_letter_key(&dead_letter), encode(&dead_letter)?)?;
+            }
+        }
+        storage.ledger.insert(
+            ledger_key(
+                &IncidentId::derive("undeliverable-test"),
+                DisasterCategory::EarthquakeReport,
+                delivered.destination_id.0,
+            ),
+            encode(&StoredDelivery {
+                delivered_at_ms: failed_at_ms,
+                event_revision: 1,
+                row: delivery_row(delivered.destination_id.0),
+            })?,
+        )?;
+
+        anyhow::ensure!(storage.deactivate_undeliverable_subscriptions(0, 4)? == 0);
+        anyhow::ensure!(storage.deactivate_undeliverable_subscriptions(0, 3)? == 1);
+        let record = storage
+            .stored_subscription(failing.id)?
+            .context("failing subscription disappeared")?;
+        anyhow::ensure!(!record.active && record.deactivated_at_ms.is_some());
+        anyhow::ensure!(storage.compiled_subscription(failing.id)?.is_none());
+        anyhow::ensure!(storage.compiled_subscription(delivered.id)?.is_some());
+        anyhow::ensure!(storage.deactivate_undeliverable_subscriptions(0, 3)? == 0);
+        Ok(())
+    }
+
     #[test]
     fn empty_recovery_scans_return_no_work() -> Result<()> {
         let directory = tempfile::tempdir()?;
@@ -2319,6 +3927,96 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn warm_up_counts_targets_per_coarse_cell() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        anyhow::ensure!(
+            storage.warm_up_subscriptions()? == crate::storage::WarmupReport::default()
+        );
+        let mut second = subscription();
+        second.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        storage.store_subscription(subscription())?;
+        storage.store_subscription(second)?;
+
+        let report = storage.warm_up_subscriptions()?;
+        anyhow::ensure!(report.subscriptions == 2);
+        anyhow::ensure!(report.targets == 2);
+        anyhow::ensure!(report.posting_keys > 0);
+        anyhow::ensure!(report.coarse_cells.len() == 1);
+        anyhow::ensure!(report.coarse_cells[0].1 == 2);
+        Ok(())
+    }
+
+    #[test]
+    fn density_counts_subscriptions_per_cell_and_region() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let mut regional = subscription();
+        regional.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        regional.targets[0].point = None;
+        regional.targets[0].region.province = "四川省".to_string();
+        let mut twice = subscription();
+        twice.targets.push(twice.targets[0].clone());
+        storage.store_subscription(twice)?;
+        storage.store_subscription(regional)?;
+
+        let density = storage.subscription_density(h3o::Resolution::Four)?;
+        anyhow::ensure!(density.cells.len() == 1 && density.cells[0].1 == 1);
+        anyhow::ensure!(
+            density.regions
+                == vec![(
+                    crate::utils::boundaries::position("四川").context("四川")?,
+                    1
+                )]
+        );
+        anyhow::ensure!(storage.subscription_density(h3o::Resolution::Nine).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn breakdown_groups_active_subscriptions() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let mut regional = subscription();
+        regional.destination = NotificationDestination::Bark {
+            base_url: "https://api.day.app".to_string(),
+            device_key: "device2".to_string(),
+        };
+        regional.targets[0].point = None;
+        regional.targets[0].region.province = "四川省".to_string();
+        let mut twice = subscription();
+        twice.targets.push(twice.targets[0].clone());
+        storage.store_subscription(twice)?;
+        storage.store_subscription(regional)?;
+
+        let breakdown = storage.subscription_breakdown()?;
+        anyhow::ensure!(breakdown.subscriptions == 2);
+        anyhow::ensure!(breakdown.channels == vec![("bark", 2)]);
+        anyhow::ensure!(breakdown.created_weeks.len() == 1 && breakdown.created_weeks[0].1 == 2);
+        let monday = breakdown.created_weeks[0].0;
+        anyhow::ensure!((monday.div_euclid(86_400_000) + 3).rem_euclid(7) == 0);
+        anyhow::ensure!(
+            breakdown
+                .regions
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>()
+                == 2
+        );
+        anyhow::ensure!(breakdown.regions.contains(&(
+            Some(crate::utils::boundaries::position("四川").context("四川")?),
+            1
+        )));
+        Ok(())
+    }
+
     #[test]
     fn deactivation_removes_compiled_record_and_postings() -> Result<()> {
         let directory = tempfile::tempdir()?;
@@ -2458,10 +4156,14 @@ mod tests {
                         interruption_level: InterruptionLevel::Critical,
                     },
                 ],
+                max_depth_km: None,
+                final_followup: false,
+                official_warnings_only: false,
             },
             DisasterCategory::EarthquakeReport => AlertRule::EarthquakeReport {
                 sources,
                 min_magnitude: 3.0 + random.index(6) as f64,
+                max_depth_km: None,
             },
             DisasterCategory::WeatherWarning => AlertRule::WeatherWarning {
                 sources,
@@ -2483,10 +4185,10 @@ mod tests {
                     TEST_REGIONS[random.index(TEST_REGIONS.len())];
                 MonitoringTarget {
                     label: format!("target{target_index}"),
-                    point: GeoPoint {
+                    point: Some(GeoPoint {
                         latitude: latitude + random.coordinate_offset(),
                         longitude: longitude + random.coordinate_offset(),
-                    },
+                    }),
                     region: AdministrativeRegion {
                         province: province.to_string(),
                         city: city.to_string(),
@@ -2540,6 +4242,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -2599,6 +4304,8 @@ mod tests {
                 incident_id,
                 event_revision,
                 created_at_ms: 1,
+                stage: crate::events::EventStage::First,
+                later_stream: false,
             })?,
         )?;
         Ok(())
@@ -2922,6 +4629,9 @@ mod tests {
             final_report: false,
             cancel: false,
             training: false,
+            max_intensity: None,
+            official_warning: None,
+            assumed_hypocenter: false,
         }
     }
 
@@ -3012,4 +4722,195 @@ mod tests {
         anyhow::ensure!(!storage.incident_aliases.is_empty()?);
         Ok(())
     }
+
+    #[test]
+    fn earthquake_history_keeps_the_latest_report_after_matching() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let first = correlated_event();
+        let mut second = first.clone();
+        second.report_num = 2;
+        second.revision = "2".to_string();
+        second.magnitude = Some(5.4);
+        let mut distant = first.clone();
+        distant.event_id = "distant".to_string();
+        distant.occurred_at = "2026-07-12T00:00:00Z".to_string();
+        distant.latitude = Some(-20.0);
+        distant.longitude = Some(-175.0);
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![second, distant], None)?;
+        // 迟到的旧报不覆盖已记录的新报。
+        storage.ingest_with_cursor(ProviderChannel::FanStudio, vec![first], None)?;
+        let coordinator = EventCoordinator::new(storage.clone());
+        while let Some(job) = coordinator.process_next()? {
+            storage.commit_match_batches(job.id, &[])?;
+        }
+
+        let mut query = crate::storage::EarthquakeQuery {
+            from_ms: 0,
+            to_ms: i64::MAX,
+            bbox: None,
+            min_magnitude: None,
+            category: None,
+            source: None,
+            limit: 10,
+        };
+        let search = storage.search_earthquakes(&query)?;
+        anyhow::ensure!(search.events.len() == 2 && !search.truncated);
+        anyhow::ensure!(
+            search.events[0].report_num == 2 && search.events[0].magnitude == Some(5.4)
+        );
+        anyhow::ensure!(search.events[1].event_id == "distant");
+
+        query.bbox = Some([170.0, -30.0, -170.0, -10.0]);
+        let search = storage.search_earthquakes(&query)?;
+        anyhow::ensure!(search.events.len() == 1 && search.events[0].event_id == "distant");
+        query.bbox = None;
+        query.min_magnitude = Some(5.2);
+        query.limit = 1;
+        let search = storage.search_earthquakes(&query)?;
+        anyhow::ensure!(search.events.len() == 1 && !search.truncated);
+        let source = search.events[0].source.clone();
+        let changed_ms = storage.earthquake_history_changed_ms();
+        let found = storage.earthquake_history_event(&["other", source.as_str()], "distant")?;
+        anyhow::ensure!(found.is_some_and(|event| event.event_id == "distant"));
+        anyhow::ensure!(
+            storage
+                .earthquake_history_event(&["other"], "distant")?
+                .is_none()
+        );
+
+        let cutoff = crate::models::rfc3339_to_epoch_millis("2026-07-12T12:00:00Z")
+            .context("invalid cutoff")?;
+        let stats = storage.prune(cutoff, 0, 0)?;
+        anyhow::ensure!(stats.earthquake_history == 1);
+        anyhow::ensure!(storage.earthquake_history_changed_ms() > changed_ms);
+        anyhow::ensure!(storage.earthquake_history.len()? == 1);
+        anyhow::ensure!(storage.earthquake_history_by_event.len()? == 1);
+        Ok(())
+    }
+
+    #[test]
+    fn imported_catalog_events_do_not_replace_existing_history() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let mut event = correlated_event();
+        event.source = "catalog.usgs".to_string();
+        let mut duplicate = event.clone();
+        duplicate.magnitude = Some(9.0);
+        let mut other = event.clone();
+        other.event_id = "other".to_string();
+
+        anyhow::ensure!(storage.import_earthquake_history(&[event, duplicate])? == 1);
+        anyhow::ensure!(storage.import_earthquake_history(&[other.clone(), other])? == 1);
+        anyhow::ensure!(storage.earthquake_history.len()? == 2);
+        anyhow::ensure!(storage.pending_inbox(10)?.is_empty());
+        let query = crate::storage::EarthquakeQuery {
+            from_ms: 0,
+            to_ms: i64::MAX,
+            bbox: None,
+            min_magnitude: Some(6.0),
+            category: None,
+            source: Some("catalog.usgs".to_string()),
+            limit: 10,
+        };
+        anyhow::ensure!(storage.search_earthquakes(&query)?.events.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn felt_reports_keep_one_per_network_and_follow_history_retention() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let mut event = correlated_event();
+        event.source = "catalog.usgs".to_string();
+        storage.import_earthquake_history(std::slice::from_ref(&event))?;
+        let event_key = event.event_key();
+        let report = |intensity| FeltReport {
+            intensity,
+            latitude: Some(35.68),
+            longitude: Some(139.77),
+            reported_at_ms: 1_000,
+        };
+
+        anyhow::ensure!(
+            storage.record_felt_report(&event_key, b"a", &report(2))?
+                == FeltReportOutcome::Recorded
+        );
+        anyhow::ensure!(
+            storage.record_felt_report(&event_key, b"a", &report(3))?
+                == FeltReportOutcome::Replaced
+        );
+        anyhow::ensure!(
+            storage.record_felt_report(&event_key, b"b", &report(1))?
+                == FeltReportOutcome::Recorded
+        );
+        let mut intensities = storage
+            .felt_reports(&event_key)?
+            .into_iter()
+            .map(|report| report.intensity)
+            .collect::<Vec<_>>();
+        intensities.sort_unstable();
+        anyhow::ensure!(intensities == [1, 3]);
+        anyhow::ensure!(storage.felt_reports("other")?.is_empty());
+        storage.record_felt_report("orphan", b"a", &report(4))?;
+        let (groups, truncated) = storage.felt_report_events()?;
+        anyhow::ensure!(!truncated && groups.len() == 1);
+        anyhow::ensure!(groups[0].0.event_key() == event_key && groups[0].1.len() == 2);
+
+        let stats = storage.prune(i64::MAX - 1, 0, 0)?;
+        anyhow::ensure!(stats.earthquake_history == 1 && stats.felt_reports == 2);
+        anyhow::ensure!(storage.felt_reports(&event_key)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn notification_opens_are_counted_exported_and_purged_per_destination() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let record = storage.store_subscription(subscription())?;
+        let destination_id = record.destination_id.0;
+        let incident_id = IncidentId::derive("open-test");
+
+        anyhow::ensure!(storage.record_notification_open(
+            destination_id,
+            &incident_id,
+            "wolfx.jma_eew",
+            "event-1",
+            1_000
+        )?);
+        anyhow::ensure!(!storage.record_notification_open(
+            destination_id,
+            &incident_id,
+            "wolfx.jma_eew",
+            "event-1",
+            2_000
+        )?);
+        storage.record_notification_open(
+            destination_id.saturating_add(1),
+            &incident_id,
+            "wolfx.jma_eew",
+            "event-1",
+            1_500,
+        )?;
+        let export = storage
+            .subscription_export(record.id)?
+            .context("subscription missing")?;
+        anyhow::ensure!(
+            export.notification_opens
+                == [NotificationOpen {
+                    incident_id: incident_id.as_str().to_string(),
+                    source: "wolfx.jma_eew".to_string(),
+                    event_id: "event-1".to_string(),
+                    first_opened_at_ms: 1_000,
+                    last_opened_at_ms: 2_000,
+                    opens: 2,
+                }]
+        );
+
+        let stats = storage.prune(0, 1_500, 0)?;
+        anyhow::ensure!(stats.notification_opens == 1);
+        anyhow::ensure!(storage.purge_destination(&subscription().destination_id())?);
+        anyhow::ensure!(storage.notification_opens.is_empty()?);
+        Ok(())
+    }
 }
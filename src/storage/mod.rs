@@ -1,10 +1,18 @@
 mod codec;
 mod facade;
 mod fjall;
+mod protection;
 
 pub(crate) use codec::{decode_record, encode_record};
-pub(crate) use facade::{BacklogCounts, RetentionPolicy, Storage};
-pub(crate) use fjall::{FjallStorage, InboxItem, IncidentResolutionCapacity};
+pub(crate) use facade::{
+    BacklogCounts, EarthquakeQuery, EarthquakeSearch, PruneStats, RetentionPolicy, Storage,
+    SubscriptionBreakdown, SubscriptionDensity, WarmupReport,
+};
+pub(crate) use fjall::{
+    FeltReportGroups, FeltReportOutcome, FjallStorage, InboxItem, IncidentResolutionCapacity,
+    SubscriptionExport,
+};
+pub(crate) use protection::StorageProtection;
 
 pub(crate) fn try_now_millis() -> anyhow::Result<i64> {
     let duration = std::time::SystemTime::now()
@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use aws_lc_rs::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use aws_lc_rs::hmac;
+use aws_lc_rs::rand::{SecureRandom, SystemRandom};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+const DESTINATION_DOMAIN: &[u8] = b"disaster-alert:destination:v1\0";
+const KEYED_DESTINATION_DOMAIN: &[u8] = b"disaster-alert:destination:v2\0";
+const RECORD_DOMAIN: &[u8] = b"disaster-alert:record:v1\0";
+const FELT_REPORTER_DOMAIN: &[u8] = b"disaster-alert:felt-reporter:v1\0";
+/// 加密记录的前缀；`0xff` 不是合法 CBOR 数据项的首字节，不会与明文记录混淆。
+const SEALED_RECORD_TAG: [u8; 2] = [0xff, 0x01];
+
+/// 订阅数据的静态保护。
+///
+/// 配置 `STORAGE_ENCRYPTION_KEY` 后，目的地索引改用带密钥的 HMAC，订阅记录、编译后的
+/// 匹配记录和待确认的订阅（含 Bark Key 与监测点坐标）以 AES-256-GCM 整体加密，并绑定所在分区和主键；
+/// 仅持有数据库文件无法还原推送凭据或精确位置。未配置时保持原有的无密钥摘要和明文记录。
+#[derive(Clone, Default)]
+pub(crate) struct StorageProtection {
+    keys: Option<Arc<ProtectionKeys>>,
+}
+
+struct ProtectionKeys {
+    index: hmac::Key,
+    seal: LessSafeKey,
+    fingerprint: [u8; 32],
+    random: SystemRandom,
+}
+
+impl StorageProtection {
+    pub(crate) fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 从 32 字节、无填充的 URL-safe Base64 密钥派生索引密钥和加密密钥。
+    pub(crate) fn from_encoded_key(value: &str) -> Result<Self> {
+        let decoded = Zeroizing::new(
+            URL_SAFE_NO_PAD
+                .decode(value.trim())
+                .context("STORAGE_ENCRYPTION_KEY must be URL-safe base64 without padding")?,
+        );
+        let secret: &[u8; 32] = decoded
+            .as_slice()
+            .try_into()
+            .map_err(|_error| anyhow::anyhow!("STORAGE_ENCRYPTION_KEY must decode to 32 bytes"))?;
+        Self::from_secret(secret)
+    }
+
+    pub(crate) fn from_secret(secret: &[u8; 32]) -> Result<Self> {
+        let root = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        let derive = |label: &[u8]| Zeroizing::new(hmac::sign(&root, label).as_ref().to_vec());
+        let index = hmac::Key::new(hmac::HMAC_SHA256, &derive(b"disaster-alert:storage:index"));
+        let seal_key = derive(b"disaster-alert:storage:seal");
+        let seal = LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &seal_key)
+                .map_err(|_error| anyhow::anyhow!("failed to initialize storage cipher"))?,
+        );
+        let fingerprint = derive(b"disaster-alert:storage:fingerprint")
+            .as_slice()
+            .try_into()
+            .context("storage key fingerprint has an invalid length")?;
+        Ok(Self {
+            keys: Some(Arc::new(ProtectionKeys {
+                index,
+                seal,
+                fingerprint,
+                random: SystemRandom::new(),
+            })),
+        })
+    }
+
+    /// 写入 `meta` 的密钥指纹，用于拒绝以错误密钥打开数据库。
+    pub(crate) fn fingerprint(&self) -> Option<[u8; 32]> {
+        self.keys.as_ref().map(|keys| keys.fingerprint)
+    }
+
+    /// 目的地索引键；启用保护时为带密钥的 HMAC，否则为无密钥摘要。
+    pub(crate) fn destination_digest(&self, base_url: &str, device_key: &str) -> [u8; 32] {
+        match &self.keys {
+            Some(keys) => {
+                let mut context = hmac::Context::with_key(&keys.index);
+                context.update(KEYED_DESTINATION_DOMAIN);
+                context.update(base_url.as_bytes());
+                context.update(&[0]);
+                context.update(device_key.as_bytes());
+                let mut digest = [0; 32];
+                digest.copy_from_slice(context.sign().as_ref());
+                digest
+            }
+            None => unkeyed_destination_digest(base_url, device_key),
+        }
+    }
+
+    /// 震感报告者在某次地震下的标识，用于同一网络对同一地震只保留一份报告。
+    /// 摘要绑定事件，不同地震的报告无法据此关联；启用保护时为带密钥的 HMAC。
+    pub(crate) fn felt_reporter_digest(&self, event_key: &str, network: &[u8]) -> [u8; 32] {
+        let mut digest = [0; 32];
+        match &self.keys {
+            Some(keys) => {
+                let mut context = hmac::Context::with_key(&keys.index);
+                context.update(FELT_REPORTER_DOMAIN);
+                context.update(event_key.as_bytes());
+                context.update(&[0]);
+                context.update(network);
+                digest.copy_from_slice(context.sign().as_ref());
+            }
+            None => {
+                let mut hash = Sha256::new();
+                hash.update(FELT_REPORTER_DOMAIN);
+                hash.update(event_key.as_bytes());
+                hash.update([0]);
+                hash.update(network);
+                digest = hash.finalize().into();
+            }
+        }
+        digest
+    }
+
+    /// 加密一条已编码的记录；未启用保护时原样返回。
+    pub(crate) fn seal_record(
+        &self,
+        keyspace: &str,
+        key: &[u8],
+        mut record: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let Some(keys) = &self.keys else {
+            return Ok(record);
+        };
+        let mut nonce = [0; NONCE_LEN];
+        keys.random
+            .fill(&mut nonce)
+            .map_err(|_error| anyhow::anyhow!("failed to generate storage nonce"))?;
+        keys.seal
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(record_aad(keyspace, key)),
+                &mut record,
+            )
+            .map_err(|_error| anyhow::anyhow!("failed to seal {keyspace} record"))?;
+        let mut sealed = Vec::with_capacity(SEALED_RECORD_TAG.len() + NONCE_LEN + record.len());
+        sealed.extend_from_slice(&SEALED_RECORD_TAG);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&record);
+        Ok(sealed)
+    }
+
+    /// 解密读出的记录。启用保护后只接受加密记录，未启用时只接受明文记录。
+    pub(crate) fn open_record<'a>(
+        &self,
+        keyspace: &str,
+        key: &[u8],
+        value: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>> {
+        let sealed = value.strip_prefix(&SEALED_RECORD_TAG);
+        let (keys, sealed) = match (&self.keys, sealed) {
+            (None, None) => return Ok(Cow::Borrowed(value)),
+            (Some(keys), Some(sealed)) => (keys, sealed),
+            (None, Some(_sealed)) => {
+                anyhow::bail!("{keyspace} record is encrypted; STORAGE_ENCRYPTION_KEY is required")
+            }
+            (Some(_keys), None) => anyhow::bail!("{keyspace} record is not encrypted"),
+        };
+        anyhow::ensure!(sealed.len() > NONCE_LEN, "{keyspace} record is truncated");
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_error| anyhow::anyhow!("{keyspace} record has an invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = keys
+            .seal
+            .open_in_place(nonce, Aad::from(record_aad(keyspace, key)), &mut in_out)
+            .map_err(|_error| anyhow::anyhow!("failed to decrypt {keyspace} record"))?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(Cow::Owned(in_out))
+    }
+}
+
+/// 附加数据绑定分区名和主键，防止把加密记录挪到其他位置后被当作合法记录读出。
+fn record_aad(keyspace: &str, key: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(RECORD_DOMAIN.len() + keyspace.len() + 1 + key.len());
+    aad.extend_from_slice(RECORD_DOMAIN);
+    aad.extend_from_slice(keyspace.as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(key);
+    aad
+}
+
+pub(crate) fn is_sealed_record(value: &[u8]) -> bool {
+    value.starts_with(&SEALED_RECORD_TAG)
+}
+
+pub(crate) fn unkeyed_destination_digest(base_url: &str, device_key: &str) -> [u8; 32] {
+    let mut hash = Sha256::new();
+    hash.update(DESTINATION_DOMAIN);
+    hash.update(base_url.as_bytes());
+    hash.update([0]);
+    hash.update(device_key.as_bytes());
+    hash.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_records_are_bound_to_keyspace_and_key() -> Result<()> {
+        let protection = StorageProtection::from_secret(&[7; 32])?;
+        let record = b"\xa1\x68latitude\xfb\x40\x41\x80\x00\x00\x00\x00\x00".to_vec();
+        let sealed = protection.seal_record("subscriptions", &[1], record.clone())?;
+        anyhow::ensure!(is_sealed_record(&sealed));
+        anyhow::ensure!(!sealed.windows(8).any(|window| window == b"latitude"));
+
+        let opened = protection.open_record("subscriptions", &[1], &sealed)?;
+        anyhow::ensure!(opened.as_ref() == record.as_slice());
+        anyhow::ensure!(
+            protection
+                .open_record("subscriptions", &[2], &sealed)
+                .is_err()
+        );
+        anyhow::ensure!(
+            protection
+                .open_record("compiled_subscriptions", &[1], &sealed)
+                .is_err()
+        );
+        anyhow::ensure!(
+            StorageProtection::disabled()
+                .open_record("subscriptions", &[1], &sealed)
+                .is_err()
+        );
+        anyhow::ensure!(
+            protection
+                .open_record("subscriptions", &[1], &record)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keyed_destination_digest_depends_on_secret() -> Result<()> {
+        let first = StorageProtection::from_secret(&[7; 32])?;
+        let second = StorageProtection::from_secret(&[8; 32])?;
+        let unkeyed = StorageProtection::disabled();
+        let digest = |protection: &StorageProtection| {
+            protection.destination_digest("https://bark.example.com", "device1")
+        };
+        anyhow::ensure!(digest(&first) != digest(&second));
+        anyhow::ensure!(digest(&first) != digest(&unkeyed));
+        anyhow::ensure!(
+            digest(&unkeyed) == unkeyed_destination_digest("https://bark.example.com", "device1")
+        );
+        anyhow::ensure!(first.fingerprint() != second.fingerprint());
+        Ok(())
+    }
+}
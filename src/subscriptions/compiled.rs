@@ -49,6 +49,10 @@ pub(crate) struct CompiledTarget {
     pub(crate) sin_latitude: f64,
     pub(crate) cos_latitude: f64,
     pub(crate) region_ids: Vec<RegionId>,
+    /// 按地区订阅的监测地点引用的内置边界，见 [`crate::utils::boundaries`]；此时坐标是边界的代表点，
+    /// 只按边界匹配，不看距离。
+    #[serde(default)]
+    pub(crate) boundary: Option<u16>,
     pub(crate) h3_cells: [u64; 3],
 }
 
@@ -62,6 +66,12 @@ pub(crate) struct CompiledRule {
     pub(crate) min_severity: u8,
     pub(crate) distance_km: f64,
     pub(crate) intensity_bands: Vec<CompiledIntensityBand>,
+    /// 地震规则的最大震源深度（千米）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_depth_km: Option<f64>,
+    /// 只匹配正式警报，见 [`AlertRule::official_warnings_only`]。
+    #[serde(default)]
+    pub(crate) official_warnings_only: bool,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -164,8 +174,11 @@ impl SubscriptionCompiler {
             .iter()
             .enumerate()
             .map(|(ordinal, target)| {
-                let latitude = target.point.latitude;
-                let longitude = target.point.longitude;
+                let anchor = target
+                    .anchor()
+                    .context("region-only target has no built-in boundary")?;
+                let latitude = anchor.latitude;
+                let longitude = anchor.longitude;
                 let lat_lng = LatLng::new(latitude, longitude)
                     .context("failed to encode target as H3 coordinate")?;
                 let latitude_radians = latitude.to_radians();
@@ -189,6 +202,7 @@ impl SubscriptionCompiler {
                     sin_latitude: latitude_radians.sin(),
                     cos_latitude: latitude_radians.cos(),
                     region_ids,
+                    boundary: target.boundary(),
                     h3_cells: H3_RESOLUTIONS
                         .map(|resolution| u64::from(lat_lng.to_cell(resolution))),
                 })
@@ -243,6 +257,25 @@ impl SourceId {
     }
 }
 
+impl CompiledSubscription {
+    /// 明确列出事件来源的规则优先，其次是适用全部来源的规则，与 `Subscription::alert_for_source` 一致。
+    pub(crate) fn rule_for(
+        &self,
+        category: DisasterCategory,
+        source: SourceId,
+    ) -> Option<&CompiledRule> {
+        let mut fallback = None;
+        for rule in self.rules.iter().filter(|rule| rule.category == category) {
+            if rule.wildcard_source {
+                fallback = Some(rule);
+            } else if rule.accepts_source(source) {
+                return Some(rule);
+            }
+        }
+        fallback
+    }
+}
+
 impl CompiledRule {
     pub(crate) fn accepts_source(&self, source: SourceId) -> bool {
         self.wildcard_source || source.bit().is_some_and(|bit| self.source_mask & bit != 0)
@@ -325,6 +358,8 @@ fn compile_rule(rule: &AlertRule) -> Result<CompiledRule> {
         min_severity,
         distance_km,
         intensity_bands,
+        max_depth_km: rule.max_depth_km(),
+        official_warnings_only: rule.official_warnings_only(),
     })
 }
 
@@ -1,5 +1,6 @@
 use crate::models::{DestinationId, Subscription};
-use crate::storage::{FjallStorage, decode_record, encode_record};
+use crate::storage::{FjallStorage, SubscriptionExport, decode_record, encode_record};
+use crate::subscriptions::SubscriptionId;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -77,7 +78,7 @@ pub(crate) struct LeasedSubscriptionConfirmation {
 }
 
 impl SubscriptionManager {
-    #[cfg(any(test, feature = "benchmarks"))]
+    /// 跳过确认推送直接写入订阅；只用于运维自检订阅、基准测试和测试。
     pub(crate) fn upsert_subscription(&self, subscription: Subscription) -> Result<()> {
         self.storage.store_subscription(subscription)?;
         Ok(())
@@ -127,6 +128,53 @@ impl SubscriptionManager {
         Ok(())
     }
 
+    /// 恢复在 `deactivated_after_ms` 之后取消的订阅；仍有效的订阅原样返回。
+    pub(crate) fn restore_subscription(
+        &self,
+        destination: &DestinationId,
+        deactivated_after_ms: i64,
+    ) -> Result<Option<Subscription>> {
+        Ok(self
+            .storage
+            .reactivate_subscription(destination, deactivated_after_ms)?
+            .map(|record| record.subscription))
+    }
+
+    /// 删除订阅及该目的地的全部送达、重试和死信记录；已取消的订阅也可以彻底删除。
+    pub(crate) fn purge_subscription(
+        &self,
+        destination: &DestinationId,
+    ) -> std::result::Result<(), DeleteSubscriptionError> {
+        if self.storage.purge_destination(destination)? {
+            Ok(())
+        } else {
+            Err(DeleteSubscriptionError::NotFound)
+        }
+    }
+
+    /// 查找目的地当前的订阅记录（含已取消的），用于签发数据导出链接。
+    pub(crate) fn export_subject(
+        &self,
+        destination: &DestinationId,
+    ) -> Result<Option<(SubscriptionId, u64, Subscription)>> {
+        Ok(self
+            .storage
+            .stored_subscription_by_destination(destination)?
+            .map(|record| (record.id, record.generation, record.subscription)))
+    }
+
+    /// 导出链接只对签发时的订阅版本有效，订阅被覆盖后旧链接随之失效。
+    pub(crate) fn export(
+        &self,
+        id: SubscriptionId,
+        generation: u64,
+    ) -> Result<Option<SubscriptionExport>> {
+        Ok(self
+            .storage
+            .subscription_export(id)?
+            .filter(|export| export.generation == generation))
+    }
+
     pub(crate) fn total_count(&self) -> Result<usize> {
         self.storage.active_subscription_count()
     }
@@ -344,10 +392,10 @@ mod tests {
             },
             vec![MonitoringTarget {
                 label: "home".to_string(),
-                point: GeoPoint {
+                point: Some(GeoPoint {
                     latitude: 35.0,
                     longitude: 105.0,
-                },
+                }),
                 region: Default::default(),
             }],
             vec![AlertRule::default_for(
@@ -427,6 +475,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn unsubscribed_device_can_be_restored_within_the_window() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let storage = FjallStorage::open(directory.path())?;
+        let manager = SubscriptionManager::new(storage.clone());
+        let leased = manager.begin_confirmation(subscription(), 100, 1_000)?;
+        anyhow::ensure!(manager.activate_confirmation(leased.id, leased.lease_token)?);
+        let destination = leased.subscription.destination_id();
+        manager.delete_subscription(&destination)?;
+
+        anyhow::ensure!(
+            manager
+                .restore_subscription(&destination, i64::MAX)?
+                .is_none()
+        );
+        anyhow::ensure!(manager.restore_subscription(&destination, 0)?.is_some());
+        anyhow::ensure!(manager.get_subscription(&destination)?.is_some());
+        let record = storage
+            .stored_subscription_by_destination(&destination)?
+            .context("missing restored subscription")?;
+        anyhow::ensure!(record.deactivated_at_ms.is_none());
+        anyhow::ensure!(storage.compiled_subscription(record.id)?.is_some());
+
+        manager.delete_subscription(&destination)?;
+        anyhow::ensure!(storage.purge_expired_subscriptions(i64::MAX)? == 1);
+        anyhow::ensure!(manager.restore_subscription(&destination, 0)?.is_none());
+        Ok(())
+    }
+
     #[test]
     fn due_confirmation_after_many_future_records_is_not_starved() -> Result<()> {
         let directory = tempfile::tempdir()?;
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+const DEFAULT_LOG_FILTER: &str = "disaster_alert=info,tower_http=info";
+#[cfg(feature = "otel")]
+const SERVICE_NAME: &str = "disaster-alert";
+
+/// 进程级日志与链路追踪。关闭时需调用 [`Telemetry::shutdown`] 以发送缓冲中的 span。
+pub(crate) struct Telemetry {
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    #[cfg(not(feature = "otel"))]
+    pub(crate) fn init() -> Result<Self> {
+        tracing_subscriber::registry()
+            .with(log_filter())
+            .with(tracing_subscriber::fmt::layer())
+            .init();
+        Ok(Self {})
+    }
+
+    /// 设置了 `OTEL_EXPORTER_OTLP_ENDPOINT` 或 `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`
+    /// 时通过 OTLP/HTTP 导出 span；地址、请求头和超时均按 OpenTelemetry 标准环境变量读取。
+    #[cfg(feature = "otel")]
+    pub(crate) fn init() -> Result<Self> {
+        let tracer_provider = if otlp_endpoint_configured() {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .build()?;
+            Some(
+                SdkTracerProvider::builder()
+                    .with_batch_exporter(exporter)
+                    .with_resource(
+                        opentelemetry_sdk::Resource::builder()
+                            .with_service_name(SERVICE_NAME)
+                            .build(),
+                    )
+                    .build(),
+            )
+        } else {
+            None
+        };
+        let otel_layer = tracer_provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+        });
+        tracing_subscriber::registry()
+            .with(log_filter())
+            .with(tracing_subscriber::fmt::layer())
+            .with(otel_layer)
+            .init();
+        if tracer_provider.is_some() {
+            tracing::info!(event = "telemetry.otlp_enabled", "telemetry.otlp_enabled");
+        }
+        Ok(Self { tracer_provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    pub(crate) fn shutdown(self) {}
+
+    #[cfg(feature = "otel")]
+    pub(crate) fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider
+            && let Err(error) = provider.shutdown()
+        {
+            tracing::warn!(event = "telemetry.shutdown_failed", error = ?error, "telemetry.shutdown_failed");
+        }
+    }
+}
+
+fn log_filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_error| DEFAULT_LOG_FILTER.into())
+}
+
+#[cfg(feature = "otel")]
+fn otlp_endpoint_configured() -> bool {
+    [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .into_iter()
+    .any(|name| std::env::var(name).is_ok_and(|value| !value.trim().is_empty()))
+}
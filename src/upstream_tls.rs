@@ -0,0 +1,228 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// 连接上游 WebSocket 和 Bark 服务器时使用的 TLS 设置：在内置根证书之外信任自建中转的
+/// 内部 CA，并可按主机名固定服务器公钥（SPKI SHA-256），防止告警链路被中间人篡改。
+#[derive(Clone)]
+pub(crate) struct UpstreamTls {
+    verifier: Arc<PinnedVerifier>,
+}
+
+impl UpstreamTls {
+    /// 未配置 `UPSTREAM_CA_FILE` 和 `UPSTREAM_TLS_PINS` 时返回 `None`，沿用各客户端默认的证书校验。
+    pub(crate) fn load(config: &Config) -> Result<Option<Self>> {
+        if config.upstream_ca_file.is_none() && config.upstream_tls_pins.is_empty() {
+            return Ok(None);
+        }
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        if let Some(path) = &config.upstream_ca_file {
+            let mut added = 0usize;
+            for certificate in CertificateDer::pem_file_iter(path)
+                .with_context(|| format!("failed to read UPSTREAM_CA_FILE {path}"))?
+            {
+                let certificate = certificate
+                    .with_context(|| format!("UPSTREAM_CA_FILE {path} contains invalid PEM"))?;
+                roots
+                    .add(certificate)
+                    .with_context(|| format!("UPSTREAM_CA_FILE {path} contains an invalid CA"))?;
+                added = added.saturating_add(1);
+            }
+            anyhow::ensure!(
+                added > 0,
+                "UPSTREAM_CA_FILE {path} contains no certificates"
+            );
+            tracing::info!(
+                event = "upstream_tls.ca_loaded",
+                path = %path,
+                certificates = added,
+                "upstream_tls.ca_loaded"
+            );
+        }
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+            .build()
+            .context("failed to build upstream certificate verifier")?;
+        Ok(Some(Self {
+            verifier: Arc::new(PinnedVerifier {
+                inner,
+                pins: config.upstream_tls_pins.clone(),
+            }),
+        }))
+    }
+
+    /// 按给定 ALPN 协议生成客户端 TLS 配置；WebSocket 只能使用 `http/1.1`。
+    pub(crate) fn client_config(&self, alpn: &[&[u8]]) -> Result<Arc<rustls::ClientConfig>> {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .context("failed to configure upstream TLS protocol versions")?
+            .dangerous()
+            .with_custom_certificate_verifier(self.verifier.clone())
+            .with_no_client_auth();
+        config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+        Ok(Arc::new(config))
+    }
+}
+
+/// 先按信任的根证书完成常规校验，再对配置了固定公钥的主机比对证书公钥摘要。
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: BTreeMap<String, Vec<[u8; 32]>>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        let ServerName::DnsName(name) = server_name else {
+            return Ok(verified);
+        };
+        let Some(pins) = self.pins.get(&name.as_ref().to_ascii_lowercase()) else {
+            return Ok(verified);
+        };
+        let digest = subject_public_key_info(end_entity).map(spki_digest).ok_or(
+            rustls::Error::InvalidCertificate(CertificateError::BadEncoding),
+        )?;
+        if !pins.contains(&digest) {
+            tracing::error!(
+                event = "upstream_tls.pin_mismatch",
+                host = %name.as_ref(),
+                "upstream_tls.pin_mismatch"
+            );
+            return Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn spki_digest(spki: &[u8]) -> [u8; 32] {
+    let hash = Sha256::digest(spki);
+    let mut digest = [0_u8; 32];
+    digest.copy_from_slice(&hash);
+    digest
+}
+
+/// 从 X.509 证书的 DER 编码中取出 SubjectPublicKeyInfo（含标签和长度），即固定公钥时摘要的对象。
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = der_element(certificate, 0x30)?;
+    let (tbs_certificate, _) = der_element(certificate, 0x30)?;
+    let mut rest = tbs_certificate;
+    // 可选的 [0] 版本号，之后依次是序列号、签名算法、颁发者、有效期和主体。
+    if rest.first() == Some(&0xa0) {
+        rest = der_element(rest, 0xa0)?.1;
+    }
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        rest = der_element(rest, tag)?.1;
+    }
+    let (_, after) = der_element(rest, 0x30)?;
+    rest.get(..rest.len().checked_sub(after.len())?)
+}
+
+/// 读取一个 DER 元素，返回内容和其后的剩余字节；标签不符或长度越界时返回 `None`。
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&actual, rest) = input.split_first()?;
+    if actual != tag {
+        return None;
+    }
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (usize::from(first), rest)
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let (bytes, rest) = rest.split_at_checked(count)?;
+        let length = bytes
+            .iter()
+            .fold(0usize, |length, byte| (length << 8) | usize::from(*byte));
+        (length, rest)
+    };
+    rest.split_at_checked(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        if contents.len() < 0x80 {
+            encoded.push(u8::try_from(contents.len()).unwrap_or(0));
+        } else {
+            encoded.push(0x82);
+            encoded.extend_from_slice(&u16::try_from(contents.len()).unwrap_or(0).to_be_bytes());
+        }
+        encoded.extend_from_slice(contents);
+        encoded
+    }
+
+    #[test]
+    fn extracts_subject_public_key_info_from_certificate() {
+        let spki = element(0x30, &[0x5a; 200]);
+        let tbs = [
+            element(0xa0, &element(0x02, &[2])),
+            element(0x02, &[1]),
+            element(0x30, &[]),
+            element(0x30, b"issuer"),
+            element(0x30, b"validity"),
+            element(0x30, b"subject"),
+            spki.clone(),
+            element(0xa3, b"extensions"),
+        ]
+        .concat();
+        let certificate = element(
+            0x30,
+            &[element(0x30, &tbs), element(0x30, &[]), element(0x03, &[0])].concat(),
+        );
+        assert_eq!(subject_public_key_info(&certificate), Some(spki.as_slice()));
+        assert_eq!(subject_public_key_info(&certificate[..40]), None);
+        assert_eq!(subject_public_key_info(b"not a certificate"), None);
+    }
+}
@@ -0,0 +1,665 @@
+use crate::models::DisasterEvent;
+use crate::utils::{distance, region};
+
+/// 内置的粗粒度行政区边界：中国省级行政区和日本都道府县，各用一个外接矩形表示。
+///
+/// 编译后的订阅按下标引用边界，只能在末尾追加，不能调整已有条目的顺序。
+pub(crate) static BOUNDARIES: [RegionBoundary; 81] = [
+    boundary(
+        "北京市",
+        &["北京"],
+        [39.44, 115.42, 41.06, 117.51],
+        (39.90, 116.41),
+    ),
+    boundary(
+        "天津市",
+        &["天津"],
+        [38.55, 116.70, 40.25, 118.06],
+        (39.13, 117.20),
+    ),
+    boundary(
+        "河北省",
+        &["河北"],
+        [36.05, 113.45, 42.62, 119.85],
+        (38.04, 114.51),
+    ),
+    boundary(
+        "山西省",
+        &["山西"],
+        [34.57, 110.23, 40.74, 114.56],
+        (37.87, 112.55),
+    ),
+    boundary(
+        "内蒙古自治区",
+        &["内蒙古"],
+        [37.40, 97.17, 53.34, 126.07],
+        (40.84, 111.75),
+    ),
+    boundary(
+        "辽宁省",
+        &["辽宁"],
+        [38.72, 118.84, 43.49, 125.78],
+        (41.80, 123.43),
+    ),
+    boundary(
+        "吉林省",
+        &["吉林"],
+        [40.86, 121.64, 46.30, 131.32],
+        (43.82, 125.32),
+    ),
+    boundary(
+        "黑龙江省",
+        &["黑龙江"],
+        [43.42, 121.18, 53.56, 135.09],
+        (45.80, 126.53),
+    ),
+    boundary(
+        "上海市",
+        &["上海"],
+        [30.68, 120.85, 31.88, 122.00],
+        (31.23, 121.47),
+    ),
+    boundary(
+        "江苏省",
+        &["江苏"],
+        [30.76, 116.36, 35.13, 121.95],
+        (32.06, 118.80),
+    ),
+    boundary(
+        "浙江省",
+        &["浙江"],
+        [27.04, 118.02, 31.18, 122.95],
+        (30.27, 120.15),
+    ),
+    boundary(
+        "安徽省",
+        &["安徽"],
+        [29.39, 114.88, 34.65, 119.65],
+        (31.82, 117.23),
+    ),
+    boundary(
+        "福建省",
+        &["福建"],
+        [23.50, 115.85, 28.32, 120.72],
+        (26.07, 119.30),
+    ),
+    boundary(
+        "江西省",
+        &["江西"],
+        [24.49, 113.57, 30.08, 118.48],
+        (28.68, 115.86),
+    ),
+    boundary(
+        "山东省",
+        &["山东"],
+        [34.38, 114.80, 38.40, 122.71],
+        (36.65, 117.12),
+    ),
+    boundary(
+        "河南省",
+        &["河南"],
+        [31.38, 110.35, 36.37, 116.65],
+        (34.75, 113.63),
+    ),
+    boundary(
+        "湖北省",
+        &["湖北"],
+        [29.03, 108.36, 33.27, 116.13],
+        (30.59, 114.31),
+    ),
+    boundary(
+        "湖南省",
+        &["湖南"],
+        [24.64, 108.79, 30.13, 114.26],
+        (28.23, 112.94),
+    ),
+    boundary(
+        "广东省",
+        &["广东"],
+        [20.22, 109.66, 25.52, 117.32],
+        (23.13, 113.26),
+    ),
+    boundary(
+        "广西壮族自治区",
+        &["广西", "广西壮族"],
+        [20.90, 104.45, 26.39, 112.06],
+        (22.82, 108.37),
+    ),
+    boundary(
+        "海南省",
+        &["海南"],
+        [18.16, 108.61, 20.16, 111.05],
+        (20.04, 110.20),
+    ),
+    boundary(
+        "重庆市",
+        &["重庆"],
+        [28.16, 105.29, 32.20, 110.20],
+        (29.56, 106.55),
+    ),
+    boundary(
+        "四川省",
+        &["四川"],
+        [26.05, 97.35, 34.31, 108.55],
+        (30.57, 104.07),
+    ),
+    boundary(
+        "贵州省",
+        &["贵州"],
+        [24.62, 103.60, 29.22, 109.59],
+        (26.65, 106.63),
+    ),
+    boundary(
+        "云南省",
+        &["云南"],
+        [21.14, 97.53, 29.23, 106.20],
+        (25.04, 102.71),
+    ),
+    boundary(
+        "西藏自治区",
+        &["西藏"],
+        [26.85, 78.40, 36.48, 99.12],
+        (29.65, 91.17),
+    ),
+    boundary(
+        "陕西省",
+        &["陕西"],
+        [31.71, 105.49, 39.59, 111.24],
+        (34.34, 108.94),
+    ),
+    boundary(
+        "甘肃省",
+        &["甘肃"],
+        [32.60, 92.34, 42.79, 108.71],
+        (36.06, 103.83),
+    ),
+    boundary(
+        "青海省",
+        &["青海"],
+        [31.60, 89.40, 39.21, 103.07],
+        (36.62, 101.78),
+    ),
+    boundary(
+        "宁夏回族自治区",
+        &["宁夏", "宁夏回族"],
+        [35.24, 104.28, 39.38, 107.65],
+        (38.49, 106.23),
+    ),
+    boundary(
+        "新疆维吾尔自治区",
+        &["新疆", "新疆维吾尔"],
+        [34.34, 73.50, 49.18, 96.38],
+        (43.83, 87.62),
+    ),
+    boundary(
+        "香港特别行政区",
+        &["香港"],
+        [22.15, 113.83, 22.56, 114.41],
+        (22.32, 114.17),
+    ),
+    boundary(
+        "澳门特别行政区",
+        &["澳门"],
+        [22.11, 113.53, 22.22, 113.60],
+        (22.20, 113.54),
+    ),
+    boundary(
+        "台湾省",
+        &["台湾"],
+        [21.90, 119.31, 25.30, 122.00],
+        (25.03, 121.56),
+    ),
+    boundary(
+        "北海道",
+        &["北海道"],
+        [41.35, 139.33, 45.56, 148.90],
+        (43.06, 141.35),
+    ),
+    boundary(
+        "青森県",
+        &["青森"],
+        [40.22, 139.49, 41.56, 141.68],
+        (40.82, 140.74),
+    ),
+    boundary(
+        "岩手県",
+        &["岩手"],
+        [38.75, 140.65, 40.45, 142.07],
+        (39.70, 141.15),
+    ),
+    boundary(
+        "宮城県",
+        &["宮城", "宫城"],
+        [37.77, 140.27, 39.00, 141.68],
+        (38.27, 140.87),
+    ),
+    boundary(
+        "秋田県",
+        &["秋田"],
+        [38.87, 139.69, 40.51, 140.99],
+        (39.72, 140.10),
+    ),
+    boundary(
+        "山形県",
+        &["山形"],
+        [37.73, 139.52, 39.21, 140.65],
+        (38.24, 140.36),
+    ),
+    boundary(
+        "福島県",
+        &["福島", "福岛"],
+        [36.79, 139.16, 37.98, 141.05],
+        (37.75, 140.47),
+    ),
+    boundary(
+        "茨城県",
+        &["茨城"],
+        [35.74, 139.69, 36.95, 140.85],
+        (36.37, 140.47),
+    ),
+    boundary(
+        "栃木県",
+        &["栃木"],
+        [36.20, 139.33, 37.16, 140.30],
+        (36.56, 139.88),
+    ),
+    boundary(
+        "群馬県",
+        &["群馬", "群马"],
+        [35.98, 138.39, 37.06, 139.67],
+        (36.39, 139.06),
+    ),
+    boundary(
+        "埼玉県",
+        &["埼玉"],
+        [35.75, 138.71, 36.28, 139.90],
+        (35.86, 139.65),
+    ),
+    boundary(
+        "千葉県",
+        &["千葉", "千叶"],
+        [34.90, 139.74, 36.10, 140.87],
+        (35.61, 140.12),
+    ),
+    boundary(
+        "東京都",
+        &["東京", "东京"],
+        [35.50, 138.94, 35.90, 139.92],
+        (35.69, 139.69),
+    ),
+    boundary(
+        "神奈川県",
+        &["神奈川"],
+        [35.13, 138.91, 35.67, 139.84],
+        (35.45, 139.64),
+    ),
+    boundary(
+        "新潟県",
+        &["新潟", "新泻"],
+        [36.74, 137.63, 38.55, 139.90],
+        (37.90, 139.02),
+    ),
+    boundary(
+        "富山県",
+        &["富山"],
+        [36.27, 136.77, 36.98, 137.76],
+        (36.70, 137.21),
+    ),
+    boundary(
+        "石川県",
+        &["石川"],
+        [36.07, 136.24, 37.86, 137.37],
+        (36.59, 136.63),
+    ),
+    boundary(
+        "福井県",
+        &["福井"],
+        [35.35, 135.45, 36.30, 136.83],
+        (36.07, 136.22),
+    ),
+    boundary(
+        "山梨県",
+        &["山梨"],
+        [35.17, 138.18, 35.97, 139.13],
+        (35.66, 138.57),
+    ),
+    boundary(
+        "長野県",
+        &["長野", "长野"],
+        [35.20, 137.32, 37.03, 138.74],
+        (36.65, 138.18),
+    ),
+    boundary(
+        "岐阜県",
+        &["岐阜"],
+        [35.13, 136.28, 36.47, 137.65],
+        (35.39, 136.72),
+    ),
+    boundary(
+        "静岡県",
+        &["静岡", "静冈"],
+        [34.57, 137.47, 35.65, 139.18],
+        (34.98, 138.38),
+    ),
+    boundary(
+        "愛知県",
+        &["愛知", "爱知"],
+        [34.57, 136.67, 35.43, 137.84],
+        (35.18, 136.91),
+    ),
+    boundary(
+        "三重県",
+        &["三重"],
+        [33.72, 135.85, 35.26, 136.99],
+        (34.73, 136.51),
+    ),
+    boundary(
+        "滋賀県",
+        &["滋賀", "滋贺"],
+        [34.79, 135.76, 35.70, 136.46],
+        (35.00, 135.87),
+    ),
+    boundary(
+        "京都府",
+        &["京都"],
+        [34.71, 134.85, 35.78, 136.06],
+        (35.02, 135.76),
+    ),
+    boundary(
+        "大阪府",
+        &["大阪"],
+        [34.27, 135.09, 35.05, 135.75],
+        (34.69, 135.52),
+    ),
+    boundary(
+        "兵庫県",
+        &["兵庫", "兵库"],
+        [34.16, 134.25, 35.68, 135.47],
+        (34.69, 135.18),
+    ),
+    boundary(
+        "奈良県",
+        &["奈良"],
+        [33.86, 135.54, 34.78, 136.23],
+        (34.69, 135.83),
+    ),
+    boundary(
+        "和歌山県",
+        &["和歌山"],
+        [33.43, 135.06, 34.39, 136.01],
+        (34.23, 135.17),
+    ),
+    boundary(
+        "鳥取県",
+        &["鳥取", "鸟取"],
+        [35.05, 133.13, 35.62, 134.52],
+        (35.50, 134.24),
+    ),
+    boundary(
+        "島根県",
+        &["島根", "岛根"],
+        [34.30, 131.67, 37.24, 133.39],
+        (35.47, 133.05),
+    ),
+    boundary(
+        "岡山県",
+        &["岡山", "冈山"],
+        [34.30, 133.27, 35.35, 134.41],
+        (34.66, 133.93),
+    ),
+    boundary(
+        "広島県",
+        &["広島", "广岛"],
+        [34.03, 132.04, 35.11, 133.47],
+        (34.40, 132.46),
+    ),
+    boundary(
+        "山口県",
+        &["山口"],
+        [33.71, 130.77, 34.80, 132.49],
+        (34.19, 131.47),
+    ),
+    boundary(
+        "徳島県",
+        &["徳島", "德岛"],
+        [33.54, 133.66, 34.25, 134.82],
+        (34.07, 134.56),
+    ),
+    boundary(
+        "香川県",
+        &["香川"],
+        [34.01, 133.45, 34.57, 134.45],
+        (34.34, 134.04),
+    ),
+    boundary(
+        "愛媛県",
+        &["愛媛", "爱媛"],
+        [32.89, 132.00, 34.30, 133.69],
+        (33.84, 132.77),
+    ),
+    boundary(
+        "高知県",
+        &["高知"],
+        [32.70, 132.48, 33.88, 134.31],
+        (33.56, 133.53),
+    ),
+    boundary(
+        "福岡県",
+        &["福岡", "福冈"],
+        [33.00, 129.99, 34.25, 131.19],
+        (33.61, 130.42),
+    ),
+    boundary(
+        "佐賀県",
+        &["佐賀", "佐贺"],
+        [32.95, 129.74, 33.62, 130.54],
+        (33.25, 130.30),
+    ),
+    boundary(
+        "長崎県",
+        &["長崎", "长崎"],
+        [32.57, 128.10, 34.73, 130.38],
+        (32.74, 129.87),
+    ),
+    boundary(
+        "熊本県",
+        &["熊本"],
+        [32.09, 129.94, 33.19, 131.33],
+        (32.79, 130.74),
+    ),
+    boundary(
+        "大分県",
+        &["大分"],
+        [32.71, 130.82, 33.74, 132.09],
+        (33.24, 131.61),
+    ),
+    boundary(
+        "宮崎県",
+        &["宮崎", "宫崎"],
+        [31.36, 130.70, 32.84, 131.89],
+        (31.91, 131.42),
+    ),
+    boundary(
+        "鹿児島県",
+        &["鹿児島", "鹿儿岛"],
+        [27.02, 128.39, 32.30, 131.21],
+        (31.56, 130.56),
+    ),
+    boundary(
+        "沖縄県",
+        &["沖縄", "冲绳"],
+        [24.04, 122.93, 27.89, 131.33],
+        (26.21, 127.68),
+    ),
+];
+
+/// 日本都道府县名称的后缀，`region::normalize` 不处理这些中文里罕见的后缀。
+const PREFECTURE_SUFFIXES: &[&str] = &["県", "县", "府", "都"];
+/// [`BOUNDARIES`] 中前 31 项为中国大陆省级行政区，其后依次为港澳台和日本都道府县。
+const MAINLAND_CHINA_END: usize = 31;
+const JAPAN_START: usize = 34;
+
+/// 坐标按内置边界粗略归属的国家或地区，用于在多家机构之间选择权威数据源。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Territory {
+    MainlandChina,
+    Japan,
+    Other,
+}
+
+#[derive(Debug)]
+pub(crate) struct RegionBoundary {
+    /// 正式名称，按地区订阅时写回订阅。
+    pub(crate) name: &'static str,
+    /// 去掉行政区划后缀的简称，含日本地名的简体中文写法。
+    aliases: &'static [&'static str],
+    south: f64,
+    west: f64,
+    north: f64,
+    east: f64,
+    /// 省会或县厅所在地，作为按地区订阅的测距代表点。
+    pub(crate) anchor: (f64, f64),
+}
+
+const fn boundary(
+    name: &'static str,
+    aliases: &'static [&'static str],
+    [south, west, north, east]: [f64; 4],
+    anchor: (f64, f64),
+) -> RegionBoundary {
+    RegionBoundary {
+        name,
+        aliases,
+        south,
+        west,
+        north,
+        east,
+        anchor,
+    }
+}
+
+impl RegionBoundary {
+    pub(crate) fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.south..=self.north).contains(&latitude)
+            && (self.west..=self.east).contains(&longitude)
+    }
+
+    /// 震中（或台风、预警中心）落在边界内，或影响地区列表中有以本地区开头的名称。
+    pub(crate) fn covers(&self, event: &DisasterEvent) -> bool {
+        event
+            .latitude
+            .zip(event.longitude)
+            .is_some_and(|(latitude, longitude)| self.contains(latitude, longitude))
+            || event.affected_regions.iter().any(|value| {
+                let value = region::normalize(value);
+                self.aliases.iter().any(|alias| value.starts_with(alias))
+            })
+    }
+}
+
+/// 按名称查找内置边界的下标，接受正式名称、简称和日本地名的简体写法。
+pub(crate) fn position(name: &str) -> Option<u16> {
+    let key = region::normalize(name);
+    if key.is_empty() {
+        return None;
+    }
+    let lookup = |key: &str| {
+        BOUNDARIES
+            .iter()
+            .position(|boundary| boundary.aliases.contains(&key))
+    };
+    let index = lookup(&key).or_else(|| {
+        PREFECTURE_SUFFIXES
+            .iter()
+            .find_map(|suffix| key.strip_suffix(suffix))
+            .filter(|stripped| !stripped.is_empty())
+            .and_then(lookup)
+    })?;
+    u16::try_from(index).ok()
+}
+
+pub(crate) fn get(index: u16) -> Option<&'static RegionBoundary> {
+    BOUNDARIES.get(usize::from(index))
+}
+
+pub(crate) fn find(name: &str) -> Option<&'static RegionBoundary> {
+    position(name).and_then(get)
+}
+
+/// 坐标所在的地区。外接矩形会互相重叠，取代表点最近的一个；边界只是近似，省界附近可能归错。
+pub(crate) fn locate(latitude: f64, longitude: f64) -> Option<&'static RegionBoundary> {
+    locate_index(latitude, longitude).and_then(|index| BOUNDARIES.get(index))
+}
+
+/// 坐标所在地区的下标，与 [`position`] 的结果可以互相比较。
+pub(crate) fn locate_position(latitude: f64, longitude: f64) -> Option<u16> {
+    locate_index(latitude, longitude).and_then(|index| u16::try_from(index).ok())
+}
+
+pub(crate) fn territory(latitude: f64, longitude: f64) -> Territory {
+    match locate_index(latitude, longitude) {
+        Some(index) if index < MAINLAND_CHINA_END => Territory::MainlandChina,
+        Some(index) if index >= JAPAN_START => Territory::Japan,
+        Some(_) | None => Territory::Other,
+    }
+}
+
+fn locate_index(latitude: f64, longitude: f64) -> Option<usize> {
+    BOUNDARIES
+        .iter()
+        .enumerate()
+        .filter(|(_, boundary)| boundary.contains(latitude, longitude))
+        .filter_map(|(index, boundary)| {
+            let (anchor_latitude, anchor_longitude) = boundary.anchor;
+            distance::vincenty_distance(latitude, longitude, anchor_latitude, anchor_longitude)
+                .map(|distance_km| (index, distance_km))
+        })
+        .min_by(|left, right| left.1.total_cmp(&right.1))
+        .map(|(index, _distance_km)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_regions_by_full_name_short_name_and_simplified_spelling() {
+        assert_eq!(find("四川省").map(|boundary| boundary.name), Some("四川省"));
+        assert_eq!(
+            find("广西").map(|boundary| boundary.name),
+            Some("广西壮族自治区")
+        );
+        assert_eq!(find("石川県").map(|boundary| boundary.name), Some("石川県"));
+        assert_eq!(find("东京都").map(|boundary| boundary.name), Some("東京都"));
+        assert_eq!(find("京都").map(|boundary| boundary.name), Some("京都府"));
+        assert_eq!(find("北海道").map(|boundary| boundary.name), Some("北海道"));
+        assert!(find("成都市").is_none());
+        assert!(find("").is_none());
+    }
+
+    #[test]
+    fn territory_splits_mainland_china_from_japan_and_the_rest() {
+        assert_eq!(BOUNDARIES[MAINLAND_CHINA_END - 1].name, "新疆维吾尔自治区");
+        assert_eq!(BOUNDARIES[JAPAN_START].name, "北海道");
+        assert_eq!(territory(30.66, 104.06), Territory::MainlandChina);
+        assert_eq!(territory(35.68, 139.69), Territory::Japan);
+        assert_eq!(territory(25.03, 121.56), Territory::Other);
+        assert_eq!(territory(0.0, 0.0), Territory::Other);
+    }
+
+    #[test]
+    fn anchors_lie_inside_their_own_boundaries() {
+        for boundary in &BOUNDARIES {
+            assert!(
+                boundary.contains(boundary.anchor.0, boundary.anchor.1),
+                "{}",
+                boundary.name
+            );
+            assert!(
+                position(boundary.name)
+                    .and_then(get)
+                    .is_some_and(|found| found.name == boundary.name)
+            );
+        }
+    }
+}
@@ -139,6 +139,118 @@ pub(crate) fn vincenty_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> O
     Some(s / 1000.0)
 }
 
+/// 从第一个点出发沿大圆前往第二个点的初始方位角，单位为度，正北为 0、顺时针增加，范围 [0, 360)。
+///
+/// 按球面计算，与椭球面方位角相差不超过约 0.2°，用于显示足够；两点重合时返回 0。
+pub(crate) fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_longitude = (lon2 - lon1).to_radians();
+    let y = delta_longitude.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_longitude.cos();
+    if y.abs() < 1e-12 && x.abs() < 1e-12 {
+        return 0.0;
+    }
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+const EARTH_MEAN_RADIUS_KM: f64 = 6_371.008_8;
+
+/// 按列（结构数组）保存的一批坐标，三个分量连续存放，便于批量距离计算逐列顺序读取。
+#[derive(Debug, Default)]
+pub(crate) struct CoordinateBatch {
+    latitude_radians: Vec<f64>,
+    longitude_radians: Vec<f64>,
+    cos_latitude: Vec<f64>,
+}
+
+impl CoordinateBatch {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            latitude_radians: Vec::with_capacity(capacity),
+            longitude_radians: Vec::with_capacity(capacity),
+            cos_latitude: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// 追加一个已换算为弧度的坐标；`cos_latitude` 由调用方预先计算以便复用。
+    pub(crate) fn push(
+        &mut self,
+        latitude_radians: f64,
+        longitude_radians: f64,
+        cos_latitude: f64,
+    ) {
+        self.latitude_radians.push(latitude_radians);
+        self.longitude_radians.push(longitude_radians);
+        self.cos_latitude.push(cos_latitude);
+    }
+}
+
+/// 用球面 haversine 公式计算 `origin`（弧度）到 `batch` 中每个坐标的大圆距离，单位为千米，
+/// 按 `batch` 的顺序追加到 `output`。
+///
+/// 循环体没有分支，只读取连续切片，便于编译器自动向量化；与 [`vincenty_distance`] 相比
+/// 误差在 0.5% 以内，足以用于订阅半径筛选。
+pub(crate) fn haversine_distance_batch(
+    origin_latitude_radians: f64,
+    origin_longitude_radians: f64,
+    origin_cos_latitude: f64,
+    batch: &CoordinateBatch,
+    output: &mut Vec<f64>,
+) {
+    output.reserve(batch.latitude_radians.len());
+    output.extend(
+        batch
+            .latitude_radians
+            .iter()
+            .zip(&batch.longitude_radians)
+            .zip(&batch.cos_latitude)
+            .map(|((latitude, longitude), cos_latitude)| {
+                let half_latitude = ((latitude - origin_latitude_radians) * 0.5).sin();
+                let half_longitude = ((longitude - origin_longitude_radians) * 0.5).sin();
+                let a = (half_latitude * half_latitude
+                    + origin_cos_latitude * cos_latitude * half_longitude * half_longitude)
+                    .clamp(0.0, 1.0);
+                EARTH_MEAN_RADIUS_KM * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+            }),
+    );
+}
+
+/// 用户提交的坐标无法使用的原因，各自对应一条具体的错误提示。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CoordinateError {
+    NonFinite,
+    LatitudeOutOfRange,
+    /// 纬度超出范围而经度落在纬度范围内，多半是经纬度填反了。
+    Swapped,
+}
+
+impl CoordinateError {
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            Self::NonFinite => "监测地点坐标必须是有效数字",
+            Self::LatitudeOutOfRange => "监测地点纬度必须在 -90 到 90 之间",
+            Self::Swapped => "监测地点纬度超出范围，经纬度可能填反了",
+        }
+    }
+}
+
+/// 校验用户提交的坐标，并把经度折算到 [-180, 180)，例如 180 记为 -180、360.5 记为 0.5，
+/// 让同一位置在地理索引中只有一种写法。
+pub(crate) fn normalize_coordinates(lat: f64, lon: f64) -> Result<(f64, f64), CoordinateError> {
+    if !(lat.is_finite() && lon.is_finite()) {
+        return Err(CoordinateError::NonFinite);
+    }
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(if (-90.0..=90.0).contains(&lon) {
+            CoordinateError::Swapped
+        } else {
+            CoordinateError::LatitudeOutOfRange
+        });
+    }
+    let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    Ok((lat, lon))
+}
+
 pub(crate) fn validate_coordinates(lat: f64, lon: f64) -> bool {
     lat.is_finite()
         && lon.is_finite()
@@ -150,6 +262,18 @@ pub(crate) fn validate_coordinates(lat: f64, lon: f64) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn initial_bearing_follows_compass_directions() {
+        assert!((initial_bearing(0.0, 0.0, 10.0, 0.0) - 0.0).abs() < 1e-9);
+        assert!((initial_bearing(0.0, 0.0, 0.0, 10.0) - 90.0).abs() < 1e-9);
+        assert!((initial_bearing(10.0, 0.0, 0.0, 0.0) - 180.0).abs() < 1e-9);
+        assert!((initial_bearing(0.0, 179.0, 0.0, -179.0) - 90.0).abs() < 1e-9);
+        // 北京到上海大致向东南。
+        let bearing = initial_bearing(39.9042, 116.4074, 31.2304, 121.4737);
+        assert!((150.0..160.0).contains(&bearing), "{bearing}");
+        assert_eq!(initial_bearing(35.0, 139.0, 35.0, 139.0), 0.0);
+    }
+
     #[test]
     fn test_same_point() {
         let dist = vincenty_distance(0.0, 0.0, 0.0, 0.0);
@@ -245,6 +369,72 @@ mod tests {
         assert!(!validate_coordinates(f64::NAN, 0.0));
     }
 
+    #[test]
+    fn normalize_coordinates_wraps_longitude_and_names_the_problem() {
+        assert_eq!(normalize_coordinates(35.0, 139.5), Ok((35.0, 139.5)));
+        assert_eq!(normalize_coordinates(0.0, 180.0), Ok((0.0, -180.0)));
+        assert_eq!(normalize_coordinates(0.0, -180.0), Ok((0.0, -180.0)));
+        assert_eq!(normalize_coordinates(10.0, 360.5), Ok((10.0, 0.5)));
+        assert_eq!(normalize_coordinates(10.0, -190.0), Ok((10.0, 170.0)));
+        assert_eq!(
+            normalize_coordinates(f64::NAN, 0.0),
+            Err(CoordinateError::NonFinite)
+        );
+        assert_eq!(
+            normalize_coordinates(0.0, f64::INFINITY),
+            Err(CoordinateError::NonFinite)
+        );
+        assert_eq!(
+            normalize_coordinates(139.7, 35.6),
+            Err(CoordinateError::Swapped)
+        );
+        assert_eq!(
+            normalize_coordinates(139.7, 135.6),
+            Err(CoordinateError::LatitudeOutOfRange)
+        );
+    }
+
+    #[test]
+    fn haversine_batch_agrees_with_vincenty() {
+        let points: [(f64, f64); _] = [
+            (31.2304, 121.4737),
+            (35.6762, 139.6503),
+            (39.9042, 116.4074),
+            (0.0, -179.0),
+        ];
+        let mut batch = CoordinateBatch::with_capacity(points.len());
+        for (latitude, longitude) in points {
+            let latitude_radians = f64::to_radians(latitude);
+            batch.push(
+                latitude_radians,
+                longitude.to_radians(),
+                latitude_radians.cos(),
+            );
+        }
+        let origin = 39.9042_f64.to_radians();
+        let mut distances = Vec::new();
+        haversine_distance_batch(
+            origin,
+            116.4074_f64.to_radians(),
+            origin.cos(),
+            &batch,
+            &mut distances,
+        );
+
+        assert_eq!(distances.len(), points.len());
+        assert!(distances[2].abs() < 1e-9);
+        for ((latitude, longitude), distance) in points.iter().zip(&distances) {
+            let Some(reference) = vincenty_distance(39.9042, 116.4074, *latitude, *longitude)
+            else {
+                continue;
+            };
+            assert!(
+                (distance - reference).abs() <= reference * 0.005 + 1e-6,
+                "haversine {distance} km vs vincenty {reference} km"
+            );
+        }
+    }
+
     #[test]
     fn test_polar_antipodal_not_zero() {
         assert_ne!(vincenty_distance(90.0, 0.0, -90.0, 0.0), Some(0.0));
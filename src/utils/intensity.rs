@@ -1,5 +1,231 @@
 //! 基于震级和震源距估算 JMA 震度
 
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// 可选的震度估算模型。
+///
+/// 当前生效的模型决定推送；另可配置一个影子模型与之并行评估，只记录分歧、不推送，
+/// 用于在切换模型前对照真实事件验证。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum IntensityModel {
+    /// 分段混合系数的经验衰减模型，见 [`estimate_intensity`]。
+    #[default]
+    Blended,
+    /// Si & Midorikawa (1999) 最大速度衰减式换算震度，见 [`estimate_si_midorikawa`]。
+    SiMidorikawa,
+}
+
+impl IntensityModel {
+    pub(crate) fn estimate(self, magnitude: f64, distance_km: f64) -> f64 {
+        match self {
+            Self::Blended => estimate_intensity(magnitude, distance_km),
+            Self::SiMidorikawa => estimate_si_midorikawa(magnitude, distance_km),
+        }
+    }
+
+    /// 返回各震度等级的预计震中距半径，按 1 到 7 级升序，未达到的等级不列出。
+    ///
+    /// 对衰减模型按距离二分反解，阈值与匹配时的四舍五入一致（震度 N 对应估算值 ≥ N - 0.5），
+    /// 用于详情页绘制等震线圈和核对候选检索半径。
+    pub(crate) fn isoseismal_radii(self, magnitude: f64, depth_km: f64) -> Vec<IsoseismalRadius> {
+        let depth = if depth_km.is_finite() {
+            depth_km.max(0.0)
+        } else {
+            0.0
+        };
+        let estimate = |distance_km: f64| {
+            self.estimate(
+                magnitude,
+                distance_km.mul_add(distance_km, depth * depth).sqrt(),
+            )
+        };
+        let mut radii = Vec::new();
+        for intensity in 1..=7_u8 {
+            let threshold = f64::from(intensity) - 0.5;
+            if estimate(0.0) < threshold {
+                break;
+            }
+            let radius_km = if estimate(MAX_ISOSEISMAL_RADIUS_KM) >= threshold {
+                MAX_ISOSEISMAL_RADIUS_KM
+            } else {
+                let (mut inside, mut outside) = (0.0, MAX_ISOSEISMAL_RADIUS_KM);
+                while outside - inside > 0.05 {
+                    let middle = (inside + outside) / 2.0;
+                    if estimate(middle) >= threshold {
+                        inside = middle;
+                    } else {
+                        outside = middle;
+                    }
+                }
+                inside
+            };
+            radii.push(IsoseismalRadius {
+                intensity,
+                radius_km: (radius_km * 10.0).round() / 10.0,
+            });
+        }
+        radii
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Blended => "blended",
+            Self::SiMidorikawa => "si_midorikawa",
+        }
+    }
+}
+
+impl fmt::Display for IntensityModel {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+/// 等震线半径的上限；衰减式在更远处已无意义。
+pub(crate) const MAX_ISOSEISMAL_RADIUS_KM: f64 = 3_000.0;
+
+/// 预计达到某一震度等级的震中距范围。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct IsoseismalRadius {
+    pub(crate) intensity: u8,
+    pub(crate) radius_km: f64,
+}
+
+/// 一份带位置的震感报告与模型估算的对照。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct IntensityObservation {
+    pub(crate) magnitude: f64,
+    pub(crate) hypocentral_distance_km: f64,
+    pub(crate) observed: f64,
+    pub(crate) estimated: f64,
+}
+
+/// 给出系数修正建议所需的最少样本数；样本更少时只报告平均偏差。
+pub(crate) const MIN_CALIBRATION_SAMPLES: usize = 30;
+/// 震级或震源距对数的方差低于该值时视为没有变化。
+const MIN_SPREAD: f64 = 1e-6;
+
+/// 按震感报告建议的衰减式修正量，形式与 `I = a * M - b * log10(D + c) + d` 对应：
+/// 修正后的估算为 `I + Δa * M - Δb * log10(D) + Δd`。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub(crate) struct CalibrationSuggestion {
+    pub(crate) samples: usize,
+    /// 观测减估算的平均值，为正表示模型整体低估。
+    pub(crate) mean_residual: f64,
+    pub(crate) rms_residual: f64,
+    /// 震级项系数的修正量；样本不足或震级缺少变化时为 `None`。
+    pub(crate) magnitude_coefficient_delta: Option<f64>,
+    /// 距离衰减系数的修正量，为正表示衰减应更快；样本不足或距离缺少变化时为 `None`。
+    pub(crate) distance_coefficient_delta: Option<f64>,
+    pub(crate) constant_delta: f64,
+}
+
+/// 对残差（观测减估算）按震级和震源距对数做最小二乘拟合，得出系数修正建议。
+///
+/// 只用于人工评估：建议量描述的是报告数据上的系统性偏差，报告本身受人群分布和主观感受影响，
+/// 不会自动应用到模型。
+pub(crate) fn suggest_calibration(
+    observations: &[IntensityObservation],
+) -> Option<CalibrationSuggestion> {
+    let samples = observations
+        .iter()
+        .filter(|observation| {
+            observation.magnitude.is_finite()
+                && observation.hypocentral_distance_km.is_finite()
+                && observation.observed.is_finite()
+                && observation.estimated.is_finite()
+        })
+        .map(|observation| {
+            (
+                observation.magnitude,
+                observation.hypocentral_distance_km.max(1.0).log10(),
+                observation.observed - observation.estimated,
+            )
+        })
+        .collect::<Vec<_>>();
+    if samples.is_empty() {
+        return None;
+    }
+    let count = samples.len() as f64;
+    let mean =
+        |select: fn(&(f64, f64, f64)) -> f64| samples.iter().map(select).sum::<f64>() / count;
+    let (mean_magnitude, mean_log_distance, mean_residual) = (
+        mean(|sample| sample.0),
+        mean(|sample| sample.1),
+        mean(|sample| sample.2),
+    );
+    let rms_residual = mean(|sample| sample.2 * sample.2).sqrt();
+
+    let (mut smm, mut sll, mut sml, mut smr, mut slr) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (magnitude, log_distance, residual) in &samples {
+        let (m, l, r) = (
+            magnitude - mean_magnitude,
+            log_distance - mean_log_distance,
+            residual - mean_residual,
+        );
+        smm += m * m;
+        sll += l * l;
+        sml += m * l;
+        smr += m * r;
+        slr += l * r;
+    }
+    // 变化太小的变量无法分辨其系数，只拟合另一项。
+    let vary_magnitude = smm / count > MIN_SPREAD;
+    let vary_distance = sll / count > MIN_SPREAD;
+    let determinant = smm * sll - sml * sml;
+    let (magnitude_slope, distance_slope) = if samples.len() < MIN_CALIBRATION_SAMPLES {
+        (None, None)
+    } else if vary_magnitude && vary_distance && determinant > MIN_SPREAD * smm * sll {
+        (
+            Some((smr * sll - slr * sml) / determinant),
+            Some((slr * smm - smr * sml) / determinant),
+        )
+    } else if vary_magnitude {
+        (Some(smr / smm), None)
+    } else if vary_distance {
+        (None, Some(slr / sll))
+    } else {
+        (None, None)
+    };
+    let constant_delta = mean_residual
+        - magnitude_slope.unwrap_or(0.0) * mean_magnitude
+        - distance_slope.unwrap_or(0.0) * mean_log_distance;
+    Some(CalibrationSuggestion {
+        samples: samples.len(),
+        mean_residual,
+        rms_residual,
+        magnitude_coefficient_delta: magnitude_slope,
+        distance_coefficient_delta: distance_slope.map(|slope| -slope),
+        constant_delta,
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct UnknownIntensityModel;
+
+impl fmt::Display for UnknownIntensityModel {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("expected blended or si_midorikawa")
+    }
+}
+
+impl std::error::Error for UnknownIntensityModel {}
+
+impl FromStr for IntensityModel {
+    type Err = UnknownIntensityModel;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "blended" => Ok(Self::Blended),
+            "si_midorikawa" => Ok(Self::SiMidorikawa),
+            _ => Err(UnknownIntensityModel),
+        }
+    }
+}
+
 /// 返回 0.0-7.0 的连续震度估算值
 ///
 /// 衰减模型为 `I = a * M - b * log10(D + c) + d`，震级分段处会混合两组系数，
@@ -16,6 +242,25 @@ pub(crate) fn estimate_intensity(magnitude: f64, distance_km: f64) -> f64 {
     intensity.clamp(0.0, 7.0)
 }
 
+/// 按 Si & Midorikawa (1999) 估算工程基岩最大速度，按 AVS30 = 400 m/s 放大到地表，
+/// 再以 `I = 2.68 + 1.72 * log10(PGV)` 换算震度。
+///
+/// 以震源距近似断层距，按地壳内地震处理且不含震源深度项，返回值限定在 0.0-7.0。
+pub(crate) fn estimate_si_midorikawa(magnitude: f64, distance_km: f64) -> f64 {
+    if !magnitude.is_finite() || !distance_km.is_finite() || magnitude <= 0.0 || distance_km < 0.0 {
+        return 0.0;
+    }
+
+    let distance = distance_km.max(1.0);
+    let near_source = 0.0028 * 10_f64.powf(0.5 * magnitude);
+    let bedrock_pgv_log =
+        0.58 * magnitude - 1.29 - (distance + near_source).log10() - 0.002 * distance;
+    let amplification_log = 1.83 - 0.66 * 400_f64.log10();
+    let intensity = 2.68 + 1.72 * (bedrock_pgv_log + amplification_log);
+
+    intensity.clamp(0.0, 7.0)
+}
+
 fn intensity_coefficients(magnitude: f64) -> (f64, f64, f64, f64) {
     let small = (2.5, 3.8, 12.0, -1.2);
     let medium = (2.5, 3.6, 10.0, -1.3);
@@ -56,10 +301,119 @@ fn lerp(left: f64, right: f64, t: f64) -> f64 {
     left + (right - left) * t
 }
 
+const SHINDO_LABELS: [&str; 10] = ["0", "1", "2", "3", "4", "5弱", "5強", "6弱", "6強", "7"];
+
+/// JMA 震度阶级（0、1、2、3、4、5弱、5強、6弱、6強、7），按先后比较大小。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ShindoClass(u8);
+
+impl ShindoClass {
+    /// 把数据源上报的最大烈度换算为震度阶级。日本气象厅和台湾中央气象署使用 7 级震度；
+    /// 其余数据源使用中国地震烈度表或修订麦卡利烈度等 12 度烈度表，按烈度减一粗略对应，
+    /// 如 VI 度约为 5 弱。12 度烈度既可以是数字，也可以是韩国气象厅等使用的罗马数字
+    /// （`Ⅳ`、`IV`、`진도 Ⅳ`）。无法识别时返回 `None`。
+    pub(crate) fn from_reported(source: &str, value: &str) -> Option<Self> {
+        if source.contains("jma") || source.contains("cwa") {
+            return value.parse().ok();
+        }
+        let value = value.trim();
+        let value = value.strip_prefix("진도").unwrap_or(value).trim();
+        let value = value.strip_suffix('度').unwrap_or(value).trim();
+        let degree = value
+            .parse::<f64>()
+            .ok()
+            .or_else(|| roman_degree(value).map(f64::from))
+            .filter(|degree| degree.is_finite() && *degree >= 0.0)?;
+        Some(Self((degree.round().min(10.0) as u8).saturating_sub(1)))
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        SHINDO_LABELS
+            .get(usize::from(self.0))
+            .copied()
+            .unwrap_or("7")
+    }
+}
+
+/// 解析 1 到 12 的罗马数字烈度，支持 ASCII 字母和 Unicode 罗马数字（`Ⅰ`–`Ⅻ`）。
+fn roman_degree(value: &str) -> Option<u8> {
+    const NUMERALS: [&str; 12] = [
+        "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII",
+    ];
+    let mut chars = value.chars();
+    if let (Some(numeral @ 'Ⅰ'..='Ⅻ'), None) = (chars.next(), chars.next()) {
+        return u8::try_from(u32::from(numeral) - u32::from('Ⅰ') + 1).ok();
+    }
+    let value = value.to_ascii_uppercase();
+    let position = NUMERALS.iter().position(|numeral| *numeral == value)?;
+    u8::try_from(position + 1).ok()
+}
+
+impl Serialize for ShindoClass {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct UnknownShindoClass;
+
+impl fmt::Display for UnknownShindoClass {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("expected a JMA seismic intensity such as 3, 5弱 or 6+")
+    }
+}
+
+impl std::error::Error for UnknownShindoClass {}
+
+impl FromStr for ShindoClass {
+    type Err = UnknownShindoClass;
+
+    /// 接受 `5弱`、`5-`、`5強`、`5+` 等写法；未区分强弱的 5、6 按弱计。
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().replace('弱', "-").replace(['強', '强'], "+");
+        let class = match value.as_str() {
+            "0" => 0,
+            "1" => 1,
+            "2" => 2,
+            "3" => 3,
+            "4" => 4,
+            "5-" | "5" => 5,
+            "5+" => 6,
+            "6-" | "6" => 7,
+            "6+" => 8,
+            "7" => 9,
+            _ => return Err(UnknownShindoClass),
+        };
+        Ok(Self(class))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn reported_intensities_map_onto_jma_classes() {
+        let class = |source: &str, value: &str| {
+            ShindoClass::from_reported(source, value).map(ShindoClass::label)
+        };
+        assert_eq!(class("wolfx.jma_eew", "5弱"), Some("5弱"));
+        assert_eq!(class("fanstudio.jma", "6+"), Some("6強"));
+        assert_eq!(class("fanstudio.cwa-eew", "4"), Some("4"));
+        assert_eq!(class("wolfx.jma_eew", "不明"), None);
+        assert_eq!(class("wolfx.cenc_eew", "6.2"), Some("5弱"));
+        assert_eq!(class("fanstudio.usgs", "3"), Some("2"));
+        assert_eq!(class("fanstudio.cenc", "12"), Some("7"));
+        assert_eq!(class("fanstudio.cenc", "0.4"), Some("0"));
+        assert_eq!(class("fanstudio.kma", "Ⅳ"), Some("3"));
+        assert_eq!(class("fanstudio.kma-eew", "진도 VI"), Some("5弱"));
+        assert_eq!(class("fanstudio.kma", "xii"), Some("7"));
+        assert_eq!(class("fanstudio.cenc", "Ⅵ度"), Some("5弱"));
+        assert_eq!(class("fanstudio.kma", "IIII"), None);
+        assert!("5-".parse::<ShindoClass>().ok() < "5強".parse::<ShindoClass>().ok());
+    }
+
     #[test]
     fn test_estimate_intensity() {
         let i1 = estimate_intensity(7.0, 10.0);
@@ -92,6 +446,93 @@ mod tests {
         assert_eq!(estimate_intensity(f64::INFINITY, 10.0), 0.0);
     }
 
+    #[test]
+    fn si_midorikawa_decays_with_distance() {
+        let near = estimate_si_midorikawa(7.0, 10.0);
+        assert!((5.0..=6.0).contains(&near), "{near}");
+        assert!(estimate_si_midorikawa(7.0, 100.0) < near);
+        assert!(estimate_si_midorikawa(5.0, 50.0) < near);
+        assert_eq!(estimate_si_midorikawa(f64::NAN, 10.0), 0.0);
+    }
+
+    #[test]
+    fn parses_model_names() {
+        assert_eq!(
+            "blended".parse::<IntensityModel>().ok(),
+            Some(IntensityModel::Blended)
+        );
+        assert_eq!(
+            " SI_MIDORIKAWA ".parse::<IntensityModel>().ok(),
+            Some(IntensityModel::SiMidorikawa)
+        );
+        assert!("linear".parse::<IntensityModel>().is_err());
+    }
+
+    #[test]
+    fn calibration_recovers_systematic_bias() {
+        let mut observations = Vec::new();
+        for magnitude in [4.0, 5.0, 6.0] {
+            for distance_km in [20.0, 50.0, 100.0, 200.0] {
+                for _ in 0..3 {
+                    let estimated = estimate_intensity(magnitude, distance_km);
+                    // 模拟模型在震级项上低估 0.2、距离衰减慢 0.5、整体偏高 0.3。
+                    let observed =
+                        estimated + 0.2 * magnitude - 0.5 * f64::log10(distance_km) - 0.3;
+                    observations.push(IntensityObservation {
+                        magnitude,
+                        hypocentral_distance_km: distance_km,
+                        observed,
+                        estimated,
+                    });
+                }
+            }
+        }
+        let suggestion = suggest_calibration(&observations);
+        assert!(suggestion.is_some_and(|suggestion| {
+            suggestion.samples == 36
+                && suggestion
+                    .magnitude_coefficient_delta
+                    .is_some_and(|delta| (delta - 0.2).abs() < 1e-9)
+                && suggestion
+                    .distance_coefficient_delta
+                    .is_some_and(|delta| (delta - 0.5).abs() < 1e-9)
+                && (suggestion.constant_delta + 0.3).abs() < 1e-9
+        }));
+
+        let few = suggest_calibration(&observations[..5]);
+        assert!(few.is_some_and(|few| {
+            few.magnitude_coefficient_delta.is_none()
+                && few.distance_coefficient_delta.is_none()
+                && (few.constant_delta - few.mean_residual).abs() < 1e-12
+        }));
+        assert!(suggest_calibration(&[]).is_none());
+    }
+
+    #[test]
+    fn isoseismal_radii_invert_the_attenuation_model() {
+        for model in [IntensityModel::Blended, IntensityModel::SiMidorikawa] {
+            let radii = model.isoseismal_radii(7.0, 10.0);
+            assert!(!radii.is_empty(), "{model}");
+            assert!(radii.windows(2).all(|pair| {
+                pair[1].intensity == pair[0].intensity + 1 && pair[1].radius_km <= pair[0].radius_km
+            }));
+            for radius in &radii {
+                let threshold = f64::from(radius.intensity) - 0.5;
+                let hypocentral = |distance: f64| distance.hypot(10.0);
+                assert!(model.estimate(7.0, hypocentral(radius.radius_km - 0.2)) >= threshold);
+                assert!(
+                    radius.radius_km >= MAX_ISOSEISMAL_RADIUS_KM
+                        || model.estimate(7.0, hypocentral(radius.radius_km + 0.2)) < threshold
+                );
+            }
+        }
+        assert!(
+            IntensityModel::Blended
+                .isoseismal_radii(1.0, 600.0)
+                .is_empty()
+        );
+    }
+
     #[test]
     fn near_field_is_continuous_at_one_kilometer() {
         let just_under = estimate_intensity(5.0, 0.99);
@@ -1,3 +1,5 @@
+pub(crate) mod boundaries;
 pub(crate) mod distance;
 pub(crate) mod intensity;
+pub(crate) mod places;
 pub(crate) mod region;
@@ -0,0 +1,233 @@
+use crate::models::MonitoringTarget;
+use crate::utils::{boundaries, distance};
+
+/// 距离最近地名超过该值时不再说“附近”，改用所在省份或都道府县描述。
+const NEARBY_KM: f64 = 30.0;
+
+/// 内置的离线地名索引：中日两国的省会、县厅所在地、主要城市，以及北京、上海和东京的市区，
+/// 用于在日志、详情页和数据导出中以大致位置代替精确坐标，不调用外部服务。
+static PLACES: &[Place] = &[
+    place("北京市东城区", 39.928, 116.416),
+    place("北京市西城区", 39.912, 116.366),
+    place("北京市朝阳区", 39.921, 116.443),
+    place("北京市海淀区", 39.959, 116.298),
+    place("北京市丰台区", 39.858, 116.287),
+    place("北京市石景山区", 39.906, 116.223),
+    place("北京市通州区", 39.910, 116.657),
+    place("北京市顺义区", 40.130, 116.654),
+    place("北京市昌平区", 40.221, 116.231),
+    place("北京市大兴区", 39.727, 116.341),
+    place("天津市", 39.084, 117.201),
+    place("上海市黄浦区", 31.231, 121.485),
+    place("上海市徐汇区", 31.188, 121.437),
+    place("上海市长宁区", 31.220, 121.424),
+    place("上海市静安区", 31.229, 121.448),
+    place("上海市普陀区", 31.249, 121.397),
+    place("上海市虹口区", 31.264, 121.505),
+    place("上海市杨浦区", 31.260, 121.526),
+    place("上海市浦东新区", 31.221, 121.544),
+    place("上海市闵行区", 31.112, 121.382),
+    place("上海市宝山区", 31.405, 121.489),
+    place("上海市嘉定区", 31.375, 121.265),
+    place("上海市松江区", 31.032, 121.228),
+    place("重庆市", 29.553, 106.569),
+    place("河北省石家庄市", 38.042, 114.515),
+    place("河北省唐山市", 39.630, 118.180),
+    place("山西省太原市", 37.870, 112.549),
+    place("内蒙古呼和浩特市", 40.842, 111.749),
+    place("内蒙古包头市", 40.657, 109.840),
+    place("辽宁省沈阳市", 41.806, 123.432),
+    place("辽宁省大连市", 38.914, 121.615),
+    place("吉林省长春市", 43.817, 125.324),
+    place("黑龙江省哈尔滨市", 45.803, 126.535),
+    place("江苏省南京市", 32.060, 118.797),
+    place("江苏省苏州市", 31.299, 120.585),
+    place("浙江省杭州市", 30.274, 120.155),
+    place("浙江省宁波市", 29.868, 121.544),
+    place("安徽省合肥市", 31.821, 117.227),
+    place("福建省福州市", 26.074, 119.296),
+    place("福建省厦门市", 24.480, 118.089),
+    place("江西省南昌市", 28.682, 115.858),
+    place("山东省济南市", 36.651, 117.120),
+    place("山东省青岛市", 36.067, 120.383),
+    place("河南省郑州市", 34.747, 113.625),
+    place("河南省洛阳市", 34.620, 112.454),
+    place("湖北省武汉市", 30.593, 114.305),
+    place("湖北省宜昌市", 30.692, 111.287),
+    place("湖南省长沙市", 28.228, 112.939),
+    place("广东省广州市", 23.129, 113.264),
+    place("广东省深圳市", 22.543, 114.058),
+    place("广东省汕头市", 23.354, 116.682),
+    place("广西南宁市", 22.817, 108.366),
+    place("广西桂林市", 25.274, 110.290),
+    place("海南省海口市", 20.044, 110.199),
+    place("海南省三亚市", 18.253, 109.512),
+    place("四川省成都市", 30.573, 104.066),
+    place("四川省绵阳市", 31.468, 104.679),
+    place("四川省雅安市", 29.980, 103.013),
+    place("四川省西昌市", 27.894, 102.264),
+    place("四川省康定市", 30.050, 101.964),
+    place("四川省宜宾市", 28.752, 104.643),
+    place("贵州省贵阳市", 26.647, 106.630),
+    place("云南省昆明市", 25.038, 102.718),
+    place("云南省大理市", 25.607, 100.268),
+    place("云南省丽江市", 26.855, 100.227),
+    place("云南省昭通市", 27.338, 103.717),
+    place("西藏拉萨市", 29.652, 91.172),
+    place("西藏日喀则市", 29.267, 88.881),
+    place("陕西省西安市", 34.341, 108.940),
+    place("甘肃省兰州市", 36.061, 103.834),
+    place("甘肃省天水市", 34.581, 105.724),
+    place("青海省西宁市", 36.617, 101.778),
+    place("青海省玉树市", 33.004, 97.006),
+    place("宁夏银川市", 38.487, 106.231),
+    place("新疆乌鲁木齐市", 43.825, 87.617),
+    place("新疆喀什市", 39.470, 75.989),
+    place("新疆伊宁市", 43.909, 81.324),
+    place("香港", 22.320, 114.170),
+    place("澳门", 22.199, 113.544),
+    place("台湾省台北市", 25.033, 121.565),
+    place("台湾省台中市", 24.148, 120.674),
+    place("台湾省高雄市", 22.627, 120.301),
+    place("台湾省花莲市", 23.987, 121.601),
+    place("北海道札幌市", 43.062, 141.354),
+    place("北海道函馆市", 41.769, 140.729),
+    place("北海道旭川市", 43.771, 142.365),
+    place("北海道钏路市", 42.985, 144.381),
+    place("青森县青森市", 40.822, 140.747),
+    place("青森县八户市", 40.512, 141.488),
+    place("岩手县盛冈市", 39.702, 141.154),
+    place("宫城县仙台市", 38.268, 140.870),
+    place("秋田县秋田市", 39.720, 140.103),
+    place("山形县山形市", 38.240, 140.363),
+    place("福岛县福岛市", 37.750, 140.468),
+    place("福岛县郡山市", 37.400, 140.360),
+    place("福岛县磐城市", 37.050, 140.888),
+    place("茨城县水户市", 36.366, 140.471),
+    place("栃木县宇都宫市", 36.555, 139.883),
+    place("群马县前桥市", 36.389, 139.063),
+    place("埼玉县埼玉市", 35.862, 139.645),
+    place("千叶县千叶市", 35.607, 140.106),
+    place("东京都千代田区", 35.694, 139.754),
+    place("东京都中央区", 35.670, 139.772),
+    place("东京都港区", 35.658, 139.752),
+    place("东京都新宿区", 35.694, 139.704),
+    place("东京都文京区", 35.708, 139.752),
+    place("东京都台东区", 35.713, 139.780),
+    place("东京都墨田区", 35.711, 139.801),
+    place("东京都江东区", 35.673, 139.817),
+    place("东京都品川区", 35.609, 139.730),
+    place("东京都目黑区", 35.641, 139.698),
+    place("东京都大田区", 35.561, 139.716),
+    place("东京都世田谷区", 35.646, 139.653),
+    place("东京都涩谷区", 35.664, 139.698),
+    place("东京都中野区", 35.707, 139.664),
+    place("东京都杉并区", 35.700, 139.637),
+    place("东京都丰岛区", 35.726, 139.717),
+    place("东京都北区", 35.753, 139.734),
+    place("东京都荒川区", 35.736, 139.783),
+    place("东京都板桥区", 35.751, 139.709),
+    place("东京都练马区", 35.736, 139.652),
+    place("东京都足立区", 35.775, 139.804),
+    place("东京都葛饰区", 35.743, 139.847),
+    place("东京都江户川区", 35.707, 139.868),
+    place("东京都八王子市", 35.666, 139.316),
+    place("东京都立川市", 35.694, 139.408),
+    place("东京都町田市", 35.548, 139.446),
+    place("神奈川县横滨市", 35.444, 139.638),
+    place("神奈川县川崎市", 35.531, 139.703),
+    place("新潟县新潟市", 37.916, 139.036),
+    place("富山县富山市", 36.695, 137.211),
+    place("石川县金泽市", 36.561, 136.656),
+    place("石川县轮岛市", 37.391, 136.899),
+    place("福井县福井市", 36.065, 136.222),
+    place("山梨县甲府市", 35.662, 138.568),
+    place("长野县长野市", 36.648, 138.194),
+    place("长野县松本市", 36.238, 137.972),
+    place("岐阜县岐阜市", 35.423, 136.760),
+    place("静冈县静冈市", 34.975, 138.383),
+    place("静冈县滨松市", 34.711, 137.726),
+    place("爱知县名古屋市", 35.181, 136.906),
+    place("三重县津市", 34.730, 136.509),
+    place("滋贺县大津市", 35.018, 135.855),
+    place("京都府京都市", 35.012, 135.768),
+    place("大阪府大阪市", 34.694, 135.502),
+    place("兵库县神户市", 34.690, 135.196),
+    place("奈良县奈良市", 34.685, 135.805),
+    place("和歌山县和歌山市", 34.226, 135.167),
+    place("鸟取县鸟取市", 35.501, 134.235),
+    place("岛根县松江市", 35.468, 133.048),
+    place("冈山县冈山市", 34.655, 133.919),
+    place("广岛县广岛市", 34.385, 132.455),
+    place("山口县山口市", 34.178, 131.474),
+    place("德岛县德岛市", 34.070, 134.555),
+    place("香川县高松市", 34.340, 134.047),
+    place("爱媛县松山市", 33.839, 132.766),
+    place("高知县高知市", 33.559, 133.531),
+    place("福冈县福冈市", 33.590, 130.402),
+    place("福冈县北九州市", 33.883, 130.875),
+    place("佐贺县佐贺市", 33.249, 130.300),
+    place("长崎县长崎市", 32.750, 129.878),
+    place("熊本县熊本市", 32.803, 130.708),
+    place("大分县大分市", 33.240, 131.613),
+    place("宫崎县宫崎市", 31.908, 131.420),
+    place("鹿儿岛县鹿儿岛市", 31.597, 130.557),
+    place("冲绳县那霸市", 26.212, 127.681),
+];
+
+struct Place {
+    name: &'static str,
+    latitude: f64,
+    longitude: f64,
+}
+
+const fn place(name: &'static str, latitude: f64, longitude: f64) -> Place {
+    Place {
+        name,
+        latitude,
+        longitude,
+    }
+}
+
+/// 把坐标描述为“东京都港区附近”这样的大致位置：最近的内置地名在 30 公里内时用该地名，
+/// 否则用所在的省份或都道府县，都不在时保留两位小数的坐标。边界只是外接矩形，省界附近可能归错。
+pub(crate) fn describe(latitude: f64, longitude: f64) -> String {
+    let nearest = PLACES
+        .iter()
+        .filter_map(|place| {
+            distance::vincenty_distance(latitude, longitude, place.latitude, place.longitude)
+                .map(|distance_km| (place, distance_km))
+        })
+        .min_by(|left, right| left.1.total_cmp(&right.1));
+    if let Some((place, _distance_km)) =
+        nearest.filter(|(_place, distance_km)| *distance_km <= NEARBY_KM)
+    {
+        return format!("{}附近", place.name);
+    }
+    if let Some(boundary) = boundaries::locate(latitude, longitude) {
+        return format!("{}境内", boundary.name);
+    }
+    format!("{latitude:.2}, {longitude:.2}")
+}
+
+/// 监测地点的大致位置；按地区订阅时就是订阅的地区名。
+pub(crate) fn describe_target(target: &MonitoringTarget) -> String {
+    match target.point {
+        Some(point) => describe(point.latitude, point.longitude),
+        None => target.region.province.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_coordinates_by_nearest_place_then_region() {
+        assert_eq!(describe(35.655, 139.750), "东京都港区附近");
+        assert_eq!(describe(31.23, 121.47), "上海市黄浦区附近");
+        // 阿坝州腹地离内置城市都较远，退回到省份。
+        assert_eq!(describe(32.9, 101.7), "四川省境内");
+        assert_eq!(describe(-33.87, 151.21), "-33.87, 151.21");
+    }
+}